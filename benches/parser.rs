@@ -0,0 +1,124 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use serde_json::json;
+
+use pixel_agents_tui::watcher::parser::{self, extract_tool_uses, extract_tool_uses_ref};
+use pixel_agents_tui::watcher::redact::Redactor;
+use pixel_agents_tui::watcher::rules::ToolClassifier;
+use pixel_agents_tui::watcher::types::JsonlRecord;
+
+/// Counts allocations, so the bench can show what borrowing saves.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// A long session's worth of records: prompts, tool uses of every kind
+/// the status formatter knows, their results, and turn ends.
+fn corpus(turns: usize) -> Vec<JsonlRecord> {
+    let mut lines = Vec::new();
+    for turn in 0..turns {
+        lines.push(
+            json!({"type": "user", "message": {"content": format!("Refactor module {turn}")}}),
+        );
+        let tools = [
+            (
+                "Read",
+                json!({"file_path": format!("/home/me/src/repo/src/module_{turn}.rs")}),
+            ),
+            (
+                "Read",
+                json!({"file_path": "/var/log/big.log", "offset": turn * 100, "limit": 100}),
+            ),
+            (
+                "Grep",
+                json!({"pattern": "fn refresh_token", "path": "/home/me/src/repo"}),
+            ),
+            (
+                "Edit",
+                json!({"file_path": format!("/home/me/src/repo/src/module_{turn}.rs"), "old_string": "a", "new_string": "b"}),
+            ),
+            (
+                "Bash",
+                json!({"command": "cargo test --workspace -- --nocapture"}),
+            ),
+            ("Skill", json!({"skill": "sdd-apply"})),
+        ];
+        for (i, (name, input)) in tools.into_iter().enumerate() {
+            let id = format!("toolu_{turn:06}_{i}");
+            lines.push(json!({"type": "assistant", "message": {"content": [
+                {"type": "text", "text": "Let me look at that."},
+                {"type": "tool_use", "id": id, "name": name, "input": input},
+            ]}}));
+            lines.push(json!({"type": "user", "message": {"content": [
+                {"type": "tool_result", "tool_use_id": id},
+            ]}}));
+        }
+        lines.push(json!({"type": "system", "subtype": "turn_duration", "duration_ms": 4200}));
+    }
+    lines
+        .iter()
+        .filter_map(|line| parser::parse_line(&line.to_string()))
+        .collect()
+}
+
+fn count_owned(records: &[JsonlRecord], redactor: &Redactor, classifier: &ToolClassifier) -> usize {
+    records
+        .iter()
+        .map(|record| extract_tool_uses(record, redactor, classifier).len())
+        .sum()
+}
+
+fn count_borrowed(
+    records: &[JsonlRecord],
+    redactor: &Redactor,
+    classifier: &ToolClassifier,
+) -> usize {
+    records
+        .iter()
+        .map(|record| extract_tool_uses_ref(record, redactor, classifier).count())
+        .sum()
+}
+
+fn allocations(f: impl FnOnce() -> usize) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    std::hint::black_box(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn bench_extract(c: &mut Criterion) {
+    let records = corpus(2000);
+    let redactor = Redactor::default();
+    let classifier = ToolClassifier::default();
+    println!(
+        "allocations counting {} tool uses in {} records: owned {}, borrowed {}",
+        count_borrowed(&records, &redactor, &classifier),
+        records.len(),
+        allocations(|| count_owned(&records, &redactor, &classifier)),
+        allocations(|| count_borrowed(&records, &redactor, &classifier)),
+    );
+    c.bench_function("extract_tool_uses 2000 turns", |b| {
+        b.iter(|| count_owned(&records, &redactor, &classifier))
+    });
+    c.bench_function("extract_tool_uses_ref 2000 turns", |b| {
+        b.iter(|| count_borrowed(&records, &redactor, &classifier))
+    });
+}
+
+criterion_group!(benches, bench_extract);
+criterion_main!(benches);
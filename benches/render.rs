@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ratatui::backend::TestBackend;
+use ratatui::layout::Rect;
+use ratatui::Terminal;
+
+use pixel_agents_tui::app::App;
+use pixel_agents_tui::state::agent::AgentState;
+use pixel_agents_tui::ui::layout;
+use pixel_agents_tui::ui::office::{self, CellPolicy, GridParams, Section};
+use pixel_agents_tui::watcher::parser::ToolUseEvent;
+
+fn app_with_agents(n: u32) -> App {
+    let mut app = App::new(PathBuf::from("/tmp"));
+    // Keep everyone on the regular (non-scale) views
+    app.scale_threshold = usize::MAX;
+    for id in 1..=n {
+        let path = PathBuf::from(format!(
+            "/home/me/.claude/projects/-home-me-src-repo-{id}/0f3c9a2e-5b1d-4e7a-9c2f-{id:012}.jsonl"
+        ));
+        let mut agent = AgentState::new(id, path);
+        agent.set_prompt_summary(
+            "Refactor the authentication middleware so that token refresh happens lazily",
+        );
+        agent.add_tool(ToolUseEvent {
+            tool_id: format!("t{id}"),
+            tool_name: "Read".to_string(),
+            display_status: "Reading middleware.rs".to_string(),
+            is_reading: true,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
+        });
+        app.agents.insert(id, agent);
+    }
+    app.selected_agent = Some(1);
+    app.refresh_display_caches();
+    app
+}
+
+fn bench_render(c: &mut Criterion) {
+    let app = app_with_agents(60);
+    let mut terminal = Terminal::new(TestBackend::new(160, 60)).unwrap();
+    c.bench_function("render 60 agents", |b| {
+        b.iter(|| {
+            terminal
+                .draw(|frame| layout::render(frame, &app))
+                .map(|_| ())
+                .unwrap()
+        })
+    });
+}
+
+fn bench_sidebar_lines(c: &mut Criterion) {
+    let app = app_with_agents(60);
+    c.bench_function("sidebar_lines 60 agents", |b| {
+        b.iter(|| layout::sidebar_lines(&app, 70).len())
+    });
+}
+
+fn bench_grid_layout(c: &mut Criterion) {
+    let sections = [
+        Section {
+            label: Some("repo".to_string()),
+            ids: (1..=40).collect(),
+        },
+        Section {
+            label: Some("other projects".to_string()),
+            ids: (41..=500).collect(),
+        },
+    ];
+    c.bench_function("grid_layout 500 agents", |b| {
+        b.iter(|| {
+            office::grid_layout(&GridParams {
+                inner: Rect::new(1, 4, 93, 52),
+                sections: &sections,
+                policy: CellPolicy::Desks { max_columns: 9 },
+                page: 3,
+            })
+            .desks
+            .len()
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_render,
+    bench_sidebar_lines,
+    bench_grid_layout
+);
+criterion_main!(benches);
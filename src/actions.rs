@@ -0,0 +1,287 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A running process, by its command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// Program first.
+    pub args: Vec<String>,
+}
+
+/// Lists processes, so finding a session's owner can be exercised against
+/// a made-up process table.
+pub trait ProcessLister {
+    /// Every process the user can see.
+    fn processes(&self) -> Vec<ProcessInfo>;
+    /// The files `pid` has open, as far as the platform tells.
+    fn open_files(&self, pid: u32) -> Vec<PathBuf>;
+}
+
+/// Lists the processes actually running: from `/proc` on Linux, `ps` and
+/// `lsof` on other Unixes, and none on Windows.
+pub struct SystemProcesses;
+
+#[cfg(target_os = "linux")]
+impl ProcessLister for SystemProcesses {
+    fn processes(&self) -> Vec<ProcessInfo> {
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid = entry.file_name().to_str()?.parse().ok()?;
+                let cmdline = std::fs::read(entry.path().join("cmdline")).ok()?;
+                let args = cmdline
+                    .split(|&b| b == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                    .collect();
+                Some(ProcessInfo { pid, args })
+            })
+            .collect()
+    }
+
+    fn open_files(&self, pid: u32) -> Vec<PathBuf> {
+        std::fs::read_dir(format!("/proc/{pid}/fd"))
+            .map(|fds| {
+                fds.flatten()
+                    .filter_map(|fd| std::fs::read_link(fd.path()).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+impl ProcessLister for SystemProcesses {
+    fn processes(&self) -> Vec<ProcessInfo> {
+        let Ok(output) = std::process::Command::new("ps")
+            .args(["-axo", "pid=,args="])
+            .output()
+        else {
+            return Vec::new();
+        };
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                let pid = words.next()?.parse().ok()?;
+                Some(ProcessInfo {
+                    pid,
+                    args: words.map(str::to_string).collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn open_files(&self, pid: u32) -> Vec<PathBuf> {
+        let Ok(output) = std::process::Command::new("lsof")
+            .args(["-p", &pid.to_string(), "-Ffn"])
+            .output()
+        else {
+            return Vec::new();
+        };
+        parse_lsof(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(windows)]
+impl ProcessLister for SystemProcesses {
+    fn processes(&self) -> Vec<ProcessInfo> {
+        Vec::new()
+    }
+
+    fn open_files(&self, _pid: u32) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Read `lsof -Ffn` output: `f` lines name a descriptor, the `n` line
+/// after each names its file. The working directory isn't an open file.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn parse_lsof(output: &str) -> Vec<PathBuf> {
+    let mut open = Vec::new();
+    let mut fd = "";
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix('f') {
+            fd = name;
+        } else if let Some(path) = line.strip_prefix('n') {
+            if fd != "cwd" {
+                open.push(PathBuf::from(path));
+            }
+        }
+    }
+    open
+}
+
+/// Whether a command line runs Claude Code: the native `claude` binary, or
+/// node running the npm package's `claude` script or `cli.js`.
+fn is_claude(args: &[String]) -> bool {
+    let named = |arg: &String, name: &str| Path::new(arg).file_name().is_some_and(|n| n == name);
+    match args {
+        [program, ..] if named(program, "claude") => true,
+        [program, script, ..] if named(program, "node") => {
+            named(script, "claude")
+                || Path::new(script)
+                    .components()
+                    .any(|part| part.as_os_str() == "claude-code")
+        }
+        _ => false,
+    }
+}
+
+/// The Claude Code process writing `session_file`: the one with the
+/// session ID on its command line (`--resume <id>`), else the one holding
+/// the file open. None rather than a guess: other sessions may run in the
+/// same project.
+pub fn find_session_pid(session_file: &Path, lister: &dyn ProcessLister) -> Option<u32> {
+    let session_id = session_file.file_stem()?.to_string_lossy();
+    let claudes: Vec<ProcessInfo> = lister
+        .processes()
+        .into_iter()
+        .filter(|process| is_claude(&process.args))
+        .collect();
+    if let Some(process) = claudes
+        .iter()
+        .find(|process| process.args.iter().any(|arg| *arg == session_id))
+    {
+        return Some(process.pid);
+    }
+
+    claudes
+        .iter()
+        .find(|process| {
+            lister
+                .open_files(process.pid)
+                .iter()
+                .any(|path| path == session_file)
+        })
+        .map(|process| process.pid)
+}
+
+/// Send SIGINT to `pid`, as Ctrl+C in its terminal would.
+#[cfg(unix)]
+pub fn interrupt(pid: u32) -> io::Result<()> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+
+    // 0 and negative PIDs address process groups, never one process
+    let pid = i32::try_from(pid)
+        .ok()
+        .filter(|pid| *pid > 0)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a process ID"))?;
+    kill(Pid::from_raw(pid), Signal::SIGINT).map_err(io::Error::from)
+}
+
+/// Send SIGINT to `pid`, as Ctrl+C in its terminal would.
+#[cfg(windows)]
+pub fn interrupt(_pid: u32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "interrupting a session needs Unix signals",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A process table of `(pid, command line, open files)`.
+    struct FakeProcesses(Vec<(u32, &'static str, Vec<&'static str>)>);
+
+    impl ProcessLister for FakeProcesses {
+        fn processes(&self) -> Vec<ProcessInfo> {
+            self.0
+                .iter()
+                .map(|(pid, cmd, _)| ProcessInfo {
+                    pid: *pid,
+                    args: cmd.split(' ').map(str::to_string).collect(),
+                })
+                .collect()
+        }
+
+        fn open_files(&self, pid: u32) -> Vec<PathBuf> {
+            let (_, _, open) = self.0.iter().find(|p| p.0 == pid).unwrap();
+            open.iter().map(PathBuf::from).collect()
+        }
+    }
+
+    const SESSION: &str = "/home/u/.claude/projects/-home-u-repo/0f3c9a2e.jsonl";
+
+    fn find(table: Vec<(u32, &'static str, Vec<&'static str>)>) -> Option<u32> {
+        find_session_pid(Path::new(SESSION), &FakeProcesses(table))
+    }
+
+    #[test]
+    fn finds_the_owner_by_id_then_open_file() {
+        // Named on the command line, over one holding some other file
+        assert_eq!(
+            find(vec![
+                (10, "claude", vec!["/tmp/other.jsonl"]),
+                (11, "claude --resume 0f3c9a2e", vec![]),
+            ]),
+            Some(11)
+        );
+        // Holding the file open
+        assert_eq!(
+            find(vec![
+                (10, "claude", vec![]),
+                (
+                    12,
+                    "node /usr/lib/node_modules/@anthropic-ai/claude-code/cli.js",
+                    vec![SESSION]
+                ),
+            ]),
+            Some(12)
+        );
+        assert_eq!(
+            find(vec![(13, "node /usr/local/bin/claude", vec![SESSION])]),
+            Some(13)
+        );
+    }
+
+    #[test]
+    fn gives_up_rather_than_guessing() {
+        // The only Claude Code process around may be another session's
+        assert_eq!(find(vec![(10, "claude", vec![])]), None);
+        // Other programs mentioning the session don't count
+        assert_eq!(find(vec![(14, "tail -f 0f3c9a2e", vec![SESSION])]), None);
+        assert_eq!(find(vec![]), None);
+    }
+
+    #[test]
+    fn only_claude_executables_count() {
+        let claude = |cmd: &str| is_claude(&cmd.split(' ').map(str::to_string).collect::<Vec<_>>());
+        assert!(claude("claude --resume 0f3c9a2e"));
+        assert!(claude("/usr/local/bin/claude"));
+        assert!(claude(
+            "node /usr/lib/node_modules/@anthropic-ai/claude-code/cli.js"
+        ));
+        assert!(!claude("claude-monitor"));
+        assert!(!claude("vim /home/u/claude-code-notes.md"));
+        assert!(!claude("python claude.py"));
+        assert!(!claude("node /srv/claude-code-proxy/index.js"));
+    }
+
+    #[test]
+    fn reads_lsof_fields() {
+        let open =
+            parse_lsof("p42\nfcwd\nn/home/u/repo\nftxt\nn/usr/bin/claude\nf3\nn/tmp/s.jsonl\n");
+        assert_eq!(
+            open,
+            [
+                PathBuf::from("/usr/bin/claude"),
+                PathBuf::from("/tmp/s.jsonl")
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn never_signals_a_process_group() {
+        assert!(interrupt(0).is_err());
+        assert!(interrupt(u32::MAX).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::SCHEMA_VERSION;
+use crate::state::agent::{AgentSnapshot, AgentStatus};
+use crate::state::clock::ClockSkew;
+use crate::state::sdd::SddPhase;
+use crate::state::timer::StatusTotals;
+
+/// Something that happened to the tracked agents, as returned by
+/// [`App::poll`](crate::app::App::poll) in the order it happened. Reading a
+/// session's history when it is first found replays its events too.
+/// Mirroring a `--serve` instance reports agents coming and going, status,
+/// phase and turn changes, but not single tools.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    /// A session file is now tracked as agent `id`.
+    AgentDiscovered { id: u32, session_file: PathBuf },
+    /// The agent's session ended, or carries on as another agent's.
+    AgentRemoved { id: u32, session_file: PathBuf },
+    ToolStarted {
+        id: u32,
+        tool_id: String,
+        tool_name: String,
+    },
+    /// A tool got its result. Tools cut short by the end of a turn or a
+    /// compaction never do.
+    ToolCompleted {
+        id: u32,
+        tool_id: String,
+        tool_name: String,
+        failed: bool,
+    },
+    StatusChanged {
+        id: u32,
+        from: AgentStatus,
+        to: AgentStatus,
+    },
+    SddPhaseChanged {
+        id: u32,
+        from: Option<SddPhase>,
+        to: Option<SddPhase>,
+    },
+    /// The agent finished a turn, taking `duration` when the transcript
+    /// says.
+    TurnCompleted { id: u32, duration: Option<Duration> },
+}
+
+impl AppEvent {
+    /// The agent the event is about.
+    pub fn agent_id(&self) -> u32 {
+        match self {
+            AppEvent::AgentDiscovered { id, .. }
+            | AppEvent::AgentRemoved { id, .. }
+            | AppEvent::ToolStarted { id, .. }
+            | AppEvent::ToolCompleted { id, .. }
+            | AppEvent::StatusChanged { id, .. }
+            | AppEvent::SddPhaseChanged { id, .. }
+            | AppEvent::TurnCompleted { id, .. } => *id,
+        }
+    }
+}
+
+/// Everything there is to show about the tracked agents at one moment,
+/// detached from the [`App`](crate::app::App) that took it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppSnapshot {
+    /// Sorted by ID.
+    pub agents: Vec<AgentSnapshot>,
+    /// Status time of agents no longer tracked.
+    pub retired_status_totals: StatusTotals,
+    pub clock_skew: Option<ClockSkew>,
+}
+
+impl AppSnapshot {
+    pub fn agent(&self, id: u32) -> Option<&AgentSnapshot> {
+        self.agents.iter().find(|agent| agent.id == id)
+    }
+
+    /// How many agents have `status`.
+    pub fn count(&self, status: AgentStatus) -> usize {
+        self.agents
+            .iter()
+            .filter(|agent| agent.status == status)
+            .count()
+    }
+}
+
+/// One frame published to `--connect` clients: every tracked agent, sorted by id. Sent as a single
+/// line of JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Snapshot {
+    pub schema_version: u32,
+    pub agents: Vec<AgentSnapshot>,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            agents: Vec::new(),
+        }
+    }
+}
+
+/// The agents as published to `--connect` clients.
+impl From<AppSnapshot> for Snapshot {
+    fn from(snapshot: AppSnapshot) -> Self {
+        Snapshot {
+            schema_version: SCHEMA_VERSION,
+            agents: snapshot.agents,
+        }
+    }
+}
@@ -1,14 +1,65 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{self, ProcessLister};
+use crate::api::{AppEvent, AppSnapshot, Snapshot};
+use crate::config::{self, Config, ConfigError};
+use crate::diff::{self, Bookmark, DiffView};
+use crate::notify::{Notification, Notifier};
+#[cfg(unix)]
+use crate::remote::RemoteClient;
 use crate::state::agent::{AgentState, AgentStatus};
+use crate::state::alerts;
+use crate::state::clock::{self, ClockSkew};
+use crate::state::escalation;
+use crate::state::events::{self, AgentLog, EventLog};
+use crate::state::growth::{GrowthMeter, NEAR_ZERO_BYTES_PER_MIN};
+use crate::state::pacing::{SelfStats, TickPacer};
+use crate::state::project::{self, HomeProject};
+use crate::state::replay::Replay;
+use crate::state::sdd::SddPhase;
+use crate::state::timer::{self, StatusTotals};
+use crate::ui::choreography::{self, AgentPosition, DepartingAgent, OfficeLayout};
+use crate::ui::layout::{self, SidebarExtent};
 use crate::ui::sprites::AnimState;
-use crate::watcher::discovery::{scan_sessions, SessionTracker};
-use crate::watcher::file_watcher::IncrementalReader;
-use crate::watcher::parser;
+use crate::ui::theme::Theme;
+use crate::watcher::discovery::{
+    self, scan_sessions, scan_sessions_batched, SessionFile, SessionTracker,
+};
+use crate::watcher::file_watcher::{FileWatcher, FsChange, IncrementalReader};
+use crate::watcher::format;
+use crate::watcher::parser::ResumedFrom;
+use crate::watcher::redact::Redactor;
+use crate::watcher::rules::{RuleSpec, ToolClassifier};
 
-const DORMANCY_TIMEOUT_SECS: u64 = 300;
-const SESSION_SCAN_INTERVAL: u64 = 20;
+/// An agent with a tool in flight and no activity for this long may be stuck.
+const STALL_AFTER: Duration = Duration::from_secs(120);
+/// How often to look for the transcripts of newly spawned sub-agents.
+const SIDECHAIN_SEARCH_INTERVAL: Duration = Duration::from_millis(500);
+/// Longest search the `/` input takes.
+const SEARCH_MAX_CHARS: usize = 64;
+/// With filesystem events, full scans only expire dormant sessions and
+/// catch anything the events missed.
+const WATCHED_SCAN_INTERVAL: Duration = Duration::from_secs(30);
+/// Agent count above which scale mode kicks in.
+pub const DEFAULT_SCALE_THRESHOLD: usize = 30;
+/// How long a footer toast stays visible.
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+/// A session scan older than this is flagged in the footer.
+const SCAN_STALE_AFTER: Duration = Duration::from_secs(10);
+/// Same, when filesystem events make full scans rare.
+const WATCHED_SCAN_STALE_AFTER: Duration = Duration::from_secs(60);
+/// Weight of the newest interval in the smoothed tick interval.
+const TICK_SMOOTHING: f64 = 0.2;
+/// Files read per tick in scale mode (round-robin).
+const SCALE_FILES_PER_TICK: usize = 25;
+/// Lines read per file per tick in scale mode.
+const SCALE_LINES_PER_FILE: usize = 200;
 
 pub struct App {
     pub agents: HashMap<u32, AgentState>,
@@ -16,16 +67,239 @@ pub struct App {
     pub session_tracker: SessionTracker,
     pub reader: IncrementalReader,
     pub claude_dir: PathBuf,
+    /// Additional Claude data directories discovered from settings/env.
+    pub extra_roots: Vec<PathBuf>,
     pub should_quit: bool,
-    pub tick_count: u64,
+    /// When the app was created, the start of its animations' clock.
+    created_at: Instant,
+    /// Time from `created_at` to the start of the latest tick. Sprite frames
+    /// are picked by it, so they keep their pace whatever the tick rate.
+    pub anim_elapsed: Duration,
     pub focus: PanelFocus,
+    pub view_mode: ViewMode,
     pub sidebar_scroll: u16,
+    /// Page of desks the office shows when they don't all fit.
+    pub office_page: usize,
+    /// The selected agent's event log is shown below the sidebar.
+    pub show_event_log: bool,
+    /// Lines scrolled back from the newest event; 0 follows new events.
+    pub event_log_scroll: u16,
+    /// Time typed so far after `t`, while the jump-to-time input is open.
+    pub time_input: Option<String>,
+    /// Agent whose session the footer asks to confirm killing.
+    pub kill_prompt: Option<u32>,
+    /// Query typed so far after `/`, while the search input is open. Keys
+    /// go to the query, not to the bindings, until Enter or Esc.
+    pub search_input: Option<String>,
+    /// Applied search, lowercased: only agents matching it are listed.
+    /// Empty lists every agent.
+    pub search_query: String,
+    /// State saved with the bookmark key, to compare against.
+    pub bookmark: Option<Bookmark>,
+    /// What changed since the bookmark, while the overlay is open.
+    pub diff_view: Option<DiffView>,
+    /// Status time of agents that have already been removed.
+    pub retired_status_totals: StatusTotals,
+    pub scale_threshold: usize,
+    /// `--scale-threshold`, which beats the config file across reloads.
+    pub cli_scale_threshold: Option<usize>,
+    /// Where each agent's character is in the office, walking or seated.
+    pub office: OfficeLayout,
+    /// Agents whose session ended, walking out of the office. Their state
+    /// is already gone.
+    pub departing: Vec<DepartingAgent>,
+    /// Render ASCII fallbacks instead of emoji icons.
+    pub ascii_icons: bool,
+    /// Styles the UI draws with, from the config and `--theme`.
+    pub theme: Theme,
+    /// When each agent was last looked at (selected); activity after this
+    /// marks the agent as unseen.
+    pub last_seen_tick: HashMap<u32, Instant>,
+    pub sidebar_filter: SidebarFilter,
+    /// The project this dashboard was launched for, if the session hook said.
+    pub home_project: Option<HomeProject>,
+    /// Persistent header warning, e.g. another instance watching the same directory.
+    pub watcher_warning: Option<String>,
+    /// Live record timestamps disagree with the local clock by this much.
+    pub clock_skew: Option<ClockSkew>,
+    /// The clock skew toast was already shown this run.
+    skew_warned: bool,
+    /// The first-run overlay is up; the next key press dismisses it.
+    pub show_welcome: bool,
+    /// The last successfully applied config.
+    pub config: Config,
+    pub toast: Option<Toast>,
+    pub source: StateSource,
+    pub event_log: EventLog,
+    /// Session file growth per agent, from its first catch-up read onwards.
+    pub growth: HashMap<u32, GrowthMeter>,
+    /// Agents still catching up on their file's existing content.
+    replays: HashMap<u32, Replay>,
+    /// Agents whose file opens with a summary of another session's last
+    /// message, by that message's UUID, until the other session is found.
+    pending_resumes: HashMap<String, u32>,
+    /// Masks secrets in tool status strings, rebuilt when the config changes.
+    pub redactor: Redactor,
+    /// The config's `[[rules]]`, rebuilt when the config changes.
+    pub classifier: ToolClassifier,
+    /// Smoothed time between ticks, shown as FPS in the footer.
+    pub tick_interval: Option<Duration>,
+    /// How long the last tick took.
+    pub tick_cost: Option<Duration>,
+    /// Main loop durations and overruns over the run.
+    pub self_stats: SelfStats,
+    /// Stretches the tick interval while the loop can't keep up.
+    pub pacer: TickPacer,
+    /// When session files were last scanned.
+    pub last_scan_at: Option<Instant>,
+    /// When sub-agent transcripts were last looked for.
+    last_sidechain_search: Option<Instant>,
+    tick_started_at: Option<Instant>,
+    /// Filesystem events for session files; None means poll every file.
+    fs_watcher: Option<FileWatcher>,
+    /// Session files with unread data, when driven by filesystem events or
+    /// size polling.
+    changed_files: HashSet<PathBuf>,
+    /// Session file sizes at the last scan, with `--remote-friendly`.
+    session_sizes: HashMap<PathBuf, u64>,
+    read_cursor: usize,
+    /// When the previous tick ran, to spot suspend/resume jumps.
+    last_tick_at: Option<Instant>,
+    /// Scan sessions on the next tick instead of waiting for the interval.
+    rescan_requested: bool,
+    /// Desktop notifications waiting to be sent, with `--notify`.
+    notifier: Notifier,
+    /// Events since the last [`poll`](Self::poll), oldest first.
+    events: Vec<AppEvent>,
+    /// Each agent's status and SDD phase as last reported in `events`.
+    reported: HashMap<u32, (AgentStatus, Option<SddPhase>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A short-lived message shown in the footer.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub level: ToastLevel,
+    pub shown_at: Instant,
+}
+
+/// A status item on the right side of the footer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FooterSegmentKind {
+    Fps,
+    TickTime,
+    ScanStaleness,
+    /// The tick interval is stretched because loops overran it.
+    Slowdown,
+    Toast,
+}
+
+impl FooterSegmentKind {
+    pub const ALL: [FooterSegmentKind; 5] = [
+        FooterSegmentKind::Fps,
+        FooterSegmentKind::TickTime,
+        FooterSegmentKind::ScanStaleness,
+        FooterSegmentKind::Slowdown,
+        FooterSegmentKind::Toast,
+    ];
+
+    /// Name used in the config's `footer.hide` list.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FooterSegmentKind::Fps => "fps",
+            FooterSegmentKind::TickTime => "tick_time",
+            FooterSegmentKind::ScanStaleness => "scan_staleness",
+            FooterSegmentKind::Slowdown => "slowdown",
+            FooterSegmentKind::Toast => "toast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// One footer segment. When the footer is too narrow, the lowest
+/// priority segments are dropped first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FooterSegment {
+    pub kind: FooterSegmentKind,
+    pub text: String,
+    pub priority: u8,
+    /// Colored like a toast of this level; dim when None.
+    pub level: Option<ToastLevel>,
+}
+
+/// Which agents the sidebar and the office list. Cycled with `u`; the
+/// status filters are toggled with their own keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidebarFilter {
+    #[default]
+    All,
+    Unseen,
+    NeedsAttention,
+    /// Working agents only.
+    Active,
+    /// Agents waiting on the user, for input or otherwise.
+    Waiting,
+}
+
+impl SidebarFilter {
+    /// The next filter `u` cycles to. The status filters aren't part of the
+    /// cycle; from one of them it starts over.
+    pub fn next(self) -> Self {
+        match self {
+            SidebarFilter::All => SidebarFilter::Unseen,
+            SidebarFilter::Unseen => SidebarFilter::NeedsAttention,
+            SidebarFilter::NeedsAttention => SidebarFilter::All,
+            SidebarFilter::Active | SidebarFilter::Waiting => SidebarFilter::Unseen,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SidebarFilter::All => "all",
+            SidebarFilter::Unseen => "unseen",
+            SidebarFilter::NeedsAttention => "needs attention",
+            SidebarFilter::Active => "active",
+            SidebarFilter::Waiting => "waiting",
+        }
+    }
+}
+
+/// Where the tracked agents come from.
+pub enum StateSource {
+    /// Watch session files in this process.
+    Local,
+    /// Mirror the snapshots a `--serve` instance publishes.
+    #[cfg(unix)]
+    Remote(RemoteClient),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PanelFocus {
     Office,
     Sidebar,
+    /// The selected agent's event log, when shown.
+    EventLog,
+}
+
+/// What the body between the header and the footer shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ViewMode {
+    /// The office and the sidebar.
+    #[default]
+    Office,
+    /// Per-agent statistics over each session.
+    Stats,
 }
 
 impl App {
@@ -33,230 +307,3409 @@ impl App {
         Self {
             agents: HashMap::new(),
             selected_agent: None,
-            session_tracker: SessionTracker::new(),
+            session_tracker: SessionTracker::default(),
             reader: IncrementalReader::new(),
             claude_dir,
+            extra_roots: Vec::new(),
             should_quit: false,
-            tick_count: 0,
+            created_at: Instant::now(),
+            anim_elapsed: Duration::ZERO,
             focus: PanelFocus::Sidebar,
+            view_mode: ViewMode::default(),
             sidebar_scroll: 0,
+            office_page: 0,
+            show_event_log: false,
+            event_log_scroll: 0,
+            time_input: None,
+            kill_prompt: None,
+            search_input: None,
+            search_query: String::new(),
+            bookmark: None,
+            diff_view: None,
+            retired_status_totals: StatusTotals::default(),
+            scale_threshold: DEFAULT_SCALE_THRESHOLD,
+            cli_scale_threshold: None,
+            office: OfficeLayout::default(),
+            departing: Vec::new(),
+            ascii_icons: false,
+            theme: Theme::default(),
+            last_seen_tick: HashMap::new(),
+            sidebar_filter: SidebarFilter::default(),
+            home_project: None,
+            watcher_warning: None,
+            clock_skew: None,
+            skew_warned: false,
+            show_welcome: false,
+            config: Config::default(),
+            toast: None,
+            source: StateSource::Local,
+            event_log: EventLog::default(),
+            growth: HashMap::new(),
+            replays: HashMap::new(),
+            pending_resumes: HashMap::new(),
+            redactor: Redactor::default(),
+            classifier: ToolClassifier::default(),
+            tick_interval: None,
+            tick_cost: None,
+            self_stats: SelfStats::default(),
+            pacer: TickPacer::new(config::DEFAULT_TICK_RATE),
+            last_scan_at: None,
+            last_sidechain_search: None,
+            tick_started_at: None,
+            fs_watcher: None,
+            changed_files: HashSet::new(),
+            session_sizes: HashMap::new(),
+            read_cursor: 0,
+            last_tick_at: None,
+            rescan_requested: false,
+            notifier: Notifier::default(),
+            events: Vec::new(),
+            reported: HashMap::new(),
         }
     }
 
+    /// Advance one tick, as [`tick`](Self::tick) does, and return what
+    /// happened since the last call, oldest first.
+    pub fn poll(&mut self) -> Vec<AppEvent> {
+        self.advance();
+        std::mem::take(&mut self.events)
+    }
+
+    /// Advance one tick: scan for sessions when the config's scan interval
+    /// says (or [`scan_now`](Self::scan_now) asked), then read what was
+    /// written to them. Use [`poll`](Self::poll) to see what happened.
     pub fn tick(&mut self) {
-        self.tick_count += 1;
+        let events = self.poll();
+        self.handle_events(&events, Instant::now());
+    }
+
+    /// Follow what [`poll`](Self::poll) returned as the dashboard does:
+    /// agents coming and going go to the event log, and status changes
+    /// worth it to the notifier when notifications are on.
+    pub fn handle_events(&mut self, events: &[AppEvent], now: Instant) {
+        for event in events {
+            let (id, message) = match event {
+                AppEvent::AgentDiscovered { id, session_file } => {
+                    (*id, format!("Tracking {}", session_file.display()))
+                }
+                AppEvent::AgentRemoved { id, session_file } => {
+                    (*id, format!("Stopped tracking {}", session_file.display()))
+                }
+                _ => continue,
+            };
+            self.event_log.push(SystemTime::now(), Some(id), message);
+        }
+        if self.config.notify {
+            self.notify_status_changes(events, now);
+        }
+    }
+
+    fn advance(&mut self) {
+        let started = Instant::now();
+        self.anim_elapsed = started.saturating_duration_since(self.created_at);
+        if let Some(prev) = self.tick_started_at.replace(started) {
+            let interval = started.saturating_duration_since(prev);
+            // A suspend would skew the average for a long while
+            if clock::clock_jump(prev, started).is_none() {
+                self.tick_interval = Some(match self.tick_interval {
+                    Some(avg) => {
+                        avg.mul_f64(1.0 - TICK_SMOOTHING) + interval.mul_f64(TICK_SMOOTHING)
+                    }
+                    None => interval,
+                });
+            }
+        }
+
+        match &mut self.source {
+            StateSource::Local => self.tick_local(),
+            #[cfg(unix)]
+            StateSource::Remote(client) => {
+                let now = Instant::now();
+                if let Some(snapshot) = client.poll(now) {
+                    self.apply_snapshot(snapshot, now);
+                }
+            }
+        }
+
+        // Whatever happens on the selected agent is being looked at
+        if let Some(id) = self.selected_agent {
+            self.last_seen_tick.insert(id, Instant::now());
+        }
+
+        // Changes made between ticks, by keys say
+        for (&id, reported) in &mut self.reported {
+            if let Some(agent) = self.agents.get(&id) {
+                push_changes(id, agent, reported, &mut self.events);
+            }
+        }
+        let now = Instant::now();
+        self.update_escalations(now);
+        for agent in self.agents.values_mut() {
+            agent.activity.advance(now);
+        }
+        // The kill prompt only stands while its agent is selected
+        if self.kill_prompt.is_some() && self.kill_prompt != self.selected_agent {
+            self.kill_prompt = None;
+        }
+        self.update_office();
 
-        // Every 20 ticks (~2s at 10fps): scan sessions, create/remove agents
-        if self.tick_count % SESSION_SCAN_INTERVAL == 0 {
-            let sessions = scan_sessions(&self.claude_dir);
-            let (new_sessions, removed_ids) = self.session_tracker.update(&sessions);
+        self.refresh_display_caches();
+        self.tick_cost = Some(started.elapsed());
+    }
 
-            // Create agents for new sessions
-            for (id, path) in new_sessions {
-                self.agents.insert(id, AgentState::new(id, path));
+    /// Scan and read session files on disk.
+    fn tick_local(&mut self) {
+        // A huge gap since the last tick means we were suspended; don't let
+        // elapsed-based checks see the gap as idle time
+        let now = Instant::now();
+        if let Some(prev) = self.last_tick_at {
+            if let Some(gap) = clock::clock_jump(prev, now) {
+                self.recover_from_clock_jump(prev, gap, now);
             }
+        }
+        self.last_tick_at = Some(now);
 
-            // Remove agents for gone sessions
-            for id in &removed_ids {
-                self.agents.remove(id);
-                self.reader.remove(
-                    &self
-                        .agents
-                        .get(id)
-                        .map(|a| a.session_file.clone())
-                        .unwrap_or_default(),
-                );
-                // Deselect if the selected agent was removed
-                if self.selected_agent == Some(*id) {
-                    self.selected_agent = None;
+        // Session files the OS reported as created, changed, or removed
+        let changes = self
+            .fs_watcher
+            .as_ref()
+            .map(FileWatcher::drain)
+            .unwrap_or_default();
+        for change in changes {
+            match change {
+                // Sub-agent transcripts are followed by `read_sidechains`
+                FsChange::Created(path) | FsChange::Modified(path)
+                    if discovery::is_sidechain_file(&path) => {}
+                // Other projects are out of scope with `--project`
+                FsChange::Created(path) | FsChange::Modified(path)
+                    if !discovery::in_project(&path, self.config.project.as_deref()) => {}
+                FsChange::Created(path) | FsChange::Modified(path) => {
+                    if let Some(id) = self.session_tracker.add(&path) {
+                        self.add_agent(id, path.clone());
+                    }
+                    self.changed_files.insert(path);
                 }
+                FsChange::Removed(path) => {
+                    if let Some(id) = self.session_tracker.remove(&path) {
+                        self.remove_agent(id);
+                    }
+                    self.changed_files.remove(&path);
+                }
+                FsChange::Rescan => self.rescan_requested = true,
             }
         }
 
+        // Every `scan_interval` (2s by default): scan sessions, create/remove agents
+        let scan_due = self
+            .last_scan_at
+            .is_none_or(|at| now.saturating_duration_since(at) >= self.scan_interval());
+        let requested = std::mem::take(&mut self.rescan_requested);
+        if scan_due || requested {
+            self.scan_sessions(now);
+        }
+
         // Every tick: read new JSONL lines for each agent and process them
-        let agent_files: Vec<(u32, PathBuf)> = self
-            .agents
-            .iter()
-            .map(|(&id, agent)| (id, agent.session_file.clone()))
+        let line_budget = if self.scale_mode() {
+            SCALE_LINES_PER_FILE
+        } else {
+            usize::MAX
+        };
+        let files = self.files_to_read();
+        self.read_files(files, line_budget);
+
+        // Check for dormant agents (300s timeout by default)
+        self.mark_dormant();
+    }
+
+    /// One synchronous pass without a UI, for `--status`: scan for sessions
+    /// and read every file to its end, from the start the first time.
+    /// Unlike `tick`, neither the scan interval nor the scale-mode budgets
+    /// apply.
+    pub fn poll_once(&mut self) {
+        self.scan_now();
+        let files = self
+            .sorted_agent_ids()
+            .into_iter()
+            .filter_map(|id| self.agents.get(&id).map(|a| (id, a.session_file.clone())))
+            .collect();
+        self.read_files(files, usize::MAX);
+        self.mark_dormant();
+        self.refresh_display_caches();
+    }
+
+    /// Scan for sessions on the next tick instead of waiting for the scan
+    /// interval.
+    pub fn request_rescan(&mut self) {
+        self.rescan_requested = true;
+    }
+
+    /// Look for new and ended sessions right away, instead of waiting for
+    /// the scan interval. New sessions are read from the next tick on.
+    pub fn scan_now(&mut self) {
+        self.scan_sessions(Instant::now());
+    }
+
+    /// Scan every root for sessions, then create and remove agents to match.
+    fn scan_sessions(&mut self, now: Instant) {
+        self.last_scan_at = Some(now);
+        // Keep sessions around at least until they can turn dormant
+        let max_age = self
+            .config
+            .dormancy_timeout
+            .max(discovery::DORMANCY_TIMEOUT);
+        let roots: Vec<PathBuf> = std::iter::once(&self.claude_dir)
+            .chain(&self.extra_roots)
+            .cloned()
             .collect();
+        let project = self.config.project.clone();
+        let sessions: Vec<PathBuf> = if self.config.remote_friendly {
+            let scanned = roots
+                .iter()
+                .flat_map(|root| scan_sessions_batched(root, max_age))
+                .filter(|session| discovery::in_project(&session.path, project.as_deref()))
+                .collect();
+            self.note_session_sizes(scanned)
+        } else {
+            let scanned = roots
+                .iter()
+                .flat_map(|root| scan_sessions(root, max_age))
+                .collect();
+            discovery::filter_to_project(scanned, project.as_deref())
+        };
+        let (new_sessions, removed_ids) = self.session_tracker.update(&sessions);
+
+        // Create agents for new sessions
+        for (id, path) in new_sessions {
+            self.add_agent(id, path);
+        }
+
+        // Remove agents for gone sessions
+        for id in &removed_ids {
+            self.remove_agent(*id);
+        }
+        self.prune_offsets();
+
+        // Events may have been missed, so check every file once
+        if self.fs_watcher.is_some() {
+            self.changed_files = sessions.into_iter().collect();
+        }
+    }
+
+    /// Apply new records from `files` to their agents, reading at most
+    /// `line_budget` lines per file.
+    fn read_files(&mut self, files: Vec<(u32, PathBuf)>, line_budget: usize) {
+        let now = Instant::now();
+        let mut newest_live_record = None;
+        for (mut id, path) in files {
+            // Merged into a resumed session earlier in the loop
+            if self
+                .agents
+                .get(&id)
+                .is_none_or(|agent| agent.session_file != path)
+            {
+                continue;
+            }
+            // The dialect is sniffed from the file's first record, then kept
+            let mut format = self.agents.get(&id).and_then(|agent| agent.format);
+            let batch = self.reader.read_batch_as(&path, line_budget, &mut format);
+            if let Some(agent) = self.agents.get_mut(&id) {
+                agent.format = format;
+            }
+            let format = format.unwrap_or(format::DEFAULT_FORMAT);
+            if batch.at_eof {
+                self.changed_files.remove(&path);
+                // Only a caught-up file's last record was just written
+                if self.growth.contains_key(&id) {
+                    newest_live_record = batch
+                        .records
+                        .iter()
+                        .rev()
+                        .find_map(|record| format.timestamp(record))
+                        .max(newest_live_record);
+                }
+            }
+            let catching_up = !self.growth.contains_key(&id);
+            match self.growth.get_mut(&id) {
+                Some(meter) => meter.record(batch.bytes_read, now),
+                // Whatever was read before catching up is history, not growth
+                None if batch.at_eof => {
+                    self.growth.insert(id, GrowthMeter::new(now));
+                }
+                None => {}
+            }
+            for record in &batch.records {
+                // A resumed session carries on at the desk of the one it
+                // resumed. A `parentSessionId` names it outright; a summary
+                // only counts ahead of the file's first message, and waits
+                // in `pending_resumes` for the session to be confirmed.
+                if catching_up {
+                    match format.resumed_from(record) {
+                        Some(ResumedFrom::Session(session_id)) => {
+                            if let Some(earlier) = self.resumed_agent(id, &session_id) {
+                                self.merge_resumed_session(id, earlier);
+                                id = earlier;
+                            }
+                        }
+                        Some(ResumedFrom::Message(leaf))
+                            if self
+                                .agents
+                                .get(&id)
+                                .is_some_and(|agent| agent.last_uuid.is_none()) =>
+                        {
+                            self.pending_resumes.insert(leaf, id);
+                        }
+                        _ => {}
+                    }
+                }
+                if let Some(uuid) = format.uuid(record) {
+                    // Its own message, summarized before a compaction
+                    if self.pending_resumes.get(uuid) == Some(&id) {
+                        self.pending_resumes.remove(uuid);
+                    }
+                    if let Some(agent) = self.agents.get_mut(&id) {
+                        agent.last_uuid = Some(uuid.to_string());
+                    }
+                }
+                if format.is_compaction(record) {
+                    if let Some(agent) = self.agents.get_mut(&id) {
+                        agent.note_compaction();
+                    }
+                }
+
+                let at = format.timestamp(record);
+                let record_at = at.map(|at| self.record_instant(at, now));
+                if let Some(agent) = self.agents.get_mut(&id) {
+                    agent.first_record_at = agent.first_record_at.or(record_at);
+                    agent.log.set_record_time(at);
+                    // Time from a prompt to the first response of the turn
+                    if format.is_user_prompt(record) {
+                        agent.latency.prompt(at);
+                        agent.usage.start_turn();
+                        if let Some(text) = format.prompt_text(record) {
+                            agent.note_prompt(&text);
+                        }
+                    } else if format.is_response(record) {
+                        agent.latency.response(at);
+                    } else if format.is_turn_end(record) {
+                        agent.latency.turn_end();
+                    }
+                    if let Some(delta) = format.usage(record) {
+                        agent.usage.record(delta);
+                    }
+                }
 
-        for (id, path) in agent_files {
-            let records = self.reader.read_new_lines(&path);
-            for record in &records {
                 // Extract tool uses and add them to the agent
-                let tool_uses = parser::extract_tool_uses(record);
+                let tool_uses = format.tool_uses(record, &self.redactor, &self.classifier);
+                if catching_up {
+                    self.replays
+                        .entry(id)
+                        .or_default()
+                        .observe(format, record, &tool_uses);
+                }
+                let mut tool_events = tool_uses.len();
+                let started_at = record_at.unwrap_or(now);
                 for tool in tool_uses {
+                    let Some(agent) = self.agents.get_mut(&id) else {
+                        continue;
+                    };
+                    // Flag the first live use of a sensitive tool past the
+                    // session's start, timed from its first record
+                    let first_use = agent.record_tool_name(&tool.tool_name) && !catching_up;
+                    let session_start = agent.first_record_at.unwrap_or(agent.started_at);
+                    let alert = alerts::first_use_alert(
+                        id,
+                        &tool,
+                        first_use,
+                        session_start.elapsed(),
+                        &self.config.alerts.sensitive_tools,
+                    );
+                    let started = AppEvent::ToolStarted {
+                        id,
+                        tool_id: tool.tool_id.clone(),
+                        tool_name: tool.tool_name.clone(),
+                    };
+                    agent.add_tool_started_at(tool, started_at);
+                    self.events.push(started);
+                    if let Some(message) = alert {
+                        self.event_log
+                            .push(SystemTime::now(), Some(id), message.clone());
+                        self.show_toast(message, ToastLevel::Warning);
+                    }
+                }
+
+                // Name each Task's sub-agent and remember its prompt
+                for spawn in format.task_spawns(record) {
                     if let Some(agent) = self.agents.get_mut(&id) {
-                        agent.add_tool(tool);
+                        agent.describe_sub_agent(&spawn);
                     }
                 }
 
                 // Extract tool results and remove completed tools
-                let tool_results = parser::extract_tool_results(record);
-                for tool_id in tool_results {
+                let tool_results = format.tool_results(record);
+                tool_events += tool_results.len();
+                for result in tool_results {
+                    if let Some(agent) = self.agents.get_mut(&id) {
+                        if let Some(tool) = agent
+                            .active_tools
+                            .iter()
+                            .find(|tool| tool.tool_id == result.tool_id)
+                        {
+                            self.events.push(AppEvent::ToolCompleted {
+                                id,
+                                tool_id: result.tool_id.clone(),
+                                tool_name: tool.tool_name.clone(),
+                                failed: result.is_error,
+                            });
+                        }
+                        agent.note_tool_result(&result);
+                        agent.remove_tool(&result.tool_id);
+                    }
+                }
+                // History read while catching up happened at other times
+                if !catching_up && tool_events > 0 {
                     if let Some(agent) = self.agents.get_mut(&id) {
-                        agent.remove_tool(&tool_id);
+                        agent.activity.record(tool_events as u32, now);
                     }
                 }
 
                 // Extract text for prompt summary
-                if let Some(text) = parser::extract_text(record) {
+                if let Some(text) = format.text(record) {
                     if let Some(agent) = self.agents.get_mut(&id) {
                         agent.set_prompt_summary(&text);
+                        agent.log_text(&text);
                     }
                 }
 
                 // Check for turn end
-                if parser::is_turn_end(record) {
+                if format.is_turn_end(record) {
                     if let Some(agent) = self.agents.get_mut(&id) {
+                        let duration = format.turn_duration(record);
+                        agent.finish_turn(duration);
                         agent.mark_waiting();
+                        self.events.push(AppEvent::TurnCompleted { id, duration });
                     }
                 }
+                self.note_changes(id);
+            }
+
+            // Caught up: the history read so far says where the session stands
+            if catching_up && batch.at_eof {
+                let summary = self.replays.remove(&id).unwrap_or_default().finish();
+                if let Some(agent) = self.agents.get_mut(&id) {
+                    let idle_for = idle_since_written(&path, SystemTime::now());
+                    agent.apply_replay(summary, idle_for, now);
+                }
+                self.note_changes(id);
             }
         }
 
-        // Check for dormant agents (300s timeout)
-        for agent in self.agents.values_mut() {
-            if agent.status != AgentStatus::Dormant && agent.is_dormant(DORMANCY_TIMEOUT_SECS) {
-                agent.status = AgentStatus::Dormant;
+        self.resolve_resumes(now);
+        self.read_sidechains(line_budget);
+        if newest_live_record.is_some() {
+            self.check_clock_skew(newest_live_record, SystemTime::now());
+        }
+    }
+
+    /// Agents blocked on a question stay as they are however long it sits
+    /// unanswered: going dormant would hide that they still need input.
+    fn mark_dormant(&mut self) {
+        let dormancy_secs = self.config.dormancy_timeout.as_secs();
+        for id in self.sorted_agent_ids() {
+            let Some(agent) = self.agents.get_mut(&id) else {
+                continue;
+            };
+            if !matches!(agent.status, AgentStatus::Dormant | AgentStatus::NeedsInput)
+                && agent.is_dormant(dormancy_secs)
+            {
+                agent.set_status(AgentStatus::Dormant);
+                self.note_changes(id);
             }
         }
     }
 
-    pub fn select_agent(&mut self, num: u32) {
-        if self.agents.contains_key(&num) {
-            self.selected_agent = Some(num);
+    /// Start tracking the session at `path` as agent `id`.
+    fn add_agent(&mut self, id: u32, path: PathBuf) {
+        let mut agent = AgentState::new(id, path.clone());
+        agent.set_project_name(project::session_project_name(&path));
+        self.reported.insert(id, (agent.status, agent.sdd_phase));
+        self.agents.insert(id, agent);
+        self.events.push(AppEvent::AgentDiscovered {
+            id,
+            session_file: path,
+        });
+    }
+
+    /// Report how agent `id`'s status and SDD phase changed since they were
+    /// last reported.
+    fn note_changes(&mut self, id: u32) {
+        if let (Some(agent), Some(reported)) = (self.agents.get(&id), self.reported.get_mut(&id)) {
+            push_changes(id, agent, reported, &mut self.events);
         }
     }
 
-    pub fn toggle_focus(&mut self) {
-        self.focus = match self.focus {
-            PanelFocus::Office => PanelFocus::Sidebar,
-            PanelFocus::Sidebar => PanelFocus::Office,
-        };
+    /// Queue notifications for the status changes in `events`. Agents still
+    /// reading their history, including ones found since, replay old
+    /// changes that aren't news.
+    fn notify_status_changes(&mut self, events: &[AppEvent], now: Instant) {
+        let found: HashSet<u32> = events
+            .iter()
+            .filter_map(|event| match event {
+                AppEvent::AgentDiscovered { id, .. } => Some(*id),
+                _ => None,
+            })
+            .collect();
+        let local = matches!(self.source, StateSource::Local);
+        for event in events {
+            let AppEvent::StatusChanged { id, from, to } = *event else {
+                continue;
+            };
+            if found.contains(&id) || (local && !self.growth.contains_key(&id)) {
+                continue;
+            }
+            if let Some(agent) = self.agents.get(&id) {
+                self.notifier.status_changed(agent, from, to, now);
+            }
+        }
     }
 
-    pub fn scroll_up(&mut self) {
-        self.sidebar_scroll = self.sidebar_scroll.saturating_sub(1);
+    /// Bytes per minute appended to the agent's session file, once known.
+    pub fn growth_rate(&self, id: u32, now: Instant) -> Option<f64> {
+        self.growth
+            .get(&id)
+            .map(|meter| meter.bytes_per_minute(now))
     }
 
-    pub fn scroll_down(&mut self) {
-        self.sidebar_scroll = self.sidebar_scroll.saturating_add(1);
+    /// Whether the agent's file has been read to its end and isn't being
+    /// appended to.
+    fn stopped_growing(&self, id: u32, now: Instant) -> bool {
+        self.growth_rate(id, now)
+            .is_some_and(|rate| rate < NEAR_ZERO_BYTES_PER_MIN)
     }
 
-    pub fn sorted_agent_ids(&self) -> Vec<u32> {
-        let mut ids: Vec<u32> = self.agents.keys().copied().collect();
-        ids.sort();
-        ids
+    /// Heuristic for a stuck agent: a tool has been in flight with no other
+    /// activity for a while, and the session file isn't growing either.
+    pub fn looks_stalled(&self, id: u32, now: Instant) -> bool {
+        let Some(agent) = self.agents.get(&id) else {
+            return false;
+        };
+        agent.status == AgentStatus::Active
+            && !agent.active_tools.is_empty()
+            && now.saturating_duration_since(agent.last_activity) >= STALL_AFTER
+            && self.stopped_growing(id, now)
     }
 
-    pub fn agent_anim_state(&self, id: u32) -> AnimState {
-        match self.agents.get(&id) {
-            Some(agent) => {
-                if agent.active_tools.is_empty() {
-                    AnimState::Idle
-                } else if agent.active_tools.iter().any(|t| t.is_reading) {
-                    AnimState::Reading
-                } else {
-                    AnimState::Typing
+    /// A read-only copy of the tracked agents as they are at `now`.
+    pub fn app_snapshot(&self, now: Instant) -> AppSnapshot {
+        AppSnapshot {
+            agents: self
+                .sorted_agent_ids()
+                .into_iter()
+                .filter_map(|id| self.agents.get(&id))
+                .map(|agent| agent.snapshot(now))
+                .collect(),
+            retired_status_totals: self.retired_status_totals,
+            clock_skew: self.clock_skew,
+        }
+    }
+
+    /// Every tracked agent, as published to `--connect` clients.
+    pub fn snapshot(&self, now: Instant) -> Snapshot {
+        self.app_snapshot(now).into()
+    }
+
+    /// Replace the tracked agents with a snapshot from a `--serve` instance.
+    pub fn apply_snapshot(&mut self, snapshot: Snapshot, now: Instant) {
+        let before = std::mem::replace(
+            &mut self.agents,
+            snapshot
+                .agents
+                .into_iter()
+                .map(|agent| (agent.id, AgentState::from_snapshot(agent, now)))
+                .collect(),
+        );
+        let mut ids: Vec<u32> = before.keys().chain(self.agents.keys()).copied().collect();
+        ids.sort_unstable();
+        ids.dedup();
+        for id in ids {
+            // An ID now on another session is one agent gone and another new
+            match (before.get(&id), self.agents.get(&id)) {
+                (Some(old), Some(new)) if old.session_file == new.session_file => {
+                    let turns = new.turns.completed().saturating_sub(old.turns.completed());
+                    // Levels already passed don't notify again
+                    let escalation = old.escalation;
+                    if let Some(agent) = self.agents.get_mut(&id) {
+                        agent.escalation = escalation;
+                    }
+                    for _ in 0..turns {
+                        self.events
+                            .push(AppEvent::TurnCompleted { id, duration: None });
+                    }
+                    self.note_changes(id);
+                }
+                (old, new) => {
+                    if let Some(old) = old {
+                        self.events.push(AppEvent::AgentRemoved {
+                            id,
+                            session_file: old.session_file.clone(),
+                        });
+                        self.reported.remove(&id);
+                    }
+                    if let Some(new) = new {
+                        self.reported.insert(id, (new.status, new.sdd_phase));
+                        self.events.push(AppEvent::AgentDiscovered {
+                            id,
+                            session_file: new.session_file.clone(),
+                        });
+                    }
                 }
             }
-            None => AnimState::Idle,
+        }
+        self.last_seen_tick
+            .retain(|id, _| self.agents.contains_key(id));
+        if self
+            .selected_agent
+            .is_some_and(|id| !self.agents.contains_key(&id))
+        {
+            self.selected_agent = None;
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Socket being mirrored, when agents come from a `--serve` instance.
+    pub fn remote_socket(&self) -> Option<&Path> {
+        match &self.source {
+            StateSource::Local => None,
+            #[cfg(unix)]
+            StateSource::Remote(client) => Some(client.socket()),
+        }
+    }
 
-    #[test]
-    fn new_app_has_empty_agents() {
-        let app = App::new(PathBuf::from("/tmp"));
-        assert!(app.agents.is_empty());
-        assert!(app.selected_agent.is_none());
-        assert!(!app.should_quit);
+    /// Banner for a remote source that isn't connected.
+    pub fn connection_banner(&self, now: Instant) -> Option<String> {
+        match &self.source {
+            StateSource::Local => None,
+            #[cfg(unix)]
+            StateSource::Remote(client) => client.banner(now),
+        }
     }
 
-    #[test]
-    fn toggle_focus() {
-        let mut app = App::new(PathBuf::from("/tmp"));
-        assert_eq!(app.focus, PanelFocus::Sidebar);
-        app.toggle_focus();
-        assert_eq!(app.focus, PanelFocus::Office);
-        app.toggle_focus();
-        assert_eq!(app.focus, PanelFocus::Sidebar);
+    /// Treat a suspend/resume as a discontinuity: re-seed activity times,
+    /// drop the gap from status timers, and rescan sessions right away.
+    pub fn recover_from_clock_jump(&mut self, gap_start: Instant, gap: Duration, now: Instant) {
+        let wall_now = SystemTime::now();
+        for agent in self.agents.values_mut() {
+            let file_age = std::fs::metadata(&agent.session_file)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|mtime| wall_now.duration_since(mtime).ok());
+            agent.last_activity = clock::reseeded_activity(agent.last_activity, gap, file_age, now);
+            agent.status_timer.skip_gap(gap_start, now);
+        }
+        // Keep "seen" marks consistent with the shifted activity times
+        for seen in self.last_seen_tick.values_mut() {
+            *seen = seen.checked_add(gap).unwrap_or(now).min(now);
+        }
+        self.rescan_requested = true;
     }
 
-    #[test]
-    fn scroll_bounds() {
-        let mut app = App::new(PathBuf::from("/tmp"));
-        app.scroll_up();
-        assert_eq!(app.sidebar_scroll, 0);
-        app.scroll_down();
-        assert_eq!(app.sidebar_scroll, 1);
+    /// The instant a record stamped `at` was written, going by this
+    /// machine's clock corrected for any skew. Never later than `now`.
+    fn record_instant(&self, at: SystemTime, now: Instant) -> Instant {
+        let wall = SystemTime::now();
+        let wall = self
+            .clock_skew
+            .map_or(wall, |skew| skew.to_record_time(wall));
+        now.checked_sub(clock::clamped_age(wall, at)).unwrap_or(now)
     }
 
-    #[test]
-    fn sorted_agent_ids() {
-        let mut app = App::new(PathBuf::from("/tmp"));
-        app.agents
-            .insert(3, AgentState::new(3, PathBuf::from("/tmp/3.jsonl")));
-        app.agents
-            .insert(1, AgentState::new(1, PathBuf::from("/tmp/1.jsonl")));
-        assert_eq!(app.sorted_agent_ids(), vec![1, 3]);
+    /// Compare the newest live record's timestamp with the local clock,
+    /// warning once per run when they disagree.
+    pub fn check_clock_skew(&mut self, newest_record: Option<SystemTime>, now: SystemTime) {
+        self.clock_skew = clock::clock_skew(newest_record, now);
+        if let Some(skew) = self.clock_skew.filter(|_| !self.skew_warned) {
+            self.skew_warned = true;
+            self.show_toast(
+                format!(
+                    "clock skew: records are stamped {} from this machine's clock",
+                    skew.label()
+                ),
+                ToastLevel::Warning,
+            );
+        }
     }
 
-    #[test]
-    fn select_agent_existing() {
-        let mut app = App::new(PathBuf::from("/tmp"));
-        app.agents
-            .insert(1, AgentState::new(1, PathBuf::from("/tmp/1.jsonl")));
-        app.select_agent(1);
-        assert_eq!(app.selected_agent, Some(1));
+    /// Read only the session files the OS reports as changed instead of
+    /// polling every file each tick. On error the app keeps polling.
+    pub fn watch_fs_events(&mut self) -> notify::Result<()> {
+        let roots: Vec<PathBuf> = self
+            .watched_roots()
+            .iter()
+            .map(|p| p.to_path_buf())
+            .collect();
+        self.fs_watcher = Some(FileWatcher::new(&roots)?);
+        self.rescan_requested = true;
+        Ok(())
     }
 
-    #[test]
-    fn select_agent_nonexistent() {
-        let mut app = App::new(PathBuf::from("/tmp"));
-        app.select_agent(99);
-        assert!(app.selected_agent.is_none());
+    pub fn is_watching_fs_events(&self) -> bool {
+        self.fs_watcher.is_some()
     }
 
-    #[test]
-    fn agent_anim_state_idle_when_no_tools() {
-        let mut app = App::new(PathBuf::from("/tmp"));
-        app.agents
-            .insert(1, AgentState::new(1, PathBuf::from("/tmp/1.jsonl")));
-        assert_eq!(app.agent_anim_state(1), AnimState::Idle);
+    /// Match new sidechain transcripts to the Task sub-agents that run them,
+    /// then follow what each sub-agent is doing.
+    fn read_sidechains(&mut self, line_budget: usize) {
+        let now = Instant::now();
+        let search_due = self
+            .last_sidechain_search
+            .is_none_or(|at| now.saturating_duration_since(at) >= SIDECHAIN_SEARCH_INTERVAL);
+        if search_due {
+            self.last_sidechain_search = Some(now);
+        }
+        for agent in self.agents.values_mut() {
+            if search_due && agent.has_unmatched_sub_agents() {
+                for path in discovery::sidechain_files(&agent.session_file) {
+                    if agent.claimed_sidechains.contains(&path) {
+                        continue;
+                    }
+                    if let Some(prompt) = discovery::sidechain_opening(&path) {
+                        agent.claim_sidechain(&path, &prompt);
+                    }
+                }
+            }
+            for sub in &mut agent.sub_agents {
+                let Some(path) = &sub.sidechain else {
+                    continue;
+                };
+                for record in self.reader.read_batch(path, line_budget).records {
+                    sub.apply_record(&record, &self.redactor, &self.classifier);
+                }
+            }
+        }
     }
 
-    #[test]
-    fn agent_anim_state_reading() {
-        let mut app = App::new(PathBuf::from("/tmp"));
-        let mut agent = AgentState::new(1, PathBuf::from("/tmp/1.jsonl"));
-        agent.add_tool(parser::ToolUseEvent {
-            tool_id: "t1".to_string(),
-            tool_name: "Read".to_string(),
-            display_status: "Reading foo.rs".to_string(),
-            is_reading: true,
+    /// Drop read offsets of files no agent or sub-agent follows anymore,
+    /// such as the sidechains of finished sub-agents.
+    fn prune_offsets(&mut self) {
+        let followed: HashSet<&Path> = self
+            .agents
+            .values()
+            .flat_map(|agent| {
+                let sidechains = agent
+                    .sub_agents
+                    .iter()
+                    .filter_map(|sub| sub.sidechain.as_deref());
+                std::iter::once(agent.session_file.as_path()).chain(sidechains)
+            })
+            .collect();
+        self.reader.retain(&followed);
+    }
+
+    /// The agent, other than `id`, of the session `session_id`.
+    fn resumed_agent(&self, id: u32, session_id: &str) -> Option<u32> {
+        self.agents
+            .values()
+            .find(|agent| agent.id != id && agent.session_id == session_id)
+            .map(|agent| agent.id)
+    }
+
+    /// Merge each pending resume whose summarized message is the last one
+    /// of another session that has stopped growing. A session still being
+    /// written to runs alongside, and only shares history.
+    fn resolve_resumes(&mut self, now: Instant) {
+        if self.pending_resumes.is_empty() {
+            return;
+        }
+        let resolved: Vec<(String, u32, u32)> =
+            self.pending_resumes
+                .iter()
+                .filter_map(|(leaf, &later)| {
+                    let earlier = self.agents.values().find(|agent| {
+                        agent.id != later && agent.last_uuid.as_ref() == Some(leaf)
+                    })?;
+                    self.stopped_growing(earlier.id, now)
+                        .then(|| (leaf.clone(), later, earlier.id))
+                })
+                .collect();
+        for (leaf, later, earlier) in resolved {
+            self.pending_resumes.remove(&leaf);
+            self.merge_resumed_session(later, earlier);
+        }
+    }
+
+    /// Carry the session of `later`, resumed from that of `earlier`, on
+    /// under `earlier`'s ID, rather than give it a second desk. The later
+    /// file is the live conversation: its agent survives, and the earlier
+    /// file is no longer followed.
+    fn merge_resumed_session(&mut self, later: u32, earlier: u32) {
+        let Some(mut agent) = self.agents.remove(&later) else {
+            return;
+        };
+        if self
+            .session_tracker
+            .resume(&agent.session_file, earlier)
+            .is_none()
+        {
+            self.agents.insert(later, agent);
+            return;
+        }
+        if let Some(mut old) = self.agents.remove(&earlier) {
+            let totals = old.finalize_status_time(Instant::now());
+            self.retired_status_totals.add(&totals);
+            self.reader.remove(&old.session_file);
+            self.changed_files.remove(&old.session_file);
+        }
+        self.event_log.push(
+            SystemTime::now(),
+            Some(earlier),
+            format!("Session resumed as {}", agent.session_id),
+        );
+        self.events.push(AppEvent::AgentRemoved {
+            id: later,
+            session_file: agent.session_file.clone(),
         });
-        app.agents.insert(1, agent);
-        assert_eq!(app.agent_anim_state(1), AnimState::Reading);
+        self.reported.remove(&later);
+        agent.renumber(earlier);
+        self.agents.insert(earlier, agent);
+        self.note_changes(earlier);
+        match self.growth.remove(&later) {
+            Some(meter) => self.growth.insert(earlier, meter),
+            None => self.growth.remove(&earlier),
+        };
+        match self.replays.remove(&later) {
+            Some(replay) => self.replays.insert(earlier, replay),
+            None => self.replays.remove(&earlier),
+        };
+        self.last_seen_tick.remove(&later);
+        self.pending_resumes
+            .retain(|_, id| *id != later && *id != earlier);
+        self.notifier.forget(later);
+        if self.selected_agent == Some(later) {
+            self.selected_agent = Some(earlier);
+        }
     }
 
-    #[test]
-    fn agent_anim_state_typing() {
+    /// Stop tracking an agent whose session is gone.
+    fn remove_agent(&mut self, id: u32) {
+        if let Some(mut agent) = self.agents.remove(&id) {
+            let totals = agent.finalize_status_time(Instant::now());
+            self.retired_status_totals.add(&totals);
+            self.reader.remove(&agent.session_file);
+            self.events.push(AppEvent::AgentRemoved {
+                id,
+                session_file: agent.session_file,
+            });
+        }
+        self.reported.remove(&id);
+        self.last_seen_tick.remove(&id);
+        self.growth.remove(&id);
+        self.replays.remove(&id);
+        self.pending_resumes.retain(|_, later| *later != id);
+        self.notifier.forget(id);
+        // Deselect if the selected agent was removed
+        if self.selected_agent == Some(id) {
+            self.selected_agent = None;
+        }
+        if self.kill_prompt.take_if(|prompt| *prompt == id).is_some() {
+            self.show_toast(
+                format!("Agent #{id}'s session ended before the kill was confirmed"),
+                ToastLevel::Info,
+            );
+        }
+    }
+
+    /// Desktop notifications queued since the last call, for the caller to
+    /// send.
+    pub fn take_notifications(&mut self) -> Vec<Notification> {
+        self.notifier.take()
+    }
+
+    /// Rebuild display caches invalidated since the last refresh.
+    pub fn refresh_display_caches(&mut self) {
+        for agent in self.agents.values_mut() {
+            agent.refresh_display();
+        }
+    }
+
+    /// Every Claude data directory being watched, primary first.
+    pub fn watched_roots(&self) -> Vec<&Path> {
+        std::iter::once(self.claude_dir.as_path())
+            .chain(self.extra_roots.iter().map(|p| p.as_path()))
+            .collect()
+    }
+
+    /// Whether enough agents are tracked to switch to the degraded scale views.
+    pub fn scale_mode(&self) -> bool {
+        self.agents.len() > self.scale_threshold
+    }
+
+    /// Time between full scans: `scan_interval` when polling, longer when
+    /// filesystem events report changes.
+    fn scan_interval(&self) -> Duration {
+        if self.fs_watcher.is_some() {
+            WATCHED_SCAN_INTERVAL
+        } else {
+            self.config.scan_interval
+        }
+    }
+
+    /// Remember the scanned sizes and mark the files that changed since the
+    /// last scan for reading. Returns the scanned paths.
+    fn note_session_sizes(&mut self, scanned: Vec<SessionFile>) -> Vec<PathBuf> {
+        let previous = std::mem::take(&mut self.session_sizes);
+        for session in &scanned {
+            if previous.get(&session.path) != Some(&session.len) {
+                self.changed_files.insert(session.path.clone());
+            }
+            self.session_sizes.insert(session.path.clone(), session.len);
+        }
+        scanned.into_iter().map(|session| session.path).collect()
+    }
+
+    /// Session files to read this tick. Normally every agent; in scale mode a
+    /// round-robin window so one busy file can't starve the rest.
+    fn files_to_read(&mut self) -> Vec<(u32, PathBuf)> {
+        let mut ids = self.sorted_agent_ids();
+        if self.fs_watcher.is_some() || self.config.remote_friendly {
+            ids.retain(|id| {
+                self.agents
+                    .get(id)
+                    .is_some_and(|a| self.changed_files.contains(&a.session_file))
+            });
+        }
+        let take = if self.scale_mode() {
+            SCALE_FILES_PER_TICK.min(ids.len())
+        } else {
+            ids.len()
+        };
+        if ids.is_empty() {
+            return Vec::new();
+        }
+        let start = self.read_cursor % ids.len();
+        self.read_cursor = (start + take) % ids.len();
+        ids.iter()
+            .cycle()
+            .skip(start)
+            .take(take)
+            .filter_map(|id| self.agents.get(id).map(|a| (*id, a.session_file.clone())))
+            .collect()
+    }
+
+    pub fn select_agent(&mut self, num: u32) {
+        if self.agents.contains_key(&num) {
+            self.selected_agent = Some(num);
+            self.last_seen_tick.insert(num, Instant::now());
+        }
+    }
+
+    /// Select the next agent listed in the sidebar, wrapping around.
+    pub fn select_next(&mut self) {
+        self.select_step(self.filtered_agent_ids(), 1);
+    }
+
+    /// Select the previous agent listed in the sidebar, wrapping around.
+    pub fn select_prev(&mut self) {
+        self.select_step(self.filtered_agent_ids(), -1);
+    }
+
+    /// Select the agent at the next desk in the office, wrapping around.
+    pub fn select_next_desk(&mut self) {
+        self.select_step(self.desk_order(), 1);
+    }
+
+    /// Select the agent at the previous desk in the office, wrapping around.
+    pub fn select_prev_desk(&mut self) {
+        self.select_step(self.desk_order(), -1);
+    }
+
+    /// Agent ids in the order the office shows their desks: with a home
+    /// project, its agents come before the other projects'.
+    fn desk_order(&self) -> Vec<u32> {
+        layout::office_sections(self)
+            .into_iter()
+            .flat_map(|section| section.ids)
+            .filter(|id| self.agents.contains_key(id))
+            .collect()
+    }
+
+    fn select_step(&mut self, ids: Vec<u32>, step: isize) {
+        if ids.is_empty() {
+            return;
+        }
+        let next = match self
+            .selected_agent
+            .and_then(|id| ids.iter().position(|&i| i == id))
+        {
+            Some(pos) => (pos as isize + step).rem_euclid(ids.len() as isize) as usize,
+            None if step > 0 => 0,
+            None => ids.len() - 1,
+        };
+        self.select_agent(ids[next]);
+    }
+
+    /// Flip to the next of the office's `pages`, stopping at the last.
+    pub fn next_office_page(&mut self, pages: usize) {
+        self.office_page = (self.office_page + 1).min(pages.saturating_sub(1));
+    }
+
+    /// Flip to the previous of the office's `pages`, stopping at the first.
+    pub fn prev_office_page(&mut self, pages: usize) {
+        self.office_page = self
+            .office_page
+            .min(pages.saturating_sub(1))
+            .saturating_sub(1);
+    }
+
+    /// Whether the agent has activity newer than the last time it was selected.
+    pub fn is_unseen(&self, id: u32) -> bool {
+        let Some(agent) = self.agents.get(&id) else {
+            return false;
+        };
+        match self.last_seen_tick.get(&id) {
+            Some(seen) => agent.last_activity > *seen,
+            None => true,
+        }
+    }
+
+    /// Apply a validated config without touching agent state. Returns the
+    /// changed settings that only take effect after a restart.
+    pub fn apply_config(&mut self, config: Config) -> Vec<&'static str> {
+        let restart_required = config::restart_required_changes(&self.config, &config);
+        self.scale_threshold = self
+            .cli_scale_threshold
+            .or(config.scale_threshold)
+            .unwrap_or(DEFAULT_SCALE_THRESHOLD);
+        // Patterns were compiled once already when the config was validated
+        self.redactor = Redactor::with_extra(&config.redact_patterns).unwrap_or_default();
+        self.classifier = ToolClassifier::new(&config.rules);
+        // The profile, notifications, project scope and theme choice come
+        // from the command line or a key, so they outlive reloads
+        let config = Config {
+            notify: config.notify || self.config.notify,
+            no_color: config.no_color || self.config.no_color,
+            project: config.project.or_else(|| self.config.project.clone()),
+            theme_override: config.theme_override.or(self.config.theme_override),
+            ..config
+        };
+        self.config = if self.config.remote_friendly {
+            config.remote_friendly()
+        } else {
+            config
+        };
+        self.refresh_theme();
+        if self.pacer.base() != self.config.tick_rate {
+            self.pacer = TickPacer::new(self.config.tick_rate);
+        }
+        restart_required
+    }
+
+    /// Account for one pass of the main loop: drawing, handling input and
+    /// ticking, not the wait for input. Loops that keep overrunning the
+    /// tick interval stretch it.
+    pub fn record_loop(&mut self, work: Duration) {
+        self.self_stats.record(work, self.pacer.is_overrun(work));
+        self.pacer.observe(work);
+    }
+
+    /// Switch to the next built-in theme until the next restart.
+    pub fn cycle_theme(&mut self) {
+        if self.config.no_color {
+            self.show_toast(
+                "NO_COLOR is set; colors stay off".to_string(),
+                ToastLevel::Warning,
+            );
+            return;
+        }
+        let next = self.theme.name.next();
+        self.config.theme_override = Some(next);
+        self.refresh_theme();
+        self.show_toast(format!("theme: {}", next.name()), ToastLevel::Info);
+    }
+
+    fn refresh_theme(&mut self) {
+        self.theme = if self.config.no_color {
+            Theme::mono()
+        } else {
+            let name = self.config.theme_override.unwrap_or(self.config.theme.name);
+            Theme::named(name).with_accent(self.config.theme.accent)
+        };
+    }
+
+    /// Apply a freshly loaded config, or keep the current one if loading
+    /// failed. Either way the outcome is reported in a toast.
+    pub fn reload_config(&mut self, loaded: Result<Config, ConfigError>) {
+        match loaded {
+            Ok(config) => {
+                let restart_required = self.apply_config(config);
+                let mut notes = Vec::new();
+                if !restart_required.is_empty() {
+                    notes.push(format!(
+                        "restart required for {}",
+                        restart_required.join(", ")
+                    ));
+                }
+                notes.extend(self.rule_warning());
+                if notes.is_empty() {
+                    self.show_toast("config reloaded".to_string(), ToastLevel::Info);
+                } else {
+                    self.show_toast(
+                        format!("config reloaded; {}", notes.join("; ")),
+                        ToastLevel::Warning,
+                    );
+                }
+            }
+            Err(e) => self.show_toast(format!("{e} (kept previous config)"), ToastLevel::Error),
+        }
+    }
+
+    /// `skipped rules[1]: missing label`, when the config had bad rules.
+    pub fn rule_warning(&self) -> Option<String> {
+        let warnings = &self.config.rule_warnings;
+        (!warnings.is_empty()).then(|| format!("skipped {}", warnings.join("; ")))
+    }
+
+    pub fn show_toast(&mut self, message: String, level: ToastLevel) {
+        self.toast = Some(Toast {
+            message,
+            level,
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// The toast to display at `now`, if one is still fresh.
+    pub fn active_toast(&self, now: Instant) -> Option<&Toast> {
+        self.toast
+            .as_ref()
+            .filter(|t| now.saturating_duration_since(t.shown_at) < TOAST_DURATION)
+    }
+
+    /// The footer's status segments at `now`, rightmost first. Segments
+    /// hidden in the config are left out.
+    pub fn footer_segments(&self, now: Instant) -> Vec<FooterSegment> {
+        let mut segments = Vec::new();
+        if let Some(interval) = self.tick_interval.filter(|d| !d.is_zero()) {
+            segments.push(FooterSegment {
+                kind: FooterSegmentKind::Fps,
+                text: format!("{:.0} FPS", 1.0 / interval.as_secs_f64()),
+                priority: 2,
+                level: None,
+            });
+        }
+        if let Some(cost) = self.tick_cost {
+            segments.push(FooterSegment {
+                kind: FooterSegmentKind::TickTime,
+                text: format!("tick {}ms", cost.as_millis()),
+                priority: 1,
+                level: None,
+            });
+        }
+        if self.pacer.is_stretched() {
+            let mut text = format!(
+                "slowed to {:.0} FPS",
+                1.0 / self.pacer.interval().as_secs_f64()
+            );
+            if let Some(p95) = self.self_stats.p95() {
+                text.push_str(&format!(", p95 {}ms", p95.as_millis()));
+            }
+            segments.push(FooterSegment {
+                kind: FooterSegmentKind::Slowdown,
+                text,
+                priority: 3,
+                level: Some(ToastLevel::Warning),
+            });
+        }
+        if let (StateSource::Local, Some(at)) = (&self.source, self.last_scan_at) {
+            let age = now.saturating_duration_since(at);
+            let stale_after = if self.fs_watcher.is_some() {
+                WATCHED_SCAN_STALE_AFTER
+            } else {
+                SCAN_STALE_AFTER.max(self.config.scan_interval * 2)
+            };
+            if age >= stale_after {
+                segments.push(FooterSegment {
+                    kind: FooterSegmentKind::ScanStaleness,
+                    text: format!("scanned {} ago", timer::format_duration_short(age)),
+                    priority: 3,
+                    level: Some(ToastLevel::Warning),
+                });
+            }
+        }
+        if let Some(toast) = self.active_toast(now) {
+            segments.push(FooterSegment {
+                kind: FooterSegmentKind::Toast,
+                text: toast.message.clone(),
+                priority: 4,
+                level: Some(toast.level),
+            });
+        }
+        segments.retain(|s| !self.config.footer.hidden.contains(&s.kind));
+        segments
+    }
+
+    pub fn cycle_sidebar_filter(&mut self) {
+        self.sidebar_filter = self.sidebar_filter.next();
+        self.sidebar_scroll = 0;
+        self.office_page = 0;
+    }
+
+    /// Show only agents in one status, or everyone again if that filter
+    /// is already on.
+    pub fn toggle_status_filter(&mut self, filter: SidebarFilter) {
+        self.sidebar_filter = if self.sidebar_filter == filter {
+            SidebarFilter::All
+        } else {
+            filter
+        };
+        self.sidebar_scroll = 0;
+        self.office_page = 0;
+    }
+
+    /// Start typing a search, from the one applied.
+    pub fn open_search_input(&mut self) {
+        self.search_input = Some(self.search_query.clone());
+    }
+
+    /// Route a key to the open search input: Enter applies the query, Esc
+    /// drops the edit and keeps the search applied before, anything
+    /// printable is typed. Returns false, doing nothing, when no search is
+    /// being typed.
+    pub fn search_key(&mut self, code: KeyCode) -> bool {
+        let Some(input) = self.search_input.as_mut() else {
+            return false;
+        };
+        match code {
+            KeyCode::Esc => self.search_input = None,
+            KeyCode::Enter => {
+                let query = input.trim().to_lowercase();
+                self.search_input = None;
+                if query != self.search_query {
+                    self.search_query = query;
+                    self.sidebar_scroll = 0;
+                    self.office_page = 0;
+                }
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) if !c.is_control() && input.chars().count() < SEARCH_MAX_CHARS => {
+                input.push(c);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Sorted ids of the agents the filter and the search let through, as
+    /// the sidebar and the office list them. The selected agent always
+    /// stays listed so its details remain visible.
+    pub fn filtered_agent_ids(&self) -> Vec<u32> {
+        self.sorted_agent_ids()
+            .into_iter()
+            .filter(|&id| {
+                let Some(agent) = self.agents.get(&id) else {
+                    return false;
+                };
+                let shown = match self.sidebar_filter {
+                    SidebarFilter::All => true,
+                    SidebarFilter::Unseen => self.is_unseen(id),
+                    SidebarFilter::NeedsAttention => agent.needs_attention(),
+                    SidebarFilter::Active => agent.status == AgentStatus::Active,
+                    SidebarFilter::Waiting => {
+                        matches!(agent.status, AgentStatus::Waiting | AgentStatus::NeedsInput)
+                    }
+                };
+                self.selected_agent == Some(id)
+                    || (shown && agent.matches_search(&self.search_query))
+            })
+            .collect()
+    }
+
+    pub fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            PanelFocus::Office => PanelFocus::Sidebar,
+            PanelFocus::Sidebar if self.show_event_log => PanelFocus::EventLog,
+            PanelFocus::Sidebar | PanelFocus::EventLog => PanelFocus::Office,
+        };
+    }
+
+    /// Switch between the office and the statistics screen.
+    pub fn toggle_stats(&mut self) {
+        self.view_mode = match self.view_mode {
+            ViewMode::Office => ViewMode::Stats,
+            ViewMode::Stats => ViewMode::Office,
+        };
+    }
+
+    /// Show or hide the event log panel. Hiding it hands focus back to
+    /// the sidebar.
+    pub fn toggle_event_log(&mut self) {
+        self.show_event_log = !self.show_event_log;
+        self.event_log_scroll = 0;
+        if !self.show_event_log && self.focus == PanelFocus::EventLog {
+            self.focus = PanelFocus::Sidebar;
+        }
+    }
+
+    /// Scroll the focused panel: the event log goes back in time, no
+    /// further than its oldest line, anything else scrolls the sidebar.
+    /// `sidebar` is what the sidebar has to show now, which the selected
+    /// agent's details change; `log_rows` is the event log's height.
+    pub fn scroll_up(&mut self, sidebar: SidebarExtent, log_rows: u16) {
+        if self.focus == PanelFocus::EventLog {
+            let rows = log_rows.max(1) as usize;
+            self.set_event_log_scroll(self.event_log_scroll_in(rows) + 1, rows);
+        } else {
+            self.set_sidebar_scroll(self.sidebar_scroll_in(sidebar).saturating_sub(1), sidebar);
+        }
+    }
+
+    /// The sidebar stops at its last line: past it, Up would take as many
+    /// presses to come back.
+    pub fn scroll_down(&mut self, sidebar: SidebarExtent, log_rows: u16) {
+        if self.focus == PanelFocus::EventLog {
+            let rows = log_rows.max(1) as usize;
+            self.set_event_log_scroll(self.event_log_scroll_in(rows).saturating_sub(1), rows);
+        } else {
+            self.set_sidebar_scroll(self.sidebar_scroll_in(sidebar) + 1, sidebar);
+        }
+    }
+
+    /// Scroll the focused panel by a page, stopping at either end.
+    /// `log_rows` is the height of the event log's text area.
+    pub fn scroll_page(&mut self, down: bool, sidebar: SidebarExtent, log_rows: u16) {
+        if self.focus == PanelFocus::EventLog {
+            let rows = log_rows.max(1) as usize;
+            let scroll = self.event_log_scroll_in(rows);
+            let scroll = if down {
+                scroll.saturating_sub(rows)
+            } else {
+                scroll + rows
+            };
+            self.set_event_log_scroll(scroll, rows);
+        } else {
+            let scroll = self.sidebar_scroll_in(sidebar);
+            let scroll = if down {
+                scroll + sidebar.page()
+            } else {
+                scroll.saturating_sub(sidebar.page())
+            };
+            self.set_sidebar_scroll(scroll, sidebar);
+        }
+    }
+
+    /// Scroll the focused panel to its start or end; the event log's start
+    /// is its oldest line.
+    pub fn scroll_to_edge(&mut self, end: bool, sidebar: SidebarExtent, log_rows: u16) {
+        if self.focus == PanelFocus::EventLog {
+            let scroll = if end { 0 } else { usize::MAX };
+            self.set_event_log_scroll(scroll, log_rows.max(1) as usize);
+        } else {
+            let scroll = if end { usize::MAX } else { 0 };
+            self.set_sidebar_scroll(scroll, sidebar);
+        }
+    }
+
+    /// Scroll the sidebar just enough to show the selected agent's header.
+    pub fn show_selected_in_sidebar(&mut self, sidebar: SidebarExtent) {
+        let Some(line) = self
+            .selected_agent
+            .and_then(|id| self.filtered_agent_ids().iter().position(|&i| i == id))
+        else {
+            return;
+        };
+        let scroll = sidebar.scroll_showing(line, self.sidebar_scroll_in(sidebar));
+        self.set_sidebar_scroll(scroll, sidebar);
+    }
+
+    /// The sidebar's scroll as shown: content that shrank since it was set
+    /// caps it.
+    fn sidebar_scroll_in(&self, sidebar: SidebarExtent) -> usize {
+        (self.sidebar_scroll as usize).min(sidebar.max_scroll())
+    }
+
+    fn set_sidebar_scroll(&mut self, scroll: usize, sidebar: SidebarExtent) {
+        self.sidebar_scroll = scroll.min(sidebar.max_scroll()).min(u16::MAX as usize) as u16;
+    }
+
+    /// The event log's scroll as shown in a `rows`-tall view: a shorter
+    /// log, say another agent's, caps it.
+    fn event_log_scroll_in(&self, rows: usize) -> usize {
+        (self.event_log_scroll as usize).min(self.event_log_max_scroll(rows))
+    }
+
+    fn event_log_max_scroll(&self, rows: usize) -> usize {
+        let len = self.shown_agent_log().map_or(0, |log| log.len());
+        len.saturating_sub(rows)
+    }
+
+    /// Scroll the event log `scroll` lines back, no further than its oldest
+    /// line at the top of a `rows`-tall view.
+    fn set_event_log_scroll(&mut self, scroll: usize, rows: usize) {
+        let max = self.event_log_max_scroll(rows);
+        self.event_log_scroll = scroll.min(max).min(u16::MAX as usize) as u16;
+    }
+
+    /// Move the event log view to the start of the previous or next turn.
+    /// `rows` is the height of the log's text area.
+    pub fn jump_turn(&mut self, forward: bool, rows: u16) {
+        let Some(log) = self.shown_agent_log() else {
+            return;
+        };
+        let rows = rows as usize;
+        let top = events::view_top(log.len(), rows, self.event_log_scroll as usize);
+        // A target shows with context above it; only jump if the view moves
+        let context = events::JUMP_CONTEXT_LINES;
+        let target = if forward {
+            log.next_turn_start(top + context)
+        } else {
+            log.prev_turn_start(top + context)
+                .filter(|&t| t.saturating_sub(context) < top)
+        };
+        if let Some(target) = target {
+            self.event_log_scroll = events::scroll_to_target(log.len(), rows, target) as u16;
+        }
+    }
+
+    /// Bookmark the current state, or with a bookmark set, open what
+    /// changed since. Pressed again with the overlay open, it closes the
+    /// overlay and drops the bookmark.
+    pub fn toggle_bookmark(&mut self, now: Instant) {
+        if self.diff_view.take().is_some() {
+            self.bookmark = None;
+            self.show_toast("bookmark cleared".to_string(), ToastLevel::Info);
+        } else if let Some(bookmark) = &self.bookmark {
+            self.diff_view = Some(DiffView {
+                changes: diff::diff_snapshots(&bookmark.snapshot, &self.snapshot(now)),
+                scroll: 0,
+                age: now.saturating_duration_since(bookmark.taken_at),
+            });
+        } else {
+            self.bookmark = Some(Bookmark {
+                snapshot: self.snapshot(now),
+                taken_at: now,
+            });
+            let key = self.config.keys.bookmark;
+            self.show_toast(
+                format!("bookmarked; press {key} again to see what changed"),
+                ToastLevel::Info,
+            );
+        }
+    }
+
+    /// Scroll the diff overlay by a row, keeping the last row in view.
+    pub fn scroll_diff(&mut self, down: bool) {
+        if let Some(view) = self.diff_view.as_mut() {
+            view.scroll = if down {
+                (view.scroll + 1).min(view.changes.len().saturating_sub(1))
+            } else {
+                view.scroll.saturating_sub(1)
+            };
+        }
+    }
+
+    /// Start typing a time to jump the event log to.
+    pub fn open_time_input(&mut self) {
+        if self.shown_agent_log().is_some() {
+            self.time_input = Some(String::new());
+        }
+    }
+
+    /// Jump the event log to the typed time and close the input.
+    pub fn submit_time_input(&mut self, rows: u16) {
+        let Some(input) = self.time_input.take() else {
+            return;
+        };
+        let Some(log) = self.shown_agent_log() else {
+            return;
+        };
+        // Clock times are on the day of the newest stamped event
+        let reference = log
+            .entries()
+            .rev()
+            .find_map(|e| e.record_at)
+            .unwrap_or_else(SystemTime::now);
+        // A typed time is local; find it on the records' clock
+        let skew = self.clock_skew;
+        let target = events::parse_jump_time(&input, reference)
+            .map(|at| skew.map_or(at, |skew| skew.to_record_time(at)))
+            .ok_or_else(|| format!("not a time: {input}"))
+            .and_then(|at| {
+                log.position_at(at)
+                    .ok_or_else(|| "no timestamped events to jump to".to_string())
+            });
+        match target {
+            Ok(target) => {
+                self.event_log_scroll =
+                    events::scroll_to_target(log.len(), rows as usize, target) as u16;
+            }
+            Err(message) => self.show_toast(message, ToastLevel::Warning),
+        }
+    }
+
+    /// Ask to confirm killing the selected agent's session.
+    pub fn open_kill_prompt(&mut self) {
+        let Some(id) = self
+            .selected_agent
+            .filter(|id| self.agents.contains_key(id))
+        else {
+            return;
+        };
+        if !matches!(self.source, StateSource::Local) {
+            self.show_toast(
+                "sessions of a remote dashboard can't be killed from here".to_string(),
+                ToastLevel::Warning,
+            );
+            return;
+        }
+        self.kill_prompt = Some(id);
+    }
+
+    /// Answer the kill prompt with yes: interrupt the Claude Code process
+    /// that owns the session, found among `processes`. If there is none,
+    /// the agent is only marked dormant here.
+    pub fn confirm_kill(
+        &mut self,
+        processes: &dyn ProcessLister,
+        interrupt: impl FnOnce(u32) -> io::Result<()>,
+    ) {
+        let Some(id) = self.kill_prompt.take() else {
+            return;
+        };
+        // The prompt names an agent; never act on a different one
+        if self.selected_agent != Some(id) {
+            return;
+        }
+        let Some(agent) = self.agents.get_mut(&id) else {
+            return;
+        };
+        let (message, level) = match actions::find_session_pid(&agent.session_file, processes) {
+            Some(pid) => match interrupt(pid) {
+                Ok(()) => (
+                    format!("sent SIGINT to Agent #{id} (pid {pid})"),
+                    ToastLevel::Info,
+                ),
+                Err(e) => (
+                    format!("couldn't interrupt Agent #{id} (pid {pid}): {e}"),
+                    ToastLevel::Error,
+                ),
+            },
+            None => {
+                agent.set_status(AgentStatus::Dormant);
+                (
+                    format!("no process found for Agent #{id}; marked dormant"),
+                    ToastLevel::Warning,
+                )
+            }
+        };
+        self.show_toast(message, level);
+    }
+
+    /// The selected agent's log, while the event log panel shows it.
+    fn shown_agent_log(&self) -> Option<&AgentLog> {
+        if !self.show_event_log {
+            return None;
+        }
+        self.selected_agent
+            .and_then(|id| self.agents.get(&id))
+            .map(|agent| &agent.log)
+    }
+
+    /// Input and output tokens summed over every agent.
+    pub fn total_tokens(&self) -> (u64, u64) {
+        self.agents.values().fold((0, 0), |(input, output), agent| {
+            (
+                input + agent.usage.total_input_tokens,
+                output + agent.usage.total_output_tokens,
+            )
+        })
+    }
+
+    /// Raise or reset each agent's escalation level from how long it has
+    /// been waiting on the user. Climbing a level notifies, when enabled.
+    pub fn update_escalations(&mut self, now: Instant) {
+        let escalation = &self.config.escalation;
+        let notify = self.config.notify && escalation.notify;
+        for agent in self.agents.values_mut() {
+            let waited = agent.status_timer.time_in_status(now);
+            let level = escalation::escalation_level(agent.status, waited, &escalation.thresholds);
+            if notify && level > agent.escalation {
+                self.notifier.escalated(agent, waited, now);
+            }
+            agent.escalation = level;
+        }
+    }
+
+    /// Agents needing the user: asking a question, or escalated after
+    /// waiting too long.
+    pub fn attention_count(&self) -> usize {
+        self.agents
+            .values()
+            .filter(|a| a.status == AgentStatus::NeedsInput || a.escalation > 0)
+            .count()
+    }
+
+    /// Agents blocked on a question to the user.
+    pub fn needs_input_count(&self) -> usize {
+        self.agents
+            .values()
+            .filter(|a| a.status == AgentStatus::NeedsInput)
+            .count()
+    }
+
+    pub fn sorted_agent_ids(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.agents.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    /// The `[[rules]]` entry behind the agent's current tool, with its
+    /// position in the config.
+    pub fn current_rule(&self, id: u32) -> Option<(usize, &RuleSpec)> {
+        let index = self.agents.get(&id)?.active_tools.last()?.rule?;
+        Some((index, self.classifier.rule(index)?))
+    }
+
+    /// Move every character one step along its walk, and send agents
+    /// that disappeared off through the entrance.
+    fn update_office(&mut self) {
+        let ticks = choreography::walk_ticks(self.config.tick_rate);
+        let departed = self.office.update(&self.agents, ticks);
+        self.departing.retain_mut(|agent| !agent.step());
+        self.departing.extend(departed);
+        // A session back before its agent made it out walks in anew
+        let agents = &self.agents;
+        self.departing
+            .retain(|agent| !agents.contains_key(&agent.id));
+    }
+
+    /// Where agent `id`'s character is, departed agents included.
+    pub fn agent_position(&self, id: u32) -> AgentPosition {
+        match self.departing.iter().find(|agent| agent.id == id) {
+            Some(agent) => agent.position(),
+            None => self.office.position(id),
+        }
+    }
+
+    pub fn agent_anim_state(&self, id: u32) -> AnimState {
+        if self.agent_position(id).is_walking() {
+            return AnimState::Walking;
+        }
+        match self.agents.get(&id) {
+            Some(agent) => {
+                if agent.status == AgentStatus::NeedsInput {
+                    AnimState::Asking
+                } else if let Some((_, rule)) = self.current_rule(id) {
+                    rule.anim
+                } else if agent.active_tools.is_empty() {
+                    AnimState::Idle
+                } else if agent.active_tools.iter().any(|t| t.is_reading) {
+                    AnimState::Reading
+                } else {
+                    AnimState::Typing
+                }
+            }
+            None => AnimState::Idle,
+        }
+    }
+}
+
+/// Queue events for how `agent`'s status and SDD phase changed since
+/// `reported`, and update it.
+fn push_changes(
+    id: u32,
+    agent: &AgentState,
+    reported: &mut (AgentStatus, Option<SddPhase>),
+    events: &mut Vec<AppEvent>,
+) {
+    let (status, phase) = std::mem::replace(reported, (agent.status, agent.sdd_phase));
+    if status != agent.status {
+        events.push(AppEvent::StatusChanged {
+            id,
+            from: status,
+            to: agent.status,
+        });
+    }
+    if phase != agent.sdd_phase {
+        events.push(AppEvent::SddPhaseChanged {
+            id,
+            from: phase,
+            to: agent.sdd_phase,
+        });
+    }
+}
+
+/// How long ago `path` was last written; zero when unknown.
+fn idle_since_written(path: &Path, now: SystemTime) -> Duration {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    use crate::state::pacing::PACER_WINDOW;
+    use crate::ui::theme::ThemeName;
+    use crate::watcher::parser;
+
+    #[test]
+    fn new_app_has_empty_agents() {
+        let app = App::new(PathBuf::from("/tmp"));
+        assert!(app.agents.is_empty());
+        assert!(app.selected_agent.is_none());
+        assert!(!app.should_quit);
+    }
+
+    #[test]
+    fn toggle_focus() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        assert_eq!(app.focus, PanelFocus::Sidebar);
+        app.toggle_focus();
+        assert_eq!(app.focus, PanelFocus::Office);
+        app.toggle_focus();
+        assert_eq!(app.focus, PanelFocus::Sidebar);
+    }
+
+    #[test]
+    fn event_log_joins_the_focus_cycle_while_shown() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        let mut agent = AgentState::new(1, PathBuf::from("/tmp/1.jsonl"));
+        for i in 0..12 {
+            agent.log.push(
+                SystemTime::now(),
+                events::AgentEventKind::Text,
+                format!("line {i}"),
+            );
+        }
+        app.agents.insert(1, agent);
+        app.select_agent(1);
+        app.toggle_event_log();
+        app.toggle_focus();
+        assert_eq!(app.focus, PanelFocus::EventLog);
+        app.toggle_focus();
+        assert_eq!(app.focus, PanelFocus::Office);
+        app.toggle_focus();
+        app.toggle_focus();
+        assert_eq!(app.focus, PanelFocus::EventLog);
+
+        // Up goes back in time; the sidebar doesn't move
+        let sidebar = SidebarExtent {
+            lines: 20,
+            height: 8,
+        };
+        app.scroll_up(sidebar, 5);
+        app.scroll_up(sidebar, 5);
+        app.scroll_down(sidebar, 5);
+        assert_eq!((app.event_log_scroll, app.sidebar_scroll), (1, 0));
+        // ...no further than the oldest line
+        for _ in 0..20 {
+            app.scroll_up(sidebar, 5);
+        }
+        assert_eq!(app.event_log_scroll, 12 - 5);
+        app.scroll_down(sidebar, 5);
+        assert_eq!(app.event_log_scroll, 12 - 5 - 1);
+        // A taller view has less to scroll, and Down moves right away
+        app.scroll_down(sidebar, 10);
+        assert_eq!(app.event_log_scroll, 12 - 10 - 1);
+
+        app.toggle_event_log();
+        assert_eq!(app.focus, PanelFocus::Sidebar);
+        assert_eq!(app.event_log_scroll, 0);
+    }
+
+    /// An app showing agent 1's log: three turns of four events each,
+    /// stamped a minute apart from 10:00 UTC.
+    fn app_with_turns() -> App {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        let mut agent = AgentState::new(1, PathBuf::from("/tmp/a.jsonl"));
+        let start = humantime::parse_rfc3339("2026-03-04T10:00:00Z").unwrap();
+        for i in 0..12u64 {
+            agent
+                .log
+                .set_record_time(Some(start + Duration::from_secs(i * 60)));
+            let kind = if i % 4 == 3 {
+                events::AgentEventKind::TurnEnd
+            } else {
+                events::AgentEventKind::Text
+            };
+            agent
+                .log
+                .push(SystemTime::now(), kind, format!("event {i}"));
+        }
+        app.agents.insert(1, agent);
+        app.selected_agent = Some(1);
+        app.toggle_event_log();
+        app
+    }
+
+    #[test]
+    fn turn_jumps_move_the_event_log_view() {
+        let mut app = app_with_turns();
+        // Following the newest, a 4-row view starts at event 8
+        app.jump_turn(false, 4);
+        // Turn 2 starts at 8; one context line above
+        assert_eq!(app.event_log_scroll, 12 - 7 - 4);
+        app.jump_turn(false, 4);
+        assert_eq!(app.event_log_scroll, 12 - 3 - 4);
+        app.jump_turn(false, 4);
+        assert_eq!(app.event_log_scroll, 12 - 4);
+        // Nothing before the first turn
+        app.jump_turn(false, 4);
+        assert_eq!(app.event_log_scroll, 12 - 4);
+        app.jump_turn(true, 4);
+        assert_eq!(app.event_log_scroll, 12 - 3 - 4);
+    }
+
+    #[test]
+    fn bookmark_key_saves_then_compares_then_clears() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.agents
+            .insert(1, AgentState::new(1, PathBuf::from("/tmp/1.jsonl")));
+        let t0 = Instant::now();
+        app.toggle_bookmark(t0);
+        assert!(app.bookmark.is_some());
+        assert!(app.diff_view.is_none());
+        assert!(app
+            .toast
+            .as_ref()
+            .unwrap()
+            .message
+            .contains("press B again"));
+
+        app.agents
+            .insert(2, AgentState::new(2, PathBuf::from("/tmp/2.jsonl")));
+        app.agents
+            .get_mut(&1)
+            .unwrap()
+            .set_status(AgentStatus::Active);
+        app.toggle_bookmark(t0 + Duration::from_secs(90));
+        let view = app.diff_view.as_ref().unwrap();
+        assert_eq!(view.age, Duration::from_secs(90));
+        let rows: Vec<String> = view.changes.iter().map(|c| c.describe()).collect();
+        assert_eq!(rows, ["#1 waiting \u{2192} active", "#2 new agent"]);
+
+        app.scroll_diff(true);
+        app.scroll_diff(true);
+        assert_eq!(app.diff_view.as_ref().unwrap().scroll, 1);
+        app.scroll_diff(false);
+        assert_eq!(app.diff_view.as_ref().unwrap().scroll, 0);
+
+        app.toggle_bookmark(t0 + Duration::from_secs(91));
+        assert!(app.diff_view.is_none());
+        assert!(app.bookmark.is_none());
+    }
+
+    #[test]
+    fn time_input_jumps_to_the_first_event_at_that_time() {
+        let mut app = app_with_turns();
+        app.open_time_input();
+        app.time_input.as_mut().unwrap().push_str("10:05");
+        app.submit_time_input(4);
+        assert_eq!(app.time_input, None);
+        assert_eq!(app.event_log_scroll, 12 - 4 - 4);
+
+        app.open_time_input();
+        app.time_input.as_mut().unwrap().push_str("soon");
+        app.submit_time_input(4);
+        assert_eq!(app.toast.as_ref().unwrap().message, "not a time: soon");
+        assert_eq!(app.event_log_scroll, 12 - 4 - 4);
+    }
+
+    #[test]
+    fn desk_selection_follows_id_order_and_wraps() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        for id in [3, 14, 7] {
+            app.agents.insert(
+                id,
+                AgentState::new(id, PathBuf::from(format!("/tmp/{id}.jsonl"))),
+            );
+        }
+        app.select_next_desk();
+        assert_eq!(app.selected_agent, Some(3));
+        app.select_next_desk();
+        app.select_next_desk();
+        assert_eq!(app.selected_agent, Some(14));
+        app.select_next_desk();
+        assert_eq!(app.selected_agent, Some(3));
+        app.select_prev_desk();
+        assert_eq!(app.selected_agent, Some(14));
+    }
+
+    #[test]
+    fn desk_selection_follows_the_home_project_first() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        for (id, project) in [
+            (1, "-src-other"),
+            (2, "-src-my-repo"),
+            (3, "-src-other"),
+            (4, "-src-my-repo"),
+        ] {
+            let path = PathBuf::from(format!("/c/projects/{project}/{id}.jsonl"));
+            app.agents.insert(id, AgentState::new(id, path));
+        }
+        app.home_project = Some(HomeProject::from_cwd(Path::new("/src/my-repo")));
+        let mut order = Vec::new();
+        for _ in 0..5 {
+            app.select_next_desk();
+            order.push(app.selected_agent.unwrap());
+        }
+        assert_eq!(order, [2, 4, 1, 3, 2]);
+        app.select_prev_desk();
+        assert_eq!(app.selected_agent, Some(3));
+        // The sidebar still goes by id
+        app.select_next();
+        assert_eq!(app.selected_agent, Some(4));
+    }
+
+    #[test]
+    fn office_pages_stop_at_both_ends() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.prev_office_page(3);
+        assert_eq!(app.office_page, 0);
+        for _ in 0..5 {
+            app.next_office_page(3);
+        }
+        assert_eq!(app.office_page, 2);
+        // The window grew to two pages: back goes from the last one
+        app.prev_office_page(2);
+        assert_eq!(app.office_page, 0);
+    }
+
+    #[test]
+    fn scroll_bounds() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        // 20 lines in 8 rows: the last shows at 13, under the hidden count
+        let sidebar = SidebarExtent {
+            lines: 20,
+            height: 8,
+        };
+        app.scroll_up(sidebar, 0);
+        assert_eq!(app.sidebar_scroll, 0);
+        app.scroll_down(sidebar, 0);
+        assert_eq!(app.sidebar_scroll, 1);
+        for _ in 0..30 {
+            app.scroll_down(sidebar, 0);
+        }
+        assert_eq!(app.sidebar_scroll, 13);
+
+        // Pages are the rows between the two hidden counts
+        app.scroll_page(false, sidebar, 0);
+        assert_eq!(app.sidebar_scroll, 7);
+        app.scroll_page(false, sidebar, 0);
+        app.scroll_page(false, sidebar, 0);
+        assert_eq!(app.sidebar_scroll, 0);
+        app.scroll_page(true, sidebar, 0);
+        assert_eq!(app.sidebar_scroll, 6);
+        app.scroll_to_edge(true, sidebar, 0);
+        assert_eq!(app.sidebar_scroll, 13);
+
+        // Content that shrank under the scroll: Up moves right away
+        let shrunk = SidebarExtent {
+            lines: 12,
+            height: 8,
+        };
+        app.scroll_up(shrunk, 0);
+        assert_eq!(app.sidebar_scroll, 4);
+        app.scroll_to_edge(false, shrunk, 0);
+        assert_eq!(app.sidebar_scroll, 0);
+    }
+
+    #[test]
+    fn selecting_an_agent_scrolls_its_header_into_view() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        for id in 1..=12 {
+            let path = PathBuf::from(format!("/tmp/{id}.jsonl"));
+            app.agents.insert(id, AgentState::new(id, path));
+        }
+        let sidebar = SidebarExtent {
+            lines: 30,
+            height: 8,
+        };
+        app.select_agent(10);
+        app.show_selected_in_sidebar(sidebar);
+        // Six lines show between the hidden counts; the header is the last
+        assert_eq!(app.sidebar_scroll, 4);
+        // Already in view: nothing moves
+        app.select_agent(5);
+        app.show_selected_in_sidebar(sidebar);
+        assert_eq!(app.sidebar_scroll, 4);
+        // Above the view, it becomes the first line shown
+        app.select_agent(2);
+        app.show_selected_in_sidebar(sidebar);
+        assert_eq!(app.sidebar_scroll, 1);
+
+        // A filter that hides agents listed above moves the header up
+        app.search_query = "no match".to_string();
+        app.select_agent(12);
+        app.show_selected_in_sidebar(sidebar);
+        assert_eq!(app.sidebar_scroll, 0);
+    }
+
+    #[test]
+    fn event_log_pages_and_jumps_to_either_end() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        let mut agent = AgentState::new(1, PathBuf::from("/tmp/1.jsonl"));
+        let now = SystemTime::now();
+        for i in 0..12 {
+            agent
+                .log
+                .push(now, events::AgentEventKind::Text, format!("line {i}"));
+        }
+        app.agents.insert(1, agent);
+        app.select_agent(1);
+        app.toggle_event_log();
+        app.focus = PanelFocus::EventLog;
+        let sidebar = SidebarExtent::default();
+
+        // Up a page goes back in time, no further than the oldest line
+        app.scroll_page(false, sidebar, 5);
+        assert_eq!(app.event_log_scroll, 5);
+        app.scroll_page(false, sidebar, 5);
+        assert_eq!(app.event_log_scroll, 7);
+        app.scroll_page(true, sidebar, 5);
+        assert_eq!(app.event_log_scroll, 2);
+        app.scroll_to_edge(true, sidebar, 5);
+        assert_eq!(app.event_log_scroll, 0);
+        app.scroll_to_edge(false, sidebar, 5);
+        assert_eq!(app.event_log_scroll, 7);
+        assert_eq!(app.sidebar_scroll, 0);
+    }
+
+    #[test]
+    fn sorted_agent_ids() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.agents
+            .insert(3, AgentState::new(3, PathBuf::from("/tmp/3.jsonl")));
+        app.agents
+            .insert(1, AgentState::new(1, PathBuf::from("/tmp/1.jsonl")));
+        assert_eq!(app.sorted_agent_ids(), vec![1, 3]);
+    }
+
+    #[test]
+    fn select_agent_existing() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.agents
+            .insert(1, AgentState::new(1, PathBuf::from("/tmp/1.jsonl")));
+        app.select_agent(1);
+        assert_eq!(app.selected_agent, Some(1));
+    }
+
+    #[test]
+    fn select_agent_nonexistent() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.select_agent(99);
+        assert!(app.selected_agent.is_none());
+    }
+
+    #[test]
+    fn agent_anim_state_idle_when_no_tools() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.agents
+            .insert(1, AgentState::new(1, PathBuf::from("/tmp/1.jsonl")));
+        assert_eq!(app.agent_anim_state(1), AnimState::Idle);
+    }
+
+    #[test]
+    fn agent_anim_state_reading() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        let mut agent = AgentState::new(1, PathBuf::from("/tmp/1.jsonl"));
+        agent.add_tool(parser::ToolUseEvent {
+            tool_id: "t1".to_string(),
+            tool_name: "Read".to_string(),
+            display_status: "Reading foo.rs".to_string(),
+            is_reading: true,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
+        });
+        app.agents.insert(1, agent);
+        assert_eq!(app.agent_anim_state(1), AnimState::Reading);
+    }
+
+    #[test]
+    fn agent_anim_state_typing() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        let mut agent = AgentState::new(1, PathBuf::from("/tmp/1.jsonl"));
+        agent.add_tool(parser::ToolUseEvent {
+            tool_id: "t1".to_string(),
+            tool_name: "Write".to_string(),
+            display_status: "Writing foo.rs".to_string(),
+            is_reading: false,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
+        });
+        app.agents.insert(1, agent);
+        assert_eq!(app.agent_anim_state(1), AnimState::Typing);
+    }
+
+    #[test]
+    fn sub_agents_follow_their_sidechain() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        let subagents = project.join("s").join("subagents");
+        std::fs::create_dir_all(&subagents).unwrap();
+        let session = project.join("s.jsonl");
+        let task = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Task","input":{"description":"Find auth","prompt":"Find the auth code","subagent_type":"Explore"}}]}}"#;
+        std::fs::write(&session, format!("{task}\n")).unwrap();
+        let opening =
+            r#"{"type":"user","isSidechain":true,"message":{"content":"Find the auth code"}}"#;
+        let read = r#"{"type":"assistant","isSidechain":true,"message":{"content":[{"type":"tool_use","id":"s1","name":"Read","input":{"file_path":"/src/auth.rs"}}]}}"#;
+        std::fs::write(
+            subagents.join("agent-a1.jsonl"),
+            format!("{opening}\n{read}\n"),
+        )
+        .unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        // The sidechain is a sub-agent, not an agent of its own
+        assert_eq!(app.agents.len(), 1);
+        let sub = &app.agents[&1].sub_agents[0];
+        assert_eq!(sub.agent_type, "Explore");
+        assert_eq!(
+            sub.active_tools
+                .iter()
+                .map(|t| t.display_status.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Reading auth.rs"]
+        );
+
+        // The parent's tool result ends the sub-agent
+        let result =
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1"}]}}"#;
+        std::fs::write(&session, format!("{task}\n{result}\n")).unwrap();
+        app.tick();
+        assert!(app.agents[&1].sub_agents.is_empty());
+    }
+
+    #[test]
+    fn resumed_session_takes_over_the_desk_it_resumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let prompt = r#"{"type":"user","uuid":"u1","message":{"content":"Fix the flaky test"}}"#;
+        std::fs::write(project.join("a.jsonl"), format!("{prompt}\n")).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        app.selected_agent = Some(1);
+        assert_eq!(app.agents[&1].last_uuid.as_deref(), Some("u1"));
+
+        let resumed = project.join("b.jsonl");
+        let bash = r#"{"type":"assistant","parentSessionId":"a","uuid":"u2","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test"}}]}}"#;
+        std::fs::write(&resumed, format!("{bash}\n")).unwrap();
+        app.request_rescan();
+        app.tick();
+        assert_eq!(app.agents.len(), 1);
+        let agent = &app.agents[&1];
+        assert_eq!(agent.session_file, resumed);
+        assert_eq!(agent.active_tools[0].tool_name, "Bash");
+        assert_eq!(app.selected_agent, Some(1));
+        assert_eq!(app.session_tracker.get_id(&resumed), Some(1));
+
+        // The ID the resumed file had briefly goes to the next session
+        std::fs::write(project.join("c.jsonl"), format!("{prompt}\n")).unwrap();
+        app.request_rescan();
+        app.tick();
+        assert_eq!(app.agents.len(), 2);
+        assert!(app.agents.contains_key(&2));
+    }
+
+    #[test]
+    fn summary_of_a_finished_session_resumes_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let prompt = r#"{"type":"user","uuid":"u1","message":{"content":"Fix the flaky test"}}"#;
+        std::fs::write(project.join("a.jsonl"), format!("{prompt}\n")).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+
+        let resumed = project.join("b.jsonl");
+        let summary = r#"{"type":"summary","summary":"Flaky test","leafUuid":"u1"}"#;
+        let next = r#"{"type":"user","uuid":"u2","message":{"content":"go on"}}"#;
+        std::fs::write(&resumed, format!("{summary}\n{next}\n")).unwrap();
+        app.request_rescan();
+        app.tick();
+        assert_eq!(app.sorted_agent_ids(), vec![1]);
+        assert_eq!(app.agents[&1].session_file, resumed);
+        assert!(app.pending_resumes.is_empty());
+    }
+
+    #[test]
+    fn summary_of_a_running_session_leaves_both_desks() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let first = project.join("a.jsonl");
+        let prompt = r#"{"type":"user","uuid":"u1","message":{"content":"Fix the flaky test"}}"#;
+        std::fs::write(&first, format!("{prompt}\n")).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+
+        // The first session keeps writing while a second one starts with a
+        // summary of it
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&first)
+            .unwrap();
+        let progress = r#"{"type":"progress","data":{"type":"bash_progress"}}"#;
+        std::io::Write::write_all(&mut file, format!("{progress}\n").as_bytes()).unwrap();
+        let second = project.join("b.jsonl");
+        let summary = r#"{"type":"summary","summary":"Flaky test","leafUuid":"u1"}"#;
+        let other = r#"{"type":"user","uuid":"v1","message":{"content":"Update the docs"}}"#;
+        std::fs::write(&second, format!("{summary}\n{other}\n")).unwrap();
+        app.request_rescan();
+        app.tick();
+        assert_eq!(app.sorted_agent_ids(), vec![1, 2]);
+        assert_eq!(app.agents[&1].session_file, first);
+        assert_eq!(app.agents[&2].session_file, second);
+
+        // A summary past the file's first message never resumes anything
+        let late = r#"{"type":"summary","summary":"Docs","leafUuid":"u1"}"#;
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&second)
+            .unwrap();
+        std::io::Write::write_all(&mut file, format!("{late}\n").as_bytes()).unwrap();
+        app.tick();
+        assert_eq!(app.sorted_agent_ids(), vec![1, 2]);
+    }
+
+    #[test]
+    fn agents_walk_in_and_out_of_the_office() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        let prompt = r#"{"type":"user","message":{"content":"Run the tests"}}"#;
+        std::fs::write(&session, format!("{prompt}\n")).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        assert_eq!(app.agent_position(1), AgentPosition::Entering(0.0));
+        assert_eq!(app.agent_anim_state(1), AnimState::Walking);
+        let walk = choreography::walk_ticks(app.config.tick_rate);
+        for _ in 1..walk {
+            app.tick();
+            assert!(matches!(app.agent_position(1), AgentPosition::Entering(_)));
+        }
+        app.tick();
+        assert_eq!(app.agent_position(1), AgentPosition::AtDesk);
+        assert_eq!(app.agent_anim_state(1), AnimState::Idle);
+
+        // The session ends: its state goes at once, its character walks out
+        std::fs::remove_file(&session).unwrap();
+        app.session_tracker.remove(&session);
+        app.remove_agent(1);
+        app.tick();
+        assert!(app.agents.is_empty());
+        assert_eq!(app.agent_position(1), AgentPosition::Leaving(0.0));
+        assert_eq!(app.agent_anim_state(1), AnimState::Walking);
+        for _ in 1..walk {
+            app.tick();
+            assert_eq!(app.departing.len(), 1);
+        }
+        app.tick();
+        assert!(app.departing.is_empty());
+        assert_eq!(app.agent_position(1), AgentPosition::AtDesk);
+    }
+
+    #[test]
+    fn prompts_update_the_request_and_queue_while_busy() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        let prompt = r#"{"type":"user","message":{"content":"Refactor the auth module"}}"#;
+        let bash = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test"}}]}}"#;
+        // Sent while the tests ran
+        let queued = r#"{"type":"user","message":{"content":"Also update the docs"}}"#;
+        let blocks = r#"{"type":"user","message":{"content":[{"type":"text","text":"And bump "},{"type":"text","text":"the version"}]}}"#;
+        let result = r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#;
+        let end = r#"{"type":"system","subtype":"turn_duration","duration_ms":1000}"#;
+        std::fs::write(&session, format!("{prompt}\n{bash}\n{queued}\n")).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        // History says what was asked last, but nothing waits on it
+        let agent = &app.agents[&1];
+        assert_eq!(agent.current_request, "Also update the docs");
+        assert_eq!(agent.queued_prompts, 0);
+
+        let mut lines = vec![prompt, bash, queued, blocks, result];
+        std::fs::write(&session, lines.join("\n") + "\n").unwrap();
+        app.tick();
+        let agent = &app.agents[&1];
+        assert_eq!(agent.current_request, "And bump the version");
+        assert_eq!(agent.prompt_summary, "And bump the version");
+        assert_eq!(agent.queued_prompts, 1);
+
+        lines.push(end);
+        std::fs::write(&session, lines.join("\n") + "\n").unwrap();
+        app.tick();
+        assert_eq!(app.agents[&1].queued_prompts, 0);
+        assert_eq!(app.agents[&1].current_request, "And bump the version");
+    }
+
+    #[test]
+    fn remote_friendly_reads_files_whose_size_changed_at_scans() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let text = |t: &str| {
+            format!(
+                r#"{{"type":"assistant","message":{{"content":[{{"type":"text","text":"{t}"}}]}}}}"#
+            )
+        };
+        let session = project.join("s.jsonl");
+        std::fs::write(&session, format!("{}\n", text("first"))).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.apply_config(Config::default().remote_friendly());
+        assert_eq!(app.scan_interval(), Duration::from_secs(10));
+        app.tick();
+        assert_eq!(app.agents[&1].prompt_summary, "first");
+
+        let read_tool = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/a.rs"}}]}}"#;
+        std::fs::write(&session, format!("{}\n{read_tool}\n", text("first"))).unwrap();
+        // Growth is only noticed by the next scan
+        app.tick();
+        assert!(app.agents[&1].active_tools.is_empty());
+        app.request_rescan();
+        app.tick();
+        assert_eq!(app.agents[&1].active_tools.len(), 1);
+    }
+
+    #[test]
+    fn questions_queue_a_notification_when_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("-src-my-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        let prompt = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Fix the auth bug"}]}}"#;
+        let ask = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"q1","name":"AskUserQuestion","input":{"questions":[]}}]}}"#;
+
+        for notify in [false, true] {
+            std::fs::write(&session, format!("{prompt}\n")).unwrap();
+            let mut app = App::new(dir.path().to_path_buf());
+            app.apply_config(Config {
+                notify,
+                ..Config::default()
+            });
+            app.tick();
+            app.tick();
+            assert!(app.take_notifications().is_empty());
+
+            std::fs::write(&session, format!("{prompt}\n{ask}\n")).unwrap();
+            app.tick();
+            assert_eq!(app.agents[&1].status, AgentStatus::NeedsInput);
+            let sent = app.take_notifications();
+            if notify {
+                assert_eq!(sent.len(), 1);
+                assert_eq!(sent[0].title, "Agent #1 needs input");
+                assert!(sent[0].body.ends_with("Fix the auth bug"));
+            } else {
+                assert!(sent.is_empty());
+            }
+        }
+    }
+
+    struct NoProcesses;
+
+    impl ProcessLister for NoProcesses {
+        fn processes(&self) -> Vec<actions::ProcessInfo> {
+            Vec::new()
+        }
+
+        fn open_files(&self, _pid: u32) -> Vec<PathBuf> {
+            Vec::new()
+        }
+    }
+
+    struct OneClaude(&'static str);
+
+    impl ProcessLister for OneClaude {
+        fn processes(&self) -> Vec<actions::ProcessInfo> {
+            vec![actions::ProcessInfo {
+                pid: 4242,
+                args: vec![
+                    "claude".to_string(),
+                    "--resume".to_string(),
+                    self.0.to_string(),
+                ],
+            }]
+        }
+
+        fn open_files(&self, _pid: u32) -> Vec<PathBuf> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn confirmed_kill_interrupts_the_owning_process() {
+        let mut app = app_with_agents(2);
+        app.select_agent(2);
+        app.open_kill_prompt();
+        assert_eq!(app.kill_prompt, Some(2));
+
+        let mut signalled = None;
+        app.confirm_kill(&OneClaude("2"), |pid| {
+            signalled = Some(pid);
+            Ok(())
+        });
+        assert_eq!(signalled, Some(4242));
+        assert_eq!(app.kill_prompt, None);
+        let toast = app.active_toast(Instant::now()).unwrap();
+        assert_eq!(toast.message, "sent SIGINT to Agent #2 (pid 4242)");
+
+        // A failed signal is reported, not retried
+        app.open_kill_prompt();
+        app.confirm_kill(&OneClaude("2"), |_| {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        });
+        assert_eq!(
+            app.active_toast(Instant::now()).unwrap().level,
+            ToastLevel::Error
+        );
+    }
+
+    #[test]
+    fn kill_without_a_process_marks_the_agent_dormant() {
+        let mut app = app_with_agents(1);
+        app.select_agent(1);
+        app.open_kill_prompt();
+        app.confirm_kill(&NoProcesses, |_| panic!("nothing to signal"));
+        assert_eq!(app.agents[&1].status, AgentStatus::Dormant);
+        assert_eq!(
+            app.active_toast(Instant::now()).unwrap().message,
+            "no process found for Agent #1; marked dormant"
+        );
+    }
+
+    #[test]
+    fn kill_prompt_follows_its_agent() {
+        let mut app = app_with_agents(2);
+        // Nothing selected, nothing to ask about
+        app.open_kill_prompt();
+        assert_eq!(app.kill_prompt, None);
+
+        // Selection moving on drops the prompt
+        app.select_agent(1);
+        app.open_kill_prompt();
+        app.select_agent(2);
+        app.tick();
+        assert_eq!(app.kill_prompt, None);
+
+        // So does the session going away
+        app.open_kill_prompt();
+        app.remove_agent(2);
+        assert_eq!(app.kill_prompt, None);
+        assert!(app
+            .active_toast(Instant::now())
+            .unwrap()
+            .message
+            .contains("ended before the kill was confirmed"));
+
+        // And a stale confirmation acts on nobody
+        app.select_agent(1);
+        app.kill_prompt = Some(2);
+        app.confirm_kill(&NoProcesses, |_| panic!("nothing to signal"));
+        assert_eq!(app.agents[&1].status, AgentStatus::Waiting);
+    }
+
+    #[test]
+    fn handled_events_feed_the_event_log_and_notifications() {
+        let mut app = app_with_agents(1);
+        app.apply_config(Config {
+            notify: true,
+            ..Config::default()
+        });
+        let now = Instant::now();
+        app.growth.insert(1, GrowthMeter::new(now));
+        app.handle_events(
+            &[
+                AppEvent::AgentRemoved {
+                    id: 2,
+                    session_file: PathBuf::from("/tmp/2.jsonl"),
+                },
+                AppEvent::StatusChanged {
+                    id: 1,
+                    from: AgentStatus::Active,
+                    to: AgentStatus::Waiting,
+                },
+            ],
+            now,
+        );
+        let logged: Vec<&str> = app
+            .event_log
+            .entries()
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(logged, ["Stopped tracking /tmp/2.jsonl"]);
+        let titles: Vec<String> = app
+            .take_notifications()
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        assert_eq!(titles, ["Agent #1 finished its turn"]);
+    }
+
+    #[test]
+    fn escalation_notifies_once_per_level() {
+        let mut app = app_with_agents(1);
+        app.apply_config(Config {
+            notify: true,
+            ..Config::default()
+        });
+        let t0 = Instant::now();
+        app.agents.get_mut(&1).unwrap().status_timer =
+            timer::StatusTimer::resume(AgentStatus::Waiting, t0, StatusTotals::default());
+        let mut titles = Vec::new();
+        for mins in [1, 5, 6, 15, 40] {
+            app.update_escalations(t0 + Duration::from_secs(mins * 60));
+            titles.extend(app.take_notifications().into_iter().map(|n| n.title));
+        }
+        assert_eq!(
+            titles,
+            [
+                "Agent #1 has waited 5m for you",
+                "Agent #1 has waited 15m for you"
+            ]
+        );
+        assert_eq!(app.agents[&1].escalation, 2);
+        assert_eq!(app.attention_count(), 1);
+
+        // Silenced by the config, while the level still rises
+        app.apply_config(config::parse("[escalation]\nnotify = false").unwrap());
+        app.agents.get_mut(&1).unwrap().escalation = 0;
+        app.update_escalations(t0 + Duration::from_secs(3600));
+        assert!(app.take_notifications().is_empty());
+        assert_eq!(app.agents[&1].escalation, 2);
+    }
+
+    #[test]
+    fn connected_escalation_notifies_once_across_snapshots() {
+        let mut source = app_with_agents(1);
+        let t0 = Instant::now();
+        let agent = source.agents.get_mut(&1).unwrap();
+        agent.status = AgentStatus::Waiting;
+        agent.status_timer =
+            timer::StatusTimer::resume(AgentStatus::Waiting, t0, StatusTotals::default());
+
+        let mut mirror = App::new(PathBuf::new());
+        mirror.apply_config(Config {
+            notify: true,
+            ..Config::default()
+        });
+        let mut titles = Vec::new();
+        for secs in [6 * 60, 6 * 60 + 1, 7 * 60] {
+            let now = t0 + Duration::from_secs(secs);
+            mirror.apply_snapshot(source.snapshot(now), now);
+            mirror.update_escalations(now);
+            titles.extend(mirror.take_notifications().into_iter().map(|n| n.title));
+        }
+        assert_eq!(titles, ["Agent #1 has waited 6m for you"]);
+        assert_eq!(mirror.agents[&1].escalation, 1);
+    }
+
+    #[test]
+    fn tools_start_at_their_record_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let tool_at = |id: &str, at: Option<SystemTime>| {
+            let timestamp = at.map_or(String::new(), |at| {
+                format!(r#""timestamp":"{}","#, humantime::format_rfc3339(at))
+            });
+            format!(
+                r#"{{"type":"assistant",{timestamp}"message":{{"content":[{{"type":"tool_use","id":"{id}","name":"Bash","input":{{"command":"sleep 1"}}}}]}}}}"#
+            )
+        };
+        let lines = [
+            tool_at("t1", Some(SystemTime::now() - Duration::from_secs(90))),
+            tool_at("t2", None),
+            // A clock ahead of ours doesn't start it in the future
+            tool_at("t3", Some(SystemTime::now() + Duration::from_secs(600))),
+        ];
+        std::fs::write(project.join("s.jsonl"), format!("{}\n", lines.join("\n"))).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        let agent = app.agents.values().next().unwrap();
+        let elapsed: Vec<u64> = agent
+            .active_tools
+            .iter()
+            .map(|tool| tool.started_at.elapsed().as_secs())
+            .collect();
+        assert!((90..100).contains(&elapsed[0]), "{elapsed:?}");
+        assert!(elapsed[1] < 10, "{elapsed:?}");
+        assert!(elapsed[2] < 10, "{elapsed:?}");
+    }
+
+    #[test]
+    fn live_records_reveal_clock_skew_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        let text_at = |at: SystemTime| {
+            format!(
+                r#"{{"type":"assistant","timestamp":"{}","message":{{"content":[{{"type":"text","text":"hi"}}]}}}}"#,
+                humantime::format_rfc3339(at)
+            )
+        };
+        let mut lines = vec![text_at(SystemTime::now() - Duration::from_secs(86_400))];
+        std::fs::write(&session, format!("{}\n", lines.join("\n"))).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        app.tick();
+        // History from yesterday isn't skew
+        assert_eq!(app.clock_skew, None);
+
+        let append = |lines: &mut Vec<String>, at: SystemTime| {
+            lines.push(text_at(at));
+            std::fs::write(&session, format!("{}\n", lines.join("\n"))).unwrap();
+        };
+        append(&mut lines, SystemTime::now() + Duration::from_secs(600));
+        app.tick();
+        assert!(matches!(app.clock_skew, Some(ClockSkew::Ahead(d)) if d.as_secs() > 590));
+        assert!(app.toast.take().unwrap().message.contains("+9m"));
+
+        append(&mut lines, SystemTime::now() + Duration::from_secs(600));
+        app.tick();
+        assert!(app.clock_skew.is_some());
+        assert!(app.toast.is_none(), "warned only once");
+
+        append(&mut lines, SystemTime::now());
+        app.tick();
+        assert_eq!(app.clock_skew, None);
+    }
+
+    #[test]
+    fn removed_agents_finalize_status_time() {
+        use crate::state::timer::StatusTimer;
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        std::fs::write(&session, "").unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        assert_eq!(app.agents.len(), 1);
+
+        let an_hour_ago = Instant::now() - Duration::from_secs(3600);
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.status_timer = StatusTimer::new(AgentStatus::Waiting, an_hour_ago);
+
+        std::fs::remove_file(&session).unwrap();
+        app.request_rescan();
+        app.tick();
+        assert!(app.agents.is_empty());
+        assert!(app.retired_status_totals.waiting >= Duration::from_secs(3600));
+    }
+
+    /// Tick until `done` holds, giving the OS time to deliver events.
+    fn tick_until(app: &mut App, done: impl Fn(&App) -> bool) -> bool {
+        for _ in 0..100 {
+            app.tick();
+            if done(app) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn fs_events_drive_discovery_and_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let mut app = App::new(dir.path().to_path_buf());
+        app.watch_fs_events().unwrap();
+        assert!(app.is_watching_fs_events());
+        app.tick();
+        assert!(app.agents.is_empty());
+        let scanned = app.last_scan_at;
+
+        // A new session shows up without waiting for a scan
+        let session = project.join("s.jsonl");
+        std::fs::write(&session, "").unwrap();
+        assert!(tick_until(&mut app, |app| app.agents.len() == 1));
+
+        // An append is read as soon as it's reported
+        let line = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"ls"}}]}}"#;
+        std::fs::write(&session, format!("{line}\n")).unwrap();
+        assert!(tick_until(&mut app, |app| {
+            app.agents[&1].active_tools.len() == 1
+        }));
+        assert!(app.reader.is_tracking(&session));
+
+        // Deleting the file retires the agent, and its read offset
+        std::fs::remove_file(&session).unwrap();
+        assert!(tick_until(&mut app, |app| app.agents.is_empty()));
+        assert!(!app.reader.is_tracking(&session));
+        // All without another full scan
+        assert_eq!(app.last_scan_at, scanned);
+    }
+
+    #[test]
+    fn unwatched_files_are_not_read() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("projects")).unwrap();
+        let mut app = App::new(dir.path().to_path_buf());
+        app.watch_fs_events().unwrap();
+        let path = PathBuf::from("/tmp/quiet.jsonl");
+        app.agents.insert(1, AgentState::new(1, path.clone()));
+        assert!(app.files_to_read().is_empty());
+        app.changed_files.insert(path.clone());
+        assert_eq!(app.files_to_read(), vec![(1, path)]);
+    }
+
+    #[test]
+    fn polls_when_watcher_cannot_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new(dir.path().to_path_buf());
+        assert!(app.watch_fs_events().is_err());
+        assert!(!app.is_watching_fs_events());
+    }
+
+    fn app_with_agents(n: u32) -> App {
         let mut app = App::new(PathBuf::from("/tmp"));
-        let mut agent = AgentState::new(1, PathBuf::from("/tmp/1.jsonl"));
+        for id in 1..=n {
+            let path = PathBuf::from(format!("/tmp/{id}.jsonl"));
+            app.agents.insert(id, AgentState::new(id, path));
+        }
+        app
+    }
+
+    #[test]
+    fn scale_mode_flips_on_above_threshold() {
+        let app = app_with_agents(DEFAULT_SCALE_THRESHOLD as u32);
+        assert!(!app.scale_mode());
+        let app = app_with_agents(100);
+        assert!(app.scale_mode());
+    }
+
+    #[test]
+    fn reads_every_file_each_tick_below_threshold() {
+        let mut app = app_with_agents(5);
+        assert_eq!(app.files_to_read().len(), 5);
+        assert_eq!(app.files_to_read().len(), 5);
+    }
+
+    #[test]
+    fn round_robin_budget_covers_all_files() {
+        let mut app = app_with_agents(100);
+        let mut seen = std::collections::HashSet::new();
+        let ticks = 100usize.div_ceil(SCALE_FILES_PER_TICK);
+        for _ in 0..ticks {
+            let batch = app.files_to_read();
+            assert_eq!(batch.len(), SCALE_FILES_PER_TICK);
+            seen.extend(batch.into_iter().map(|(id, _)| id));
+        }
+        assert_eq!(seen.len(), 100);
+    }
+
+    #[test]
+    fn extra_roots_are_scanned() {
+        let primary = tempfile::tempdir().unwrap();
+        let extra = tempfile::tempdir().unwrap();
+        let project = extra.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("s.jsonl"), "").unwrap();
+
+        let mut app = App::new(primary.path().to_path_buf());
+        app.extra_roots.push(extra.path().to_path_buf());
+        app.tick();
+        assert_eq!(app.agents.len(), 1);
+        assert_eq!(app.watched_roots().len(), 2);
+    }
+
+    #[test]
+    fn project_scope_skips_other_projects_and_survives_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        for project in ["-src-mine", "-src-theirs"] {
+            let project = dir.path().join("projects").join(project);
+            std::fs::create_dir_all(&project).unwrap();
+            std::fs::write(project.join("s.jsonl"), "").unwrap();
+        }
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.apply_config(Config {
+            project: Some("-src-mine".to_string()),
+            ..Config::default()
+        });
+        app.apply_config(Config::default());
+        app.tick();
+        assert_eq!(app.agents.len(), 1);
+        assert!(app.agents[&1].session_file.ends_with("-src-mine/s.jsonl"));
+    }
+
+    #[test]
+    fn first_sensitive_tool_use_is_logged_after_grace() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        let bash = |id: &str, cmd: &str| {
+            format!(
+                r#"{{"type":"assistant","message":{{"content":[{{"type":"tool_use","id":"{id}","name":"Bash","input":{{"command":"{cmd}"}}}}]}}}}"#
+            ) + "\n"
+        };
+        // Used during the first minutes of the session: expected, silent
+        std::fs::write(&session, bash("t1", "cargo build")).unwrap();
+        let mut app = App::new(dir.path().to_path_buf());
+        app.config.alerts.sensitive_tools = vec!["Write".to_string()];
+        app.tick();
+        // Only the session being found is logged
+        assert_eq!(app.event_log.len(), 1);
+        assert!(app
+            .event_log
+            .entries()
+            .all(|e| e.message.starts_with("Tracking ")));
+        assert!(app.toast.is_none());
+
+        // Later, a repeat is silent but a new sensitive tool is flagged
+        let agent = app.agents.values_mut().next().unwrap();
+        agent.started_at = Instant::now()
+            .checked_sub(alerts::FIRST_USE_GRACE)
+            .expect("uptime longer than the grace period");
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&session)
+            .unwrap();
+        std::io::Write::write_all(&mut f, bash("t2", "cargo test").as_bytes()).unwrap();
+        std::io::Write::write_all(
+            &mut f,
+            br#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t3","name":"Write","input":{"file_path":"/src/a.rs"}}]}}
+"#,
+        )
+        .unwrap();
+        app.tick();
+        let messages: Vec<&str> = app
+            .event_log
+            .entries()
+            .skip(1)
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("used Write for the first time (Writing a.rs)"));
+        assert_eq!(app.toast.as_ref().unwrap().level, ToastLevel::Warning);
+        assert_eq!(app.agents.values().next().unwrap().tools_seen.len(), 2);
+    }
+
+    #[test]
+    fn first_use_grace_counts_from_the_sessions_first_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        // A session that started well before it was tracked
+        let started = SystemTime::now() - alerts::FIRST_USE_GRACE * 5;
+        std::fs::write(
+            &session,
+            format!(
+                r#"{{"type":"user","timestamp":"{}","message":{{"content":"refactor"}}}}"#,
+                humantime::format_rfc3339(started)
+            ) + "\n",
+        )
+        .unwrap();
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        assert!(app.toast.is_none());
+
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&session)
+            .unwrap();
+        std::io::Write::write_all(
+            &mut f,
+            br#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo publish"}}]}}
+"#,
+        )
+        .unwrap();
+        app.tick();
+        let toast = app.toast.as_ref().expect("first Bash use is flagged");
+        assert!(toast.message.contains("used Bash for the first time"));
+    }
+
+    #[test]
+    fn growth_rate_counts_appends_but_not_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let session = project.join("s.jsonl");
+        std::fs::write(&session, "x".repeat(4999) + "\n").unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+        let id = *app.agents.keys().next().unwrap();
+        assert_eq!(app.growth_rate(id, Instant::now()), Some(0.0));
+
+        // 1000 bytes within the first bucket: 1000 B per 10s
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&session)
+            .unwrap();
+        std::io::Write::write_all(&mut f, ("y".repeat(999) + "\n").as_bytes()).unwrap();
+        app.tick();
+        app.tick();
+        assert_eq!(app.growth_rate(id, Instant::now()), Some(6000.0));
+    }
+
+    #[test]
+    fn stalled_needs_a_tool_in_flight_and_no_growth() {
+        let mut app = app_with_agents(1);
+        let agent = app.agents.get_mut(&1).unwrap();
         agent.add_tool(parser::ToolUseEvent {
             tool_id: "t1".to_string(),
-            tool_name: "Write".to_string(),
-            display_status: "Writing foo.rs".to_string(),
+            tool_name: "Bash".to_string(),
+            display_status: "Running: cargo build".to_string(),
             is_reading: false,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
         });
-        app.agents.insert(1, agent);
-        assert_eq!(app.agent_anim_state(1), AnimState::Typing);
+        let start = agent.last_activity;
+        let later = start + STALL_AFTER;
+        // Growth unknown: no verdict
+        assert!(!app.looks_stalled(1, later));
+
+        app.growth.insert(1, GrowthMeter::new(start));
+        assert!(!app.looks_stalled(1, start + Duration::from_secs(30)));
+        assert!(app.looks_stalled(1, later));
+
+        // The file still growing means it's busy, not stuck
+        app.growth
+            .get_mut(&1)
+            .unwrap()
+            .record(4096, later - Duration::from_secs(5));
+        assert!(!app.looks_stalled(1, later));
+
+        app.growth.insert(1, GrowthMeter::new(start));
+        app.agents.get_mut(&1).unwrap().mark_waiting();
+        assert!(!app.looks_stalled(1, later));
+    }
+
+    #[test]
+    fn stored_tool_status_is_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(
+            project.join("s.jsonl"),
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"deploy corp-123456"}}]}}
+"#,
+        )
+        .unwrap();
+        let mut app = App::new(dir.path().to_path_buf());
+        app.apply_config(Config {
+            redact_patterns: vec!["corp-[0-9]+".to_string()],
+            ..Config::default()
+        });
+        app.tick();
+        let agent = app.agents.values().next().unwrap();
+        assert_eq!(
+            agent.active_tools[0].display_status,
+            "Running: deploy \u{2022}\u{2022}\u{2022}"
+        );
+    }
+
+    #[test]
+    fn token_usage_accumulates_across_turns() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        // The first message has two blocks, each record repeating its usage;
+        // the second turn's record has no usage at all
+        std::fs::write(
+            project.join("s.jsonl"),
+            concat!(
+                r#"{"type":"user","message":{"content":"go"}}"#,
+                "\n",
+                r#"{"type":"assistant","message":{"id":"m1","model":"claude-opus-4-1","content":[{"type":"text","text":"ok"}],"usage":{"input_tokens":1200,"output_tokens":40,"cache_read_input_tokens":30000}}}"#,
+                "\n",
+                r#"{"type":"assistant","message":{"id":"m1","content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"ls"}}],"usage":{"input_tokens":1200,"output_tokens":90,"cache_read_input_tokens":30000}}}"#,
+                "\n",
+                r#"{"type":"system","subtype":"turn_duration","duration_ms":900}"#,
+                "\n",
+                r#"{"type":"user","message":{"content":"again"}}"#,
+                "\n",
+                r#"{"type":"assistant","message":{"id":"m2","content":[{"type":"text","text":"done"}],"usage":{"input_tokens":300,"output_tokens":10}}}"#,
+                "\n",
+                r#"{"type":"assistant","message":{"id":"m3","content":[{"type":"text","text":"more"}]}}"#,
+                "\n",
+                r#"{"type":"system","subtype":"turn_duration","duration_ms":400}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let mut app = App::new(dir.path().to_path_buf());
+        app.tick();
+
+        let usage = &app.agents.values().next().unwrap().usage;
+        assert_eq!(usage.total_input_tokens, 1500);
+        assert_eq!(usage.total_output_tokens, 100);
+        assert_eq!(usage.total_cache_read_tokens, 30000);
+        assert_eq!(
+            (usage.turn_input_tokens, usage.turn_output_tokens),
+            (300, 10)
+        );
+        assert_eq!(usage.model.as_deref(), Some("claude-opus-4-1"));
+        assert_eq!(app.total_tokens(), (1500, 100));
+    }
+
+    #[test]
+    fn unseen_badge_follows_selection_and_activity() {
+        let mut app = app_with_agents(2);
+        // Never looked at
+        assert!(app.is_unseen(1));
+
+        app.select_agent(1);
+        assert!(!app.is_unseen(1));
+
+        // Moving away keeps it seen until something new happens
+        app.select_agent(2);
+        assert!(!app.is_unseen(1));
+        app.agents.get_mut(&1).unwrap().last_activity = Instant::now() + Duration::from_secs(1);
+        assert!(app.is_unseen(1));
+
+        // Looking again clears it
+        app.last_seen_tick
+            .insert(1, Instant::now() + Duration::from_secs(2));
+        assert!(!app.is_unseen(1));
+    }
+
+    #[test]
+    fn selected_agent_stays_seen_across_ticks() {
+        let mut app = app_with_agents(1);
+        app.select_agent(1);
+        app.agents.get_mut(&1).unwrap().last_activity = Instant::now();
+        app.tick();
+        assert!(!app.is_unseen(1));
+    }
+
+    #[test]
+    fn sidebar_filter_cycles_and_filters() {
+        let mut app = app_with_agents(3);
+        app.select_agent(1);
+        app.select_agent(2);
+        app.selected_agent = None;
+        assert_eq!(app.filtered_agent_ids(), vec![1, 2, 3]);
+
+        app.cycle_sidebar_filter();
+        assert_eq!(app.sidebar_filter, SidebarFilter::Unseen);
+        assert_eq!(app.filtered_agent_ids(), vec![3]);
+
+        app.agents
+            .get_mut(&2)
+            .unwrap()
+            .set_status(AgentStatus::Dormant);
+        app.cycle_sidebar_filter();
+        assert_eq!(app.sidebar_filter, SidebarFilter::NeedsAttention);
+        assert_eq!(app.filtered_agent_ids(), vec![1, 3]);
+
+        // The selection is never filtered out
+        app.selected_agent = Some(2);
+        assert_eq!(app.filtered_agent_ids(), vec![1, 2, 3]);
+
+        app.cycle_sidebar_filter();
+        assert_eq!(app.sidebar_filter, SidebarFilter::All);
+    }
+
+    #[test]
+    fn status_filters_toggle_and_follow_status() {
+        let mut app = app_with_agents(4);
+        app.agents
+            .get_mut(&1)
+            .unwrap()
+            .set_status(AgentStatus::Active);
+        app.agents
+            .get_mut(&2)
+            .unwrap()
+            .set_status(AgentStatus::NeedsInput);
+        app.agents
+            .get_mut(&4)
+            .unwrap()
+            .set_status(AgentStatus::Dormant);
+
+        app.toggle_status_filter(SidebarFilter::Active);
+        assert_eq!(app.filtered_agent_ids(), vec![1]);
+        app.toggle_status_filter(SidebarFilter::Waiting);
+        assert_eq!(app.filtered_agent_ids(), vec![2, 3]);
+        // Desk and sidebar navigation stay within the filter
+        app.select_next_desk();
+        app.select_next_desk();
+        assert_eq!(app.selected_agent, Some(3));
+        app.select_next();
+        assert_eq!(app.selected_agent, Some(2));
+
+        app.toggle_status_filter(SidebarFilter::Waiting);
+        assert_eq!(app.sidebar_filter, SidebarFilter::All);
+        app.toggle_status_filter(SidebarFilter::Active);
+        app.cycle_sidebar_filter();
+        assert_eq!(app.sidebar_filter, SidebarFilter::Unseen);
+    }
+
+    #[test]
+    fn search_input_takes_every_key_until_closed() {
+        let mut app = app_with_agents(2);
+        app.agents.get_mut(&1).unwrap().project_name = "billing-api".to_string();
+        app.agents
+            .get_mut(&2)
+            .unwrap()
+            .set_prompt_summary("Query the quota service");
+        // Nothing is routed to a closed input
+        assert!(!app.search_key(KeyCode::Char('q')));
+
+        app.open_search_input();
+        for code in [
+            KeyCode::Char('Q'),
+            KeyCode::Char('u'),
+            KeyCode::Char('x'),
+            KeyCode::Backspace,
+            KeyCode::Tab,
+            KeyCode::Char('o'),
+        ] {
+            assert!(app.search_key(code));
+        }
+        assert!(!app.should_quit);
+        assert_eq!(app.search_input.as_deref(), Some("Quo"));
+        // Typing alone filters nothing
+        assert_eq!(app.filtered_agent_ids(), vec![1, 2]);
+        app.search_key(KeyCode::Enter);
+        assert_eq!(app.search_input, None);
+        assert_eq!(app.search_query, "quo");
+        assert_eq!(app.filtered_agent_ids(), vec![2]);
+
+        // Esc drops the edit and keeps the applied search
+        app.open_search_input();
+        assert_eq!(app.search_input.as_deref(), Some("quo"));
+        app.search_key(KeyCode::Char('x'));
+        app.search_key(KeyCode::Esc);
+        assert_eq!(app.search_query, "quo");
+
+        // The selection stays listed; an empty search lists everyone
+        app.select_agent(1);
+        assert_eq!(app.filtered_agent_ids(), vec![1, 2]);
+        app.selected_agent = None;
+        app.open_search_input();
+        for _ in 0..3 {
+            app.search_key(KeyCode::Backspace);
+        }
+        app.search_key(KeyCode::Enter);
+        assert_eq!(app.filtered_agent_ids(), vec![1, 2]);
+    }
+
+    #[test]
+    fn reload_applies_config_and_keeps_agents() {
+        let mut app = app_with_agents(2);
+        app.select_agent(2);
+        app.reload_config(config::parse("scale_threshold = 1\n[keys]\nquit = \"Q\""));
+        assert_eq!(app.scale_threshold, 1);
+        assert_eq!(app.config.keys.quit, config::Key::Char('Q'));
+        assert_eq!(app.agents.len(), 2);
+        assert_eq!(app.selected_agent, Some(2));
+        let toast = app.active_toast(Instant::now()).unwrap();
+        assert_eq!(toast.level, ToastLevel::Info);
+    }
+
+    #[test]
+    fn reload_rejects_invalid_config_and_keeps_old() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.reload_config(config::parse("[theme]\naccent = \"magenta\""));
+        app.reload_config(config::parse(
+            "scale_threshold = 0\n[theme]\naccent = \"red\"",
+        ));
+        assert_eq!(app.config.theme.accent, Some(Color::Magenta));
+        assert_eq!(app.scale_threshold, DEFAULT_SCALE_THRESHOLD);
+        let toast = app.active_toast(Instant::now()).unwrap();
+        assert_eq!(toast.level, ToastLevel::Error);
+        assert!(toast.message.contains("scale_threshold"));
+    }
+
+    #[test]
+    fn scale_threshold_flag_survives_reloads() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.cli_scale_threshold = Some(3);
+        app.apply_config(Config::default());
+        assert_eq!(app.scale_threshold, 3);
+        app.reload_config(config::parse("scale_threshold = 20"));
+        assert_eq!(app.scale_threshold, 3);
+
+        // Without the flag the file decides, and its default when unset
+        app.cli_scale_threshold = None;
+        app.reload_config(config::parse("scale_threshold = 20"));
+        assert_eq!(app.scale_threshold, 20);
+        app.reload_config(config::parse(""));
+        assert_eq!(app.scale_threshold, DEFAULT_SCALE_THRESHOLD);
+    }
+
+    #[test]
+    fn theme_cycles_survives_reloads_and_yields_to_no_color() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.apply_config(config::parse("[theme]\naccent = \"red\"").unwrap());
+        assert_eq!(app.theme.name, ThemeName::Dark);
+        assert_eq!(app.theme.accent.fg, Some(Color::Red));
+
+        app.cycle_theme();
+        assert_eq!(app.theme.name, ThemeName::Light);
+        assert_eq!(
+            app.active_toast(Instant::now()).unwrap().message,
+            "theme: light"
+        );
+        // A picked theme outlives a reload of the file
+        app.reload_config(config::parse("[theme]\nname = \"dark\""));
+        assert_eq!(app.theme.name, ThemeName::Light);
+
+        app.apply_config(Config {
+            no_color: true,
+            ..Config::default()
+        });
+        assert_eq!(app.theme, Theme::mono());
+        app.cycle_theme();
+        assert_eq!(app.theme, Theme::mono());
+        assert_eq!(app.config.theme_override, Some(ThemeName::Light));
+    }
+
+    #[test]
+    fn overrunning_loops_slow_the_tick_and_show_in_the_footer() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        let slowdown = |app: &App| {
+            app.footer_segments(Instant::now())
+                .into_iter()
+                .find(|s| s.kind == FooterSegmentKind::Slowdown)
+                .map(|s| s.text)
+        };
+        for _ in 0..PACER_WINDOW {
+            app.record_loop(Duration::from_millis(10));
+        }
+        assert_eq!(slowdown(&app), None);
+
+        for _ in 0..PACER_WINDOW {
+            app.record_loop(Duration::from_millis(180));
+        }
+        assert_eq!(app.pacer.interval(), Duration::from_millis(150));
+        assert_eq!((app.self_stats.loops, app.self_stats.overruns), (40, 20));
+        assert_eq!(
+            slowdown(&app).as_deref(),
+            Some("slowed to 7 FPS, p95 180ms")
+        );
+
+        // A new tick rate starts over from it
+        app.apply_config(config::parse("tick_rate_ms = 50").unwrap());
+        assert_eq!(app.pacer.interval(), Duration::from_millis(50));
+        assert_eq!(slowdown(&app), None);
+    }
+
+    #[test]
+    fn reload_warns_about_restart_required_settings() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.reload_config(config::parse("claude_dir = \"/elsewhere\""));
+        assert_eq!(app.claude_dir, PathBuf::from("/tmp"));
+        let toast = app.active_toast(Instant::now()).unwrap();
+        assert_eq!(toast.level, ToastLevel::Warning);
+        assert!(toast.message.contains("restart required for claude_dir"));
+    }
+
+    #[test]
+    fn toasts_expire() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.show_toast("hi".to_string(), ToastLevel::Info);
+        let shown = app.toast.as_ref().unwrap().shown_at;
+        assert!(app.active_toast(shown).is_some());
+        assert!(app.active_toast(shown + TOAST_DURATION).is_none());
+    }
+
+    #[test]
+    fn agents_needing_input_never_turn_dormant() {
+        let mut app = app_with_agents(2);
+        let long_ago = Instant::now() - app.config.dormancy_timeout - Duration::from_secs(1);
+        for agent in app.agents.values_mut() {
+            agent.last_activity = long_ago;
+        }
+        app.agents
+            .get_mut(&1)
+            .unwrap()
+            .set_status(AgentStatus::NeedsInput);
+        app.mark_dormant();
+        assert_eq!(app.agents[&1].status, AgentStatus::NeedsInput);
+        assert_eq!(app.agents[&2].status, AgentStatus::Dormant);
+    }
+
+    #[test]
+    fn clock_jump_does_not_flip_agents_dormant() {
+        let mut app = app_with_agents(2);
+        let gap = Duration::from_secs(3 * 3600);
+        let before_gap = Instant::now();
+        let now = before_gap + gap + Duration::from_secs(5);
+        for agent in app.agents.values_mut() {
+            agent.last_activity = before_gap;
+        }
+        app.recover_from_clock_jump(before_gap + Duration::from_secs(5), gap, now);
+        for agent in app.agents.values() {
+            assert!(!agent.is_dormant(app.config.dormancy_timeout.as_secs()));
+            assert_eq!(now - agent.last_activity, Duration::from_secs(5));
+        }
+        assert!(app.rescan_requested);
+    }
+
+    #[test]
+    fn select_next_and_prev_wrap_around_the_sidebar() {
+        let mut app = app_with_agents(3);
+        app.select_next();
+        assert_eq!(app.selected_agent, Some(1));
+        app.select_prev();
+        assert_eq!(app.selected_agent, Some(3));
+        app.select_next();
+        assert_eq!(app.selected_agent, Some(1));
+
+        let mut empty = app_with_agents(0);
+        empty.select_prev();
+        assert_eq!(empty.selected_agent, None);
+    }
+
+    #[test]
+    fn rescan_request_is_consumed_by_next_tick() {
+        let mut app = app_with_agents(0);
+        app.rescan_requested = true;
+        app.tick();
+        assert!(!app.rescan_requested);
+    }
+
+    #[test]
+    fn applied_snapshot_replaces_agents_and_drops_stale_selection() {
+        let source = app_with_agents(3);
+        let now = Instant::now();
+        let snapshot = source.snapshot(now);
+        assert_eq!(
+            snapshot.agents.iter().map(|a| a.id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        let mut mirror = App::new(PathBuf::new());
+        mirror.selected_agent = Some(9);
+        mirror.apply_snapshot(snapshot, now);
+        assert_eq!(mirror.sorted_agent_ids(), vec![1, 2, 3]);
+        assert_eq!(mirror.selected_agent, None);
+
+        mirror.select_agent(2);
+        mirror.apply_snapshot(source.snapshot(now), now);
+        assert_eq!(mirror.selected_agent, Some(2));
     }
 
     #[test]
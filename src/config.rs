@@ -0,0 +1,1139 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crossterm::event::KeyCode;
+use ratatui::style::Color;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::app::FooterSegmentKind;
+use crate::state::alerts;
+use crate::state::escalation;
+use crate::ui::sprites::AnimState;
+use crate::ui::theme::ThemeName;
+use crate::watcher::redact::Redactor;
+use crate::watcher::rules::{RuleMatcher, RuleSpec};
+
+/// File name inside the config directory (see `paths::config_dir`).
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// The config file as written on disk. Every field is optional and unknown
+/// fields are rejected so typos don't go unnoticed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    scale_threshold: Option<usize>,
+    claude_dir: Option<PathBuf>,
+    tick_rate_ms: Option<u64>,
+    dormancy_timeout_secs: Option<u64>,
+    scan_interval_secs: Option<u64>,
+    office_width_percent: Option<u16>,
+    desks_per_row: Option<usize>,
+    #[serde(default)]
+    keys: RawKeys,
+    #[serde(default)]
+    theme: RawTheme,
+    #[serde(default)]
+    alerts: RawAlerts,
+    #[serde(default)]
+    escalation: RawEscalation,
+    #[serde(default)]
+    redact: RawRedact,
+    #[serde(default)]
+    footer: RawFooter,
+    /// Kept loose so one bad rule is skipped instead of failing the file.
+    #[serde(default)]
+    rules: Vec<toml::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawKeys {
+    quit: Option<String>,
+    refresh: Option<String>,
+    filter: Option<String>,
+    export: Option<String>,
+    focus: Option<String>,
+    scroll_up: Option<String>,
+    scroll_down: Option<String>,
+    scroll_top: Option<String>,
+    scroll_bottom: Option<String>,
+    select_next: Option<String>,
+    select_prev: Option<String>,
+    event_log: Option<String>,
+    prev_turn: Option<String>,
+    next_turn: Option<String>,
+    jump_to_time: Option<String>,
+    next_desk: Option<String>,
+    prev_desk: Option<String>,
+    next_page: Option<String>,
+    prev_page: Option<String>,
+    bookmark: Option<String>,
+    cycle_theme: Option<String>,
+    kill_session: Option<String>,
+    search: Option<String>,
+    filter_active: Option<String>,
+    filter_waiting: Option<String>,
+    stats: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTheme {
+    name: Option<String>,
+    accent: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRedact {
+    #[serde(default)]
+    extra_patterns: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFooter {
+    #[serde(default)]
+    hide: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    tool: Option<String>,
+    command: Option<String>,
+    mcp: Option<String>,
+    label: Option<String>,
+    anim: Option<String>,
+    color: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAlerts {
+    sensitive_tools: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEscalation {
+    after_mins: Option<Vec<u64>>,
+    notify: Option<bool>,
+}
+
+pub const DEFAULT_TICK_RATE: Duration = Duration::from_millis(100);
+/// Agents with no activity for this long are shown as dormant.
+pub const DEFAULT_DORMANCY_TIMEOUT: Duration = Duration::from_secs(300);
+/// How often session directories are scanned when polling.
+pub const DEFAULT_SCAN_INTERVAL: Duration = Duration::from_secs(2);
+/// Floors `--remote-friendly` puts under the scan interval and dormancy
+/// timeout, so network mounts are listed rarely and coarse mtimes don't
+/// turn agents dormant early.
+const REMOTE_SCAN_INTERVAL: Duration = Duration::from_secs(10);
+const REMOTE_DORMANCY_TIMEOUT: Duration = Duration::from_secs(900);
+pub const DEFAULT_OFFICE_WIDTH_PERCENT: u16 = 55;
+pub const DEFAULT_DESKS_PER_ROW: usize = 3;
+
+/// A fully validated config, safe to apply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Overrides the scale mode threshold when set.
+    pub scale_threshold: Option<usize>,
+    /// Claude data directory, below `--claude-dir` and the env var.
+    pub claude_dir: Option<PathBuf>,
+    /// How often the dashboard reads sessions and redraws.
+    pub tick_rate: Duration,
+    pub dormancy_timeout: Duration,
+    /// How often session directories are scanned when polling.
+    pub scan_interval: Duration,
+    /// Sessions live on a network mount (`--remote-friendly`): scans list
+    /// each directory once and only files whose size changed are read.
+    pub remote_friendly: bool,
+    /// Send a desktop notification when an agent finishes its turn or
+    /// needs input (`--notify`).
+    pub notify: bool,
+    /// `--no-color` or `NO_COLOR`: draw every theme without colors.
+    pub no_color: bool,
+    /// Only show sessions of the project with this directory name under
+    /// `~/.claude/projects/` (`--project`); every project when unset.
+    pub project: Option<String>,
+    /// Share of the body width given to the office; the sidebar gets the rest.
+    pub office_width_percent: u16,
+    pub desks_per_row: usize,
+    pub keys: KeyMap,
+    pub theme: ThemeSettings,
+    /// Theme picked with `--theme` or cycled at runtime; wins over
+    /// `theme.name`.
+    pub theme_override: Option<ThemeName>,
+    pub alerts: Alerts,
+    pub escalation: Escalation,
+    /// Regexes masked in tool status strings on top of the built-in ones.
+    pub redact_patterns: Vec<String>,
+    pub footer: Footer,
+    /// Valid `[[rules]]` entries, in order.
+    pub rules: Vec<RuleSpec>,
+    /// Why `[[rules]]` entries were skipped, e.g. `rules[2]: missing label`.
+    pub rule_warnings: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            scale_threshold: None,
+            claude_dir: None,
+            tick_rate: DEFAULT_TICK_RATE,
+            dormancy_timeout: DEFAULT_DORMANCY_TIMEOUT,
+            scan_interval: DEFAULT_SCAN_INTERVAL,
+            remote_friendly: false,
+            notify: false,
+            no_color: false,
+            project: None,
+            office_width_percent: DEFAULT_OFFICE_WIDTH_PERCENT,
+            desks_per_row: DEFAULT_DESKS_PER_ROW,
+            keys: KeyMap::default(),
+            theme: ThemeSettings::default(),
+            theme_override: None,
+            alerts: Alerts::default(),
+            escalation: Escalation::default(),
+            redact_patterns: Vec::new(),
+            footer: Footer::default(),
+            rules: Vec::new(),
+            rule_warnings: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// The `--remote-friendly` profile: poll sizes instead of following
+    /// filesystem events, scan less often and wait longer before calling an
+    /// agent dormant. Values already above the profile's floors are kept.
+    pub fn remote_friendly(self) -> Self {
+        Self {
+            remote_friendly: true,
+            scan_interval: self.scan_interval.max(REMOTE_SCAN_INTERVAL),
+            dormancy_timeout: self.dormancy_timeout.max(REMOTE_DORMANCY_TIMEOUT),
+            ..self
+        }
+    }
+}
+
+/// A bindable key: one character, or a named key such as `tab` or `up`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Tab,
+    Enter,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+}
+
+/// Names accepted for keys that aren't a single character.
+const NAMED_KEYS: &[(&str, Key)] = &[
+    ("tab", Key::Tab),
+    ("enter", Key::Enter),
+    ("esc", Key::Esc),
+    ("up", Key::Up),
+    ("down", Key::Down),
+    ("left", Key::Left),
+    ("right", Key::Right),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("space", Key::Char(' ')),
+];
+
+impl Key {
+    pub fn matches(self, code: KeyCode) -> bool {
+        match (self, code) {
+            (Key::Char(c), KeyCode::Char(pressed)) => c == pressed,
+            (Key::Tab, KeyCode::Tab)
+            | (Key::Enter, KeyCode::Enter)
+            | (Key::Esc, KeyCode::Esc)
+            | (Key::Up, KeyCode::Up)
+            | (Key::Down, KeyCode::Down)
+            | (Key::Left, KeyCode::Left)
+            | (Key::Right, KeyCode::Right)
+            | (Key::PageUp, KeyCode::PageUp)
+            | (Key::PageDown, KeyCode::PageDown)
+            | (Key::Home, KeyCode::Home)
+            | (Key::End, KeyCode::End) => true,
+            _ => false,
+        }
+    }
+}
+
+/// How the key appears in hints: `q`, `Tab`, `\u{2191}`.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Key::Char(' ') => "Space",
+            Key::Char(c) => return write!(f, "{c}"),
+            Key::Tab => "Tab",
+            Key::Enter => "Enter",
+            Key::Esc => "Esc",
+            Key::Up => "\u{2191}",
+            Key::Down => "\u{2193}",
+            Key::Left => "\u{2190}",
+            Key::Right => "\u{2192}",
+            Key::PageUp => "PgUp",
+            Key::PageDown => "PgDn",
+            Key::Home => "Home",
+            Key::End => "End",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a remappable key does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Refresh,
+    Filter,
+    Export,
+    Focus,
+    ScrollUp,
+    ScrollDown,
+    ScrollTop,
+    ScrollBottom,
+    SelectNext,
+    SelectPrev,
+    ToggleEventLog,
+    PrevTurn,
+    NextTurn,
+    JumpToTime,
+    NextDesk,
+    PrevDesk,
+    NextPage,
+    PrevPage,
+    Bookmark,
+    CycleTheme,
+    KillSession,
+    Search,
+    FilterActive,
+    FilterWaiting,
+    Stats,
+}
+
+/// Bindings for the remappable actions. Digits always select agents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyMap {
+    pub quit: Key,
+    pub refresh: Key,
+    pub filter: Key,
+    pub export: Key,
+    pub focus: Key,
+    pub scroll_up: Key,
+    pub scroll_down: Key,
+    pub scroll_top: Key,
+    pub scroll_bottom: Key,
+    pub select_next: Key,
+    pub select_prev: Key,
+    pub event_log: Key,
+    pub prev_turn: Key,
+    pub next_turn: Key,
+    pub jump_to_time: Key,
+    pub next_desk: Key,
+    pub prev_desk: Key,
+    pub next_page: Key,
+    pub prev_page: Key,
+    pub bookmark: Key,
+    pub cycle_theme: Key,
+    pub kill_session: Key,
+    pub search: Key,
+    pub filter_active: Key,
+    pub filter_waiting: Key,
+    pub stats: Key,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            quit: Key::Char('q'),
+            refresh: Key::Char('r'),
+            filter: Key::Char('u'),
+            export: Key::Char('x'),
+            focus: Key::Tab,
+            scroll_up: Key::Up,
+            scroll_down: Key::Down,
+            scroll_top: Key::Home,
+            scroll_bottom: Key::End,
+            select_next: Key::Char('n'),
+            select_prev: Key::Char('p'),
+            event_log: Key::Char('l'),
+            prev_turn: Key::Char('['),
+            next_turn: Key::Char(']'),
+            jump_to_time: Key::Char('t'),
+            next_desk: Key::Char('j'),
+            prev_desk: Key::Char('k'),
+            next_page: Key::PageDown,
+            prev_page: Key::PageUp,
+            bookmark: Key::Char('B'),
+            // `t` already jumps to a time
+            cycle_theme: Key::Char('T'),
+            // `x` already exports
+            kill_session: Key::Char('K'),
+            search: Key::Char('/'),
+            filter_active: Key::Char('a'),
+            filter_waiting: Key::Char('w'),
+            stats: Key::Char('s'),
+        }
+    }
+}
+
+impl KeyMap {
+    /// Every binding with its `[keys]` name.
+    fn bindings(&self) -> [(&'static str, Key, Action); 26] {
+        [
+            ("quit", self.quit, Action::Quit),
+            ("refresh", self.refresh, Action::Refresh),
+            ("filter", self.filter, Action::Filter),
+            ("export", self.export, Action::Export),
+            ("focus", self.focus, Action::Focus),
+            ("scroll_up", self.scroll_up, Action::ScrollUp),
+            ("scroll_down", self.scroll_down, Action::ScrollDown),
+            ("scroll_top", self.scroll_top, Action::ScrollTop),
+            ("scroll_bottom", self.scroll_bottom, Action::ScrollBottom),
+            ("select_next", self.select_next, Action::SelectNext),
+            ("select_prev", self.select_prev, Action::SelectPrev),
+            ("event_log", self.event_log, Action::ToggleEventLog),
+            ("prev_turn", self.prev_turn, Action::PrevTurn),
+            ("next_turn", self.next_turn, Action::NextTurn),
+            ("jump_to_time", self.jump_to_time, Action::JumpToTime),
+            ("next_desk", self.next_desk, Action::NextDesk),
+            ("prev_desk", self.prev_desk, Action::PrevDesk),
+            ("next_page", self.next_page, Action::NextPage),
+            ("prev_page", self.prev_page, Action::PrevPage),
+            ("bookmark", self.bookmark, Action::Bookmark),
+            ("cycle_theme", self.cycle_theme, Action::CycleTheme),
+            ("kill_session", self.kill_session, Action::KillSession),
+            ("search", self.search, Action::Search),
+            ("filter_active", self.filter_active, Action::FilterActive),
+            ("filter_waiting", self.filter_waiting, Action::FilterWaiting),
+            ("stats", self.stats, Action::Stats),
+        ]
+    }
+
+    /// The action bound to a pressed key, if any.
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings()
+            .into_iter()
+            .find(|(_, key, _)| key.matches(code))
+            .map(|(_, _, action)| action)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThemeSettings {
+    pub name: ThemeName,
+    /// Replaces the theme's title and focused-border color.
+    pub accent: Option<Color>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alerts {
+    /// Tool name patterns (`*` wildcards) whose first use in a session is
+    /// flagged. Empty turns the alerts off.
+    pub sensitive_tools: Vec<String>,
+}
+
+impl Default for Alerts {
+    fn default() -> Self {
+        Self {
+            sensitive_tools: alerts::default_sensitive_tools(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escalation {
+    /// How long an agent may wait on the user before each escalation
+    /// level, ascending. Empty turns escalation off.
+    pub thresholds: Vec<Duration>,
+    /// Notify at each level, with `--notify`.
+    pub notify: bool,
+}
+
+impl Default for Escalation {
+    fn default() -> Self {
+        Self {
+            thresholds: escalation::default_thresholds(),
+            notify: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Footer {
+    /// Segments never shown on the right side of the footer.
+    pub hidden: Vec<FooterSegmentKind>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    Io(String),
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(msg) => write!(f, "cannot read config: {msg}"),
+            ConfigError::Parse(msg) => write!(f, "config syntax error: {msg}"),
+            ConfigError::Invalid(msg) => write!(f, "invalid config: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Parse and validate config text without applying anything.
+pub fn parse(text: &str) -> Result<Config, ConfigError> {
+    let raw: RawConfig =
+        toml::from_str(text).map_err(|e| ConfigError::Parse(e.message().to_string()))?;
+    validate(raw)
+}
+
+/// Load the config file. A missing file yields the defaults.
+pub fn load(path: &Path) -> Result<Config, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(ConfigError::Io(format!("{}: {e}", path.display()))),
+    }
+}
+
+fn validate(raw: RawConfig) -> Result<Config, ConfigError> {
+    if raw.scale_threshold == Some(0) {
+        return Err(ConfigError::Invalid(
+            "scale_threshold must be at least 1".to_string(),
+        ));
+    }
+
+    let tick_rate = match raw.tick_rate_ms {
+        Some(ms) if !(10..=5000).contains(&ms) => {
+            return Err(ConfigError::Invalid(
+                "tick_rate_ms must be between 10 and 5000".to_string(),
+            ))
+        }
+        Some(ms) => Duration::from_millis(ms),
+        None => DEFAULT_TICK_RATE,
+    };
+    let dormancy_timeout = match raw.dormancy_timeout_secs {
+        Some(0) => {
+            return Err(ConfigError::Invalid(
+                "dormancy_timeout_secs must be at least 1".to_string(),
+            ))
+        }
+        Some(secs) => Duration::from_secs(secs),
+        None => DEFAULT_DORMANCY_TIMEOUT,
+    };
+    let scan_interval = match raw.scan_interval_secs {
+        Some(secs) if !(1..=600).contains(&secs) => {
+            return Err(ConfigError::Invalid(
+                "scan_interval_secs must be between 1 and 600".to_string(),
+            ))
+        }
+        Some(secs) => Duration::from_secs(secs),
+        None => DEFAULT_SCAN_INTERVAL,
+    };
+    let office_width_percent = raw
+        .office_width_percent
+        .unwrap_or(DEFAULT_OFFICE_WIDTH_PERCENT);
+    if !(20..=80).contains(&office_width_percent) {
+        return Err(ConfigError::Invalid(
+            "office_width_percent must be between 20 and 80".to_string(),
+        ));
+    }
+    let desks_per_row = raw.desks_per_row.unwrap_or(DEFAULT_DESKS_PER_ROW);
+    if !(1..=12).contains(&desks_per_row) {
+        return Err(ConfigError::Invalid(
+            "desks_per_row must be between 1 and 12".to_string(),
+        ));
+    }
+
+    let defaults = KeyMap::default();
+    let keys = KeyMap {
+        quit: parse_key("keys.quit", raw.keys.quit, defaults.quit)?,
+        refresh: parse_key("keys.refresh", raw.keys.refresh, defaults.refresh)?,
+        filter: parse_key("keys.filter", raw.keys.filter, defaults.filter)?,
+        export: parse_key("keys.export", raw.keys.export, defaults.export)?,
+        focus: parse_key("keys.focus", raw.keys.focus, defaults.focus)?,
+        scroll_up: parse_key("keys.scroll_up", raw.keys.scroll_up, defaults.scroll_up)?,
+        scroll_down: parse_key(
+            "keys.scroll_down",
+            raw.keys.scroll_down,
+            defaults.scroll_down,
+        )?,
+        scroll_top: parse_key("keys.scroll_top", raw.keys.scroll_top, defaults.scroll_top)?,
+        scroll_bottom: parse_key(
+            "keys.scroll_bottom",
+            raw.keys.scroll_bottom,
+            defaults.scroll_bottom,
+        )?,
+        select_next: parse_key(
+            "keys.select_next",
+            raw.keys.select_next,
+            defaults.select_next,
+        )?,
+        select_prev: parse_key(
+            "keys.select_prev",
+            raw.keys.select_prev,
+            defaults.select_prev,
+        )?,
+        event_log: parse_key("keys.event_log", raw.keys.event_log, defaults.event_log)?,
+        prev_turn: parse_key("keys.prev_turn", raw.keys.prev_turn, defaults.prev_turn)?,
+        next_turn: parse_key("keys.next_turn", raw.keys.next_turn, defaults.next_turn)?,
+        jump_to_time: parse_key(
+            "keys.jump_to_time",
+            raw.keys.jump_to_time,
+            defaults.jump_to_time,
+        )?,
+        next_desk: parse_key("keys.next_desk", raw.keys.next_desk, defaults.next_desk)?,
+        prev_desk: parse_key("keys.prev_desk", raw.keys.prev_desk, defaults.prev_desk)?,
+        next_page: parse_key("keys.next_page", raw.keys.next_page, defaults.next_page)?,
+        prev_page: parse_key("keys.prev_page", raw.keys.prev_page, defaults.prev_page)?,
+        bookmark: parse_key("keys.bookmark", raw.keys.bookmark, defaults.bookmark)?,
+        cycle_theme: parse_key(
+            "keys.cycle_theme",
+            raw.keys.cycle_theme,
+            defaults.cycle_theme,
+        )?,
+        kill_session: parse_key(
+            "keys.kill_session",
+            raw.keys.kill_session,
+            defaults.kill_session,
+        )?,
+        search: parse_key("keys.search", raw.keys.search, defaults.search)?,
+        filter_active: parse_key(
+            "keys.filter_active",
+            raw.keys.filter_active,
+            defaults.filter_active,
+        )?,
+        filter_waiting: parse_key(
+            "keys.filter_waiting",
+            raw.keys.filter_waiting,
+            defaults.filter_waiting,
+        )?,
+        stats: parse_key("keys.stats", raw.keys.stats, defaults.stats)?,
+    };
+    let bound = keys.bindings();
+    for (i, (name, key, _)) in bound.iter().enumerate() {
+        if let Some((other, _, _)) = bound[..i].iter().find(|(_, k, _)| k == key) {
+            return Err(ConfigError::Invalid(format!(
+                "keys.{other} and keys.{name} are both bound to {key}"
+            )));
+        }
+    }
+
+    let theme = ThemeSettings {
+        name: match raw.theme.name {
+            Some(name) => ThemeName::from_name(&name).ok_or_else(|| {
+                let known: Vec<&str> = ThemeName::ALL.iter().map(|t| t.name()).collect();
+                ConfigError::Invalid(format!(
+                    "theme.name: unknown theme {name:?} (expected one of {})",
+                    known.join(", ")
+                ))
+            })?,
+            None => ThemeName::default(),
+        },
+        accent: raw
+            .theme
+            .accent
+            .map(|name| {
+                Color::from_str(&name).map_err(|_| {
+                    ConfigError::Invalid(format!("theme.accent: unknown color {name:?}"))
+                })
+            })
+            .transpose()?,
+    };
+
+    let alerts = match raw.alerts.sensitive_tools {
+        Some(patterns) if patterns.iter().any(|p| p.is_empty()) => {
+            return Err(ConfigError::Invalid(
+                "alerts.sensitive_tools: patterns must not be empty".to_string(),
+            ))
+        }
+        Some(sensitive_tools) => Alerts { sensitive_tools },
+        None => Alerts::default(),
+    };
+
+    let thresholds = match raw.escalation.after_mins {
+        Some(mins) if mins.first() == Some(&0) || mins.windows(2).any(|w| w[0] >= w[1]) => {
+            return Err(ConfigError::Invalid(
+                "escalation.after_mins must be increasing and at least 1".to_string(),
+            ))
+        }
+        Some(mins) => mins
+            .iter()
+            .map(|m| {
+                m.checked_mul(60).map(Duration::from_secs).ok_or_else(|| {
+                    ConfigError::Invalid(format!("escalation.after_mins: {m} is too large"))
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        None => escalation::default_thresholds(),
+    };
+    let escalation = Escalation {
+        thresholds,
+        notify: raw.escalation.notify.unwrap_or(true),
+    };
+
+    if let Err(e) = Redactor::with_extra(&raw.redact.extra_patterns) {
+        return Err(ConfigError::Invalid(format!("redact.extra_patterns: {e}")));
+    }
+
+    let hidden = raw
+        .footer
+        .hide
+        .iter()
+        .map(|name| {
+            FooterSegmentKind::from_name(name).ok_or_else(|| {
+                let known: Vec<&str> = FooterSegmentKind::ALL.iter().map(|k| k.name()).collect();
+                ConfigError::Invalid(format!(
+                    "footer.hide: unknown segment {name:?} (expected one of {})",
+                    known.join(", ")
+                ))
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut rules = Vec::new();
+    let mut rule_warnings = Vec::new();
+    for (i, value) in raw.rules.into_iter().enumerate() {
+        match parse_rule(value) {
+            Ok(rule) => rules.push(rule),
+            Err(reason) => rule_warnings.push(format!("rules[{i}]: {reason}")),
+        }
+    }
+
+    Ok(Config {
+        scale_threshold: raw.scale_threshold,
+        claude_dir: raw.claude_dir,
+        tick_rate,
+        dormancy_timeout,
+        scan_interval,
+        remote_friendly: false,
+        notify: false,
+        no_color: false,
+        project: None,
+        office_width_percent,
+        desks_per_row,
+        keys,
+        theme,
+        theme_override: None,
+        alerts,
+        escalation,
+        redact_patterns: raw.redact.extra_patterns,
+        footer: Footer { hidden },
+        rules,
+        rule_warnings,
+    })
+}
+
+/// One `[[rules]]` entry: exactly one of `tool`, `command` or `mcp`, a
+/// label, and optionally an animation (`typing` when unset) and a color.
+fn parse_rule(value: toml::Value) -> Result<RuleSpec, String> {
+    let raw: RawRule = value.try_into().map_err(|e| e.message().to_string())?;
+    let matcher = match (raw.tool, raw.command, raw.mcp) {
+        (Some(tool), None, None) => RuleMatcher::Tool(tool),
+        (None, Some(command), None) => {
+            Regex::new(&command).map_err(|e| format!("command: {e}"))?;
+            RuleMatcher::Command(command)
+        }
+        (None, None, Some(mcp)) => RuleMatcher::Mcp(mcp),
+        _ => return Err("needs exactly one of tool, command or mcp".to_string()),
+    };
+    if matches!(&matcher, RuleMatcher::Tool(p) | RuleMatcher::Mcp(p) if p.is_empty()) {
+        return Err("pattern must not be empty".to_string());
+    }
+    let label = match raw.label {
+        Some(label) if !label.trim().is_empty() => label,
+        _ => return Err("missing label".to_string()),
+    };
+    let anim = match raw.anim {
+        Some(name) => AnimState::from_name(&name).ok_or_else(|| {
+            let known: Vec<&str> = AnimState::ALL.iter().map(|s| s.name()).collect();
+            format!(
+                "unknown anim {name:?} (expected one of {})",
+                known.join(", ")
+            )
+        })?,
+        None => AnimState::Typing,
+    };
+    let color = match raw.color {
+        Some(name) => Some(Color::from_str(&name).map_err(|_| format!("unknown color {name:?}"))?),
+        None => None,
+    };
+    Ok(RuleSpec {
+        matcher,
+        label,
+        anim,
+        color,
+    })
+}
+
+/// A binding is one character or a key name (see `NAMED_KEYS`); digits are
+/// reserved for agent selection.
+fn parse_key(name: &str, value: Option<String>, default: Key) -> Result<Key, ConfigError> {
+    let Some(value) = value else {
+        return Ok(default);
+    };
+    if let Some((_, key)) = NAMED_KEYS
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(&value))
+    {
+        return Ok(*key);
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_ascii_digit() && !c.is_whitespace() => Ok(Key::Char(c)),
+        _ => {
+            let names: Vec<&str> = NAMED_KEYS.iter().map(|(n, _)| *n).collect();
+            Err(ConfigError::Invalid(format!(
+                "{name} must be a single non-digit character or one of {}, got {value:?}",
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+/// Settings that differ between `old` and `new` but are only read at
+/// startup, so changing them needs a restart.
+pub fn restart_required_changes(old: &Config, new: &Config) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    if old.claude_dir != new.claude_dir {
+        changed.push("claude_dir");
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_file_is_defaults() {
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn parses_all_sections() {
+        let config = parse(
+            r#"
+            scale_threshold = 12
+            claude_dir = "/data/claude"
+
+            [keys]
+            quit = "Q"
+            filter = "f"
+
+            [theme]
+            name = "light"
+            accent = "magenta"
+
+            [alerts]
+            sensitive_tools = ["Bash", "mcp__github__*"]
+
+            [escalation]
+            after_mins = [10, 30, 60]
+            notify = false
+
+            [redact]
+            extra_patterns = ["corp-[0-9]+"]
+
+            [footer]
+            hide = ["fps", "tick_time"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.scale_threshold, Some(12));
+        assert_eq!(config.claude_dir, Some(PathBuf::from("/data/claude")));
+        assert_eq!(config.keys.quit, Key::Char('Q'));
+        assert_eq!(config.keys.refresh, Key::Char('r'));
+        assert_eq!(config.keys.filter, Key::Char('f'));
+        assert_eq!(config.theme.name, ThemeName::Light);
+        assert_eq!(config.theme.accent, Some(Color::Magenta));
+        assert_eq!(
+            config.alerts.sensitive_tools,
+            vec!["Bash", "mcp__github__*"]
+        );
+        assert_eq!(
+            config.escalation,
+            Escalation {
+                thresholds: [600, 1800, 3600].map(Duration::from_secs).to_vec(),
+                notify: false,
+            }
+        );
+        assert_eq!(config.redact_patterns, vec!["corp-[0-9]+"]);
+        assert_eq!(
+            config.footer.hidden,
+            vec![FooterSegmentKind::Fps, FooterSegmentKind::TickTime]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(matches!(
+            parse("scale_threshold = 0"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse("[keys]\nquit = \"qq\""),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse("[keys]\nquit = \"3\""),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse("[keys]\nrefresh = \"u\""),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse("[keys]\nexport = \"q\""),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse("[theme]\naccent = \"sparkly\""),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse("[theme]\nname = \"solarized\""),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse("[alerts]\nsensitive_tools = [\"\"]"),
+            Err(ConfigError::Invalid(_))
+        ));
+        for ladder in ["[0, 5]", "[15, 5]", "[5, 5]", "[5, 9223372036854775807]"] {
+            assert!(matches!(
+                parse(&format!("[escalation]\nafter_mins = {ladder}")),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+        assert!(parse("[escalation]\nafter_mins = []")
+            .unwrap()
+            .escalation
+            .thresholds
+            .is_empty());
+        assert!(matches!(
+            parse("[redact]\nextra_patterns = [\"(unclosed\"]"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            parse("[footer]\nhide = [\"clock\"]"),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn parses_timing_and_layout_settings() {
+        let config = parse(
+            r#"
+            tick_rate_ms = 250
+            dormancy_timeout_secs = 60
+            scan_interval_secs = 5
+            office_width_percent = 70
+            desks_per_row = 4
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.tick_rate, Duration::from_millis(250));
+        assert_eq!(config.dormancy_timeout, Duration::from_secs(60));
+        assert_eq!(config.scan_interval, Duration::from_secs(5));
+        assert_eq!(config.office_width_percent, 70);
+        assert_eq!(config.desks_per_row, 4);
+
+        let defaults = Config::default();
+        assert_eq!(defaults.tick_rate, Duration::from_millis(100));
+        assert_eq!(defaults.dormancy_timeout, Duration::from_secs(300));
+        assert_eq!(
+            (defaults.office_width_percent, defaults.desks_per_row),
+            (55, 3)
+        );
+
+        for bad in [
+            "tick_rate_ms = 1",
+            "dormancy_timeout_secs = 0",
+            "scan_interval_secs = 0",
+            "office_width_percent = 95",
+            "desks_per_row = 0",
+        ] {
+            assert!(matches!(parse(bad), Err(ConfigError::Invalid(_))), "{bad}");
+        }
+    }
+
+    #[test]
+    fn remote_friendly_raises_scan_and_dormancy_floors() {
+        let profile = Config::default().remote_friendly();
+        assert_eq!(profile.scan_interval, Duration::from_secs(10));
+        assert_eq!(profile.dormancy_timeout, Duration::from_secs(900));
+        assert_eq!(profile.tick_rate, DEFAULT_TICK_RATE);
+
+        let slower = parse("scan_interval_secs = 30").unwrap().remote_friendly();
+        assert_eq!(slower.scan_interval, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn translates_pressed_keys_through_the_key_map() {
+        let defaults = KeyMap::default();
+        assert_eq!(defaults.action(KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(defaults.action(KeyCode::Tab), Some(Action::Focus));
+        assert_eq!(defaults.action(KeyCode::Down), Some(Action::ScrollDown));
+        assert_eq!(defaults.action(KeyCode::End), Some(Action::ScrollBottom));
+        assert_eq!(defaults.action(KeyCode::Char('z')), None);
+
+        let keys = parse(
+            r#"
+            [keys]
+            focus = "Enter"
+            scroll_up = "k"
+            scroll_down = "j"
+            select_next = "right"
+            next_desk = "."
+            prev_desk = ","
+            "#,
+        )
+        .unwrap()
+        .keys;
+        assert_eq!(keys.action(KeyCode::Enter), Some(Action::Focus));
+        assert_eq!(keys.action(KeyCode::Tab), None);
+        assert_eq!(keys.action(KeyCode::Char('j')), Some(Action::ScrollDown));
+        assert_eq!(keys.action(KeyCode::Right), Some(Action::SelectNext));
+        assert_eq!(keys.scroll_up.to_string(), "k");
+        assert_eq!(keys.select_next.to_string(), "\u{2192}");
+
+        let Err(ConfigError::Invalid(msg)) = parse("[keys]\nscroll_down = \"n\"") else {
+            panic!("expected a clash");
+        };
+        assert_eq!(
+            msg,
+            "keys.scroll_down and keys.select_next are both bound to n"
+        );
+    }
+
+    #[test]
+    fn rejects_syntax_errors_and_unknown_fields() {
+        assert!(matches!(
+            parse("scale_threshold = "),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            parse("scale_treshold = 5"),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn missing_file_is_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = load(&dir.path().join(CONFIG_FILE_NAME)).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn detects_restart_required_changes() {
+        let old = Config::default();
+        let mut new = parse("scale_threshold = 5").unwrap();
+        assert!(restart_required_changes(&old, &new).is_empty());
+        new.claude_dir = Some(PathBuf::from("/elsewhere"));
+        assert_eq!(restart_required_changes(&old, &new), vec!["claude_dir"]);
+    }
+
+    #[test]
+    fn rules_keep_their_order() {
+        let config = parse(
+            r#"
+            [[rules]]
+            command = '^cargo (test|nextest)'
+            label = "Testing"
+            anim = "testing"
+            color = "magenta"
+
+            [[rules]]
+            mcp = "github"
+            label = "On GitHub"
+            "#,
+        )
+        .unwrap();
+        assert!(config.rule_warnings.is_empty());
+        assert_eq!(
+            config.rules,
+            vec![
+                RuleSpec {
+                    matcher: RuleMatcher::Command("^cargo (test|nextest)".to_string()),
+                    label: "Testing".to_string(),
+                    anim: AnimState::Testing,
+                    color: Some(Color::Magenta),
+                },
+                RuleSpec {
+                    matcher: RuleMatcher::Mcp("github".to_string()),
+                    label: "On GitHub".to_string(),
+                    anim: AnimState::Typing,
+                    color: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_rules_are_skipped_with_a_warning() {
+        let config = parse(
+            r#"
+            [[rules]]
+            command = "cargo ("
+            label = "Broken"
+
+            [[rules]]
+            tool = "Bash"
+            command = "make"
+            label = "Both"
+
+            [[rules]]
+            tool = "Bash"
+            label = "Shell"
+            anim = "dancing"
+
+            [[rules]]
+            tool = "Bash"
+            label = "Shell"
+            colour = "red"
+
+            [[rules]]
+            tool = "Bash"
+
+            [[rules]]
+            tool = "Write"
+            label = "Writing"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.rules[0].label, "Writing");
+        let warnings = &config.rule_warnings;
+        assert_eq!(warnings.len(), 5);
+        assert!(warnings[0].starts_with("rules[0]: command:"));
+        assert_eq!(
+            warnings[1],
+            "rules[1]: needs exactly one of tool, command or mcp"
+        );
+        assert!(warnings[2].starts_with("rules[2]: unknown anim \"dancing\""));
+        assert!(warnings[3].contains("colour"));
+        assert_eq!(warnings[4], "rules[4]: missing label");
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::api::Snapshot;
+use crate::state::agent::{AgentSnapshot, AgentStatus};
+use crate::state::sdd::SddPhase;
+
+/// What changed about one agent between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The agent appeared after the older snapshot.
+    Added {
+        prompt_summary: String,
+    },
+    /// The agent was gone by the newer snapshot.
+    Removed {
+        prompt_summary: String,
+    },
+    Status {
+        from: AgentStatus,
+        to: AgentStatus,
+    },
+    Phase {
+        from: Option<SddPhase>,
+        to: Option<SddPhase>,
+    },
+    /// Tools started and turns finished in between.
+    Activity {
+        tools: u64,
+        turns: u32,
+    },
+    /// A file its tools read or changed for the first time.
+    File(String),
+}
+
+/// Whether a change adds to, takes from, or just changes the picture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sign {
+    Plus,
+    Minus,
+    Changed,
+}
+
+impl Sign {
+    /// `+`, `−` or `~`; a plain hyphen for minus with `ascii`.
+    pub fn symbol(self, ascii: bool) -> &'static str {
+        match self {
+            Sign::Plus => "+",
+            Sign::Minus if ascii => "-",
+            Sign::Minus => "\u{2212}",
+            Sign::Changed => "~",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentChange {
+    pub id: u32,
+    pub change: Change,
+}
+
+impl AgentChange {
+    /// Phases moving back and agents going away count against.
+    pub fn sign(&self) -> Sign {
+        match &self.change {
+            Change::Added { .. } | Change::Activity { .. } | Change::File(_) => Sign::Plus,
+            Change::Removed { .. } => Sign::Minus,
+            Change::Status { .. } => Sign::Changed,
+            Change::Phase { from, to } => {
+                if to < from {
+                    Sign::Minus
+                } else {
+                    Sign::Plus
+                }
+            }
+        }
+    }
+
+    /// `#3 Spec → Apply`, as listed in the diff overlay.
+    pub fn describe(&self) -> String {
+        let id = self.id;
+        let phase = |phase: &Option<SddPhase>| phase.map_or("none", |p| p.label());
+        let plural = |n: u64, word: &str| {
+            if n == 1 {
+                format!("{n} {word}")
+            } else {
+                format!("{n} {word}s")
+            }
+        };
+        match &self.change {
+            Change::Added { prompt_summary } if prompt_summary.is_empty() => {
+                format!("#{id} new agent")
+            }
+            Change::Added { prompt_summary } => format!("#{id} new: {prompt_summary}"),
+            Change::Removed { prompt_summary } if prompt_summary.is_empty() => {
+                format!("#{id} gone")
+            }
+            Change::Removed { prompt_summary } => format!("#{id} gone: {prompt_summary}"),
+            Change::Status { from, to } => {
+                format!("#{id} {} \u{2192} {}", from.label(), to.label())
+            }
+            Change::Phase { from, to } => {
+                format!("#{id} SDD {} \u{2192} {}", phase(from), phase(to))
+            }
+            Change::Activity { tools, turns } => format!(
+                "#{id} {}, {}",
+                plural(*tools, "tool"),
+                plural(u64::from(*turns), "turn")
+            ),
+            Change::File(path) => format!("#{id} {path}"),
+        }
+    }
+}
+
+/// Everything that changed from `old` to `new`, by agent ID. An ID that
+/// now belongs to another session counts as one agent gone and another new.
+pub fn diff_snapshots(old: &Snapshot, new: &Snapshot) -> Vec<AgentChange> {
+    let old_agents: HashMap<u32, &AgentSnapshot> =
+        old.agents.iter().map(|agent| (agent.id, agent)).collect();
+    let new_agents: HashMap<u32, &AgentSnapshot> =
+        new.agents.iter().map(|agent| (agent.id, agent)).collect();
+    let mut ids: Vec<u32> = old_agents
+        .keys()
+        .chain(new_agents.keys())
+        .copied()
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let mut changes = Vec::new();
+    for id in ids {
+        let mut push = |change| changes.push(AgentChange { id, change });
+        match (old_agents.get(&id), new_agents.get(&id)) {
+            (Some(before), Some(after)) if before.session_file == after.session_file => {
+                if before.status != after.status {
+                    push(Change::Status {
+                        from: before.status,
+                        to: after.status,
+                    });
+                }
+                if before.sdd_phase != after.sdd_phase {
+                    push(Change::Phase {
+                        from: before.sdd_phase,
+                        to: after.sdd_phase,
+                    });
+                }
+                let tools = after.tools_started.saturating_sub(before.tools_started);
+                let turns = after
+                    .turns
+                    .completed()
+                    .saturating_sub(before.turns.completed());
+                if tools > 0 || turns > 0 {
+                    push(Change::Activity { tools, turns });
+                }
+                for path in after.files_touched.difference(&before.files_touched) {
+                    push(Change::File(path.clone()));
+                }
+            }
+            (before, after) => {
+                if let Some(before) = before {
+                    push(Change::Removed {
+                        prompt_summary: before.prompt_summary.clone(),
+                    });
+                }
+                if let Some(after) = after {
+                    push(Change::Added {
+                        prompt_summary: after.prompt_summary.clone(),
+                    });
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// State saved with `B`, to compare against later.
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    pub snapshot: Snapshot,
+    pub taken_at: Instant,
+}
+
+/// The open diff overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffView {
+    pub changes: Vec<AgentChange>,
+    /// Rows scrolled past at the top.
+    pub scroll: usize,
+    /// How old the bookmark was when the overlay opened.
+    pub age: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::state::agent::AgentState;
+    use crate::watcher::parser::ToolUseEvent;
+
+    fn tool(name: &str, file: Option<&str>) -> ToolUseEvent {
+        ToolUseEvent {
+            tool_id: format!("t-{name}"),
+            tool_name: name.to_string(),
+            display_status: name.to_string(),
+            is_reading: false,
+            read_range: None,
+            file_path: file.map(str::to_string),
+            rule: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    fn agent(id: u32, summary: &str) -> AgentState {
+        let mut agent = AgentState::new(id, PathBuf::from(format!("/c/projects/-app/{id}.jsonl")));
+        agent.set_prompt_summary(summary);
+        agent
+    }
+
+    fn snapshot(agents: &[&AgentState]) -> Snapshot {
+        let now = Instant::now();
+        Snapshot {
+            agents: agents.iter().map(|agent| agent.snapshot(now)).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn skill(phase: &str) -> ToolUseEvent {
+        let mut skill = tool("Skill", None);
+        skill.display_status = format!("Skill: sdd-{phase}");
+        skill
+    }
+
+    #[test]
+    fn every_kind_of_change_is_listed_per_agent() {
+        let mut working = agent(1, "Fix the auth bug");
+        working.add_tool(skill("spec"));
+        working.add_tool(tool("Read", Some("src/auth.rs")));
+        let mut regressing = agent(2, "Refactor parser");
+        regressing.add_tool(skill("apply"));
+        let leaving = agent(3, "Old work");
+        let old = snapshot(&[&working, &regressing, &leaving]);
+
+        working.add_tool(skill("apply"));
+        working.add_tool(tool("Edit", Some("src/auth.rs")));
+        working.add_tool(tool("Edit", Some("src/login.rs")));
+        working.finish_turn(None);
+        working.mark_waiting();
+        regressing.add_tool(skill("spec"));
+        let arriving = agent(4, "");
+        let new = snapshot(&[&working, &regressing, &arriving]);
+
+        let rows: Vec<(Sign, String)> = diff_snapshots(&old, &new)
+            .iter()
+            .map(|change| (change.sign(), change.describe()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (Sign::Changed, "#1 active \u{2192} waiting".to_string()),
+                (Sign::Plus, "#1 SDD Spec \u{2192} Apply".to_string()),
+                (Sign::Plus, "#1 3 tools, 1 turn".to_string()),
+                (Sign::Plus, "#1 src/login.rs".to_string()),
+                (Sign::Minus, "#2 SDD Apply \u{2192} Spec".to_string()),
+                (Sign::Plus, "#2 1 tool, 0 turns".to_string()),
+                (Sign::Minus, "#3 gone: Old work".to_string()),
+                (Sign::Plus, "#4 new agent".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_snapshots_have_no_changes() {
+        let mut a = agent(1, "work");
+        a.add_tool(tool("Read", Some("src/main.rs")));
+        let before = snapshot(&[&a]);
+        assert!(diff_snapshots(&before, &snapshot(&[&a])).is_empty());
+        assert!(diff_snapshots(&Snapshot::default(), &Snapshot::default()).is_empty());
+    }
+
+    #[test]
+    fn reused_id_is_a_different_agent() {
+        let old = snapshot(&[&agent(1, "first")]);
+        let mut other = agent(1, "second");
+        other.session_file = PathBuf::from("/c/projects/-app/other.jsonl");
+        let changes = diff_snapshots(&old, &snapshot(&[&other]));
+        let rows: Vec<String> = changes.iter().map(AgentChange::describe).collect();
+        assert_eq!(rows, ["#1 gone: first", "#1 new: second"]);
+    }
+}
@@ -0,0 +1,374 @@
+use std::path::{Path, PathBuf};
+
+use crate::paths::{ClaudeDir, PathError};
+use crate::singleton::{self, PidFileState};
+use crate::terminal::{build_split_command, TerminalKind};
+use crate::ui::text::elide_path;
+use crate::watcher::discovery::newest_session_file;
+use crate::watcher::parser;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Something is off, but the app still works.
+    Warn,
+    /// Blocking: the app won't work until this is fixed.
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "\u{2713}",
+            CheckStatus::Warn => "!",
+            CheckStatus::Fail => "\u{2717}",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub remediation: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            remediation: None,
+        }
+    }
+
+    fn problem(
+        name: &'static str,
+        status: CheckStatus,
+        detail: impl Into<String>,
+        remediation: impl Into<String>,
+    ) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            remediation: Some(remediation.into()),
+        }
+    }
+}
+
+/// Everything the doctor inspects, injected so checks can run against fixtures.
+pub struct DoctorEnv<'a> {
+    pub claude_dir: Result<ClaudeDir, PathError>,
+    pub terminal: TerminalKind,
+    pub pid_file: PathBuf,
+    pub locale_utf8: bool,
+    pub program_exists: &'a dyn Fn(&str) -> bool,
+    pub kitty_remote_control: &'a dyn Fn() -> bool,
+    pub is_alive: &'a dyn Fn(u32) -> bool,
+}
+
+/// Run every check in order.
+pub fn run_checks(env: &DoctorEnv) -> Vec<CheckResult> {
+    let mut results = vec![check_claude_dir(&env.claude_dir)];
+    results.push(check_terminal(env.terminal));
+    if let Some(result) = check_split_command(env.terminal, env.program_exists) {
+        results.push(result);
+    }
+    if env.terminal == TerminalKind::Kitty {
+        results.push(check_kitty_remote_control(env.kitty_remote_control));
+    }
+    results.push(check_pid_file(&env.pid_file, env.is_alive));
+    if let Ok(dir) = &env.claude_dir {
+        results.push(check_session_files(&dir.path));
+    }
+    results.push(check_locale(env.locale_utf8));
+    results
+}
+
+fn check_claude_dir(claude_dir: &Result<ClaudeDir, PathError>) -> CheckResult {
+    const NAME: &str = "Claude directory";
+    match claude_dir {
+        Err(e) => CheckResult::problem(
+            NAME,
+            CheckStatus::Fail,
+            e.to_string(),
+            "pass --claude-dir <path> or set $HOME",
+        ),
+        Ok(dir) => {
+            let projects = dir.path.join("projects");
+            if projects.is_dir() {
+                CheckResult::pass(
+                    NAME,
+                    format!("{} (from {})", projects.display(), dir.source.label()),
+                )
+            } else {
+                CheckResult::problem(
+                    NAME,
+                    CheckStatus::Fail,
+                    format!("{} does not exist", projects.display()),
+                    "run a Claude Code session once, or point --claude-dir at the right place",
+                )
+            }
+        }
+    }
+}
+
+fn check_terminal(kind: TerminalKind) -> CheckResult {
+    const NAME: &str = "Terminal";
+    if kind == TerminalKind::Unknown {
+        CheckResult::problem(
+            NAME,
+            CheckStatus::Warn,
+            "no supported multiplexer detected; the launcher will open a new window",
+            "run inside Zellij, WezTerm, Kitty, tmux, or Windows Terminal for split panes",
+        )
+    } else {
+        CheckResult::pass(NAME, kind.label())
+    }
+}
+
+fn check_split_command(
+    kind: TerminalKind,
+    program_exists: &dyn Fn(&str) -> bool,
+) -> Option<CheckResult> {
+    const NAME: &str = "Split command";
+    let cmd = build_split_command(kind, "pixel-agents-tui")?;
+    Some(if program_exists(&cmd.program) {
+        CheckResult::pass(NAME, format!("{} found", cmd.program))
+    } else {
+        CheckResult::problem(
+            NAME,
+            CheckStatus::Fail,
+            format!("{} is not on PATH", cmd.program),
+            format!("install {} or add it to PATH", cmd.program),
+        )
+    })
+}
+
+fn check_kitty_remote_control(probe: &dyn Fn() -> bool) -> CheckResult {
+    const NAME: &str = "Kitty remote control";
+    if probe() {
+        CheckResult::pass(NAME, "enabled")
+    } else {
+        CheckResult::problem(
+            NAME,
+            CheckStatus::Fail,
+            "`kitty @ ls` failed",
+            "add `allow_remote_control yes` to kitty.conf and restart kitty",
+        )
+    }
+}
+
+fn check_pid_file(path: &Path, is_alive: &dyn Fn(u32) -> bool) -> CheckResult {
+    const NAME: &str = "PID file";
+    match singleton::read_state(path, is_alive) {
+        PidFileState::Missing => CheckResult::pass(NAME, "no instance running"),
+        PidFileState::Running(pid) => {
+            CheckResult::pass(NAME, format!("instance running (pid {pid})"))
+        }
+        PidFileState::Stale(pid) => CheckResult::problem(
+            NAME,
+            CheckStatus::Warn,
+            format!("stale: pid {pid} is not running"),
+            format!("rm {}", path.display()),
+        ),
+        PidFileState::Invalid => CheckResult::problem(
+            NAME,
+            CheckStatus::Warn,
+            "does not contain a PID",
+            format!("rm {}", path.display()),
+        ),
+    }
+}
+
+/// Session paths are long and only the tail identifies the file.
+const SESSION_PATH_WIDTH: usize = 56;
+
+fn check_session_files(claude_dir: &Path) -> CheckResult {
+    const NAME: &str = "Session files";
+    let Some(newest) = newest_session_file(claude_dir) else {
+        return CheckResult::pass(NAME, "no session files yet");
+    };
+    let shown = elide_path(&newest.to_string_lossy(), SESSION_PATH_WIDTH);
+    let contents = match std::fs::read_to_string(&newest) {
+        Ok(c) => c,
+        Err(e) => {
+            return CheckResult::problem(
+                NAME,
+                CheckStatus::Fail,
+                format!("cannot read {shown}: {e}"),
+                "check file permissions on ~/.claude/projects",
+            )
+        }
+    };
+    let lines = contents.lines().filter(|l| !l.trim().is_empty()).count();
+    let parsed = contents.lines().filter_map(parser::parse_line).count();
+    if lines > 0 && parsed == 0 {
+        CheckResult::problem(
+            NAME,
+            CheckStatus::Fail,
+            format!("none of {lines} lines in {shown} parse"),
+            "the session format may have changed; please file an issue",
+        )
+    } else {
+        CheckResult::pass(NAME, format!("{parsed}/{lines} lines parse in {shown}"))
+    }
+}
+
+fn check_locale(utf8: bool) -> CheckResult {
+    const NAME: &str = "Locale";
+    if utf8 {
+        CheckResult::pass(NAME, "UTF-8")
+    } else {
+        CheckResult::problem(
+            NAME,
+            CheckStatus::Warn,
+            "not UTF-8; icons fall back to ASCII",
+            "export LANG=en_US.UTF-8 (or another UTF-8 locale)",
+        )
+    }
+}
+
+/// Format results as `✓ name  detail` lines with indented remediations.
+pub fn render_report(results: &[CheckResult]) -> String {
+    let mut out = String::new();
+    for r in results {
+        out.push_str(&format!(
+            "{} {:<22} {}\n",
+            r.status.symbol(),
+            r.name,
+            r.detail
+        ));
+        if let Some(fix) = &r.remediation {
+            out.push_str(&format!("  \u{2192} {fix}\n"));
+        }
+    }
+    out
+}
+
+/// Process exit code: non-zero if any blocking check failed.
+pub fn exit_code(results: &[CheckResult]) -> i32 {
+    if results.iter().any(|r| r.status == CheckStatus::Fail) {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths::ClaudeDirSource;
+
+    fn fixture_claude_dir(root: &Path) -> PathBuf {
+        let claude = root.join(".claude");
+        let project = claude.join("projects").join("my-repo");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(
+            project.join("s.jsonl"),
+            "{\"type\":\"system\",\"subtype\":\"turn_duration\",\"duration_ms\":5}\n",
+        )
+        .unwrap();
+        claude
+    }
+
+    fn env_for<'a>(
+        claude_dir: Result<ClaudeDir, PathError>,
+        pid_file: PathBuf,
+        is_alive: &'a dyn Fn(u32) -> bool,
+    ) -> DoctorEnv<'a> {
+        DoctorEnv {
+            claude_dir,
+            terminal: TerminalKind::Tmux,
+            pid_file,
+            locale_utf8: true,
+            program_exists: &|_| true,
+            kitty_remote_control: &|| true,
+            is_alive,
+        }
+    }
+
+    fn status_of(results: &[CheckResult], name: &str) -> CheckStatus {
+        results.iter().find(|r| r.name == name).unwrap().status
+    }
+
+    #[test]
+    fn healthy_environment_passes() {
+        let root = tempfile::tempdir().unwrap();
+        let claude = fixture_claude_dir(root.path());
+        let dir = Ok(ClaudeDir {
+            path: claude,
+            source: ClaudeDirSource::Home,
+        });
+        let env = env_for(dir, root.path().join("app.pid"), &|_| false);
+        let results = run_checks(&env);
+        assert!(results.iter().all(|r| r.status == CheckStatus::Pass));
+        assert_eq!(exit_code(&results), 0);
+        assert!(render_report(&results).contains("1/1 lines parse"));
+    }
+
+    #[test]
+    fn missing_claude_dir_is_blocking() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = Ok(ClaudeDir {
+            path: root.path().join("nope"),
+            source: ClaudeDirSource::Flag,
+        });
+        let env = env_for(dir, root.path().join("app.pid"), &|_| false);
+        let results = run_checks(&env);
+        assert_eq!(status_of(&results, "Claude directory"), CheckStatus::Fail);
+        assert_eq!(exit_code(&results), 1);
+
+        let env = env_for(Err(PathError::NoClaudeDir), root.path().join("p"), &|_| {
+            false
+        });
+        assert_eq!(exit_code(&run_checks(&env)), 1);
+    }
+
+    #[test]
+    fn stale_pid_is_reported_with_remediation() {
+        let root = tempfile::tempdir().unwrap();
+        let claude = fixture_claude_dir(root.path());
+        let pid_path = root.path().join("app.pid");
+        std::fs::write(&pid_path, "4242").unwrap();
+        let dir = Ok(ClaudeDir {
+            path: claude,
+            source: ClaudeDirSource::Home,
+        });
+        let env = env_for(dir, pid_path.clone(), &|_| false);
+        let results = run_checks(&env);
+        let pid = results.iter().find(|r| r.name == "PID file").unwrap();
+        assert_eq!(pid.status, CheckStatus::Warn);
+        assert!(pid.detail.contains("4242"));
+        assert_eq!(
+            pid.remediation.as_deref(),
+            Some(format!("rm {}", pid_path.display()).as_str())
+        );
+        // A stale PID file doesn't block launching
+        assert_eq!(exit_code(&results), 0);
+    }
+
+    #[test]
+    fn kitty_without_remote_control_fails() {
+        let root = tempfile::tempdir().unwrap();
+        let claude = fixture_claude_dir(root.path());
+        let mut env = env_for(
+            Ok(ClaudeDir {
+                path: claude,
+                source: ClaudeDirSource::Home,
+            }),
+            root.path().join("app.pid"),
+            &|_| false,
+        );
+        env.terminal = TerminalKind::Kitty;
+        env.kitty_remote_control = &|| false;
+        let results = run_checks(&env);
+        assert_eq!(
+            status_of(&results, "Kitty remote control"),
+            CheckStatus::Fail
+        );
+    }
+}
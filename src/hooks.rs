@@ -0,0 +1,410 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::terminal::detect::shell_quote;
+
+/// Claude Code's user settings, in its data directory.
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+/// The flag a registered hook runs the binary with.
+pub const SESSION_HOOK_FLAG: &str = "--session-hook";
+/// The hook event the dashboard launches on.
+const HOOK_EVENT: &str = "SessionStart";
+/// Commands running this binary with [`SESSION_HOOK_FLAG`] are ours, from
+/// whatever path.
+const BINARY_NAME: &str = "pixel-agents-tui";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookError {
+    Io(String),
+    /// Not JSON, or not shaped like Claude Code settings. The file is left
+    /// as it is.
+    Malformed(String),
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookError::Io(msg) => write!(f, "cannot update settings: {msg}"),
+            HookError::Malformed(msg) => write!(f, "malformed settings: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// The hook command that runs the binary at `binary_path`.
+pub fn hook_command(binary_path: &str) -> String {
+    format!("{} {SESSION_HOOK_FLAG}", shell_quote(binary_path))
+}
+
+fn is_ours(hook: &Value) -> bool {
+    hook.get("command")
+        .and_then(Value::as_str)
+        .is_some_and(|command| {
+            let program = first_shell_word(command);
+            let name = Path::new(&program)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            let exe = format!("{BINARY_NAME}{}", std::env::consts::EXE_SUFFIX);
+            (name == BINARY_NAME || name == exe) && command.trim_end().ends_with(SESSION_HOOK_FLAG)
+        })
+}
+
+/// The first word of a shell command line with its quoting undone, as
+/// [`shell_quote`] writes it: `'/my bin/x' --flag` gives `/my bin/x`.
+fn first_shell_word(command: &str) -> String {
+    let mut word = String::new();
+    let mut chars = command.trim_start().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => word.extend(chars.by_ref().take_while(|&c| c != '\'')),
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.extend(chars.next()),
+            c if c.is_whitespace() => break,
+            c => word.push(c),
+        }
+    }
+    word
+}
+
+/// Register `command` as a `SessionStart` hook in the settings text `data`
+/// (None for no file yet), next to any hooks already there. A hook of ours
+/// from another path is pointed at `command` instead of added twice.
+/// Returns the new text, or None when `command` is registered already.
+pub fn install_hook(data: Option<&str>, command: &str) -> Result<Option<String>, HookError> {
+    let mut settings = parse_settings(data)?;
+    let groups = session_start_groups(&mut settings)?;
+    let mut found = false;
+    let mut changed = false;
+    for hook in groups
+        .iter_mut()
+        .flat_map(group_hooks)
+        .filter(|h| is_ours(h))
+    {
+        found = true;
+        if hook["command"] != command {
+            hook["command"] = Value::from(command);
+            changed = true;
+        }
+    }
+    if !found {
+        // As the plugin registers it: new sessions only, without holding
+        // up their start
+        groups.push(json!({
+            "matcher": "startup",
+            "hooks": [{"type": "command", "command": command, "async": true}],
+        }));
+        changed = true;
+    }
+    Ok(changed.then(|| render(&settings)))
+}
+
+/// Take every hook of ours out of the settings text `data`, dropping what
+/// that leaves empty. Returns the new text, or None when there was none.
+pub fn uninstall_hook(data: Option<&str>) -> Result<Option<String>, HookError> {
+    let Some(data) = data else {
+        return Ok(None);
+    };
+    let mut settings = parse_settings(Some(data))?;
+    let groups = session_start_groups(&mut settings)?;
+    let mut removed = false;
+    groups.retain_mut(|group| {
+        let Some(hooks) = group.get_mut("hooks").and_then(Value::as_array_mut) else {
+            return true;
+        };
+        let before = hooks.len();
+        hooks.retain(|hook| !is_ours(hook));
+        if hooks.len() == before {
+            return true;
+        }
+        removed = true;
+        !hooks.is_empty()
+    });
+    if !removed {
+        return Ok(None);
+    }
+    let hooks = settings["hooks"].as_object_mut().expect("checked above");
+    if hooks[HOOK_EVENT].as_array().is_some_and(Vec::is_empty) {
+        hooks.remove(HOOK_EVENT);
+    }
+    if hooks.is_empty() {
+        settings
+            .as_object_mut()
+            .expect("checked above")
+            .remove("hooks");
+    }
+    Ok(Some(render(&settings)))
+}
+
+/// The settings as an object; an empty file is one without settings.
+fn parse_settings(data: Option<&str>) -> Result<Value, HookError> {
+    let data = data.unwrap_or_default();
+    if data.trim().is_empty() {
+        return Ok(Value::Object(Map::new()));
+    }
+    let settings: Value =
+        serde_json::from_str(data).map_err(|e| HookError::Malformed(e.to_string()))?;
+    if !settings.is_object() {
+        return Err(HookError::Malformed("not a JSON object".to_string()));
+    }
+    Ok(settings)
+}
+
+/// `hooks.SessionStart`, created empty if missing.
+fn session_start_groups(settings: &mut Value) -> Result<&mut Vec<Value>, HookError> {
+    let hooks = settings
+        .as_object_mut()
+        .expect("parse_settings returns an object")
+        .entry("hooks")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| HookError::Malformed("hooks is not an object".to_string()))?;
+    hooks
+        .entry(HOOK_EVENT)
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+        .ok_or_else(|| HookError::Malformed(format!("hooks.{HOOK_EVENT} is not an array")))
+}
+
+/// A matcher group's hooks; none for groups shaped otherwise, which are
+/// left alone.
+fn group_hooks(group: &mut Value) -> impl Iterator<Item = &mut Value> {
+    group
+        .get_mut("hooks")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+}
+
+/// Indented with two spaces, as Claude Code writes the file.
+fn render(settings: &Value) -> String {
+    let mut text = serde_json::to_string_pretty(settings).expect("JSON values serialize");
+    text.push('\n');
+    text
+}
+
+/// Install the hook in the settings file at `path`, creating it if
+/// missing. Returns whether the file changed.
+pub fn install(path: &Path, command: &str) -> Result<bool, HookError> {
+    let data = read(path)?;
+    let Some(text) = install_hook(data.as_deref(), command)? else {
+        return Ok(false);
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    write(path, &text)?;
+    Ok(true)
+}
+
+/// Remove the hook from the settings file at `path`. Returns whether the
+/// file changed.
+pub fn uninstall(path: &Path) -> Result<bool, HookError> {
+    let data = read(path)?;
+    let Some(text) = uninstall_hook(data.as_deref())? else {
+        return Ok(false);
+    };
+    write(path, &text)?;
+    Ok(true)
+}
+
+fn read(path: &Path) -> Result<Option<String>, HookError> {
+    match fs::read_to_string(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(io_error(e)),
+    }
+}
+
+/// Written to a temp file beside the real one and renamed over it, so
+/// Claude Code never reads half a file. A symlink, say into a dotfiles
+/// repo, is followed first and stays a symlink.
+fn write(path: &Path, text: &str) -> Result<(), HookError> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut tmp_name = target.as_os_str().to_owned();
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = Path::new(&tmp_name);
+    fs::write(tmp, text).map_err(io_error)?;
+    fs::rename(tmp, &target).map_err(|e| {
+        let _ = fs::remove_file(tmp);
+        io_error(e)
+    })
+}
+
+fn io_error(e: io::Error) -> HookError {
+    HookError::Io(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMAND: &str = "/usr/local/bin/pixel-agents-tui --session-hook";
+
+    /// A user's settings with hooks of their own, SessionStart included.
+    const USER_SETTINGS: &str = r#"{
+  "model": "opus",
+  "permissions": {"allow": ["Bash(cargo test:*)"]},
+  "hooks": {
+    "SessionStart": [
+      {"matcher": "startup", "hooks": [{"type": "command", "command": "echo hi"}]}
+    ],
+    "Stop": [
+      {"hooks": [{"type": "command", "command": "say done"}]}
+    ]
+  }
+}"#;
+
+    fn parse(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    fn commands(settings: &Value) -> Vec<&str> {
+        settings["hooks"]["SessionStart"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|group| group["hooks"].as_array().unwrap())
+            .map(|hook| hook["command"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn installs_next_to_existing_hooks() {
+        let text = install_hook(Some(USER_SETTINGS), COMMAND).unwrap().unwrap();
+        let settings = parse(&text);
+        assert_eq!(commands(&settings), ["echo hi", COMMAND]);
+        assert_eq!(
+            settings["hooks"]["Stop"],
+            parse(USER_SETTINGS)["hooks"]["Stop"]
+        );
+        assert_eq!(settings["model"], "opus");
+        // The user's order stays
+        let keys: Vec<&String> = settings.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["model", "permissions", "hooks"]);
+        assert!(text.ends_with("}\n"));
+    }
+
+    #[test]
+    fn installing_twice_changes_nothing() {
+        let once = install_hook(None, COMMAND).unwrap().unwrap();
+        assert_eq!(install_hook(Some(&once), COMMAND).unwrap(), None);
+        assert_eq!(commands(&parse(&once)), [COMMAND]);
+
+        // A binary that moved is pointed at, not registered again
+        let moved = hook_command("/opt/pixel agents/pixel-agents-tui");
+        assert_eq!(moved, "'/opt/pixel agents/pixel-agents-tui' --session-hook");
+        let text = install_hook(Some(&once), &moved).unwrap().unwrap();
+        assert_eq!(commands(&parse(&text)), [moved.as_str()]);
+    }
+
+    #[test]
+    fn only_our_binary_counts_as_ours() {
+        let hook = |command: &str| is_ours(&json!({"type": "command", "command": command}));
+        assert!(hook(COMMAND));
+        assert!(hook("'/opt/pixel agents/pixel-agents-tui' --session-hook"));
+        assert!(hook(r#""/it's/pixel-agents-tui" --session-hook"#));
+        assert!(hook(r"/my\ bin/pixel-agents-tui --session-hook"));
+        assert!(!hook("other-pixel-agents-tui-wrapper --session-hook"));
+        assert!(!hook("/bin/pixel-agents-tui-dev --session-hook"));
+        assert!(!hook("echo pixel-agents-tui --session-hook"));
+        assert!(!hook("pixel-agents-tui --attach"));
+    }
+
+    #[test]
+    fn uninstall_takes_out_only_ours() {
+        let installed = install_hook(Some(USER_SETTINGS), COMMAND).unwrap().unwrap();
+        let text = uninstall_hook(Some(&installed)).unwrap().unwrap();
+        assert_eq!(parse(&text), parse(USER_SETTINGS));
+        assert_eq!(uninstall_hook(Some(&text)).unwrap(), None);
+        assert_eq!(uninstall_hook(None).unwrap(), None);
+
+        // Hooks left empty go, the rest of the settings stay
+        let installed = install_hook(Some(r#"{"model": "opus"}"#), COMMAND)
+            .unwrap()
+            .unwrap();
+        let text = uninstall_hook(Some(&installed)).unwrap().unwrap();
+        assert_eq!(parse(&text), json!({"model": "opus"}));
+    }
+
+    #[test]
+    fn malformed_settings_are_refused() {
+        for (data, error) in [
+            ("{ \"model\": ", "EOF while parsing"),
+            ("[1, 2]", "not a JSON object"),
+            (r#"{"hooks": []}"#, "hooks is not an object"),
+            (
+                r#"{"hooks": {"SessionStart": {}}}"#,
+                "hooks.SessionStart is not an array",
+            ),
+        ] {
+            for result in [
+                install_hook(Some(data), COMMAND),
+                uninstall_hook(Some(data)),
+            ] {
+                let Err(HookError::Malformed(msg)) = result else {
+                    panic!("{data:?} was accepted");
+                };
+                assert!(msg.contains(error), "{msg}");
+            }
+        }
+        // An empty file has no settings yet
+        let text = install_hook(Some("\n"), COMMAND).unwrap().unwrap();
+        assert_eq!(commands(&parse(&text)), [COMMAND]);
+    }
+
+    #[test]
+    fn install_creates_the_file_and_leaves_bad_ones_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("claude").join(SETTINGS_FILE_NAME);
+        assert!(install(&path, COMMAND).unwrap());
+        assert!(!install(&path, COMMAND).unwrap());
+        assert_eq!(
+            commands(&parse(&fs::read_to_string(&path).unwrap())),
+            [COMMAND]
+        );
+        assert!(uninstall(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}\n");
+
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            install(&path, COMMAND),
+            Err(HookError::Malformed(_))
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not json");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_symlinked_settings_file_stays_a_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("dotfiles.json");
+        fs::write(&real, USER_SETTINGS).unwrap();
+        let link = dir.path().join(SETTINGS_FILE_NAME);
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        assert!(install(&link, COMMAND).unwrap());
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(
+            commands(&parse(&fs::read_to_string(&real).unwrap())),
+            ["echo hi", COMMAND]
+        );
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
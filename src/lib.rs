@@ -1,5 +1,20 @@
+pub mod actions;
+pub mod api;
 pub mod app;
+pub mod config;
+pub mod diff;
+pub mod doctor;
+pub mod hooks;
+pub mod notify;
+pub mod paths;
+pub mod persist;
+#[cfg(unix)]
+pub mod remote;
+pub mod schema;
+pub mod singleton;
 pub mod state;
+pub mod status_file;
 pub mod terminal;
 pub mod ui;
 pub mod watcher;
+pub mod watchers;
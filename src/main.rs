@@ -1,17 +1,47 @@
 use std::fs;
-use std::io;
-use std::process::Command;
-use std::time::Duration;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use clap::Parser;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use clap::{Parser, ValueEnum};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Position, Rect, Size};
+#[cfg(unix)]
+use signal_hook::consts::{SIGINT, SIGTERM, SIGUSR1};
 
-use pixel_agents_tui::app::App;
-use pixel_agents_tui::terminal::{build_fallback_command, build_split_command, detect_terminal};
-use pixel_agents_tui::ui::layout;
-
-const PID_FILE: &str = "/tmp/pixel-agents-tui.pid";
-const TICK_RATE: Duration = Duration::from_millis(100);
+use pixel_agents_tui::actions::{self, SystemProcesses};
+#[cfg(unix)]
+use pixel_agents_tui::app::StateSource;
+use pixel_agents_tui::app::{App, PanelFocus, SidebarFilter, ToastLevel, ViewMode};
+use pixel_agents_tui::config::{self, Action, Config, ConfigError, CONFIG_FILE_NAME};
+use pixel_agents_tui::doctor::{self, DoctorEnv};
+use pixel_agents_tui::hooks;
+use pixel_agents_tui::notify;
+use pixel_agents_tui::paths::{self, process_env};
+use pixel_agents_tui::persist::{self, PersistedState, UiPrefs};
+#[cfg(unix)]
+use pixel_agents_tui::remote::{self, RemoteClient};
+use pixel_agents_tui::schema::{self, OutputKind};
+use pixel_agents_tui::singleton::{self, is_process_alive, PidFileState};
+use pixel_agents_tui::state::project::{cwd_from_hook_input, encode_project_dir, HomeProject};
+use pixel_agents_tui::state::timer::format_duration_short;
+use pixel_agents_tui::status_file::{self, StatusCounts, StatusReport};
+use pixel_agents_tui::terminal::{
+    build_fallback_command_with_args, build_focus_return_command, build_split_command_with_args,
+    detect_terminal, kitty_remote_control_probe, launch, probe_succeeds, program_on_path,
+    Diagnostics, LaunchError, LaunchOutcome, LaunchPlan, SplitDirection, SplitOptions,
+    SystemRunner, DEFAULT_SPLIT_PERCENT,
+};
+use pixel_agents_tui::ui::export::{self, ExportFormat};
+use pixel_agents_tui::ui::text::elide_path;
+use pixel_agents_tui::ui::theme::{self, ThemeName};
+use pixel_agents_tui::ui::{layout, sprites};
+use pixel_agents_tui::watcher::archive;
+use pixel_agents_tui::watcher::discovery::{self, SessionTracker, SESSIONS_FILE_NAME};
+use pixel_agents_tui::watcher::settings::{self, CLAUDE_CONFIG_DIR_ENV};
+use pixel_agents_tui::watchers::{self, WatcherEntry};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -26,119 +56,1108 @@ struct Cli {
     /// Launched from a Claude Code session hook
     #[arg(long)]
     session_hook: bool,
+
+    /// Register this binary as a SessionStart hook in Claude Code's
+    /// settings.json, then exit
+    #[arg(long, conflicts_with_all = ["attach", "uninstall_hook"])]
+    install_hook: bool,
+
+    /// Remove the hook --install-hook registered, then exit
+    #[arg(long, conflicts_with = "attach")]
+    uninstall_hook: bool,
+
+    /// Claude data directory (defaults to $PIXEL_AGENTS_CLAUDE_DIR, then ~/.claude)
+    #[arg(long, value_name = "PATH")]
+    claude_dir: Option<PathBuf>,
+
+    /// Agent count above which the dashboard switches to scale mode
+    /// (defaults to the config file, then 30)
+    #[arg(long, value_name = "N")]
+    scale_threshold: Option<usize>,
+
+    /// Check the environment end-to-end and exit
+    #[arg(long)]
+    doctor: bool,
+
+    /// Print the agents as JSON and exit
+    #[arg(long, conflicts_with = "attach")]
+    status: bool,
+
+    /// List every session file, archives included, with its counts and exit
+    #[arg(long, conflicts_with_all = ["attach", "status"])]
+    list_sessions: bool,
+
+    /// With --status, print a JSON line every SECS seconds (2 by default)
+    /// until interrupted
+    #[arg(
+        long,
+        value_name = "SECS",
+        num_args = 0..=1,
+        default_missing_value = "2",
+        requires = "status"
+    )]
+    watch: Option<u64>,
+
+    /// List running instances and the directories they watch, then exit
+    #[arg(long)]
+    instances: bool,
+
+    /// Print the JSON Schema of a JSON output, or of all of them, and exit
+    #[arg(long, value_enum, value_name = "KIND", num_args = 0..=1)]
+    print_schema: Option<Option<OutputKind>>,
+
+    /// Print a one-token agent summary for shell prompts and exit
+    #[arg(long)]
+    print_prompt_segment: bool,
+
+    /// Disable colors in the TUI and in --print-prompt-segment output,
+    /// same as setting NO_COLOR
+    #[arg(long)]
+    no_color: bool,
+
+    /// Color theme; `T` cycles through them while running
+    #[arg(long, value_enum, value_name = "THEME")]
+    theme: Option<ThemeName>,
+
+    /// Project directory whose agents get the first desks in the office
+    /// (set automatically when launched from the session hook)
+    #[arg(long, value_name = "PATH")]
+    home_project: Option<PathBuf>,
+
+    /// File format for office snapshots exported with `x`
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = ExportFormat::Text)]
+    export_format: ExportFormat,
+
+    /// Watch sessions headlessly and publish them to `--connect` clients
+    #[cfg(unix)]
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "attach",
+            "install_hook",
+            "uninstall_hook",
+            "status",
+            "percent",
+            "direction",
+            "diagnostics_json",
+            "welcome",
+        ]
+    )]
+    serve: bool,
+
+    /// Socket for --serve (defaults to pixel-agents-tui.sock in the runtime dir)
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH", requires = "serve")]
+    socket: Option<PathBuf>,
+
+    /// Octal permissions for the --serve socket, e.g. 660 so a group can
+    /// --connect (the umask decides otherwise)
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_name = "MODE",
+        requires = "serve",
+        value_parser = remote::parse_socket_mode
+    )]
+    socket_mode: Option<u32>,
+
+    /// Share of the terminal the launcher's split pane takes, in percent
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_SPLIT_PERCENT,
+        value_parser = clap::value_parser!(u8).range(1..100),
+        conflicts_with = "attach"
+    )]
+    percent: u8,
+
+    /// Side of the current pane the launcher's split opens on
+    #[arg(
+        long,
+        value_enum,
+        value_name = "DIRECTION",
+        default_value_t = SplitDirection::Right,
+        conflicts_with = "attach"
+    )]
+    direction: SplitDirection,
+
+    /// Print a JSON summary of the launch to stdout, or write it to PATH
+    #[arg(long, value_name = "PATH", num_args = 0..=1, conflicts_with = "attach")]
+    diagnostics_json: Option<Option<PathBuf>>,
+
+    /// Show the first-run overlay again
+    #[arg(long)]
+    welcome: bool,
+
+    /// Mirror a --serve instance instead of watching sessions locally
+    #[cfg(unix)]
+    #[arg(long, value_name = "SOCKET", requires = "attach")]
+    connect: Option<PathBuf>,
+
+    /// Go easy on a Claude directory mounted over the network (sshfs):
+    /// scan rarely, list directories in batches and poll file sizes
+    #[arg(long)]
+    remote_friendly: bool,
+
+    /// Send a desktop notification when an agent finishes its turn or
+    /// needs input
+    #[arg(long)]
+    notify: bool,
+
+    /// Only show agents working in the project at PATH. Instances for
+    /// different projects run side by side
+    #[arg(long, value_name = "PATH")]
+    project: Option<PathBuf>,
+
+    /// Show agents of every project (the default); overrides --project
+    #[arg(long, overrides_with = "project")]
+    all: bool,
 }
 
 fn main() -> io::Result<()> {
-    let cli = Cli::parse();
-    if cli.attach {
-        run_tui()
+    let mut cli = Cli::parse();
+    if cli.all {
+        cli.project = None;
+    }
+    let config_path = paths::config_dir(process_env).map(|dir| dir.join(CONFIG_FILE_NAME));
+    if cli.doctor {
+        std::process::exit(run_doctor(
+            cli.claude_dir.as_deref(),
+            config_path.as_deref(),
+        ));
+    }
+    if let Some(kind) = cli.print_schema {
+        println!("{:#}", schema::schema_document(kind));
+        return Ok(());
+    }
+    if cli.instances {
+        print_instances();
+        return Ok(());
+    }
+    if cli.install_hook || cli.uninstall_hook {
+        std::process::exit(update_session_hook(
+            cli.install_hook,
+            cli.claude_dir.as_deref(),
+            config_path.as_deref(),
+        ));
+    }
+    let no_color = cli.no_color || theme::no_color_requested(process_env("NO_COLOR").as_deref());
+    if cli.print_prompt_segment {
+        print_prompt_segment(!no_color);
+        return Ok(());
+    }
+    if cli.status || cli.list_sessions {
+        let config = match load_config(config_path.as_deref()) {
+            Ok(config) => Config {
+                project: cli.project.as_deref().map(project_dir_key),
+                ..config
+            },
+            Err(e) => {
+                eprintln!("pixel-agents-tui: {e}");
+                std::process::exit(2);
+            }
+        };
+        let claude_dir = match paths::resolve_claude_dir(
+            cli.claude_dir.as_deref(),
+            config.claude_dir.as_deref(),
+            process_env,
+        ) {
+            Ok(dir) => dir.path,
+            Err(e) => {
+                eprintln!("pixel-agents-tui: {e}");
+                std::process::exit(2);
+            }
+        };
+        if cli.list_sessions {
+            return print_session_list(&claude_dir, config.project.as_deref());
+        }
+        return match print_agent_status(claude_dir, config, cli.watch) {
+            // The reader went away, e.g. `| head`
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => result,
+        };
+    }
+    // Unix sockets carry `--serve` and `--connect`
+    #[cfg(unix)]
+    let serve = cli.serve;
+    #[cfg(not(unix))]
+    let serve = false;
+    if cli.attach || serve {
+        // Load and resolve before touching the terminal so errors are readable
+        let config = match load_config(config_path.as_deref()) {
+            Ok(config) => Config {
+                notify: cli.notify,
+                project: cli.project.as_deref().map(project_dir_key),
+                no_color,
+                theme_override: cli.theme,
+                ..config
+            },
+            Err(e) => {
+                eprintln!("pixel-agents-tui: {e}");
+                std::process::exit(2);
+            }
+        };
+        let config = if cli.remote_friendly {
+            config.remote_friendly()
+        } else {
+            config
+        };
+        #[cfg(unix)]
+        if let Some(socket) = cli.connect {
+            return run_tui(
+                TuiSource::Remote(socket),
+                cli.scale_threshold,
+                config,
+                config_path,
+                cli.export_format,
+                cli.home_project,
+                cli.welcome,
+            );
+        }
+        let claude_dir = match paths::resolve_claude_dir(
+            cli.claude_dir.as_deref(),
+            config.claude_dir.as_deref(),
+            process_env,
+        ) {
+            Ok(dir) => dir.path,
+            Err(e) => {
+                eprintln!("pixel-agents-tui: {e}");
+                std::process::exit(2);
+            }
+        };
+        #[cfg(unix)]
+        if serve {
+            // Only the default runtime dir is ours to create; a --socket
+            // elsewhere must point into a directory that already exists
+            let socket = match cli.socket {
+                Some(socket) => socket,
+                None => {
+                    let socket = paths::socket_file(process_env);
+                    if let Some(parent) = socket.parent() {
+                        paths::create_runtime_dir(parent)?;
+                    }
+                    socket
+                }
+            };
+            return run_server(
+                claude_dir,
+                cli.scale_threshold,
+                config,
+                &socket,
+                cli.socket_mode,
+            );
+        }
+        run_tui(
+            TuiSource::Local(claude_dir),
+            cli.scale_threshold,
+            config,
+            config_path,
+            cli.export_format,
+            cli.home_project,
+            cli.welcome,
+        )
     } else {
-        launch_split()
+        let forwarded = forwarded_args(&cli);
+        let result = launch_split(
+            cli.session_hook,
+            cli.project.as_deref(),
+            SplitOptions {
+                percent: cli.percent,
+                direction: cli.direction,
+                cwd: None,
+            },
+            cli.diagnostics_json,
+            forwarded,
+        );
+        // The fix is the whole message, so it shouldn't drown in error noise
+        if let Err(e) = &result {
+            let blocked = e
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<LaunchError>())
+                .is_some_and(|inner| matches!(inner, LaunchError::SplitBlocked { .. }));
+            if blocked {
+                eprintln!("pixel-agents-tui: {e}");
+                std::process::exit(1);
+            }
+        }
+        result
     }
 }
 
-/// Check if a process with the given PID is still alive.
-fn is_process_alive(pid: &str) -> bool {
-    Command::new("kill")
-        .args(["-0", pid])
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
+/// The flags the launcher passes on to the dashboard it starts: everything
+/// that shapes what it shows or how it reads sessions. Paths are made
+/// absolute, since the split may start in another directory.
+fn forwarded_args(cli: &Cli) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut push = |flag: &str, value: Option<String>| {
+        args.push(flag.to_string());
+        args.extend(value);
+    };
+    let path = |path: &Path| {
+        std::path::absolute(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    };
+    if let Some(dir) = &cli.claude_dir {
+        push("--claude-dir", Some(path(dir)));
+    }
+    if let Some(threshold) = cli.scale_threshold {
+        push("--scale-threshold", Some(threshold.to_string()));
+    }
+    if cli.no_color {
+        push("--no-color", None);
+    }
+    if let Some(theme) = cli.theme {
+        let name = theme.to_possible_value().map(|v| v.get_name().to_string());
+        push("--theme", name);
+    }
+    if let Some(home) = &cli.home_project {
+        push("--home-project", Some(path(home)));
+    }
+    if cli.export_format != ExportFormat::Text {
+        let name = cli
+            .export_format
+            .to_possible_value()
+            .map(|v| v.get_name().to_string());
+        push("--export-format", name);
+    }
+    if cli.remote_friendly {
+        push("--remote-friendly", None);
+    }
+    if cli.notify {
+        push("--notify", None);
+    }
+    if cli.welcome {
+        push("--welcome", None);
+    }
+    args
 }
 
-/// Launcher mode: detect terminal, create a split pane, and launch the TUI in --attach mode.
-fn launch_split() -> io::Result<()> {
-    // Check PID file - if process is still alive, don't launch another instance
-    if let Ok(pid_str) = fs::read_to_string(PID_FILE) {
-        let pid = pid_str.trim();
-        if !pid.is_empty() && is_process_alive(pid) {
-            return Ok(());
+/// Absolute form of a `--project` path, without `.` parts or a trailing
+/// slash, as Claude Code records the session's cwd.
+fn absolute_project(path: &Path) -> PathBuf {
+    std::path::absolute(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .components()
+        .collect()
+}
+
+/// The `--project` path's directory name under `~/.claude/projects/`.
+fn project_dir_key(path: &Path) -> String {
+    encode_project_dir(&absolute_project(path))
+}
+
+/// The PID file of the instance for `project`, or of the all-projects one.
+fn instance_pid_file(project: Option<&str>) -> PathBuf {
+    match project {
+        Some(dir_key) => paths::project_pid_file(process_env, dir_key),
+        None => paths::pid_file(process_env),
+    }
+}
+
+/// The status file of the instance for `project`, or of the all-projects
+/// one, so instances side by side don't overwrite or remove each other's.
+fn instance_status_file(project: Option<&str>) -> PathBuf {
+    match project {
+        Some(dir_key) => paths::project_status_file(process_env, dir_key),
+        None => paths::status_file(process_env),
+    }
+}
+
+/// Where the instance for `project`, or the all-projects one, keeps the
+/// agents' numbers, so instances side by side don't overwrite each other's.
+fn instance_sessions_file(state_dir: &Path, project: Option<&str>) -> PathBuf {
+    match project {
+        Some(dir_key) => state_dir.join(paths::project_sessions_file_name(dir_key)),
+        None => state_dir.join(SESSIONS_FILE_NAME),
+    }
+}
+
+/// Load the config file, or the defaults when there is no config directory.
+fn load_config(path: Option<&Path>) -> Result<Config, ConfigError> {
+    match path {
+        Some(path) => config::load(path),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Run the environment checks against the real system and print the report.
+fn run_doctor(claude_dir_flag: Option<&Path>, config_path: Option<&Path>) -> i32 {
+    let config = load_config(config_path).unwrap_or_default();
+    let path_var = process_env("PATH");
+    let program_exists = |program: &str| program_on_path(program, path_var.as_deref());
+    let kitty_remote_control =
+        || probe_succeeds(&kitty_remote_control_probe(), Duration::from_secs(2));
+    let env = DoctorEnv {
+        claude_dir: paths::resolve_claude_dir(
+            claude_dir_flag,
+            config.claude_dir.as_deref(),
+            process_env,
+        ),
+        terminal: detect_terminal(),
+        pid_file: paths::pid_file(process_env),
+        locale_utf8: sprites::locale_is_utf8(),
+        program_exists: &program_exists,
+        kitty_remote_control: &kitty_remote_control,
+        is_alive: &is_process_alive,
+    };
+    let results = doctor::run_checks(&env);
+    print!("{}", doctor::render_report(&results));
+    doctor::exit_code(&results)
+}
+
+/// Register the session hook in the Claude directory's settings, or remove
+/// it, and say what was done. Returns the exit code.
+fn update_session_hook(
+    install: bool,
+    claude_dir_flag: Option<&Path>,
+    config_path: Option<&Path>,
+) -> i32 {
+    let config = match load_config(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("pixel-agents-tui: {e}");
+            return 2;
+        }
+    };
+    let claude_dir =
+        match paths::resolve_claude_dir(claude_dir_flag, config.claude_dir.as_deref(), process_env)
+        {
+            Ok(dir) => dir.path,
+            Err(e) => {
+                eprintln!("pixel-agents-tui: {e}");
+                return 2;
+            }
+        };
+    let path = claude_dir.join(hooks::SETTINGS_FILE_NAME);
+    let result = if install {
+        let binary_path = std::env::current_exe()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "pixel-agents-tui".to_string());
+        hooks::install(&path, &hooks::hook_command(&binary_path))
+    } else {
+        hooks::uninstall(&path)
+    };
+    let message = match (result, install) {
+        (Ok(true), true) => "registered the session hook in",
+        (Ok(false), true) => "the session hook is already registered in",
+        (Ok(true), false) => "removed the session hook from",
+        (Ok(false), false) => "no session hook to remove in",
+        (Err(e), _) => {
+            eprintln!("pixel-agents-tui: {}: {e}", path.display());
+            return 1;
+        }
+    };
+    println!("{message} {}", path.display());
+    0
+}
+
+/// Print every live instance from the watcher registry.
+fn print_instances() {
+    let entries = watchers::prune(
+        watchers::load(&paths::watchers_file(process_env)),
+        is_process_alive,
+    );
+    if entries.is_empty() {
+        println!("no running instances");
+        return;
+    }
+    let now = unix_now();
+    for entry in entries {
+        let age = Duration::from_secs(now.saturating_sub(entry.started_at));
+        let scope = entry
+            .project
+            .map(|project| format!(" [{project}]"))
+            .unwrap_or_default();
+        println!(
+            "pid {:<8} {}{scope}  (up {})",
+            entry.pid,
+            entry.claude_dir.display(),
+            format_duration_short(age)
+        );
+    }
+}
+
+/// Read every session once and print the agents as JSON. With `watch`, keep
+/// reading and print one document per line every `watch` seconds.
+fn print_agent_status(claude_dir: PathBuf, config: Config, watch: Option<u64>) -> io::Result<()> {
+    let home = process_env("HOME").map(PathBuf::from);
+    let extra_roots = settings::discover_roots(
+        &claude_dir,
+        process_env(CLAUDE_CONFIG_DIR_ENV).map(PathBuf::from),
+        home.as_deref(),
+    );
+    let mut app = App::new(claude_dir);
+    app.extra_roots = extra_roots;
+    app.apply_config(config);
+    let mut out = io::stdout().lock();
+    loop {
+        app.poll_once();
+        serde_json::to_writer(&mut out, &StatusReport::new(&app)).map_err(io::Error::other)?;
+        writeln!(out)?;
+        out.flush()?;
+        match watch {
+            Some(secs) => std::thread::sleep(Duration::from_secs(secs.max(1))),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Print one line per session file of the project, or of all of them:
+/// prompts, finished turns and tool uses, then the path. Gzipped archives
+/// are read like live sessions.
+fn print_session_list(claude_dir: &Path, project: Option<&str>) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    writeln!(
+        out,
+        "{:>7} {:>7} {:>7}  SESSION",
+        "PROMPTS", "TURNS", "TOOLS"
+    )?;
+    for path in archive::list_sessions(claude_dir) {
+        if !discovery::in_project(&path, project) {
+            continue;
+        }
+        match archive::session_stats(&path) {
+            Ok(stats) => writeln!(
+                out,
+                "{:>7} {:>7} {:>7}  {}",
+                stats.prompts,
+                stats.turns,
+                stats.tool_uses,
+                path.display()
+            )?,
+            Err(e) => eprintln!("pixel-agents-tui: {}: {e}", path.display()),
         }
     }
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Print the prompt segment from the running instances' status files.
+/// Prints nothing when no instance is running or the files are stale.
+fn print_prompt_segment(color: bool) {
+    let Some(counts) = status_file::read_combined(
+        &paths::status_file(process_env),
+        &paths::project_status_files(process_env),
+        SystemTime::now(),
+    ) else {
+        return;
+    };
+    if let Some(segment) = status_file::format_segment(&counts, color) {
+        println!("{segment}");
+    }
+}
+
+/// Launcher mode: detect terminal, create a split pane, and launch the TUI in --attach mode.
+/// From the session hook, the pane opens in the session's cwd, which also becomes the attached
+/// instance's home project.
+/// With `project`, only an instance for that project counts as already running.
+/// `split` says where the pane goes and how big it is.
+/// With `diagnostics`, a JSON summary of the run goes to that file, or to stdout for `None`.
+fn launch_split(
+    session_hook: bool,
+    project: Option<&Path>,
+    mut split: SplitOptions,
+    diagnostics: Option<Option<PathBuf>>,
+    mut extra_args: Vec<String>,
+) -> io::Result<()> {
+    let started = Instant::now();
+    // Check PID file - if process is still alive, don't launch another instance.
+    // A file left by a crash is removed
+    let project = project.map(absolute_project);
+    let pid_file = instance_pid_file(project.as_deref().map(encode_project_dir).as_deref());
+    let pid_state = singleton::read_state_clearing_stale(&pid_file, is_process_alive);
 
     // Get the current binary path
     let binary_path = std::env::current_exe()
         .map(|p| p.to_string_lossy().to_string())
         .unwrap_or_else(|_| "pixel-agents-tui".to_string());
 
+    // The hook passes session details as JSON on stdin
+    let running = matches!(pid_state, PidFileState::Running(_));
+    if session_hook && !running && !io::stdin().is_terminal() {
+        let mut input = String::new();
+        if io::stdin().read_to_string(&mut input).is_ok() {
+            if let Some(cwd) = cwd_from_hook_input(&input) {
+                // An explicit --home-project wins over the session's cwd
+                if !extra_args.iter().any(|arg| arg == "--home-project") {
+                    extra_args.push("--home-project".to_string());
+                    extra_args.push(cwd.to_string_lossy().into_owned());
+                }
+                split.cwd = Some(cwd);
+            }
+        }
+    }
+    if let Some(project) = &project {
+        extra_args.push("--project".to_string());
+        extra_args.push(project.to_string_lossy().into_owned());
+    }
+
     // Detect terminal and build the appropriate split command
     let kind = detect_terminal();
-    let split_cmd = build_split_command(kind, &binary_path)
-        .unwrap_or_else(|| build_fallback_command(&binary_path));
-
-    // Spawn the split command
-    Command::new(&split_cmd.program)
-        .args(&split_cmd.args)
-        .spawn()
-        .map_err(|e| {
-            io::Error::other(format!(
-                "Failed to spawn {} {}: {}",
-                split_cmd.program,
-                split_cmd.args.join(" "),
-                e
-            ))
-        })?;
+    let plan = LaunchPlan {
+        kind,
+        split: build_split_command_with_args(kind, &binary_path, &extra_args, &split),
+        focus_return: build_focus_return_command(kind, split.direction),
+        fallback: build_fallback_command_with_args(&binary_path, &extra_args),
+        pid_state,
+    };
+
+    // The hook can fire while the multiplexer session is still starting,
+    // so splits are retried on transient failures before falling back
+    let mut log = open_hook_log();
+    let report = launch(&plan, &mut SystemRunner, std::thread::sleep, &mut log);
+
+    if let Some(target) = diagnostics {
+        let json = serde_json::to_string(&Diagnostics::new(&plan, &report, started.elapsed()))
+            .map_err(io::Error::other)?;
+        match target {
+            Some(path) => fs::write(path, json + "\n")?,
+            None => println!("{json}"),
+        }
+    }
+    match report.outcome {
+        // Passed on whole, for main to print on its own
+        LaunchOutcome::Failed(e @ LaunchError::SplitBlocked { .. }) => Err(io::Error::other(e)),
+        LaunchOutcome::Failed(e) => Err(io::Error::other(e.to_string())),
+        LaunchOutcome::Fallback {
+            split_error: Some(reason),
+        } => {
+            eprintln!(
+                "pixel-agents-tui: {} split failed ({reason}); opened a new window instead",
+                kind.label()
+            );
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
 
+/// Open the hook log for appending, or discard output if it can't be opened.
+fn open_hook_log() -> Box<dyn Write> {
+    let path = paths::hook_log_file(process_env);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match fs::OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => Box::new(file),
+        Err(_) => Box::new(io::sink()),
+    }
+}
+
+/// Where an attached TUI gets its agents.
+enum TuiSource {
+    /// Watch this Claude data directory.
+    Local(PathBuf),
+    /// Mirror the `--serve` instance on this socket.
+    #[cfg(unix)]
+    Remote(PathBuf),
+}
+
+/// Runtime files owned by an instance that watches sessions itself.
+struct LocalInstance {
+    pid: u32,
+    pid_file: PathBuf,
+    status_path: PathBuf,
+    watchers_path: PathBuf,
+}
+
+impl LocalInstance {
+    /// Write the PID file, per project with `--project`, and register as a
+    /// watcher of `claude_dir`. Other live instances on the same directory
+    /// and projects are returned for a warning, not a refusal.
+    fn register(claude_dir: &Path, project: Option<&str>) -> io::Result<(Self, Vec<WatcherEntry>)> {
+        let pid_file = instance_pid_file(project);
+        if let Some(dir) = pid_file.parent() {
+            paths::create_runtime_dir(dir)?;
+        }
+        let pid = std::process::id();
+        fs::write(&pid_file, pid.to_string())?;
+        let watchers_path = paths::watchers_file(process_env);
+        let me = WatcherEntry {
+            pid,
+            claude_dir: watchers::normalize_dir(claude_dir),
+            started_at: unix_now(),
+            project: project.map(str::to_string),
+        };
+        let others = watchers::register(&watchers_path, me, is_process_alive).unwrap_or_default();
+        let instance = Self {
+            pid,
+            pid_file,
+            status_path: instance_status_file(project),
+            watchers_path,
+        };
+        Ok((instance, others))
+    }
+
+    fn cleanup(&self) {
+        let _ = fs::remove_file(&self.pid_file);
+        let _ = fs::remove_file(&self.status_path);
+        let _ = watchers::unregister(&self.watchers_path, self.pid, is_process_alive);
+    }
+}
+
+/// Serve mode: watch sessions without a UI and publish a snapshot to every
+/// connected client each tick, until interrupted.
+#[cfg(unix)]
+fn run_server(
+    claude_dir: PathBuf,
+    scale_threshold: Option<usize>,
+    config: Config,
+    socket: &Path,
+    socket_mode: Option<u32>,
+) -> io::Result<()> {
+    let mut server = remote::Server::bind(socket, socket_mode)?;
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(SIGINT, Arc::clone(&stop))?;
+    signal_hook::flag::register(SIGTERM, Arc::clone(&stop))?;
+    eprintln!(
+        "pixel-agents-tui: serving {} on {}",
+        claude_dir.display(),
+        server.path().display()
+    );
+
+    let home = process_env("HOME").map(PathBuf::from);
+    let extra_roots = settings::discover_roots(
+        &claude_dir,
+        process_env(CLAUDE_CONFIG_DIR_ENV).map(PathBuf::from),
+        home.as_deref(),
+    );
+    let mut app = App::new(claude_dir);
+    app.extra_roots = extra_roots;
+    // Clients see the same agent numbers across server restarts
+    let state_dir = paths::state_dir(process_env);
+    let sessions_file = instance_sessions_file(&state_dir, config.project.as_deref());
+    app.session_tracker = SessionTracker::new(Some(sessions_file));
+    if let Some(warning) = app.session_tracker.take_warning() {
+        eprintln!("pixel-agents-tui: {warning}");
+    }
+    // Without filesystem events the app polls every session file each tick.
+    // Network mounts don't report remote writes, so they poll sizes instead
+    if !config.remote_friendly {
+        let _ = app.watch_fs_events();
+    }
+    app.cli_scale_threshold = scale_threshold;
+    app.apply_config(config);
+
+    while !stop.load(Ordering::Relaxed) {
+        app.tick();
+        server.publish(&app.app_snapshot(Instant::now()).into());
+        std::thread::sleep(app.config.tick_rate);
+    }
+    app.session_tracker.save();
+    // Dropping the server removes the socket
     Ok(())
 }
 
 /// Attach mode: run the TUI with the event loop.
-fn run_tui() -> io::Result<()> {
-    // Write PID file
-    let pid = std::process::id();
-    fs::write(PID_FILE, pid.to_string())?;
+fn run_tui(
+    source: TuiSource,
+    scale_threshold: Option<usize>,
+    config: Config,
+    config_path: Option<PathBuf>,
+    export_format: ExportFormat,
+    home_project: Option<PathBuf>,
+    welcome: bool,
+) -> io::Result<()> {
+    // A mirroring client leaves the PID, status and watcher files to the server
+    let (claude_dir, instance, other_watchers) = match &source {
+        TuiSource::Local(dir) => {
+            let (instance, others) = LocalInstance::register(dir, config.project.as_deref())?;
+            (dir.clone(), Some(instance), others)
+        }
+        #[cfg(unix)]
+        TuiSource::Remote(_) => (PathBuf::new(), None, Vec::new()),
+    };
+    let mut last_status_write: Option<Instant> = None;
 
-    // Determine the Claude directory
-    let claude_dir = directories::BaseDirs::new()
-        .map(|d| d.home_dir().join(".claude"))
-        .unwrap_or_else(|| std::path::PathBuf::from(".claude"));
+    // SIGUSR1 asks for a config reload, same as Ctrl+e
+    let reload_signal = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    signal_hook::flag::register(SIGUSR1, Arc::clone(&reload_signal))?;
+
+    // State carried over from the previous run
+    let state_dir = paths::state_dir(process_env);
+    let state_path = state_dir.join(persist::STATE_FILE_NAME);
+    let persisted = persist::load(&state_path);
 
     // Initialize the terminal
     let mut terminal = ratatui::init();
 
     // Create the application state
     let mut app = App::new(claude_dir);
+    match source {
+        TuiSource::Local(claude_dir) => {
+            let home = process_env("HOME").map(PathBuf::from);
+            app.extra_roots = settings::discover_roots(
+                &claude_dir,
+                process_env(CLAUDE_CONFIG_DIR_ENV).map(PathBuf::from),
+                home.as_deref(),
+            );
+            // Agents keep their numbers (and colors) across restarts
+            let sessions_file = instance_sessions_file(&state_dir, config.project.as_deref());
+            app.session_tracker = SessionTracker::new(Some(sessions_file));
+            // Without filesystem events the app polls every session file each
+            // tick. Network mounts don't report remote writes, so they poll
+            // sizes instead
+            if !config.remote_friendly {
+                let _ = app.watch_fs_events();
+            }
+        }
+        #[cfg(unix)]
+        TuiSource::Remote(socket) => {
+            app.source = StateSource::Remote(RemoteClient::connect(socket));
+        }
+    }
+    app.home_project = home_project.as_deref().map(HomeProject::from_cwd);
+    app.watcher_warning = watchers::conflict_warning(&other_watchers);
+    // The flag beats the config file, also after a reload
+    app.cli_scale_threshold = scale_threshold;
+    app.apply_config(config);
+    app.ascii_icons = !sprites::locale_is_utf8();
+    app.sidebar_filter = persisted.state.ui.sidebar_filter;
+    app.show_welcome = welcome || !persisted.state.first_run_done;
+    if let Some(warning) = app.rule_warning() {
+        app.show_toast(format!("config: {warning}"), ToastLevel::Warning);
+    }
+    // One toast shows at a time; both problems are rare
+    let sessions_warning = app.session_tracker.take_warning();
+    if let Some(warning) = persisted.warning.clone().or(sessions_warning) {
+        app.show_toast(warning, ToastLevel::Warning);
+    }
 
     // Main event loop
     let result = loop {
         // Draw the UI
+        let loop_started = Instant::now();
         if let Err(e) = terminal.draw(|frame| layout::render(frame, &app)) {
             break Err(e);
         }
+        let draw_cost = loop_started.elapsed();
 
-        // Poll for events at the tick rate (10 FPS)
-        if event::poll(TICK_RATE)? {
-            if let Event::Key(key) = event::read()? {
+        // Poll for events at the tick rate (10 FPS by default), stretched
+        // while the loop keeps overrunning it
+        let mut reload_requested = reload_signal.swap(false, Ordering::Relaxed);
+        let polled = event::poll(app.pacer.interval())?;
+        let work_started = Instant::now();
+        if polled {
+            let event = event::read()?;
+            if let Event::Resize(width, height) = event {
+                fit_to_size(&mut app, Size::new(width, height));
+            }
+            if let Event::Key(key) = event {
                 // Only handle key press events (not release/repeat)
-                if key.kind == KeyEventKind::Press {
+                if key.kind == KeyEventKind::Press && app.show_welcome {
+                    // Any key just dismisses the first-run overlay
+                    app.show_welcome = false;
+                } else if key.kind == KeyEventKind::Press && app.time_input.is_some() {
+                    // The jump-to-time input takes every key until closed
                     match key.code {
-                        KeyCode::Char('q') => {
+                        KeyCode::Enter => {
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            let rows =
+                                layout::event_log_rows(frame_area, app.config.office_width_percent);
+                            app.submit_time_input(rows);
+                        }
+                        KeyCode::Esc => app.time_input = None,
+                        KeyCode::Backspace => {
+                            if let Some(input) = app.time_input.as_mut() {
+                                input.pop();
+                            }
+                        }
+                        KeyCode::Char(c) if !c.is_control() => {
+                            if let Some(input) = app.time_input.as_mut() {
+                                if input.len() < 32 {
+                                    input.push(c);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                } else if key.kind == KeyEventKind::Press && app.search_key(key.code) {
+                    // Typed into the search, not a binding
+                } else if key.kind == KeyEventKind::Press && app.kill_prompt.is_some() {
+                    // Only `y` confirms; any other key cancels
+                    if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+                        app.confirm_kill(&SystemProcesses, actions::interrupt);
+                    } else {
+                        app.kill_prompt = None;
+                    }
+                } else if key.kind == KeyEventKind::Press && app.diff_view.is_some() {
+                    // The bookmark diff scrolls until closed
+                    match (key.code, app.config.keys.action(key.code)) {
+                        (KeyCode::Esc, _) => app.diff_view = None,
+                        (_, Some(Action::Quit)) => app.should_quit = true,
+                        (_, Some(Action::Bookmark)) => app.toggle_bookmark(Instant::now()),
+                        (_, Some(Action::ScrollUp)) => app.scroll_diff(false),
+                        (_, Some(Action::ScrollDown)) => app.scroll_diff(true),
+                        _ => {}
+                    }
+                } else if key.kind == KeyEventKind::Press {
+                    let action = app.config.keys.action(key.code);
+                    match (key.code, action) {
+                        (KeyCode::Char('e'), _)
+                            if key.modifiers.contains(KeyModifiers::CONTROL) =>
+                        {
+                            reload_requested = true;
+                        }
+                        (_, Some(Action::Quit)) => {
                             app.should_quit = true;
                         }
-                        KeyCode::Tab => {
+                        (_, Some(Action::Focus)) => {
                             app.toggle_focus();
                         }
-                        KeyCode::Up => {
-                            app.scroll_up();
+                        (_, Some(Action::ScrollUp)) => {
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            let rows =
+                                layout::event_log_rows(frame_area, app.config.office_width_percent);
+                            app.scroll_up(layout::sidebar_extent(&app, frame_area), rows);
+                        }
+                        (_, Some(Action::ScrollDown)) => {
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            let rows =
+                                layout::event_log_rows(frame_area, app.config.office_width_percent);
+                            app.scroll_down(layout::sidebar_extent(&app, frame_area), rows);
+                        }
+                        (_, Some(action @ (Action::ScrollTop | Action::ScrollBottom))) => {
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            let rows =
+                                layout::event_log_rows(frame_area, app.config.office_width_percent);
+                            app.scroll_to_edge(
+                                action == Action::ScrollBottom,
+                                layout::sidebar_extent(&app, frame_area),
+                                rows,
+                            );
+                        }
+                        (_, Some(Action::SelectNext)) => {
+                            app.select_next();
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            app.show_selected_in_sidebar(layout::sidebar_extent(&app, frame_area));
+                        }
+                        (_, Some(Action::SelectPrev)) => {
+                            app.select_prev();
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            app.show_selected_in_sidebar(layout::sidebar_extent(&app, frame_area));
+                        }
+                        (_, Some(Action::ToggleEventLog)) => {
+                            app.toggle_event_log();
+                        }
+                        (_, Some(action @ (Action::PrevTurn | Action::NextTurn))) => {
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            let rows =
+                                layout::event_log_rows(frame_area, app.config.office_width_percent);
+                            app.jump_turn(action == Action::NextTurn, rows);
                         }
-                        KeyCode::Down => {
-                            app.scroll_down();
+                        (_, Some(Action::JumpToTime)) => {
+                            app.open_time_input();
                         }
-                        KeyCode::Char(c) if c.is_ascii_digit() && c != '0' => {
+                        (_, Some(Action::Bookmark)) => {
+                            app.toggle_bookmark(Instant::now());
+                        }
+                        (_, Some(Action::CycleTheme)) => app.cycle_theme(),
+                        (_, Some(Action::KillSession)) => app.open_kill_prompt(),
+                        (KeyCode::Char(c), _) if c.is_ascii_digit() && c != '0' => {
                             app.select_agent(c.to_digit(10).unwrap());
+                            show_selected_agent(&mut app, terminal.size()?);
+                        }
+                        (_, Some(Action::NextDesk)) => {
+                            app.select_next_desk();
+                            show_selected_agent(&mut app, terminal.size()?);
+                        }
+                        (_, Some(Action::PrevDesk)) => {
+                            app.select_prev_desk();
+                            show_selected_agent(&mut app, terminal.size()?);
+                        }
+                        (_, Some(action @ (Action::NextPage | Action::PrevPage)))
+                            if app.focus == PanelFocus::Office =>
+                        {
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            let pages = layout::office_page_count(&app, frame_area);
+                            if action == Action::NextPage {
+                                app.next_office_page(pages);
+                            } else {
+                                app.prev_office_page(pages);
+                            }
+                        }
+                        (_, Some(action @ (Action::NextPage | Action::PrevPage))) => {
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            let rows =
+                                layout::event_log_rows(frame_area, app.config.office_width_percent);
+                            app.scroll_page(
+                                action == Action::NextPage,
+                                layout::sidebar_extent(&app, frame_area),
+                                rows,
+                            );
+                        }
+                        (_, Some(Action::Filter)) => {
+                            app.cycle_sidebar_filter();
                         }
-                        KeyCode::Char('r') => {
-                            // Reset tick count to force an immediate refresh
-                            app.tick_count = 0;
+                        (_, Some(Action::FilterActive)) => {
+                            app.toggle_status_filter(SidebarFilter::Active);
                         }
+                        (_, Some(Action::FilterWaiting)) => {
+                            app.toggle_status_filter(SidebarFilter::Waiting);
+                        }
+                        (_, Some(Action::Search)) => app.open_search_input(),
+                        (_, Some(Action::Stats)) => app.toggle_stats(),
+                        (KeyCode::Esc, _) if app.view_mode == ViewMode::Stats => {
+                            app.view_mode = ViewMode::Office;
+                        }
+                        (_, Some(Action::Export)) => {
+                            let frame_area = Rect::from((Position::ORIGIN, terminal.size()?));
+                            let exported = export::export_office(
+                                &app,
+                                frame_area,
+                                export_format,
+                                &paths::export_dir(process_env),
+                                SystemTime::now(),
+                            );
+                            match exported {
+                                Ok(path) => {
+                                    // Keep the file name visible in the footer
+                                    let width = (frame_area.width as usize).saturating_sub(16);
+                                    let shown = elide_path(&path.to_string_lossy(), width);
+                                    app.show_toast(format!("exported to {shown}"), ToastLevel::Info)
+                                }
+                                Err(e) => {
+                                    app.show_toast(format!("export failed: {e}"), ToastLevel::Error)
+                                }
+                            }
+                        }
+                        (_, Some(Action::Refresh)) => app.request_rescan(),
                         _ => {}
                     }
                 }
             }
         }
+        if reload_requested {
+            app.reload_config(load_config(config_path.as_deref()));
+        }
 
         // Tick the app state forward
-        app.tick();
+        let events = app.poll();
+        app.handle_events(&events, Instant::now());
+        for notification in app.take_notifications() {
+            notify::send(&notification);
+        }
+        app.record_loop(draw_cost + work_started.elapsed());
+
+        // Refresh the shell prompt status file every few seconds
+        let status_due = last_status_write
+            .is_none_or(|t| t.elapsed().as_secs() >= status_file::WRITE_INTERVAL_SECS);
+        if let Some(instance) = instance.as_ref().filter(|_| status_due) {
+            let statuses = app.agents.values().map(|agent| agent.status);
+            let counts = StatusCounts::from_statuses(statuses, SystemTime::now());
+            let _ = status_file::write_atomic(&instance.status_path, &counts);
+            last_status_write = Some(Instant::now());
+        }
 
         if app.should_quit {
             break Ok(());
@@ -148,8 +1167,54 @@ fn run_tui() -> io::Result<()> {
     // Restore the terminal
     ratatui::restore();
 
-    // Cleanup PID file
-    let _ = fs::remove_file(PID_FILE);
+    // A state file from a newer version is left as it was
+    if !persisted.read_only {
+        let state = PersistedState {
+            ui: UiPrefs {
+                sidebar_filter: app.sidebar_filter,
+            },
+            first_run_done: true,
+            ..persisted.state
+        };
+        let _ = persist::save(&state_path, &state);
+    }
+    app.session_tracker.save();
+
+    // Cleanup runtime files
+    if let Some(instance) = &instance {
+        instance.cleanup();
+    }
 
     result
 }
+
+/// Bring the office page and sidebar scroll back in range for a new
+/// terminal size, keeping the selected agent's desk in view.
+fn fit_to_size(app: &mut App, size: Size) {
+    let frame_area = Rect::from((Position::ORIGIN, size));
+    let pages = layout::office_page_count(app, frame_area);
+    app.office_page = app.office_page.min(pages.saturating_sub(1));
+    show_selected_desk(app, size);
+    app.sidebar_scroll = app
+        .sidebar_scroll
+        .min(layout::sidebar_max_scroll(app, frame_area));
+}
+
+/// Bring the selected agent into view: its desk in the office and its
+/// header in the sidebar.
+fn show_selected_agent(app: &mut App, size: Size) {
+    show_selected_desk(app, size);
+    let frame_area = Rect::from((Position::ORIGIN, size));
+    app.show_selected_in_sidebar(layout::sidebar_extent(app, frame_area));
+}
+
+/// Flip the office to the page with the selected agent's desk.
+fn show_selected_desk(app: &mut App, size: Size) {
+    let frame_area = Rect::from((Position::ORIGIN, size));
+    if let Some(page) = app
+        .selected_agent
+        .and_then(|id| layout::office_page_of(app, frame_area, id))
+    {
+        app.office_page = page;
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::mem::{discriminant, Discriminant};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::state::agent::{AgentState, AgentStatus};
+use crate::state::timer::format_duration_short;
+
+/// An agent that sent a kind of notification within this long stays quiet
+/// about it, so status flapping doesn't turn into a stream of notifications.
+pub const NOTIFY_DEBOUNCE: Duration = Duration::from_secs(30);
+/// Prompt summaries are cut to this many characters in the body.
+const BODY_SUMMARY_CHARS: usize = 80;
+
+/// Status changes worth a desktop notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyEvent {
+    /// The agent finished its turn and waits for the next prompt.
+    TurnFinished,
+    /// The agent asked a question and is blocked until it is answered.
+    NeedsInput,
+    /// The agent has been waiting on the user this long, past an
+    /// `[escalation]` threshold.
+    Escalated(Duration),
+}
+
+/// Which transition notifies, if any. Going dormant, waking up and moving
+/// between questions and work stay silent.
+pub fn notify_event(old: AgentStatus, new: AgentStatus) -> Option<NotifyEvent> {
+    match (old, new) {
+        (AgentStatus::Active, AgentStatus::Waiting) => Some(NotifyEvent::TurnFinished),
+        (old, AgentStatus::NeedsInput) if old != AgentStatus::NeedsInput => {
+            Some(NotifyEvent::NeedsInput)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+}
+
+impl Notification {
+    /// `Agent #3 finished its turn` over `my-repo: Fix the auth bug`.
+    pub fn new(event: NotifyEvent, agent: &AgentState) -> Self {
+        let what = match event {
+            NotifyEvent::TurnFinished => "finished its turn".to_string(),
+            NotifyEvent::NeedsInput => "needs input".to_string(),
+            NotifyEvent::Escalated(waited) => {
+                format!("has waited {} for you", format_duration_short(waited))
+            }
+        };
+        let mut summary: String = agent
+            .prompt_summary
+            .chars()
+            .take(BODY_SUMMARY_CHARS)
+            .collect();
+        if summary.len() < agent.prompt_summary.len() {
+            summary.push('\u{2026}');
+        }
+        let body = match (agent.project_name.is_empty(), summary.is_empty()) {
+            (false, false) => format!("{}: {summary}", agent.project_name),
+            (false, true) => agent.project_name.clone(),
+            (true, _) => summary,
+        };
+        Self {
+            title: format!("Agent #{} {what}", agent.id),
+            body,
+        }
+    }
+}
+
+/// Queues notifications for status changes, at most one of each kind per
+/// agent per `NOTIFY_DEBOUNCE`: a turn end doesn't hold back the question
+/// that follows it. Sending is left to the caller.
+#[derive(Debug, Default)]
+pub struct Notifier {
+    last_sent: HashMap<(u32, Discriminant<NotifyEvent>), Instant>,
+    outbox: Vec<Notification>,
+}
+
+impl Notifier {
+    /// Note `agent` going from `old` to `new`.
+    pub fn status_changed(
+        &mut self,
+        agent: &AgentState,
+        old: AgentStatus,
+        new: AgentStatus,
+        now: Instant,
+    ) {
+        if let Some(event) = notify_event(old, new) {
+            self.queue(event, agent, now);
+        }
+    }
+
+    /// Note `agent` passing an escalation threshold after waiting `waited`.
+    pub fn escalated(&mut self, agent: &AgentState, waited: Duration, now: Instant) {
+        self.queue(NotifyEvent::Escalated(waited), agent, now);
+    }
+
+    fn queue(&mut self, event: NotifyEvent, agent: &AgentState, now: Instant) {
+        let key = (agent.id, discriminant(&event));
+        let recent = self
+            .last_sent
+            .get(&key)
+            .is_some_and(|&at| now.saturating_duration_since(at) < NOTIFY_DEBOUNCE);
+        if recent {
+            return;
+        }
+        self.last_sent.insert(key, now);
+        self.outbox.push(Notification::new(event, agent));
+    }
+
+    /// Stop remembering a removed agent.
+    pub fn forget(&mut self, id: u32) {
+        self.last_sent.retain(|&(agent, _), _| agent != id);
+    }
+
+    /// Notifications queued since the last call.
+    pub fn take(&mut self) -> Vec<Notification> {
+        std::mem::take(&mut self.outbox)
+    }
+}
+
+/// How to show `notification` on this platform: `osascript` on macOS,
+/// `notify-send` elsewhere.
+pub fn notify_command(notification: &Notification, macos: bool) -> (String, Vec<String>) {
+    if macos {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "display notification {} with title {}",
+            quote(&notification.body),
+            quote(&notification.title)
+        );
+        ("osascript".to_string(), vec!["-e".to_string(), script])
+    } else {
+        (
+            "notify-send".to_string(),
+            vec![
+                "--app-name=pixel-agents-tui".to_string(),
+                notification.title.clone(),
+                notification.body.clone(),
+            ],
+        )
+    }
+}
+
+/// Show `notification` without blocking the UI. Failures, such as a missing
+/// `notify-send`, are ignored: notifications are best effort.
+pub fn send(notification: &Notification) {
+    let (program, args) = notify_command(notification, cfg!(target_os = "macos"));
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Ok(mut child) = child {
+        // Reap it off the UI thread
+        std::thread::spawn(move || child.wait());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use AgentStatus::*;
+
+    fn agent(summary: &str) -> AgentState {
+        let mut agent = AgentState::new(3, PathBuf::from("/c/projects/-src-my-repo/s.jsonl"));
+        agent.project_name = "my-repo".to_string();
+        agent.set_prompt_summary(summary);
+        agent
+    }
+
+    #[test]
+    fn only_turn_ends_and_questions_notify() {
+        assert_eq!(
+            notify_event(Active, Waiting),
+            Some(NotifyEvent::TurnFinished)
+        );
+        assert_eq!(
+            notify_event(Active, NeedsInput),
+            Some(NotifyEvent::NeedsInput)
+        );
+        assert_eq!(
+            notify_event(Waiting, NeedsInput),
+            Some(NotifyEvent::NeedsInput)
+        );
+        for (old, new) in [
+            (Waiting, Active),
+            (NeedsInput, Active),
+            (NeedsInput, NeedsInput),
+            (Active, Dormant),
+            (Waiting, Dormant),
+            (Dormant, Waiting),
+            (NeedsInput, Waiting),
+        ] {
+            assert_eq!(notify_event(old, new), None, "{old:?} -> {new:?}");
+        }
+    }
+
+    #[test]
+    fn notification_names_project_and_prompt() {
+        let n = Notification::new(NotifyEvent::NeedsInput, &agent("Fix the auth bug"));
+        assert_eq!(n.title, "Agent #3 needs input");
+        assert_eq!(n.body, "my-repo: Fix the auth bug");
+        let long = Notification::new(NotifyEvent::TurnFinished, &agent(&"x".repeat(120)));
+        assert_eq!(long.title, "Agent #3 finished its turn");
+        assert!(long.body.ends_with("x\u{2026}"));
+        let waited = Notification::new(
+            NotifyEvent::Escalated(Duration::from_secs(15 * 60)),
+            &agent("Fix the auth bug"),
+        );
+        assert_eq!(waited.title, "Agent #3 has waited 15m for you");
+    }
+
+    #[test]
+    fn flapping_is_debounced_per_agent() {
+        let mut notifier = Notifier::default();
+        let a = agent("work");
+        let t0 = Instant::now();
+        notifier.status_changed(&a, Active, Waiting, t0);
+        notifier.status_changed(&a, Waiting, Active, t0 + Duration::from_secs(1));
+        notifier.status_changed(&a, Active, Waiting, t0 + Duration::from_secs(2));
+        assert_eq!(notifier.take().len(), 1);
+        notifier.status_changed(&a, Active, Waiting, t0 + NOTIFY_DEBOUNCE);
+        assert_eq!(notifier.take().len(), 1);
+        assert!(notifier.take().is_empty());
+    }
+
+    #[test]
+    fn a_question_right_after_a_turn_end_still_notifies() {
+        let mut notifier = Notifier::default();
+        let a = agent("work");
+        let t0 = Instant::now();
+        notifier.status_changed(&a, Active, Waiting, t0);
+        notifier.status_changed(&a, Waiting, NeedsInput, t0 + Duration::from_secs(1));
+        notifier.status_changed(&a, NeedsInput, Active, t0 + Duration::from_secs(2));
+        notifier.status_changed(&a, Active, NeedsInput, t0 + Duration::from_secs(3));
+        let titles: Vec<String> = notifier.take().into_iter().map(|n| n.title).collect();
+        assert_eq!(
+            titles,
+            ["Agent #3 finished its turn", "Agent #3 needs input"]
+        );
+    }
+
+    #[test]
+    fn escalations_leave_status_notifications_alone() {
+        let mut notifier = Notifier::default();
+        let a = agent("work");
+        let t0 = Instant::now();
+        notifier.escalated(&a, Duration::from_secs(300), t0);
+        notifier.escalated(&a, Duration::from_secs(301), t0 + Duration::from_secs(1));
+        notifier.status_changed(&a, Active, NeedsInput, t0 + Duration::from_secs(2));
+        let titles: Vec<String> = notifier.take().into_iter().map(|n| n.title).collect();
+        assert_eq!(
+            titles,
+            ["Agent #3 has waited 5m for you", "Agent #3 needs input"]
+        );
+    }
+
+    #[test]
+    fn commands_per_platform() {
+        let n = Notification {
+            title: "Agent #1 needs input".to_string(),
+            body: r#"repo: say "hi""#.to_string(),
+        };
+        let (program, args) = notify_command(&n, true);
+        assert_eq!(program, "osascript");
+        assert_eq!(
+            args[1],
+            r#"display notification "repo: say \"hi\"" with title "Agent #1 needs input""#
+        );
+        let (program, args) = notify_command(&n, false);
+        assert_eq!(program, "notify-send");
+        assert_eq!(&args[1..], [n.title.as_str(), n.body.as_str()]);
+    }
+}
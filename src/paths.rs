@@ -0,0 +1,455 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Env var that overrides the Claude data directory (below `--claude-dir`).
+pub const CLAUDE_DIR_ENV: &str = "PIXEL_AGENTS_CLAUDE_DIR";
+
+const APP_DIR_NAME: &str = "pixel-agents-tui";
+
+/// Where the Claude directory was resolved from, for diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaudeDirSource {
+    Flag,
+    Env,
+    Config,
+    Home,
+}
+
+impl ClaudeDirSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClaudeDirSource::Flag => "--claude-dir",
+            ClaudeDirSource::Env => CLAUDE_DIR_ENV,
+            ClaudeDirSource::Config => "config.toml",
+            ClaudeDirSource::Home => "$HOME/.claude",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaudeDir {
+    pub path: PathBuf,
+    pub source: ClaudeDirSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// Neither `--claude-dir`, the env var, nor `$HOME` is available.
+    NoClaudeDir,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::NoClaudeDir => write!(
+                f,
+                "cannot locate the Claude directory: $HOME is not set. \
+                 Pass --claude-dir <path> or set {CLAUDE_DIR_ENV}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Read an env var from the real process environment.
+pub fn process_env(key: &str) -> Option<OsString> {
+    std::env::var_os(key)
+}
+
+/// Look up an env var through `env`, treating empty values as unset.
+fn non_empty<E>(env: &E, key: &str) -> Option<PathBuf>
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    env(key).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+/// Resolve the Claude data directory.
+///
+/// Order: `--claude-dir` flag > `PIXEL_AGENTS_CLAUDE_DIR` > `claude_dir` in
+/// the config file > `$HOME/.claude`.
+/// Fails instead of guessing a relative path when none of them is available.
+pub fn resolve_claude_dir<E>(
+    flag: Option<&Path>,
+    config: Option<&Path>,
+    env: E,
+) -> Result<ClaudeDir, PathError>
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    if let Some(path) = flag {
+        return Ok(ClaudeDir {
+            path: path.to_path_buf(),
+            source: ClaudeDirSource::Flag,
+        });
+    }
+    if let Some(path) = non_empty(&env, CLAUDE_DIR_ENV) {
+        return Ok(ClaudeDir {
+            path,
+            source: ClaudeDirSource::Env,
+        });
+    }
+    if let Some(path) = config {
+        return Ok(ClaudeDir {
+            path: path.to_path_buf(),
+            source: ClaudeDirSource::Config,
+        });
+    }
+    if let Some(home) = non_empty(&env, "HOME") {
+        return Ok(ClaudeDir {
+            path: home.join(".claude"),
+            source: ClaudeDirSource::Home,
+        });
+    }
+    Err(PathError::NoClaudeDir)
+}
+
+/// Directory for runtime files (PID file, sockets).
+///
+/// `$XDG_RUNTIME_DIR` > `$TMPDIR` > `%TEMP%` (per user on Windows) >
+/// `/tmp/pixel-agents-tui-<uid>`, so users sharing `/tmp` don't share files.
+pub fn runtime_dir<E>(env: E) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    non_empty(&env, "XDG_RUNTIME_DIR")
+        .or_else(|| non_empty(&env, "TMPDIR"))
+        .or_else(|| non_empty(&env, "TEMP"))
+        .unwrap_or_else(user_tmp_dir)
+}
+
+#[cfg(unix)]
+fn user_tmp_dir() -> PathBuf {
+    PathBuf::from(format!("/tmp/{APP_DIR_NAME}-{}", nix::unistd::getuid()))
+}
+
+#[cfg(not(unix))]
+fn user_tmp_dir() -> PathBuf {
+    std::env::temp_dir()
+}
+
+/// Create the runtime directory if needed, readable by its owner only.
+pub fn create_runtime_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+/// Directory for persisted state.
+///
+/// `$XDG_STATE_HOME/pixel-agents-tui` > `$HOME/.local/state/pixel-agents-tui` >
+/// `<runtime dir>/pixel-agents-tui` (not persistent across reboots, but writable).
+pub fn state_dir<E>(env: E) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    if let Some(dir) = non_empty(&env, "XDG_STATE_HOME") {
+        return dir.join(APP_DIR_NAME);
+    }
+    if let Some(home) = non_empty(&env, "HOME") {
+        return home.join(".local").join("state").join(APP_DIR_NAME);
+    }
+    runtime_dir(env).join(APP_DIR_NAME)
+}
+
+/// Directory holding the config file.
+///
+/// `$XDG_CONFIG_HOME/pixel-agents-tui` > `$HOME/.config/pixel-agents-tui`.
+/// Returns `None` when neither is set; callers then run with defaults.
+pub fn config_dir<E>(env: E) -> Option<PathBuf>
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    if let Some(dir) = non_empty(&env, "XDG_CONFIG_HOME") {
+        return Some(dir.join(APP_DIR_NAME));
+    }
+    non_empty(&env, "HOME").map(|home| home.join(".config").join(APP_DIR_NAME))
+}
+
+/// Path of the singleton PID file.
+pub fn pid_file<E>(env: E) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    runtime_dir(env).join(format!("{APP_DIR_NAME}.pid"))
+}
+
+/// Path of the PID file of an instance scoped to one project (`--project`),
+/// keyed by a hash of the project's directory name under
+/// `~/.claude/projects/` so instances for different projects coexist.
+pub fn project_pid_file<E>(env: E, dir_key: &str) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    runtime_dir(env).join(format!("{APP_DIR_NAME}-{:016x}.pid", fnv1a(dir_key)))
+}
+
+/// File name, in the state dir, of the session IDs an instance scoped to
+/// one project keeps across runs, keyed like its PID file.
+pub fn project_sessions_file_name(dir_key: &str) -> String {
+    format!("sessions-{:016x}.json", fnv1a(dir_key))
+}
+
+/// FNV-1a, which unlike `DefaultHasher` gives the same hash in every build.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Path of the compact status file read by shell prompt integrations.
+pub fn status_file<E>(env: E) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    runtime_dir(env).join(format!("{APP_DIR_NAME}.status.json"))
+}
+
+/// Path of the status file of an instance scoped to one project, keyed
+/// like its PID file.
+pub fn project_status_file<E>(env: E, dir_key: &str) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    runtime_dir(env).join(format!(
+        "{APP_DIR_NAME}-{:016x}.status.json",
+        fnv1a(dir_key)
+    ))
+}
+
+/// Status files of every per-project instance, running or left behind.
+pub fn project_status_files<E>(env: E) -> Vec<PathBuf>
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    let Ok(entries) = std::fs::read_dir(runtime_dir(env)) else {
+        return Vec::new();
+    };
+    let prefix = format!("{APP_DIR_NAME}-");
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|rest| rest.strip_suffix(".status.json"))
+                .is_some_and(|hash| hash.len() == 16)
+        })
+        .collect()
+}
+
+/// Path of the registry of running instances and the directories they watch.
+pub fn watchers_file<E>(env: E) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    runtime_dir(env).join(format!("{APP_DIR_NAME}.watchers.json"))
+}
+
+/// Default socket a `--serve` instance publishes snapshots on.
+pub fn socket_file<E>(env: E) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    runtime_dir(env).join(format!("{APP_DIR_NAME}.sock"))
+}
+
+/// Directory exported snapshots are written to.
+///
+/// `$XDG_DOWNLOAD_DIR` > `$HOME/Downloads` > runtime dir.
+pub fn export_dir<E>(env: E) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    if let Some(dir) = non_empty(&env, "XDG_DOWNLOAD_DIR") {
+        return dir;
+    }
+    if let Some(home) = non_empty(&env, "HOME") {
+        return home.join("Downloads");
+    }
+    runtime_dir(env)
+}
+
+/// Log file for launcher runs started by the session hook.
+pub fn hook_log_file<E>(env: E) -> PathBuf
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    state_dir(env).join("hook.log")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn fake_env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let map: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), OsString::from(v)))
+            .collect();
+        move |key| map.get(key).cloned()
+    }
+
+    #[test]
+    fn claude_dir_flag_wins() {
+        let env = fake_env(&[(CLAUDE_DIR_ENV, "/env/claude"), ("HOME", "/home/me")]);
+        let dir = resolve_claude_dir(Some(Path::new("/flag/claude")), None, env).unwrap();
+        assert_eq!(dir.path, PathBuf::from("/flag/claude"));
+        assert_eq!(dir.source, ClaudeDirSource::Flag);
+    }
+
+    #[test]
+    fn claude_dir_env_beats_home() {
+        let env = fake_env(&[(CLAUDE_DIR_ENV, "/env/claude"), ("HOME", "/home/me")]);
+        let dir = resolve_claude_dir(None, None, env).unwrap();
+        assert_eq!(dir.path, PathBuf::from("/env/claude"));
+        assert_eq!(dir.source, ClaudeDirSource::Env);
+    }
+
+    #[test]
+    fn claude_dir_falls_back_to_home() {
+        let env = fake_env(&[("HOME", "/home/me")]);
+        let dir = resolve_claude_dir(None, None, env).unwrap();
+        assert_eq!(dir.path, PathBuf::from("/home/me/.claude"));
+        assert_eq!(dir.source, ClaudeDirSource::Home);
+    }
+
+    #[test]
+    fn claude_dir_config_sits_between_env_and_home() {
+        let config = Some(Path::new("/config/claude"));
+        let env = fake_env(&[("HOME", "/home/me")]);
+        let dir = resolve_claude_dir(None, config, env).unwrap();
+        assert_eq!(dir.path, PathBuf::from("/config/claude"));
+        assert_eq!(dir.source, ClaudeDirSource::Config);
+
+        let env = fake_env(&[(CLAUDE_DIR_ENV, "/env/claude")]);
+        let dir = resolve_claude_dir(None, config, env).unwrap();
+        assert_eq!(dir.source, ClaudeDirSource::Env);
+    }
+
+    #[test]
+    fn claude_dir_errors_without_home() {
+        let env = fake_env(&[]);
+        assert_eq!(
+            resolve_claude_dir(None, None, env),
+            Err(PathError::NoClaudeDir)
+        );
+    }
+
+    #[test]
+    fn empty_env_values_are_ignored() {
+        let env = fake_env(&[(CLAUDE_DIR_ENV, ""), ("HOME", "")]);
+        assert_eq!(
+            resolve_claude_dir(None, None, env),
+            Err(PathError::NoClaudeDir)
+        );
+    }
+
+    #[test]
+    fn runtime_dir_fallbacks() {
+        let env = fake_env(&[
+            ("XDG_RUNTIME_DIR", "/run/user/1000"),
+            ("TMPDIR", "/var/tmp"),
+        ]);
+        assert_eq!(runtime_dir(env), PathBuf::from("/run/user/1000"));
+        let env = fake_env(&[("TMPDIR", "/var/tmp"), ("TEMP", r"C:\Users\me\Temp")]);
+        assert_eq!(runtime_dir(env), PathBuf::from("/var/tmp"));
+        let env = fake_env(&[("TEMP", r"C:\Users\me\Temp")]);
+        assert_eq!(runtime_dir(env), PathBuf::from(r"C:\Users\me\Temp"));
+        assert_eq!(runtime_dir(fake_env(&[])), user_tmp_dir());
+        #[cfg(unix)]
+        assert_eq!(
+            user_tmp_dir(),
+            PathBuf::from(format!("/tmp/pixel-agents-tui-{}", nix::unistd::getuid()))
+        );
+    }
+
+    #[test]
+    fn state_dir_fallbacks() {
+        let env = fake_env(&[("XDG_STATE_HOME", "/xdg/state"), ("HOME", "/home/me")]);
+        assert_eq!(state_dir(env), PathBuf::from("/xdg/state/pixel-agents-tui"));
+        let env = fake_env(&[("HOME", "/home/me")]);
+        assert_eq!(
+            state_dir(env),
+            PathBuf::from("/home/me/.local/state/pixel-agents-tui")
+        );
+        assert_eq!(
+            state_dir(fake_env(&[])),
+            runtime_dir(fake_env(&[])).join("pixel-agents-tui")
+        );
+    }
+
+    #[test]
+    fn config_dir_fallbacks() {
+        let env = fake_env(&[("XDG_CONFIG_HOME", "/xdg/config"), ("HOME", "/home/me")]);
+        assert_eq!(
+            config_dir(env),
+            Some(PathBuf::from("/xdg/config/pixel-agents-tui"))
+        );
+        let env = fake_env(&[("HOME", "/home/me")]);
+        assert_eq!(
+            config_dir(env),
+            Some(PathBuf::from("/home/me/.config/pixel-agents-tui"))
+        );
+        assert_eq!(config_dir(fake_env(&[])), None);
+    }
+
+    #[test]
+    fn pid_file_lives_in_runtime_dir() {
+        let env = fake_env(&[("XDG_RUNTIME_DIR", "/run/user/1000")]);
+        assert_eq!(
+            pid_file(env),
+            PathBuf::from("/run/user/1000/pixel-agents-tui.pid")
+        );
+    }
+
+    #[test]
+    fn project_pid_files_are_keyed_by_project() {
+        let env = || fake_env(&[("XDG_RUNTIME_DIR", "/run/user/1000")]);
+        let mine = project_pid_file(env(), "-src-mine");
+        assert_eq!(mine.parent(), Some(Path::new("/run/user/1000")));
+        assert_eq!(mine, project_pid_file(env(), "-src-mine"));
+        assert_ne!(mine, project_pid_file(env(), "-src-theirs"));
+        assert_ne!(mine, pid_file(env()));
+        assert_ne!(
+            project_sessions_file_name("-src-mine"),
+            project_sessions_file_name("-src-theirs")
+        );
+        // Stable across builds: the standard FNV-1a test vector
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn export_dir_fallbacks() {
+        let env = fake_env(&[("XDG_DOWNLOAD_DIR", "/dl"), ("HOME", "/home/me")]);
+        assert_eq!(export_dir(env), PathBuf::from("/dl"));
+        let env = fake_env(&[("HOME", "/home/me")]);
+        assert_eq!(export_dir(env), PathBuf::from("/home/me/Downloads"));
+        assert_eq!(export_dir(fake_env(&[])), runtime_dir(fake_env(&[])));
+    }
+
+    #[test]
+    fn hook_log_lives_in_state_dir() {
+        let env = fake_env(&[("XDG_STATE_HOME", "/xdg/state")]);
+        assert_eq!(
+            hook_log_file(env),
+            PathBuf::from("/xdg/state/pixel-agents-tui/hook.log")
+        );
+    }
+
+    #[test]
+    fn socket_lives_in_runtime_dir() {
+        let env = fake_env(&[("XDG_RUNTIME_DIR", "/run/user/1000")]);
+        assert_eq!(
+            socket_file(env),
+            PathBuf::from("/run/user/1000/pixel-agents-tui.sock")
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app::SidebarFilter;
+use crate::status_file;
+
+/// File name inside the state directory (see `paths::state_dir`).
+pub const STATE_FILE_NAME: &str = "state.json";
+
+/// Format version written by this build.
+pub const CURRENT_VERSION: u64 = 1;
+
+/// A step that upgrades the JSON of version `n` to version `n + 1`.
+pub type Migration = fn(Value) -> Value;
+
+/// Upgrades from each older version, oldest first: `MIGRATIONS[i]` turns
+/// version `i + 1` into version `i + 2`. Version 1 is the first format.
+const MIGRATIONS: &[Migration] = &[];
+
+/// A file in the state directory with a `version` field, loaded through
+/// [`load_versioned`].
+pub trait Versioned: DeserializeOwned + Default {
+    /// What warnings call the file, e.g. `state file`.
+    const LABEL: &'static str;
+    /// Upgrades from each older version, laid out like [`MIGRATIONS`].
+    const MIGRATIONS: &'static [Migration];
+}
+
+/// Everything persisted between runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedState {
+    pub version: u64,
+    #[serde(default)]
+    pub ui: UiPrefs,
+    /// The first-run overlay has been shown.
+    #[serde(default)]
+    pub first_run_done: bool,
+}
+
+impl Default for PersistedState {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            ui: UiPrefs::default(),
+            first_run_done: false,
+        }
+    }
+}
+
+impl Versioned for PersistedState {
+    const LABEL: &'static str = "state file";
+    const MIGRATIONS: &'static [Migration] = MIGRATIONS;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiPrefs {
+    #[serde(default)]
+    pub sidebar_filter: SidebarFilter,
+}
+
+/// Result of loading the state file, or another [`Versioned`] one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadOutcome<T = PersistedState> {
+    pub state: T,
+    /// Don't save over the file (it was written by a newer version).
+    pub read_only: bool,
+    /// Something the user should know about, e.g. a file moved aside.
+    pub warning: Option<String>,
+}
+
+impl<T> LoadOutcome<T> {
+    fn fresh(state: T) -> Self {
+        Self {
+            state,
+            read_only: false,
+            warning: None,
+        }
+    }
+}
+
+/// Load the state file, upgrading older formats.
+pub fn load(path: &Path) -> LoadOutcome {
+    load_versioned(path)
+}
+
+/// Load a [`Versioned`] file, upgrading older formats.
+///
+/// Missing files yield defaults. Corrupted files are renamed to `.bak` and
+/// replaced by defaults. Files from a newer version are used as far as they
+/// parse, but marked read-only so this build doesn't clobber them.
+pub fn load_versioned<T: Versioned>(path: &Path) -> LoadOutcome<T> {
+    let label = T::LABEL;
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return LoadOutcome::fresh(T::default()),
+        Err(e) => {
+            return LoadOutcome {
+                state: T::default(),
+                read_only: true,
+                warning: Some(format!("cannot read {}: {e}", path.display())),
+            }
+        }
+    };
+    match decode(&data, T::MIGRATIONS) {
+        Ok(state) => LoadOutcome::fresh(state),
+        Err(DecodeError::Newer(version, value)) => LoadOutcome {
+            state: serde_json::from_value(value).unwrap_or_default(),
+            read_only: true,
+            warning: Some(format!(
+                "{label} is version {version}, newer than this build \
+                 (version {}); it won't be saved",
+                T::MIGRATIONS.len() + 1
+            )),
+        },
+        Err(DecodeError::Corrupt(reason)) => {
+            let backup = backup_path(path);
+            let warning = match fs::rename(path, &backup) {
+                Ok(()) => format!(
+                    "{label} was unreadable ({reason}); moved to {}",
+                    backup.display()
+                ),
+                Err(e) => {
+                    format!("{label} was unreadable ({reason}) and could not be moved: {e}")
+                }
+            };
+            LoadOutcome {
+                state: T::default(),
+                read_only: false,
+                warning: Some(warning),
+            }
+        }
+    }
+}
+
+/// Write the state file, or another [`Versioned`] one, atomically,
+/// creating its directory if needed.
+pub fn save<T: Serialize>(path: &Path, state: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    status_file::write_atomic(path, state)
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+#[derive(Debug)]
+enum DecodeError {
+    /// Written by a newer version; the raw JSON is kept for a best-effort read.
+    Newer(u64, Value),
+    Corrupt(String),
+}
+
+/// Parse, migrate to [`CURRENT_VERSION`] via `migrations`, and deserialize.
+fn decode<T: DeserializeOwned>(data: &[u8], migrations: &[Migration]) -> Result<T, DecodeError> {
+    let value: Value =
+        serde_json::from_slice(data).map_err(|e| DecodeError::Corrupt(e.to_string()))?;
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .filter(|v| *v >= 1)
+        .ok_or_else(|| DecodeError::Corrupt("missing version".to_string()))?;
+    let current = migrations.len() as u64 + 1;
+    if version > current {
+        return Err(DecodeError::Newer(version, value));
+    }
+    let migrated = migrate(value, version, migrations);
+    serde_json::from_value(migrated).map_err(|e| DecodeError::Corrupt(e.to_string()))
+}
+
+/// Apply `migrations` from `version` up to the latest, one step at a time.
+fn migrate(mut value: Value, version: u64, migrations: &[Migration]) -> Value {
+    for (i, step) in migrations.iter().enumerate().skip(version as usize - 1) {
+        value = step(value);
+        value["version"] = Value::from(i as u64 + 2);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn missing_file_is_default_and_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let outcome = load(&dir.path().join(STATE_FILE_NAME));
+        assert_eq!(outcome, LoadOutcome::fresh(PersistedState::default()));
+        assert!(!outcome.state.first_run_done);
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(STATE_FILE_NAME);
+        let state = PersistedState {
+            version: CURRENT_VERSION,
+            ui: UiPrefs {
+                sidebar_filter: SidebarFilter::Unseen,
+            },
+            first_run_done: true,
+        };
+        save(&path, &state).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("\"version\":1"));
+        assert_eq!(load(&path), LoadOutcome::fresh(state));
+    }
+
+    #[test]
+    fn migrations_run_step_by_step() {
+        // A pretend history: v1 -> v2 renames a field, v2 -> v3 adds one
+        fn v1_to_v2(mut v: Value) -> Value {
+            let filter = v["ui"]["filter"].take();
+            v["ui"] = json!({ "sidebar_filter": filter });
+            v
+        }
+        fn v2_to_v3(mut v: Value) -> Value {
+            v["ui"]["added_in_v3"] = json!(true);
+            v
+        }
+        let steps: &[Migration] = &[v1_to_v2, v2_to_v3];
+
+        let v1 = json!({"version": 1, "ui": {"filter": "unseen"}});
+        let migrated = migrate(v1, 1, steps);
+        assert_eq!(migrated["version"], 3);
+        assert_eq!(migrated["ui"]["sidebar_filter"], "unseen");
+        assert_eq!(migrated["ui"]["added_in_v3"], true);
+
+        // Starting from v2 only runs the second step
+        let v2 = json!({"version": 2, "ui": {"sidebar_filter": "all"}});
+        let migrated = migrate(v2, 2, steps);
+        assert_eq!(migrated["version"], 3);
+        assert_eq!(migrated["ui"]["sidebar_filter"], "all");
+    }
+
+    #[test]
+    fn newer_version_is_read_only_and_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATE_FILE_NAME);
+        let contents = r#"{"version":99,"ui":{"sidebar_filter":"needs_attention"},"pins":[3]}"#;
+        fs::write(&path, contents).unwrap();
+        let outcome = load(&path);
+        assert!(outcome.read_only);
+        assert!(outcome.warning.unwrap().contains("version 99"));
+        assert_eq!(
+            outcome.state.ui.sidebar_filter,
+            SidebarFilter::NeedsAttention
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+    }
+
+    #[test]
+    fn corrupted_file_is_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATE_FILE_NAME);
+        for garbage in [
+            "{ not json",
+            r#"{"ui":{}}"#,
+            r#"{"version":1,"ui":{"sidebar_filter":42}}"#,
+        ] {
+            fs::write(&path, garbage).unwrap();
+            let outcome = load(&path);
+            assert!(!outcome.read_only);
+            assert_eq!(outcome.state, PersistedState::default());
+            assert!(outcome.warning.unwrap().contains(".bak"));
+            assert!(!path.exists());
+            assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), garbage);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::api::Snapshot;
+use crate::state::timer::format_duration_short;
+use crate::ui::text::elide_path;
+
+/// A client that can't take a snapshot within this long is dropped.
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(250);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(5);
+/// The banner shares the header with the title and counts.
+const BANNER_PATH_WIDTH: usize = 40;
+
+/// The `--serve` end: accepts clients on a Unix socket and sends each of them
+/// every published snapshot.
+pub struct Server {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<UnixStream>,
+}
+
+impl Server {
+    /// Listen on `path`, replacing a socket left behind by a dead server.
+    /// Fails if another server is still listening there, or if `path`'s
+    /// directory doesn't exist. `mode` sets the socket's permissions (say
+    /// `0o660` to let a group connect); without it the umask decides.
+    pub fn bind(path: &Path, mode: Option<u32>) -> io::Result<Self> {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a server is already listening on {}", path.display()),
+            ));
+        }
+        if fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+            fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        if let Some(mode) = mode {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+        }
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Accept waiting clients, then send `snapshot` to everyone. Clients
+    /// that hung up or stall are dropped.
+    pub fn publish(&mut self, snapshot: &Snapshot) {
+        self.accept_pending();
+        let Ok(mut line) = serde_json::to_vec(snapshot) else {
+            return;
+        };
+        line.push(b'\n');
+        self.clients
+            .retain_mut(|client| client.write_all(&line).is_ok());
+    }
+
+    fn accept_pending(&mut self) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            // Accepted sockets inherit non-blocking mode on some platforms
+            if stream.set_nonblocking(false).is_ok()
+                && stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT)).is_ok()
+            {
+                self.clients.push(stream);
+            }
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No connection has succeeded yet.
+    Connecting,
+    Connected,
+    /// The server went away; reconnecting in the background.
+    Lost {
+        since: Instant,
+    },
+}
+
+enum ClientEvent {
+    Connected,
+    Snapshot(Snapshot),
+    Disconnected,
+    /// A connection attempt failed; also lets the thread notice it was dropped.
+    Retrying,
+}
+
+/// The `--connect` end: a background thread reads snapshots from the server
+/// and reconnects with backoff whenever the connection drops.
+pub struct RemoteClient {
+    socket: PathBuf,
+    events: Receiver<ClientEvent>,
+    state: ConnectionState,
+}
+
+impl RemoteClient {
+    /// Start connecting to `socket` in the background. The thread exits once
+    /// the client is dropped.
+    pub fn connect(socket: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let path = socket.clone();
+        thread::spawn(move || connection_loop(&path, &tx));
+        Self {
+            socket,
+            events: rx,
+            state: ConnectionState::Connecting,
+        }
+    }
+
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Process everything received since the last call and return the newest
+    /// snapshot, if any.
+    pub fn poll(&mut self, now: Instant) -> Option<Snapshot> {
+        let mut latest = None;
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                ClientEvent::Connected => self.state = ConnectionState::Connected,
+                ClientEvent::Snapshot(snapshot) => latest = Some(snapshot),
+                ClientEvent::Disconnected => {
+                    if self.state == ConnectionState::Connected {
+                        self.state = ConnectionState::Lost { since: now };
+                    }
+                }
+                ClientEvent::Retrying => {}
+            }
+        }
+        latest
+    }
+
+    /// Header banner text for any state but connected.
+    pub fn banner(&self, now: Instant) -> Option<String> {
+        let socket = elide_path(&self.socket.to_string_lossy(), BANNER_PATH_WIDTH);
+        match self.state {
+            ConnectionState::Connected => None,
+            ConnectionState::Connecting => Some(format!("connecting to {socket}\u{2026}")),
+            ConnectionState::Lost { since } => Some(format!(
+                "connection to {socket} lost {} ago, reconnecting\u{2026}",
+                format_duration_short(now.saturating_duration_since(since))
+            )),
+        }
+    }
+}
+
+fn connection_loop(path: &Path, events: &Sender<ClientEvent>) {
+    let mut attempt = 0;
+    loop {
+        let event = match UnixStream::connect(path) {
+            Ok(stream) => {
+                attempt = 0;
+                if events.send(ClientEvent::Connected).is_err() {
+                    return;
+                }
+                for line in BufReader::new(stream).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    let Ok(snapshot) = serde_json::from_str(&line) else {
+                        continue;
+                    };
+                    if events.send(ClientEvent::Snapshot(snapshot)).is_err() {
+                        return;
+                    }
+                }
+                ClientEvent::Disconnected
+            }
+            Err(_) => ClientEvent::Retrying,
+        };
+        if events.send(event).is_err() {
+            return;
+        }
+        thread::sleep(reconnect_delay(attempt));
+        attempt += 1;
+    }
+}
+
+/// Parse a `--socket-mode` value: octal permission bits, as for chmod.
+pub fn parse_socket_mode(text: &str) -> Result<u32, String> {
+    u32::from_str_radix(text, 8)
+        .ok()
+        .filter(|&mode| mode <= 0o777)
+        .ok_or_else(|| format!("expected an octal mode such as 660, got {text:?}"))
+}
+
+/// Delay before reconnect attempt `attempt` (0-based): doubling from 250ms,
+/// capped at 5s.
+pub fn reconnect_delay(attempt: u32) -> Duration {
+    let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+    RECONNECT_BASE_DELAY
+        .saturating_mul(factor)
+        .min(RECONNECT_MAX_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for<T>(mut f: impl FnMut() -> Option<T>) -> T {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(value) = f() {
+                return value;
+            }
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_then_caps() {
+        assert_eq!(reconnect_delay(0), Duration::from_millis(250));
+        assert_eq!(reconnect_delay(1), Duration::from_millis(500));
+        assert_eq!(reconnect_delay(3), Duration::from_secs(2));
+        assert_eq!(reconnect_delay(5), RECONNECT_MAX_DELAY);
+        assert_eq!(reconnect_delay(40), RECONNECT_MAX_DELAY);
+    }
+
+    #[test]
+    fn bind_refuses_a_live_server_and_replaces_a_stale_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.sock");
+        let server = Server::bind(&path, None).unwrap();
+        let err = Server::bind(&path, None).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        // A leftover socket file with nobody listening is replaced
+        drop(server);
+        let stale = UnixListener::bind(&path).unwrap();
+        drop(stale);
+        assert!(path.exists());
+        assert!(Server::bind(&path, None).is_ok());
+    }
+
+    #[test]
+    fn bind_applies_the_socket_mode_and_leaves_missing_dirs_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shared.sock");
+        let _server = Server::bind(&path, Some(0o660)).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+
+        let missing = dir.path().join("missing").join("test.sock");
+        assert!(Server::bind(&missing, None).is_err());
+        assert!(!dir.path().join("missing").exists());
+    }
+
+    #[test]
+    fn socket_mode_parses_as_octal() {
+        assert_eq!(parse_socket_mode("660"), Ok(0o660));
+        assert_eq!(parse_socket_mode("0666"), Ok(0o666));
+        assert!(parse_socket_mode("1777").is_err());
+        assert!(parse_socket_mode("rw").is_err());
+    }
+
+    #[test]
+    fn client_reports_lost_connection_and_reconnects() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.sock");
+        let mut client = RemoteClient::connect(path.clone());
+        assert!(client
+            .banner(Instant::now())
+            .unwrap()
+            .contains("connecting"));
+
+        let mut server = Server::bind(&path, None).unwrap();
+        let snapshot = Snapshot::default();
+        wait_for(|| {
+            server.publish(&snapshot);
+            client.poll(Instant::now())
+        });
+        assert_eq!(client.state(), ConnectionState::Connected);
+        assert!(client.banner(Instant::now()).is_none());
+
+        drop(server);
+        wait_for(|| {
+            client.poll(Instant::now());
+            matches!(client.state(), ConnectionState::Lost { .. }).then_some(())
+        });
+        assert!(client.banner(Instant::now()).unwrap().contains("lost"));
+
+        let mut server = Server::bind(&path, None).unwrap();
+        wait_for(|| {
+            server.publish(&snapshot);
+            client.poll(Instant::now())
+        });
+        assert_eq!(client.state(), ConnectionState::Connected);
+    }
+}
@@ -0,0 +1,261 @@
+use schemars::{schema_for, Schema};
+use serde_json::Value;
+
+use crate::api::Snapshot;
+use crate::status_file::{StatusCounts, StatusReport};
+use crate::terminal::Diagnostics;
+
+/// Version of every JSON document written for other programs, carried in
+/// its `schema_version` field. Adding a field keeps the version; removing
+/// or renaming one, or changing its type, bumps it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The JSON documents other programs can rely on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputKind {
+    /// `--status` output, one document per line with `--watch`.
+    Status,
+    /// The status file read by `--print-prompt-segment`.
+    StatusFile,
+    /// What a `--serve` instance sends to `--connect` clients, one per line.
+    Snapshot,
+    /// `--diagnostics-json` output.
+    Diagnostics,
+}
+
+impl OutputKind {
+    pub const ALL: [OutputKind; 4] = [
+        OutputKind::Status,
+        OutputKind::StatusFile,
+        OutputKind::Snapshot,
+        OutputKind::Diagnostics,
+    ];
+
+    /// Name on the command line and in `--print-schema` output.
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputKind::Status => "status",
+            OutputKind::StatusFile => "status-file",
+            OutputKind::Snapshot => "snapshot",
+            OutputKind::Diagnostics => "diagnostics",
+        }
+    }
+
+    /// JSON Schema of the document.
+    pub fn schema(self) -> Schema {
+        match self {
+            OutputKind::Status => schema_for!(StatusReport),
+            OutputKind::StatusFile => schema_for!(StatusCounts),
+            OutputKind::Snapshot => schema_for!(Snapshot),
+            OutputKind::Diagnostics => schema_for!(Diagnostics),
+        }
+    }
+}
+
+/// What `--print-schema` prints: the schema of `kind`, or of every kind
+/// keyed by name.
+pub fn schema_document(kind: Option<OutputKind>) -> Value {
+    match kind {
+        Some(kind) => kind.schema().to_value(),
+        None => Value::Object(
+            OutputKind::ALL
+                .iter()
+                .map(|kind| (kind.name().to_string(), kind.schema().to_value()))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    use crate::app::App;
+    use crate::state::agent::AgentState;
+
+    /// Property names of `object` in `schema`: the top level for `None`,
+    /// otherwise one of its `$defs`.
+    fn properties(schema: &Value, object: Option<&str>) -> Vec<String> {
+        let object = match object {
+            Some(name) => &schema["$defs"][name],
+            None => schema,
+        };
+        let mut names: Vec<String> = object["properties"]
+            .as_object()
+            .unwrap_or_else(|| panic!("{object} has no properties"))
+            .keys()
+            .cloned()
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    fn keys(value: &Value) -> Vec<String> {
+        let mut names: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        names.sort_unstable();
+        names
+    }
+
+    fn sample_app() -> App {
+        let mut app = App::new(PathBuf::from("/c"));
+        let mut agent = AgentState::new(1, PathBuf::from("/c/projects/-src-app/s.jsonl"));
+        agent.set_prompt_summary("Fix the auth bug");
+        app.agents.insert(1, agent);
+        app
+    }
+
+    /// Renaming or removing any of these breaks consumers: bump
+    /// `SCHEMA_VERSION` and update the lists together.
+    #[test]
+    fn field_names_are_pinned() {
+        let pinned: [(OutputKind, Option<&str>, &[&str]); 6] = [
+            (OutputKind::Status, None, &["agents", "schema_version"]),
+            (
+                OutputKind::Status,
+                Some("AgentSummary"),
+                &[
+                    "id",
+                    "project",
+                    "prompt_summary",
+                    "sdd_phase",
+                    "status",
+                    "sub_agents",
+                    "tool",
+                ],
+            ),
+            (
+                OutputKind::StatusFile,
+                None,
+                &[
+                    "active",
+                    "dormant",
+                    "needs_input",
+                    "schema_version",
+                    "updated_at",
+                    "waiting",
+                ],
+            ),
+            (OutputKind::Snapshot, None, &["agents", "schema_version"]),
+            (
+                OutputKind::Snapshot,
+                Some("AgentSnapshot"),
+                &[
+                    "active_tools",
+                    "activity",
+                    "closed_totals",
+                    "files_touched",
+                    "id",
+                    "idle_for",
+                    "in_status_for",
+                    "latency",
+                    "log",
+                    "project_name",
+                    "prompt_summary",
+                    "sdd_history",
+                    "sdd_phase",
+                    "session_file",
+                    "status",
+                    "sub_agents",
+                    "tool_counts",
+                    "tool_elapsed",
+                    "tool_history",
+                    "tools_seen",
+                    "tools_started",
+                    "turns",
+                    "usage",
+                ],
+            ),
+            (
+                OutputKind::Diagnostics,
+                None,
+                &[
+                    "command_attempted",
+                    "duration_ms",
+                    "error_detail",
+                    "error_kind",
+                    "outcome",
+                    "pid_file_state",
+                    "schema_version",
+                    "terminal_kind",
+                ],
+            ),
+        ];
+        for (kind, object, fields) in pinned {
+            let schema = kind.schema().to_value();
+            assert_eq!(properties(&schema, object), fields, "{kind:?} {object:?}");
+        }
+    }
+
+    #[test]
+    fn documents_match_their_schema_and_round_trip() {
+        let app = sample_app();
+        let now = UNIX_EPOCH + Duration::from_secs(1_760_000_000);
+
+        let report = StatusReport::new(&app);
+        let json = serde_json::to_value(&report).unwrap();
+        let schema = OutputKind::Status.schema().to_value();
+        assert_eq!(keys(&json), properties(&schema, None));
+        assert_eq!(
+            keys(&json["agents"][0]),
+            properties(&schema, Some("AgentSummary"))
+        );
+        assert_eq!(
+            serde_json::from_value::<StatusReport>(json).unwrap(),
+            report
+        );
+
+        let counts = StatusCounts::from_snapshot(&app.app_snapshot(Instant::now()), now);
+        let json = serde_json::to_value(counts).unwrap();
+        let schema = OutputKind::StatusFile.schema().to_value();
+        assert_eq!(keys(&json), properties(&schema, None));
+        assert_eq!(
+            serde_json::from_value::<StatusCounts>(json).unwrap(),
+            counts
+        );
+
+        let snapshot = app.snapshot(Instant::now());
+        let json = serde_json::to_value(&snapshot).unwrap();
+        let schema = OutputKind::Snapshot.schema().to_value();
+        assert_eq!(keys(&json), properties(&schema, None));
+        assert_eq!(
+            keys(&json["agents"][0]),
+            properties(&schema, Some("AgentSnapshot"))
+        );
+        assert_eq!(serde_json::from_value::<Snapshot>(json).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn every_document_carries_the_version() {
+        let app = sample_app();
+        let documents = [
+            serde_json::to_value(StatusReport::new(&app)).unwrap(),
+            serde_json::to_value(StatusCounts::from_snapshot(
+                &app.app_snapshot(Instant::now()),
+                SystemTime::now(),
+            ))
+            .unwrap(),
+            serde_json::to_value(app.snapshot(Instant::now())).unwrap(),
+        ];
+        for document in documents {
+            assert_eq!(document["schema_version"], SCHEMA_VERSION);
+        }
+        for kind in OutputKind::ALL {
+            let schema = kind.schema().to_value();
+            let required = schema["required"].as_array().unwrap();
+            assert!(required.contains(&"schema_version".into()), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn print_schema_covers_every_kind() {
+        let all = schema_document(None);
+        assert_eq!(
+            keys(&all),
+            ["diagnostics", "snapshot", "status", "status-file"]
+        );
+        assert_eq!(schema_document(Some(OutputKind::Status)), all["status"]);
+        assert!(all["status"]["$schema"].is_string());
+    }
+}
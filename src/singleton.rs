@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::Path;
+
+/// What the PID file says about a running instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PidFileState {
+    Missing,
+    Running(u32),
+    /// The file names a process that is no longer alive (e.g. after a crash).
+    Stale(u32),
+    /// The file exists but doesn't contain a PID.
+    Invalid,
+}
+
+impl PidFileState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PidFileState::Missing => "missing",
+            PidFileState::Running(_) => "running",
+            PidFileState::Stale(_) => "stale",
+            PidFileState::Invalid => "invalid",
+        }
+    }
+}
+
+/// Read the PID file and classify it using `is_alive`.
+pub fn read_state<F>(path: &Path, is_alive: F) -> PidFileState
+where
+    F: Fn(u32) -> bool,
+{
+    let Ok(contents) = fs::read_to_string(path) else {
+        return PidFileState::Missing;
+    };
+    match contents.trim().parse::<u32>() {
+        Ok(pid) if is_alive(pid) => PidFileState::Running(pid),
+        Ok(pid) => PidFileState::Stale(pid),
+        Err(_) => PidFileState::Invalid,
+    }
+}
+
+/// Like [`read_state`], but removes a file left behind by a crash (stale or
+/// invalid) so the next instance starts from a clean slate. The state found
+/// is returned either way.
+pub fn read_state_clearing_stale<F>(path: &Path, is_alive: F) -> PidFileState
+where
+    F: Fn(u32) -> bool,
+{
+    let state = read_state(path, is_alive);
+    if matches!(state, PidFileState::Stale(_) | PidFileState::Invalid) {
+        let _ = fs::remove_file(path);
+    }
+    state
+}
+
+/// Check if a process with the given PID is still alive.
+#[cfg(unix)]
+pub fn is_process_alive(pid: u32) -> bool {
+    use nix::errno::Errno;
+    use nix::sys::signal::kill;
+    use nix::unistd::Pid;
+
+    // 0 and negative PIDs address process groups, never one process
+    let Ok(pid) = i32::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // Signal 0 only checks; EPERM means it exists but belongs to someone else
+    matches!(kill(Pid::from_raw(pid), None), Ok(()) | Err(Errno::EPERM))
+}
+
+/// Check if a process with the given PID is still alive.
+#[cfg(windows)]
+pub fn is_process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // SAFETY: the handle is checked before use and closed exactly once
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return false;
+        }
+        let mut code = 0u32;
+        let queried = GetExitCodeProcess(handle, &mut code) != 0;
+        CloseHandle(handle);
+        queried && code == STILL_ACTIVE as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_pid_file_states() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.pid");
+        assert_eq!(read_state(&path, |_| true), PidFileState::Missing);
+
+        fs::write(&path, "4242\n").unwrap();
+        assert_eq!(read_state(&path, |_| true), PidFileState::Running(4242));
+        assert_eq!(read_state(&path, |_| false), PidFileState::Stale(4242));
+
+        fs::write(&path, "garbage").unwrap();
+        assert_eq!(read_state(&path, |_| true), PidFileState::Invalid);
+    }
+
+    #[test]
+    fn crash_leftovers_are_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.pid");
+        fs::write(&path, "4242").unwrap();
+        assert_eq!(
+            read_state_clearing_stale(&path, |_| true),
+            PidFileState::Running(4242)
+        );
+        assert!(path.exists(), "a live instance keeps its file");
+
+        assert_eq!(
+            read_state_clearing_stale(&path, |_| false),
+            PidFileState::Stale(4242)
+        );
+        assert!(!path.exists());
+
+        fs::write(&path, "garbage").unwrap();
+        assert_eq!(
+            read_state_clearing_stale(&path, |_| true),
+            PidFileState::Invalid
+        );
+        assert!(!path.exists());
+        assert_eq!(
+            read_state_clearing_stale(&path, |_| true),
+            PidFileState::Missing
+        );
+    }
+
+    #[test]
+    fn this_process_is_alive() {
+        assert!(is_process_alive(std::process::id()));
+        assert!(!is_process_alive(0));
+        assert!(!is_process_alive(u32::MAX));
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant};
+
+/// Tool events are counted in buckets this wide.
+pub const ACTIVITY_BUCKET: Duration = Duration::from_secs(5);
+/// Buckets kept, covering the last five minutes.
+pub const ACTIVITY_BUCKETS: usize = 60;
+
+/// Tool starts and results per [`ACTIVITY_BUCKET`] over the last
+/// [`ACTIVITY_BUCKETS`] of them. Buckets roll over with elapsed time, so
+/// a gap without ticks (a suspend, say) leaves empty buckets behind
+/// rather than squeezing the counts together.
+#[derive(Debug, Clone)]
+pub struct ActivityHistogram {
+    /// Ring of counts; `newest` is the bucket being filled.
+    buckets: [u32; ACTIVITY_BUCKETS],
+    newest: usize,
+    /// When the bucket being filled started.
+    newest_start: Instant,
+}
+
+impl ActivityHistogram {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            buckets: [0; ACTIVITY_BUCKETS],
+            newest: 0,
+            newest_start: now,
+        }
+    }
+
+    /// Rebuild a histogram from [`Self::counts`] taken elsewhere, with its
+    /// newest bucket starting at `now`. Extra counts drop the oldest;
+    /// missing ones are empty.
+    pub fn from_counts(counts: &[u32], now: Instant) -> Self {
+        let mut histogram = Self::new(now);
+        let counts = &counts[counts.len().saturating_sub(ACTIVITY_BUCKETS)..];
+        let first = ACTIVITY_BUCKETS - counts.len();
+        histogram.buckets[first..].copy_from_slice(counts);
+        histogram.newest = ACTIVITY_BUCKETS - 1;
+        histogram
+    }
+
+    /// Roll over to the bucket `now` falls in, emptying those passed on
+    /// the way.
+    pub fn advance(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.newest_start);
+        let steps = (elapsed.as_millis() / ACTIVITY_BUCKET.as_millis()) as usize;
+        if steps == 0 {
+            return;
+        }
+        for _ in 0..steps.min(ACTIVITY_BUCKETS) {
+            self.newest = (self.newest + 1) % ACTIVITY_BUCKETS;
+            self.buckets[self.newest] = 0;
+        }
+        self.newest_start += ACTIVITY_BUCKET * steps as u32;
+    }
+
+    /// Count `events` at `now`.
+    pub fn record(&mut self, events: u32, now: Instant) {
+        self.advance(now);
+        self.buckets[self.newest] += events;
+    }
+
+    /// Counts per bucket, oldest first.
+    pub fn counts(&self) -> impl Iterator<Item = u32> + '_ {
+        let oldest = (self.newest + 1) % ACTIVITY_BUCKETS;
+        self.buckets[oldest..]
+            .iter()
+            .chain(&self.buckets[..oldest])
+            .copied()
+    }
+
+    /// Whether nothing happened in the whole window.
+    pub fn is_quiet(&self) -> bool {
+        self.buckets.iter().all(|&count| count == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(t0: Instant, secs: u64) -> Instant {
+        t0 + Duration::from_secs(secs)
+    }
+
+    fn newest(histogram: &ActivityHistogram, n: usize) -> Vec<u32> {
+        let counts: Vec<u32> = histogram.counts().collect();
+        counts[ACTIVITY_BUCKETS - n..].to_vec()
+    }
+
+    #[test]
+    fn events_land_in_the_bucket_of_their_time() {
+        let t0 = Instant::now();
+        let mut histogram = ActivityHistogram::new(t0);
+        assert!(histogram.is_quiet());
+        histogram.record(2, at(t0, 1));
+        histogram.record(1, at(t0, 4));
+        histogram.record(3, at(t0, 5));
+        histogram.record(1, at(t0, 17));
+        assert_eq!(newest(&histogram, 4), [3, 3, 0, 1]);
+        assert_eq!(histogram.counts().count(), ACTIVITY_BUCKETS);
+        assert!(!histogram.is_quiet());
+    }
+
+    #[test]
+    fn gaps_without_ticks_leave_empty_buckets() {
+        let t0 = Instant::now();
+        let mut histogram = ActivityHistogram::new(t0);
+        histogram.record(4, at(t0, 0));
+        // Suspended for 20s: no ticks, then one
+        histogram.advance(at(t0, 20));
+        histogram.record(1, at(t0, 21));
+        assert_eq!(newest(&histogram, 5), [4, 0, 0, 0, 1]);
+        // Ticks within a bucket change nothing
+        histogram.advance(at(t0, 22));
+        histogram.advance(at(t0, 24));
+        assert_eq!(newest(&histogram, 2), [0, 1]);
+    }
+
+    #[test]
+    fn old_events_roll_out_of_the_window() {
+        let t0 = Instant::now();
+        let mut histogram = ActivityHistogram::new(t0);
+        histogram.record(1, at(t0, 0));
+        histogram.advance(at(t0, 5 * 59));
+        assert_eq!(histogram.counts().next(), Some(1));
+        histogram.advance(at(t0, 5 * 60));
+        assert!(histogram.is_quiet());
+
+        // A gap longer than the window empties everything at once
+        histogram.record(7, at(t0, 301));
+        histogram.advance(at(t0, 301 + 3600));
+        assert!(histogram.is_quiet());
+        histogram.record(2, at(t0, 301 + 3601));
+        assert_eq!(newest(&histogram, 1), [2]);
+    }
+
+    #[test]
+    fn counts_rebuild_the_same_histogram() {
+        let t0 = Instant::now();
+        let mut histogram = ActivityHistogram::new(t0);
+        histogram.record(3, at(t0, 0));
+        histogram.record(1, at(t0, 12));
+        let counts: Vec<u32> = histogram.counts().collect();
+        let rebuilt = ActivityHistogram::from_counts(&counts, t0);
+        assert!(rebuilt.counts().eq(counts.iter().copied()));
+
+        // Short or long, the newest counts stay newest
+        let short = ActivityHistogram::from_counts(&[5, 6], t0);
+        assert_eq!(newest(&short, 3), [0, 5, 6]);
+        let long = vec![1; ACTIVITY_BUCKETS + 5];
+        assert!(ActivityHistogram::from_counts(&long, t0)
+            .counts()
+            .all(|count| count == 1));
+        assert!(ActivityHistogram::from_counts(&[], t0).is_quiet());
+    }
+}
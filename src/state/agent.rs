@@ -1,12 +1,35 @@
-use std::path::PathBuf;
-use std::time::Instant;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::state::sdd::{detect_sdd_phase, SddPhase};
-use crate::watcher::parser::ToolUseEvent;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::state::activity::ActivityHistogram;
+use crate::state::events::{AgentEventKind, AgentLog};
+use crate::state::history::ToolHistory;
+use crate::state::latency::LatencyTracker;
+use crate::state::project;
+use crate::state::replay::ReplaySummary;
+use crate::state::sdd::{detect_sdd_phase, SddHistory, SddPhase};
+use crate::state::stats::ToolCounts;
+use crate::state::timer::{StatusTimer, StatusTotals};
+use crate::state::turns::{TurnHistory, TurnStats, TurnSummary};
+use crate::state::usage::TokenUsage;
+use crate::ui::text::elide_middle;
+use crate::watcher::format::{self, TranscriptFormat};
+use crate::watcher::parser::{self, TaskSpawn, ToolResultEvent, ToolUseEvent};
+use crate::watcher::redact::Redactor;
+use crate::watcher::rules::ToolClassifier;
+use crate::watcher::types::JsonlRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
 pub enum AgentStatus {
     Active,
+    /// Blocked on the user: an `AskUserQuestion` prompt is open.
+    #[serde(rename = "needs_input")]
+    NeedsInput,
     Waiting,
     Dormant,
 }
@@ -15,6 +38,7 @@ impl AgentStatus {
     pub fn label(&self) -> &'static str {
         match self {
             AgentStatus::Active => "active",
+            AgentStatus::NeedsInput => "needs input",
             AgentStatus::Waiting => "waiting",
             AgentStatus::Dormant => "dormant",
         }
@@ -23,18 +47,116 @@ impl AgentStatus {
     pub fn symbol(&self) -> &'static str {
         match self {
             AgentStatus::Active => "●",
+            AgentStatus::NeedsInput => "⚠",
             AgentStatus::Waiting => "○",
             AgentStatus::Dormant => "◌",
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SubAgent {
     pub id: i32,
     pub parent_tool_id: String,
     pub agent_type: String,
     pub active_tools: Vec<ToolUseEvent>,
+    /// The prompt the Task gave the sub-agent, to recognize its sidechain.
+    #[serde(skip)]
+    pub prompt: String,
+    /// The sub-agent's own transcript, once found.
+    #[serde(default)]
+    pub sidechain: Option<PathBuf>,
+}
+
+impl SubAgent {
+    /// Follow a record of the sub-agent's sidechain transcript.
+    pub fn apply_record(
+        &mut self,
+        record: &JsonlRecord,
+        redactor: &Redactor,
+        classifier: &ToolClassifier,
+    ) {
+        self.active_tools
+            .extend(parser::extract_tool_uses(record, redactor, classifier));
+        for result in parser::extract_tool_results(record) {
+            self.active_tools.retain(|t| t.tool_id != result.tool_id);
+        }
+        if parser::is_turn_end(record) {
+            self.active_tools.clear();
+        }
+    }
+}
+
+/// Derived display strings, rebuilt only when the underlying fields change.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplayCache {
+    pub project_name: String,
+    pub short_session_id: String,
+    /// `Agent #3 · my-repo `
+    pub header: String,
+    /// `● active`
+    pub status_text: String,
+    /// Prompt summary pre-truncated for a single sidebar line.
+    pub summary_line: String,
+    /// The latest request, pre-truncated the same way.
+    pub request_line: String,
+}
+
+const SUMMARY_LINE_CHARS: usize = 35;
+/// Prompt summaries and requests are cut to this many characters.
+const REQUEST_CHARS: usize = 150;
+/// Text messages are cut to this many characters in the agent's log.
+const LOG_TEXT_CHARS: usize = 120;
+const SHORT_SESSION_ID_CHARS: usize = 8;
+/// Project names longer than this are elided in the sidebar header.
+const HEADER_PROJECT_WIDTH: usize = 24;
+/// `files_touched` stops growing at this many paths, so a long session
+/// does not make every snapshot and bookmark heavier.
+pub const FILES_TOUCHED_CAPACITY: usize = 500;
+
+/// An agent as sent to `--connect` clients. Instants don't cross process
+/// boundaries, so times are carried as ages relative to the snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentSnapshot {
+    pub id: u32,
+    pub session_file: PathBuf,
+    pub status: AgentStatus,
+    pub active_tools: Vec<ToolUseEvent>,
+    pub sub_agents: Vec<SubAgent>,
+    pub sdd_phase: Option<SddPhase>,
+    pub prompt_summary: String,
+    pub idle_for: Duration,
+    pub in_status_for: Duration,
+    /// Status time from finished intervals, excluding `in_status_for`.
+    pub closed_totals: StatusTotals,
+    pub tools_seen: BTreeSet<String>,
+    #[serde(default)]
+    pub latency: LatencyTracker,
+    #[serde(default)]
+    pub tool_history: ToolHistory,
+    #[serde(default)]
+    pub log: AgentLog,
+    #[serde(default)]
+    pub usage: TokenUsage,
+    #[serde(default)]
+    pub turns: TurnHistory,
+    /// How long each of `active_tools` has been running.
+    #[serde(default)]
+    pub tool_elapsed: Vec<Duration>,
+    /// SDD phases the agent went through, with how long ago each started.
+    #[serde(default)]
+    pub sdd_history: Vec<(SddPhase, Duration)>,
+    #[serde(default)]
+    pub tools_started: u64,
+    #[serde(default)]
+    pub files_touched: BTreeSet<String>,
+    #[serde(default)]
+    pub tool_counts: ToolCounts,
+    /// Tool events per activity bucket, oldest first.
+    #[serde(default)]
+    pub activity: Vec<u32>,
+    #[serde(default)]
+    pub project_name: String,
 }
 
 #[derive(Debug)]
@@ -45,31 +167,213 @@ pub struct AgentState {
     pub active_tools: Vec<ToolUseEvent>,
     pub sub_agents: Vec<SubAgent>,
     pub sdd_phase: Option<SddPhase>,
+    /// Every SDD phase change, including steps back.
+    pub sdd_history: SddHistory,
+    /// The latest request once the user made one, else the first thing
+    /// the model said.
     pub prompt_summary: String,
+    /// What the user most recently asked, cut to 150 characters.
+    pub current_request: String,
+    /// Prompts sent while the agent was busy with tools, since its turn
+    /// began. They wait for the running tools to finish.
+    pub queued_prompts: u32,
     pub last_activity: Instant,
+    pub status_timer: StatusTimer,
+    /// Readable name of the project the session belongs to.
+    pub project_name: String,
+    /// The session file's name without `.jsonl`.
+    pub session_id: String,
+    /// When this agent was first tracked.
+    pub started_at: Instant,
+    /// When the session's first timestamped record was written, which can
+    /// be long before it was tracked.
+    pub first_record_at: Option<Instant>,
+    /// Every tool name used in the session so far.
+    pub tools_seen: BTreeSet<String>,
+    /// Prompt-to-response gaps, from record timestamps.
+    pub latency: LatencyTracker,
+    /// Tools started this session, most recent last.
+    pub tool_history: ToolHistory,
+    /// How many tools were started this session, including ones
+    /// `tool_history` no longer holds.
+    pub tools_started: u64,
+    /// The files the session's tools read or changed, up to
+    /// [`FILES_TOUCHED_CAPACITY`] of them.
+    pub files_touched: BTreeSet<String>,
+    /// `tools_started` by the kind of work the tools do.
+    pub tool_counts: ToolCounts,
+    /// Tool starts and results, messages and turn ends, newest last.
+    pub log: AgentLog,
+    /// Token totals over the session and its latest turn.
+    pub usage: TokenUsage,
+    /// Summaries of the last few finished turns.
+    pub turns: TurnHistory,
+    /// Tools, files and errors of the turn in progress.
+    pub current_turn: TurnStats,
+    /// Sidechain files already matched to a sub-agent, finished or not.
+    pub claimed_sidechains: HashSet<PathBuf>,
+    /// The session file's dialect, once its first record was sniffed.
+    pub format: Option<&'static dyn TranscriptFormat>,
+    /// How many `[escalation]` thresholds its wait on the user has passed.
+    pub escalation: usize,
+    /// Tool starts and results over the last few minutes, live only.
+    pub activity: ActivityHistogram,
+    /// Tool results that reported a failure this session.
+    pub error_count: u64,
+    /// The latest failure: the tool's name and the start of its error.
+    pub last_error: Option<(String, String)>,
+    /// Whether the most recent tool result was a failure.
+    pub last_result_failed: bool,
+    /// ID of the latest record read that had one.
+    pub last_uuid: Option<String>,
+    display: DisplayCache,
+    display_dirty: bool,
 }
 
 impl AgentState {
     pub fn new(id: u32, session_file: PathBuf) -> Self {
-        Self {
+        let now = Instant::now();
+        // The key as it reads; `set_project_name` takes the resolved name
+        let project_name = session_file
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|key| project::decode_project_name(&key.to_string_lossy(), |_| Vec::new()))
+            .unwrap_or_default();
+        let session_id = session_file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut agent = Self {
             id,
             session_file,
             status: AgentStatus::Waiting,
             active_tools: Vec::new(),
             sub_agents: Vec::new(),
             sdd_phase: None,
+            sdd_history: SddHistory::default(),
             prompt_summary: String::new(),
-            last_activity: Instant::now(),
+            current_request: String::new(),
+            queued_prompts: 0,
+            last_activity: now,
+            status_timer: StatusTimer::new(AgentStatus::Waiting, now),
+            project_name,
+            session_id,
+            started_at: now,
+            first_record_at: None,
+            tools_seen: BTreeSet::new(),
+            latency: LatencyTracker::default(),
+            tool_history: ToolHistory::default(),
+            tools_started: 0,
+            files_touched: BTreeSet::new(),
+            tool_counts: ToolCounts::default(),
+            log: AgentLog::default(),
+            usage: TokenUsage::default(),
+            turns: TurnHistory::default(),
+            current_turn: TurnStats::default(),
+            claimed_sidechains: HashSet::new(),
+            format: None,
+            escalation: 0,
+            activity: ActivityHistogram::new(now),
+            error_count: 0,
+            last_error: None,
+            last_result_failed: false,
+            last_uuid: None,
+            display: DisplayCache::default(),
+            display_dirty: true,
+        };
+        agent.refresh_display();
+        agent
+    }
+
+    /// How to read the session file's records.
+    pub fn transcript_format(&self) -> &'static dyn TranscriptFormat {
+        self.format.unwrap_or(format::DEFAULT_FORMAT)
+    }
+
+    /// Cached display strings. May lag behind a mutation until the next
+    /// `refresh_display` (the app refreshes every tick).
+    pub fn display(&self) -> &DisplayCache {
+        &self.display
+    }
+
+    pub fn is_display_dirty(&self) -> bool {
+        self.display_dirty
+    }
+
+    /// Rebuild the display cache if a mutating method invalidated it.
+    pub fn refresh_display(&mut self) {
+        if !self.display_dirty {
+            return;
         }
+        let header = if self.project_name.is_empty() {
+            format!("Agent #{} ", self.id)
+        } else {
+            format!(
+                "Agent #{} \u{b7} {} ",
+                self.id,
+                elide_middle(&self.project_name, HEADER_PROJECT_WIDTH)
+            )
+        };
+        self.display = DisplayCache {
+            project_name: self.project_name.clone(),
+            short_session_id: self
+                .session_id
+                .chars()
+                .take(SHORT_SESSION_ID_CHARS)
+                .collect(),
+            header,
+            status_text: format!("{} {}", self.status.symbol(), self.status.label()),
+            summary_line: self
+                .prompt_summary
+                .chars()
+                .take(SUMMARY_LINE_CHARS)
+                .collect(),
+            request_line: self
+                .current_request
+                .chars()
+                .take(SUMMARY_LINE_CHARS)
+                .collect(),
+        };
+        self.display_dirty = false;
+    }
+
+    fn invalidate_display(&mut self) {
+        self.display_dirty = true;
+    }
+
+    /// Change status, recording the transition in the status timer.
+    pub fn set_status(&mut self, status: AgentStatus) {
+        self.set_status_at(status, Instant::now());
+    }
+
+    pub fn set_status_at(&mut self, status: AgentStatus, now: Instant) {
+        self.status_timer.transition(status, now);
+        if self.status != status {
+            self.status = status;
+            self.invalidate_display();
+        }
+    }
+
+    /// Close out status timing when the agent is removed.
+    pub fn finalize_status_time(&mut self, now: Instant) -> StatusTotals {
+        self.status_timer.finalize(now)
     }
 
     pub fn add_tool(&mut self, tool: ToolUseEvent) {
-        self.status = AgentStatus::Active;
-        self.last_activity = Instant::now();
+        self.add_tool_started_at(tool, Instant::now());
+    }
+
+    /// [`Self::add_tool`] for a tool that started at `started_at`, such as
+    /// its record's timestamp. Never later than now.
+    pub fn add_tool_started_at(&mut self, mut tool: ToolUseEvent, started_at: Instant) {
+        let now = Instant::now();
+        self.last_activity = now;
+        tool.started_at = started_at.min(now);
 
         // Check for SDD phase from Skill tools
         if let Some(phase) = detect_sdd_phase(&tool) {
             self.sdd_phase = Some(phase);
+            self.sdd_history.record(phase, now);
         }
 
         // Spawn a sub-agent for Task tools
@@ -79,32 +383,264 @@ impl AgentState {
                 parent_tool_id: tool.tool_id.clone(),
                 agent_type: "task".to_string(),
                 active_tools: Vec::new(),
+                prompt: String::new(),
+                sidechain: None,
             };
             self.sub_agents.push(sub_agent);
         }
 
+        self.current_turn.tools += 1;
+        self.tools_started += 1;
+        self.tool_counts.count(&tool);
+        if let Some(path) = &tool.file_path {
+            self.current_turn.files.insert(path.clone());
+            if self.files_touched.len() < FILES_TOUCHED_CAPACITY {
+                self.files_touched.insert(path.clone());
+            }
+        }
+        self.tool_history.push(&tool);
+        self.log.push(
+            SystemTime::now(),
+            AgentEventKind::ToolStart,
+            tool.display_status.clone(),
+        );
         self.active_tools.push(tool);
+        self.update_input_status();
+    }
+
+    /// The conversation was compacted: tools started before it will never
+    /// get their results.
+    pub fn note_compaction(&mut self) {
+        self.active_tools.clear();
+        self.sub_agents.clear();
+        self.last_activity = Instant::now();
+        if self.status == AgentStatus::NeedsInput {
+            self.update_input_status();
+        }
+        self.invalidate_display();
+    }
+
+    /// Take over `id`, e.g. that of the session this one resumed.
+    pub fn renumber(&mut self, id: u32) {
+        self.id = id;
+        self.invalidate_display();
+    }
+
+    /// Fill in the type and prompt of the sub-agent a Task spawned.
+    pub fn describe_sub_agent(&mut self, spawn: &TaskSpawn) {
+        let Some(sub) = self
+            .sub_agents
+            .iter_mut()
+            .find(|s| s.parent_tool_id == spawn.tool_id)
+        else {
+            return;
+        };
+        if !spawn.subagent_type.is_empty() {
+            sub.agent_type = spawn.subagent_type.clone();
+        }
+        sub.prompt = spawn.prompt.clone();
+    }
+
+    /// Match a sidechain transcript opening with `prompt` to the first
+    /// sub-agent given that prompt that has no transcript yet.
+    pub fn claim_sidechain(&mut self, path: &Path, prompt: &str) -> bool {
+        if self.claimed_sidechains.contains(path) {
+            return false;
+        }
+        let Some(sub) = self
+            .sub_agents
+            .iter_mut()
+            .find(|s| s.sidechain.is_none() && s.prompt == prompt)
+        else {
+            return false;
+        };
+        sub.sidechain = Some(path.to_path_buf());
+        self.claimed_sidechains.insert(path.to_path_buf());
+        true
+    }
+
+    /// Whether a sub-agent is still waiting for its transcript to be found.
+    pub fn has_unmatched_sub_agents(&self) -> bool {
+        self.sub_agents.iter().any(|s| s.sidechain.is_none())
+    }
+
+    /// Note a tool name; true on its first use in this session.
+    pub fn record_tool_name(&mut self, name: &str) -> bool {
+        if self.tools_seen.contains(name) {
+            return false;
+        }
+        self.tools_seen.insert(name.to_string())
     }
 
     pub fn remove_tool(&mut self, tool_id: &str) {
+        if let Some(tool) = self.active_tools.iter().find(|t| t.tool_id == tool_id) {
+            let text = tool.display_status.clone();
+            self.log
+                .push(SystemTime::now(), AgentEventKind::ToolResult, text);
+        }
         self.active_tools.retain(|t| t.tool_id != tool_id);
         self.sub_agents.retain(|s| s.parent_tool_id != tool_id);
         self.last_activity = Instant::now();
+        if self.status == AgentStatus::NeedsInput {
+            self.update_input_status();
+        }
+    }
+
+    /// Active, or needing input while an `AskUserQuestion` is open.
+    fn update_input_status(&mut self) {
+        let asking = self
+            .active_tools
+            .iter()
+            .any(|t| t.tool_name == "AskUserQuestion");
+        self.set_status(if asking {
+            AgentStatus::NeedsInput
+        } else {
+            AgentStatus::Active
+        });
+    }
+
+    /// How long the newest active tool has been running.
+    pub fn current_tool_elapsed(&self, now: Instant) -> Option<Duration> {
+        self.active_tools
+            .last()
+            .map(|t| now.saturating_duration_since(t.started_at))
+    }
+
+    /// How long the most recent finished turn took, when it was recorded.
+    pub fn last_turn_duration(&self) -> Option<Duration> {
+        self.turns.recent().next().and_then(|turn| turn.duration)
+    }
+
+    /// Take note of how a tool went, before `remove_tool` forgets it. A
+    /// failure counts against the session and the current turn.
+    pub fn note_tool_result(&mut self, result: &ToolResultEvent) {
+        self.last_result_failed = result.is_error;
+        if !result.is_error {
+            return;
+        }
+        let tool_name = self
+            .active_tools
+            .iter()
+            .find(|t| t.tool_id == result.tool_id)
+            .map_or("tool", |t| t.tool_name.as_str());
+        self.last_error = Some((tool_name.to_string(), result.snippet.clone()));
+        self.error_count += 1;
+        self.current_turn.errored = true;
+    }
+
+    /// Names of the files the session's tools touched, without their
+    /// directories.
+    pub fn touched_file_names(&self) -> BTreeSet<&str> {
+        self.files_touched
+            .iter()
+            .map(|path| {
+                Path::new(path)
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or(path)
+            })
+            .collect()
+    }
+
+    /// Summarize the turn that just ended and start counting the next.
+    /// Called before `mark_waiting`, which doesn't touch the counts.
+    pub fn finish_turn(&mut self, duration: Option<Duration>) -> &TurnSummary {
+        let stats = std::mem::take(&mut self.current_turn);
+        self.turns.finish(stats, duration)
     }
 
     pub fn mark_waiting(&mut self) {
-        self.status = AgentStatus::Waiting;
+        self.log.push(
+            SystemTime::now(),
+            AgentEventKind::TurnEnd,
+            "turn finished".to_string(),
+        );
+        self.set_status(AgentStatus::Waiting);
         self.active_tools.clear();
         self.sub_agents.clear();
+        self.queued_prompts = 0;
         self.last_activity = Instant::now();
     }
 
+    /// Log a text message, cut to its first line.
+    pub fn log_text(&mut self, text: &str) {
+        let line = text.trim().lines().next().unwrap_or_default();
+        if line.is_empty() {
+            return;
+        }
+        let mut shown: String = line.chars().take(LOG_TEXT_CHARS).collect();
+        if shown.len() < line.len() {
+            shown.push('\u{2026}');
+        }
+        self.log
+            .push(SystemTime::now(), AgentEventKind::Text, shown);
+    }
+
+    /// Summarize the session with the model's first words, until the user
+    /// asks for something (see [`Self::note_prompt`]).
+    pub fn set_project_name(&mut self, name: String) {
+        if name != self.project_name {
+            self.project_name = name;
+            self.invalidate_display();
+        }
+    }
+
     pub fn set_prompt_summary(&mut self, text: &str) {
         if !self.prompt_summary.is_empty() {
             return;
         }
-        let chars: String = text.chars().take(150).collect();
+        let chars: String = text.chars().take(REQUEST_CHARS).collect();
         self.prompt_summary = chars;
+        self.invalidate_display();
+    }
+
+    /// The user asked for something. Sent while tools are running, it
+    /// queues behind them.
+    pub fn note_prompt(&mut self, text: &str) {
+        let request: String = text.trim().chars().take(REQUEST_CHARS).collect();
+        self.prompt_summary.clone_from(&request);
+        self.current_request = request;
+        if self.status == AgentStatus::Active {
+            self.queued_prompts += 1;
+        }
+        self.invalidate_display();
+    }
+
+    /// Bring an agent caught up from a file's existing content to where
+    /// the session stands now. `idle_for` is how long ago the file was
+    /// last written.
+    pub fn apply_replay(&mut self, summary: ReplaySummary, idle_for: Duration, now: Instant) {
+        if let Some(text) = summary.prompt_summary {
+            self.prompt_summary = text;
+        }
+        if let Some(text) = summary.last_prompt {
+            self.current_request = text;
+        }
+        // Prompts from history were answered or are the turn in progress
+        self.queued_prompts = 0;
+        self.sdd_phase = summary.sdd_phase;
+        let open = &summary.open_tools;
+        self.active_tools.retain(|t| open.contains(&t.tool_id));
+        self.sub_agents.retain(|s| open.contains(&s.parent_tool_id));
+        self.last_activity = now.checked_sub(idle_for).unwrap_or(now);
+        if !self.active_tools.is_empty() {
+            self.update_input_status();
+        } else if summary.in_turn {
+            self.set_status_at(AgentStatus::Active, now);
+        } else {
+            self.set_status_at(AgentStatus::Waiting, now);
+        }
+        self.invalidate_display();
+    }
+
+    /// Whether the agent is blocked on an `AskUserQuestion` prompt.
+    pub fn is_asking_user(&self) -> bool {
+        self.status == AgentStatus::NeedsInput
+    }
+
+    /// Whether the agent is waiting on the user: finished its turn or asking a question.
+    pub fn needs_attention(&self) -> bool {
+        self.status == AgentStatus::Waiting || self.is_asking_user()
     }
 
     pub fn is_dormant(&self, timeout_secs: u64) -> bool {
@@ -114,11 +650,90 @@ impl AgentState {
     pub fn current_tool_display(&self) -> Option<&str> {
         self.active_tools.last().map(|t| t.display_status.as_str())
     }
+
+    /// Whether the project name, prompt summary or current tool contains
+    /// `query`, which must be lowercase. An empty query matches anything.
+    pub fn matches_search(&self, query: &str) -> bool {
+        query.is_empty()
+            || [
+                self.project_name.as_str(),
+                self.prompt_summary.as_str(),
+                self.current_tool_display().unwrap_or(""),
+            ]
+            .iter()
+            .any(|text| text.to_lowercase().contains(query))
+    }
+
+    pub fn snapshot(&self, now: Instant) -> AgentSnapshot {
+        AgentSnapshot {
+            id: self.id,
+            session_file: self.session_file.clone(),
+            status: self.status,
+            active_tools: self.active_tools.clone(),
+            sub_agents: self.sub_agents.clone(),
+            sdd_phase: self.sdd_phase,
+            prompt_summary: self.prompt_summary.clone(),
+            idle_for: now.saturating_duration_since(self.last_activity),
+            in_status_for: self.status_timer.time_in_status(now),
+            closed_totals: self.status_timer.closed_totals(),
+            tools_seen: self.tools_seen.clone(),
+            latency: self.latency.clone(),
+            tool_history: self.tool_history.clone(),
+            log: self.log.clone(),
+            usage: self.usage.clone(),
+            turns: self.turns.clone(),
+            tool_elapsed: self
+                .active_tools
+                .iter()
+                .map(|t| now.saturating_duration_since(t.started_at))
+                .collect(),
+            sdd_history: self.sdd_history.ages(now),
+            tools_started: self.tools_started,
+            files_touched: self.files_touched.clone(),
+            tool_counts: self.tool_counts,
+            activity: self.activity.counts().collect(),
+            project_name: self.project_name.clone(),
+        }
+    }
+
+    /// Rebuild an agent from a snapshot taken elsewhere, anchoring its ages at `now`.
+    pub fn from_snapshot(snapshot: AgentSnapshot, now: Instant) -> Self {
+        let since = now.checked_sub(snapshot.in_status_for).unwrap_or(now);
+        let mut agent = Self::new(snapshot.id, snapshot.session_file);
+        agent.status = snapshot.status;
+        agent.active_tools = snapshot.active_tools;
+        for (tool, elapsed) in agent.active_tools.iter_mut().zip(snapshot.tool_elapsed) {
+            tool.started_at = now.checked_sub(elapsed).unwrap_or(now);
+        }
+        if !snapshot.project_name.is_empty() {
+            agent.project_name = snapshot.project_name;
+        }
+        agent.sub_agents = snapshot.sub_agents;
+        agent.sdd_phase = snapshot.sdd_phase;
+        agent.sdd_history = SddHistory::from_ages(&snapshot.sdd_history, now);
+        agent.prompt_summary = snapshot.prompt_summary;
+        agent.last_activity = now.checked_sub(snapshot.idle_for).unwrap_or(now);
+        agent.status_timer = StatusTimer::resume(snapshot.status, since, snapshot.closed_totals);
+        agent.tools_seen = snapshot.tools_seen;
+        agent.latency = snapshot.latency;
+        agent.tool_history = snapshot.tool_history;
+        agent.tools_started = snapshot.tools_started;
+        agent.files_touched = snapshot.files_touched;
+        agent.tool_counts = snapshot.tool_counts;
+        agent.activity = ActivityHistogram::from_counts(&snapshot.activity, now);
+        agent.log = snapshot.log;
+        agent.usage = snapshot.usage;
+        agent.turns = snapshot.turns;
+        agent.invalidate_display();
+        agent.refresh_display();
+        agent
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::turns::TURN_SUMMARIES_KEPT;
 
     fn make_agent() -> AgentState {
         AgentState::new(1, PathBuf::from("/tmp/test.jsonl"))
@@ -130,6 +745,10 @@ mod tests {
             tool_name: "Read".to_string(),
             display_status: "Reading main.rs".to_string(),
             is_reading: true,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
         }
     }
 
@@ -139,6 +758,10 @@ mod tests {
             tool_name: "Task".to_string(),
             display_status: "Subtask: explore code".to_string(),
             is_reading: false,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
         }
     }
 
@@ -148,6 +771,10 @@ mod tests {
             tool_name: "Skill".to_string(),
             display_status: "Skill: sdd-apply".to_string(),
             is_reading: false,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
         }
     }
 
@@ -175,6 +802,10 @@ mod tests {
             tool_name: "Write".to_string(),
             display_status: "Writing foo.rs".to_string(),
             is_reading: false,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
         });
         agent.remove_tool("t1");
         assert_eq!(agent.active_tools.len(), 1);
@@ -190,6 +821,173 @@ mod tests {
         assert!(agent.active_tools.is_empty());
     }
 
+    #[test]
+    fn log_records_tools_text_and_turn_ends_in_order() {
+        let mut agent = make_agent();
+        agent.add_tool(read_tool());
+        agent.log_text("Found it.\nThe rest of the message");
+        agent.remove_tool("t1");
+        agent.remove_tool("unknown");
+        agent.log_text("   ");
+        agent.mark_waiting();
+        let entries: Vec<(AgentEventKind, &str)> = agent
+            .log
+            .entries()
+            .map(|e| (e.kind, e.text.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (AgentEventKind::ToolStart, "Reading main.rs"),
+                (AgentEventKind::Text, "Found it."),
+                (AgentEventKind::ToolResult, "Reading main.rs"),
+                (AgentEventKind::TurnEnd, "turn finished"),
+            ]
+        );
+    }
+
+    fn tool_on(id: &str, file: Option<&str>) -> ToolUseEvent {
+        ToolUseEvent {
+            tool_id: id.to_string(),
+            tool_name: "Edit".to_string(),
+            display_status: "Editing".to_string(),
+            is_reading: false,
+            read_range: None,
+            file_path: file.map(str::to_string),
+            rule: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    fn failed(id: &str, snippet: &str) -> ToolResultEvent {
+        ToolResultEvent {
+            tool_id: id.to_string(),
+            is_error: true,
+            snippet: snippet.to_string(),
+        }
+    }
+
+    #[test]
+    fn failed_results_are_counted_with_their_tool() {
+        let mut agent = make_agent();
+        agent.add_tool(tool_on("t1", None));
+        agent.note_tool_result(&failed("t1", "Exit code 1"));
+        agent.remove_tool("t1");
+        // Its tool already gone, say after a restart mid-call
+        agent.note_tool_result(&failed("t9", "File not found"));
+        assert_eq!(agent.error_count, 2);
+        assert_eq!(
+            agent.last_error,
+            Some(("tool".to_string(), "File not found".to_string()))
+        );
+        assert!(agent.last_result_failed);
+        assert!(agent.current_turn.errored);
+
+        // Success clears the marker, not the count
+        agent.add_tool(tool_on("t2", None));
+        agent.note_tool_result(&ToolResultEvent {
+            is_error: false,
+            ..failed("t2", "ok")
+        });
+        assert!(!agent.last_result_failed);
+        assert_eq!(agent.error_count, 2);
+        assert_eq!(agent.last_error.as_ref().unwrap().0, "tool");
+
+        agent.add_tool(tool_on("t3", None));
+        agent.note_tool_result(&failed("t3", "denied"));
+        assert_eq!(agent.last_error.as_ref().unwrap().0, "Edit");
+    }
+
+    #[test]
+    fn turns_are_summarized_as_they_end() {
+        let mut agent = make_agent();
+        agent.add_tool(tool_on("t1", Some("/src/a.rs")));
+        agent.add_tool(tool_on("t2", Some("/src/a.rs")));
+        agent.add_tool(tool_on("t3", Some("/src/b.rs")));
+        agent.add_tool(tool_on("t4", None));
+        agent.finish_turn(Some(Duration::from_secs(42)));
+        agent.mark_waiting();
+
+        // A turn with a failed tool, then one with no tools at all
+        agent.add_tool(tool_on("t5", None));
+        agent.note_tool_result(&failed("t5", "Exit code 1"));
+        agent.finish_turn(None);
+        agent.finish_turn(Some(Duration::from_secs(1)));
+
+        let turns: Vec<&TurnSummary> = agent.turns.recent().collect();
+        assert_eq!(turns.len(), 3);
+        assert_eq!(
+            (
+                turns[2].number,
+                turns[2].tools,
+                turns[2].files,
+                turns[2].errored
+            ),
+            (1, 4, 2, false)
+        );
+        assert_eq!(turns[2].duration, Some(Duration::from_secs(42)));
+        assert_eq!(
+            (turns[1].number, turns[1].tools, turns[1].errored),
+            (2, 1, true)
+        );
+        assert_eq!(
+            (turns[0].number, turns[0].tools, turns[0].errored),
+            (3, 0, false)
+        );
+        assert_eq!(agent.turns.completed(), 3);
+        assert_eq!(agent.tool_counts.writes, 5);
+        assert_eq!(
+            agent.touched_file_names().into_iter().collect::<Vec<_>>(),
+            ["a.rs", "b.rs"]
+        );
+        // Turns without a duration add nothing
+        assert_eq!(agent.turns.total_duration(), Duration::from_secs(43));
+    }
+
+    #[test]
+    fn only_the_last_turns_are_kept() {
+        let mut agent = make_agent();
+        for _ in 0..TURN_SUMMARIES_KEPT + 3 {
+            agent.add_tool(tool_on("t", None));
+            agent.finish_turn(None);
+        }
+        let numbers: Vec<u32> = agent.turns.recent().map(|t| t.number).collect();
+        assert_eq!(numbers, vec![8, 7, 6, 5, 4]);
+
+        let restored = AgentState::from_snapshot(agent.snapshot(Instant::now()), Instant::now());
+        assert_eq!(restored.turns, agent.turns);
+    }
+
+    #[test]
+    fn files_touched_stop_growing_at_capacity() {
+        let mut agent = make_agent();
+        for i in 0..FILES_TOUCHED_CAPACITY + 10 {
+            agent.add_tool(tool_on("t", Some(&format!("/src/{i}.rs"))));
+        }
+        // Files already seen still count once
+        agent.add_tool(tool_on("t", Some("/src/0.rs")));
+        assert_eq!(agent.files_touched.len(), FILES_TOUCHED_CAPACITY);
+        assert_eq!(agent.tools_started, FILES_TOUCHED_CAPACITY as u64 + 11);
+        assert!(agent.files_touched.contains("/src/0.rs"));
+    }
+
+    #[test]
+    fn tool_elapsed_survives_a_snapshot() {
+        let mut agent = make_agent();
+        agent.add_tool(read_tool());
+        let started = Instant::now() - Duration::from_secs(48);
+        agent.active_tools[0].started_at = started;
+        let now = Instant::now();
+        assert!(agent.current_tool_elapsed(now).unwrap() >= Duration::from_secs(48));
+
+        let restored = AgentState::from_snapshot(agent.snapshot(now), now);
+        assert_eq!(
+            restored.current_tool_elapsed(now),
+            agent.current_tool_elapsed(now)
+        );
+        assert_eq!(make_agent().current_tool_elapsed(now), None);
+    }
+
     #[test]
     fn task_tool_spawns_sub_agent() {
         let mut agent = make_agent();
@@ -199,6 +997,36 @@ mod tests {
         assert_eq!(agent.sub_agents[0].parent_tool_id, "t2");
     }
 
+    #[test]
+    fn sidechain_is_matched_by_task_prompt() {
+        let mut agent = make_agent();
+        agent.add_tool(task_tool());
+        agent.describe_sub_agent(&TaskSpawn {
+            tool_id: "t2".to_string(),
+            subagent_type: "Explore".to_string(),
+            prompt: "Find the auth code".to_string(),
+        });
+        assert_eq!(agent.sub_agents[0].agent_type, "Explore");
+
+        let path = Path::new("/tmp/agent-1.jsonl");
+        assert!(!agent.claim_sidechain(path, "Something else"));
+        assert!(agent.has_unmatched_sub_agents());
+        assert!(agent.claim_sidechain(path, "Find the auth code"));
+        assert_eq!(agent.sub_agents[0].sidechain.as_deref(), Some(path));
+        assert!(!agent.has_unmatched_sub_agents());
+
+        // A finished sidechain isn't handed to a later Task with the same prompt
+        agent.remove_tool("t2");
+        agent.add_tool(task_tool());
+        agent.describe_sub_agent(&TaskSpawn {
+            tool_id: "t2".to_string(),
+            subagent_type: String::new(),
+            prompt: "Find the auth code".to_string(),
+        });
+        assert_eq!(agent.sub_agents[0].agent_type, "task");
+        assert!(!agent.claim_sidechain(path, "Find the auth code"));
+    }
+
     #[test]
     fn removing_task_tool_removes_sub_agent() {
         let mut agent = make_agent();
@@ -214,6 +1042,45 @@ mod tests {
         assert_eq!(agent.sdd_phase, Some(SddPhase::Apply));
     }
 
+    #[test]
+    fn search_matches_project_prompt_and_tool() {
+        let mut agent = make_agent();
+        agent.project_name = "Auth-Service".to_string();
+        agent.set_prompt_summary("Fix the flaky login test");
+        assert!(agent.matches_search(""));
+        assert!(agent.matches_search("auth"));
+        assert!(agent.matches_search("flaky login"));
+        assert!(!agent.matches_search("main.rs"));
+
+        agent.add_tool(tool_on("t1", None));
+        assert!(agent.matches_search("editing"));
+        agent.remove_tool("t1");
+        assert!(!agent.matches_search("editing"));
+    }
+
+    #[test]
+    fn prompts_replace_the_request_and_queue_behind_tools() {
+        let mut agent = make_agent();
+        agent.set_prompt_summary("Let me look around");
+        agent.note_prompt("Fix the login flow");
+        assert_eq!(agent.current_request, "Fix the login flow");
+        // The user's words beat the model's
+        assert_eq!(agent.prompt_summary, "Fix the login flow");
+        assert_eq!(agent.queued_prompts, 0);
+
+        agent.add_tool(tool_on("t1", None));
+        agent.note_prompt("  and the signup one\n");
+        agent.note_prompt(&"x".repeat(400));
+        assert_eq!(agent.queued_prompts, 2);
+        assert_eq!(agent.current_request.len(), 150);
+
+        agent.mark_waiting();
+        assert_eq!(agent.queued_prompts, 0);
+        agent.note_prompt("and the signup one");
+        assert_eq!(agent.queued_prompts, 0);
+        assert_eq!(agent.current_request, "and the signup one");
+    }
+
     #[test]
     fn prompt_summary_set_once() {
         let mut agent = make_agent();
@@ -230,11 +1097,135 @@ mod tests {
         assert_eq!(agent.current_tool_display(), Some("Reading main.rs"));
     }
 
+    #[test]
+    fn status_changes_drive_the_timer() {
+        let mut agent = make_agent();
+        let t0 = Instant::now();
+        agent.set_status_at(AgentStatus::Active, t0);
+        agent.set_status_at(
+            AgentStatus::Dormant,
+            t0 + std::time::Duration::from_secs(30),
+        );
+        let totals = agent.finalize_status_time(t0 + std::time::Duration::from_secs(90));
+        assert_eq!(totals.active, std::time::Duration::from_secs(30));
+        assert_eq!(totals.dormant, std::time::Duration::from_secs(60));
+        assert_eq!(agent.status, AgentStatus::Dormant);
+    }
+
+    #[test]
+    fn display_cache_is_built_on_creation() {
+        let agent = AgentState::new(
+            3,
+            PathBuf::from("/home/me/.claude/projects/my-repo/0f3c9a2e-5b1d.jsonl"),
+        );
+        assert!(!agent.is_display_dirty());
+        let d = agent.display();
+        assert_eq!(d.project_name, "my-repo");
+        assert_eq!(d.short_session_id, "0f3c9a2e");
+        assert_eq!(d.header, "Agent #3 \u{b7} my-repo ");
+        assert_eq!(agent.session_id, "0f3c9a2e-5b1d");
+        assert_eq!(d.status_text, "\u{25cb} waiting");
+    }
+
+    #[test]
+    fn prompt_change_invalidates_display() {
+        let mut agent = make_agent();
+        agent.set_prompt_summary("Fix the login flow");
+        assert!(agent.is_display_dirty());
+        agent.refresh_display();
+        assert!(!agent.is_display_dirty());
+        assert_eq!(agent.display().summary_line, "Fix the login flow");
+
+        // Set-once summary: a second call changes nothing and stays clean
+        agent.set_prompt_summary("ignored");
+        assert!(!agent.is_display_dirty());
+    }
+
+    #[test]
+    fn status_change_invalidates_display() {
+        let mut agent = make_agent();
+        agent.add_tool(read_tool());
+        assert!(agent.is_display_dirty());
+        agent.refresh_display();
+        assert_eq!(agent.display().status_text, "\u{25cf} active");
+
+        agent.mark_waiting();
+        assert!(agent.is_display_dirty());
+        agent.refresh_display();
+
+        agent.set_status(AgentStatus::Dormant);
+        assert!(agent.is_display_dirty());
+        agent.refresh_display();
+        assert_eq!(agent.display().status_text, "\u{25cc} dormant");
+
+        // No-op transitions keep the cache clean
+        agent.set_status(AgentStatus::Dormant);
+        assert!(!agent.is_display_dirty());
+    }
+
     #[test]
     fn status_labels_and_symbols() {
         assert_eq!(AgentStatus::Active.label(), "active");
         assert_eq!(AgentStatus::Active.symbol(), "●");
         assert_eq!(AgentStatus::Waiting.symbol(), "○");
         assert_eq!(AgentStatus::Dormant.symbol(), "◌");
+        assert_eq!(AgentStatus::NeedsInput.label(), "needs input");
+        assert_eq!(AgentStatus::NeedsInput.symbol(), "⚠");
+        assert_eq!(
+            serde_json::to_string(&AgentStatus::NeedsInput).unwrap(),
+            r#""needs_input""#
+        );
+    }
+
+    #[test]
+    fn open_question_needs_input_until_answered() {
+        let mut agent = make_agent();
+        agent.add_tool(ToolUseEvent {
+            tool_name: "AskUserQuestion".to_string(),
+            ..tool_on("q1", None)
+        });
+        agent.add_tool(tool_on("t1", None));
+        assert_eq!(agent.status, AgentStatus::NeedsInput);
+        assert!(agent.needs_attention());
+        agent.remove_tool("t1");
+        assert_eq!(agent.status, AgentStatus::NeedsInput);
+        agent.remove_tool("q1");
+        assert_eq!(agent.status, AgentStatus::Active);
+        assert!(!agent.needs_attention());
+    }
+
+    #[test]
+    fn record_tool_name_reports_first_use_only() {
+        let mut agent = make_agent();
+        assert!(agent.record_tool_name("Bash"));
+        assert!(!agent.record_tool_name("Bash"));
+        assert!(agent.record_tool_name("Read"));
+        assert_eq!(agent.tools_seen.len(), 2);
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        let mut agent = make_agent();
+        agent.set_project_name("my-repo".to_string());
+        agent.set_prompt_summary("Fix the login flow");
+        agent.add_tool(sdd_skill_tool());
+        agent.add_tool(task_tool());
+        agent.record_tool_name("Task");
+        agent.activity.record(2, agent.last_activity);
+        let now = agent.last_activity + Duration::from_secs(30);
+        let snapshot = agent.snapshot(now);
+        assert_eq!(snapshot.idle_for, Duration::from_secs(30));
+        assert_eq!(snapshot.activity.iter().sum::<u32>(), 2);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let decoded: AgentSnapshot = serde_json::from_str(&json).unwrap();
+        // Rebuilt on another clock, the ages are the same
+        let elsewhere = now + Duration::from_secs(3600);
+        let restored = AgentState::from_snapshot(decoded, elsewhere);
+        assert_eq!(restored.snapshot(elsewhere), snapshot);
+        assert_eq!(restored.display().summary_line, "Fix the login flow");
+        assert_eq!(restored.display().status_text, "\u{25cf} active");
+        // Named by the source, not by this machine's directories
+        assert_eq!(restored.display().project_name, "my-repo");
     }
 }
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use crate::watcher::parser::ToolUseEvent;
+
+/// First uses this early in a session are expected and stay silent.
+pub const FIRST_USE_GRACE: Duration = Duration::from_secs(120);
+
+/// Tools whose first use is worth a second look, unless the config says otherwise.
+pub fn default_sensitive_tools() -> Vec<String> {
+    ["Bash", "Write", "Edit", "mcp__*"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Match a tool name against a pattern where `*` stands for any run of
+/// characters, e.g. `mcp__*` or `mcp__*__write*`.
+pub fn matches_tool_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: exact match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+pub fn is_sensitive(name: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|p| matches_tool_pattern(p, name))
+}
+
+/// Alert text when `tool` is an agent's first use of a sensitive tool after
+/// the grace period, e.g. `Agent #3 used Bash for the first time (Running: cargo publish)`.
+pub fn first_use_alert(
+    agent_id: u32,
+    tool: &ToolUseEvent,
+    first_use: bool,
+    session_age: Duration,
+    sensitive: &[String],
+) -> Option<String> {
+    let alert =
+        first_use && session_age >= FIRST_USE_GRACE && is_sensitive(&tool.tool_name, sensitive);
+    alert.then(|| {
+        format!(
+            "Agent #{agent_id} used {} for the first time ({})",
+            tool.tool_name, tool.display_status
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn tool(name: &str, status: &str) -> ToolUseEvent {
+        ToolUseEvent {
+            tool_id: "t1".to_string(),
+            tool_name: name.to_string(),
+            display_status: status.to_string(),
+            is_reading: false,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    const LATE: Duration = Duration::from_secs(600);
+
+    #[test]
+    fn patterns_support_wildcards() {
+        assert!(matches_tool_pattern("Bash", "Bash"));
+        assert!(!matches_tool_pattern("Bash", "BashOutput"));
+        assert!(matches_tool_pattern("mcp__*", "mcp__github__create_pr"));
+        assert!(!matches_tool_pattern("mcp__*", "Read"));
+        assert!(matches_tool_pattern(
+            "mcp__*__write*",
+            "mcp__fs__write_file"
+        ));
+        assert!(!matches_tool_pattern(
+            "mcp__*__write*",
+            "mcp__fs__read_file"
+        ));
+        assert!(matches_tool_pattern("*", "anything"));
+        assert!(!matches_tool_pattern("a*a", "a"));
+    }
+
+    #[test]
+    fn first_sensitive_use_after_grace_alerts() {
+        let sensitive = default_sensitive_tools();
+        let alert = first_use_alert(
+            3,
+            &tool("Bash", "Running: cargo publish"),
+            true,
+            LATE,
+            &sensitive,
+        );
+        assert_eq!(
+            alert.as_deref(),
+            Some("Agent #3 used Bash for the first time (Running: cargo publish)")
+        );
+        let mcp = tool("mcp__github__merge_pr", "mcp__github__merge_pr");
+        assert!(first_use_alert(3, &mcp, true, LATE, &sensitive).is_some());
+    }
+
+    #[test]
+    fn repeat_uses_stay_silent() {
+        let sensitive = default_sensitive_tools();
+        assert!(
+            first_use_alert(3, &tool("Bash", "Running: ls"), false, LATE, &sensitive).is_none()
+        );
+    }
+
+    #[test]
+    fn grace_window_stays_silent() {
+        let sensitive = default_sensitive_tools();
+        let early = FIRST_USE_GRACE - Duration::from_secs(1);
+        assert!(
+            first_use_alert(3, &tool("Write", "Writing a.rs"), true, early, &sensitive).is_none()
+        );
+        assert!(first_use_alert(
+            3,
+            &tool("Write", "Writing a.rs"),
+            true,
+            FIRST_USE_GRACE,
+            &sensitive
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn non_sensitive_tools_stay_silent() {
+        let sensitive = default_sensitive_tools();
+        for name in ["Read", "Grep", "Glob", "Task"] {
+            assert!(first_use_alert(3, &tool(name, name), true, LATE, &sensitive).is_none());
+        }
+        assert!(first_use_alert(3, &tool("Bash", "Running: ls"), true, LATE, &[]).is_none());
+    }
+}
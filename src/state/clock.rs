@@ -0,0 +1,172 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::state::timer;
+
+/// A gap between ticks this much larger than the tick rate means the
+/// process was suspended (laptop lid closed, SIGSTOP) rather than slow.
+pub const CLOCK_JUMP_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// The suspended gap, if the time between two ticks looks like a jump.
+pub fn clock_jump(prev_tick: Instant, now: Instant) -> Option<Duration> {
+    let delta = now.saturating_duration_since(prev_tick);
+    (delta > CLOCK_JUMP_THRESHOLD).then_some(delta)
+}
+
+/// Re-seed an agent's `last_activity` after a jump of `gap`.
+///
+/// Time spent suspended doesn't count as idle, so the old value is shifted
+/// forward by the gap. If the session file was written more recently than
+/// that (`file_age` since its mtime), the file wins. Never later than `now`.
+pub fn reseeded_activity(
+    last_activity: Instant,
+    gap: Duration,
+    file_age: Option<Duration>,
+    now: Instant,
+) -> Instant {
+    let shifted = last_activity.checked_add(gap).unwrap_or(now);
+    let from_file = file_age.and_then(|age| now.checked_sub(age));
+    shifted.max(from_file.unwrap_or(shifted)).min(now)
+}
+
+/// Live record timestamps further than this from local wall time mean one
+/// of the two clocks is off. Covers write and read delays with room to spare.
+pub const SKEW_TOLERANCE: Duration = Duration::from_secs(120);
+
+/// How far record timestamps are from this machine's clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSkew {
+    /// Records are stamped later than local time.
+    Ahead(Duration),
+    /// Records are stamped earlier than local time.
+    Behind(Duration),
+}
+
+impl ClockSkew {
+    /// `+4m` when records are ahead, `-4m` when behind.
+    pub fn label(&self) -> String {
+        match self {
+            ClockSkew::Ahead(d) => format!("+{}", timer::format_duration_short(*d)),
+            ClockSkew::Behind(d) => format!("-{}", timer::format_duration_short(*d)),
+        }
+    }
+
+    /// The record-clock time matching local wall time `local`.
+    pub fn to_record_time(&self, local: SystemTime) -> SystemTime {
+        match self {
+            ClockSkew::Ahead(d) => local.checked_add(*d),
+            ClockSkew::Behind(d) => local.checked_sub(*d),
+        }
+        .unwrap_or(local)
+    }
+}
+
+/// Compare the newest timestamp of a just-written record with local wall
+/// time. `None` when there is no timestamp or the two agree within
+/// `SKEW_TOLERANCE`.
+pub fn clock_skew(newest_record: Option<SystemTime>, now: SystemTime) -> Option<ClockSkew> {
+    let skew = match newest_record?.duration_since(now) {
+        Ok(ahead) => ClockSkew::Ahead(ahead),
+        Err(behind) => ClockSkew::Behind(behind.duration()),
+    };
+    match skew {
+        ClockSkew::Ahead(d) | ClockSkew::Behind(d) if d > SKEW_TOLERANCE => Some(skew),
+        _ => None,
+    }
+}
+
+/// How long ago `then` was at `now`; zero rather than negative when a
+/// skewed clock puts `then` in the future.
+pub fn clamped_age(now: SystemTime, then: SystemTime) -> Duration {
+    now.duration_since(then).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test]
+    fn normal_ticks_are_not_jumps() {
+        let t0 = Instant::now();
+        assert_eq!(clock_jump(t0, t0 + Duration::from_millis(100)), None);
+        assert_eq!(clock_jump(t0, t0 + secs(5)), None);
+        assert_eq!(clock_jump(t0, t0 + CLOCK_JUMP_THRESHOLD), None);
+    }
+
+    #[test]
+    fn long_gaps_are_jumps() {
+        let t0 = Instant::now();
+        assert_eq!(clock_jump(t0, t0 + secs(31)), Some(secs(31)));
+        assert_eq!(clock_jump(t0, t0 + secs(3 * 3600)), Some(secs(3 * 3600)));
+    }
+
+    #[test]
+    fn backwards_deltas_are_not_jumps() {
+        let t0 = Instant::now() + secs(60);
+        assert_eq!(clock_jump(t0, t0 - secs(60)), None);
+    }
+
+    #[test]
+    fn reseed_discounts_the_gap() {
+        let t0 = Instant::now();
+        // Active 10s before a 2h suspend: still 10s idle afterwards
+        let now = t0 + secs(10) + secs(7200);
+        let reseeded = reseeded_activity(t0, secs(7200), None, now);
+        assert_eq!(now - reseeded, secs(10));
+    }
+
+    #[test]
+    fn reseed_prefers_a_newer_file_mtime() {
+        let t0 = Instant::now();
+        let now = t0 + secs(7210);
+        let reseeded = reseeded_activity(t0, secs(7200), Some(secs(2)), now);
+        assert_eq!(now - reseeded, secs(2));
+        // An older mtime doesn't push activity back into the gap
+        let reseeded = reseeded_activity(t0, secs(7200), Some(secs(7000)), now);
+        assert_eq!(now - reseeded, secs(10));
+    }
+
+    #[test]
+    fn records_ahead_of_local_time() {
+        let now = SystemTime::UNIX_EPOCH + secs(1_000_000);
+        let skew = clock_skew(Some(now + secs(4 * 60 + 10)), now).unwrap();
+        assert_eq!(skew, ClockSkew::Ahead(secs(250)));
+        assert_eq!(skew.label(), "+4m");
+        assert_eq!(skew.to_record_time(now), now + secs(250));
+    }
+
+    #[test]
+    fn records_behind_local_time() {
+        let now = SystemTime::UNIX_EPOCH + secs(1_000_000);
+        let skew = clock_skew(Some(now - secs(3 * 3600)), now).unwrap();
+        assert_eq!(skew, ClockSkew::Behind(secs(3 * 3600)));
+        assert_eq!(skew.label(), "-3h00m");
+        assert_eq!(skew.to_record_time(now), now - secs(3 * 3600));
+    }
+
+    #[test]
+    fn small_or_unknown_skew_is_ignored() {
+        let now = SystemTime::UNIX_EPOCH + secs(1_000_000);
+        assert_eq!(clock_skew(None, now), None);
+        assert_eq!(clock_skew(Some(now), now), None);
+        assert_eq!(clock_skew(Some(now + SKEW_TOLERANCE), now), None);
+        assert_eq!(clock_skew(Some(now - secs(90)), now), None);
+    }
+
+    #[test]
+    fn future_times_have_no_age() {
+        let now = SystemTime::UNIX_EPOCH + secs(1_000_000);
+        assert_eq!(clamped_age(now, now - secs(5)), secs(5));
+        assert_eq!(clamped_age(now, now + secs(240)), Duration::ZERO);
+    }
+
+    #[test]
+    fn reseed_never_lands_in_the_future() {
+        let t0 = Instant::now();
+        let now = t0 + secs(40);
+        assert_eq!(reseeded_activity(t0 + secs(30), secs(40), None, now), now);
+    }
+}
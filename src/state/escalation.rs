@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use crate::state::agent::AgentStatus;
+
+/// How long an agent may wait on the user before each escalation level.
+pub fn default_thresholds() -> Vec<Duration> {
+    vec![Duration::from_secs(5 * 60), Duration::from_secs(15 * 60)]
+}
+
+/// How many of the ascending `thresholds` an agent has been waiting on the
+/// user past, having spent `in_status` in `status`. Working and dormant
+/// agents never escalate.
+pub fn escalation_level(
+    status: AgentStatus,
+    in_status: Duration,
+    thresholds: &[Duration],
+) -> usize {
+    match status {
+        AgentStatus::Waiting | AgentStatus::NeedsInput => thresholds
+            .iter()
+            .take_while(|&&threshold| in_status >= threshold)
+            .count(),
+        AgentStatus::Active | AgentStatus::Dormant => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AgentStatus::*;
+
+    fn mins(n: u64) -> Duration {
+        Duration::from_secs(n * 60)
+    }
+
+    #[test]
+    fn levels_follow_status_and_time_in_it() {
+        let ladder = default_thresholds();
+        let cases = [
+            (Waiting, mins(0), 0),
+            (Waiting, mins(5) - Duration::from_secs(1), 0),
+            (Waiting, mins(5), 1),
+            (Waiting, mins(14), 1),
+            (Waiting, mins(15), 2),
+            (Waiting, mins(600), 2),
+            (NeedsInput, mins(6), 1),
+            (NeedsInput, mins(20), 2),
+            (Active, mins(60), 0),
+            (Dormant, mins(60), 0),
+        ];
+        for (status, in_status, level) in cases {
+            assert_eq!(
+                escalation_level(status, in_status, &ladder),
+                level,
+                "{status:?} for {in_status:?}"
+            );
+        }
+        // No thresholds, no escalation
+        assert_eq!(escalation_level(Waiting, mins(600), &[]), 0);
+    }
+}
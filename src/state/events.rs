@@ -0,0 +1,375 @@
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Oldest entries are dropped past this many.
+pub const EVENT_LOG_CAPACITY: usize = 500;
+/// Per-agent cap; an agent's log keeps only its most recent events.
+pub const AGENT_LOG_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub at: SystemTime,
+    pub agent_id: Option<u32>,
+    pub message: String,
+}
+
+/// Notable things that happened this run, newest last.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    entries: VecDeque<LogEntry>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, at: SystemTime, agent_id: Option<u32>, message: String) {
+        if self.entries.len() == EVENT_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            at,
+            agent_id,
+            message,
+        });
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentEventKind {
+    ToolStart,
+    ToolResult,
+    Text,
+    TurnEnd,
+}
+
+impl AgentEventKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AgentEventKind::ToolStart => "tool",
+            AgentEventKind::ToolResult => "done",
+            AgentEventKind::Text => "text",
+            AgentEventKind::TurnEnd => "turn",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentEvent {
+    pub at: SystemTime,
+    pub kind: AgentEventKind,
+    pub text: String,
+    /// Timestamp of the transcript record this came from, when it had one.
+    #[serde(default)]
+    pub record_at: Option<SystemTime>,
+    /// Turns finished before this event; a turn's end belongs to that turn.
+    #[serde(default)]
+    pub turn: u32,
+}
+
+/// What one agent did recently, newest last.
+///
+/// Events stamped later than every earlier stamped event are also indexed
+/// by time as they arrive, so a jump to a time is a binary search even with
+/// out-of-order or missing record timestamps in between.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentLog {
+    entries: VecDeque<AgentEvent>,
+    /// Record timestamp given to the events pushed next.
+    #[serde(skip)]
+    record_at: Option<SystemTime>,
+    #[serde(default)]
+    turn: u32,
+    /// Sequence number of `entries[0]`; every push takes the next one.
+    #[serde(default)]
+    first_seq: u64,
+    /// `(record timestamp, sequence number)`, strictly increasing in time.
+    #[serde(default)]
+    time_index: VecDeque<(SystemTime, u64)>,
+}
+
+impl AgentLog {
+    /// Stamp the events pushed from now on with a record's timestamp.
+    pub fn set_record_time(&mut self, at: Option<SystemTime>) {
+        self.record_at = at;
+    }
+
+    pub fn push(&mut self, at: SystemTime, kind: AgentEventKind, text: String) {
+        if self.entries.len() == AGENT_LOG_CAPACITY {
+            self.entries.pop_front();
+            self.first_seq += 1;
+            while self
+                .time_index
+                .front()
+                .is_some_and(|&(_, seq)| seq < self.first_seq)
+            {
+                self.time_index.pop_front();
+            }
+        }
+        let seq = self.first_seq + self.entries.len() as u64;
+        if let Some(record_at) = self.record_at {
+            if self
+                .time_index
+                .back()
+                .is_none_or(|&(last, _)| record_at > last)
+            {
+                self.time_index.push_back((record_at, seq));
+            }
+        }
+        self.entries.push_back(AgentEvent {
+            at,
+            kind,
+            text,
+            record_at: self.record_at,
+            turn: self.turn,
+        });
+        if kind == AgentEventKind::TurnEnd {
+            self.turn += 1;
+        }
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &AgentEvent> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Position of the last turn start before `pos`.
+    pub fn prev_turn_start(&self, pos: usize) -> Option<usize> {
+        let last = pos.min(self.entries.len()).checked_sub(1)?;
+        let turn = self.entries[last].turn;
+        Some(self.entries.partition_point(|e| e.turn < turn))
+    }
+
+    /// Position of the first turn start after `pos`.
+    pub fn next_turn_start(&self, pos: usize) -> Option<usize> {
+        let turn = self.entries.get(pos)?.turn;
+        let start = self.entries.partition_point(|e| e.turn <= turn);
+        (start < self.entries.len()).then_some(start)
+    }
+
+    /// Position of the first indexed event stamped at or after `time`, or of
+    /// the latest stamped one when `time` is past them all. `None` when no
+    /// event has a record timestamp.
+    pub fn position_at(&self, time: SystemTime) -> Option<usize> {
+        let i = self.time_index.partition_point(|&(at, _)| at < time);
+        let &(_, seq) = self.time_index.get(i).or_else(|| self.time_index.back())?;
+        Some((seq - self.first_seq) as usize)
+    }
+}
+
+/// Rows kept above a jump target so it isn't shown without context.
+pub const JUMP_CONTEXT_LINES: usize = 1;
+
+/// Top visible line of a `rows`-tall view of `len` lines scrolled `scroll`
+/// lines back from the newest.
+pub fn view_top(len: usize, rows: usize, scroll: usize) -> usize {
+    let max_scroll = len.saturating_sub(rows);
+    max_scroll - scroll.min(max_scroll)
+}
+
+/// The scroll (lines back from the newest) that puts `target` near the top
+/// of a `rows`-tall view, with context above it. Targets on the last page
+/// just show the last page.
+pub fn scroll_to_target(len: usize, rows: usize, target: usize) -> usize {
+    let top = target.saturating_sub(JUMP_CONTEXT_LINES);
+    len.saturating_sub(top + rows)
+}
+
+/// Parse a time to jump to: a full RFC 3339 timestamp, or `HH:MM[:SS]` on
+/// the UTC day of `reference` (transcripts are stamped in UTC).
+pub fn parse_jump_time(input: &str, reference: SystemTime) -> Option<SystemTime> {
+    let input = input.trim();
+    if let Ok(at) = humantime::parse_rfc3339_weak(input) {
+        return Some(at);
+    }
+    let mut parts = input.split(':');
+    let mut field = |max: u64| -> Option<u64> {
+        let value: u64 = parts.next()?.parse().ok()?;
+        (value < max).then_some(value)
+    };
+    let hours = field(24)?;
+    let minutes = field(60)?;
+    let seconds = match field(60) {
+        Some(s) => s,
+        None if input.matches(':').count() == 1 => 0,
+        None => return None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    let day = reference
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_secs()
+        / 86_400;
+    let secs = day * 86_400 + hours * 3_600 + minutes * 60 + seconds;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_past_capacity() {
+        let mut log = EventLog::default();
+        for i in 0..EVENT_LOG_CAPACITY + 3 {
+            log.push(SystemTime::UNIX_EPOCH, Some(1), format!("event {i}"));
+        }
+        assert_eq!(log.len(), EVENT_LOG_CAPACITY);
+        assert_eq!(log.entries().next().unwrap().message, "event 3");
+        assert_eq!(
+            log.entries().next_back().unwrap().message,
+            format!("event {}", EVENT_LOG_CAPACITY + 2)
+        );
+    }
+
+    #[test]
+    fn agent_log_keeps_order_and_cap() {
+        let mut log = AgentLog::default();
+        log.push(
+            SystemTime::UNIX_EPOCH,
+            AgentEventKind::ToolStart,
+            "a".into(),
+        );
+        log.push(SystemTime::UNIX_EPOCH, AgentEventKind::TurnEnd, "b".into());
+        let kinds: Vec<AgentEventKind> = log.entries().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![AgentEventKind::ToolStart, AgentEventKind::TurnEnd]
+        );
+
+        for i in 0..AGENT_LOG_CAPACITY {
+            log.push(SystemTime::UNIX_EPOCH, AgentEventKind::Text, format!("{i}"));
+        }
+        assert_eq!(log.len(), AGENT_LOG_CAPACITY);
+        assert_eq!(log.entries().next().unwrap().text, "0");
+    }
+
+    /// A log of `(seconds, kind)` events with timestamps where `seconds` is
+    /// `Some`, in the given order.
+    fn synthetic(events: &[(Option<u64>, AgentEventKind)]) -> AgentLog {
+        let mut log = AgentLog::default();
+        for (i, &(secs, kind)) in events.iter().enumerate() {
+            log.set_record_time(secs.map(|s| SystemTime::UNIX_EPOCH + Duration::from_secs(s)));
+            log.push(SystemTime::UNIX_EPOCH, kind, format!("{i}"));
+        }
+        log
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    use AgentEventKind::{Text, ToolStart, TurnEnd};
+
+    #[test]
+    fn turn_jumps_land_on_turn_starts() {
+        // Turns start at 0, 3 and 5
+        let log = synthetic(&[
+            (Some(10), Text),
+            (Some(11), ToolStart),
+            (Some(12), TurnEnd),
+            (Some(20), Text),
+            (None, TurnEnd),
+            (Some(30), ToolStart),
+            (Some(31), Text),
+        ]);
+        assert_eq!(log.next_turn_start(0), Some(3));
+        assert_eq!(log.next_turn_start(3), Some(5));
+        assert_eq!(log.next_turn_start(5), None);
+        assert_eq!(log.next_turn_start(7), None);
+        // Inside a turn, back goes to its start; at a start, to the turn before
+        assert_eq!(log.prev_turn_start(6), Some(5));
+        assert_eq!(log.prev_turn_start(5), Some(3));
+        assert_eq!(log.prev_turn_start(3), Some(0));
+        assert_eq!(log.prev_turn_start(0), None);
+        assert_eq!(log.prev_turn_start(9), Some(5));
+    }
+
+    #[test]
+    fn time_jumps_skip_missing_and_out_of_order_stamps() {
+        let log = synthetic(&[
+            (Some(100), Text),
+            (None, Text),
+            (Some(160), ToolStart),
+            // Clock went backwards: not indexed
+            (Some(130), Text),
+            (Some(400), TurnEnd),
+            (None, Text),
+        ]);
+        assert_eq!(log.position_at(at(0)), Some(0));
+        assert_eq!(log.position_at(at(100)), Some(0));
+        assert_eq!(log.position_at(at(101)), Some(2));
+        assert_eq!(log.position_at(at(200)), Some(4));
+        // Past the end: the latest stamped event
+        assert_eq!(log.position_at(at(9_000)), Some(4));
+        assert_eq!(synthetic(&[(None, Text)]).position_at(at(0)), None);
+    }
+
+    #[test]
+    fn time_index_follows_evictions() {
+        let mut log = AgentLog::default();
+        for i in 0..AGENT_LOG_CAPACITY as u64 + 10 {
+            log.set_record_time(Some(at(i * 10)));
+            log.push(SystemTime::UNIX_EPOCH, Text, format!("{i}"));
+        }
+        // The first ten are gone; time before the rest lands on the oldest
+        assert_eq!(log.position_at(at(0)), Some(0));
+        assert_eq!(log.entries().next().unwrap().text, "10");
+        assert_eq!(log.position_at(at(205)), Some(11));
+    }
+
+    #[test]
+    fn jump_viewport_keeps_a_context_line() {
+        // 100 lines, 10 rows: target 40 shows 39..49 at the top
+        let scroll = scroll_to_target(100, 10, 40);
+        assert_eq!(view_top(100, 10, scroll), 39);
+        // Near the end, the last page
+        assert_eq!(scroll_to_target(100, 10, 95), 0);
+        assert_eq!(view_top(100, 10, 0), 90);
+        // Shorter than the view
+        assert_eq!(view_top(5, 10, 3), 0);
+    }
+
+    #[test]
+    fn jump_times_parse_clock_and_rfc3339() {
+        let reference = humantime::parse_rfc3339("2026-03-04T18:00:00Z").unwrap();
+        let expect = |s| Some(humantime::parse_rfc3339(s).unwrap());
+        assert_eq!(
+            parse_jump_time("14:05", reference),
+            expect("2026-03-04T14:05:00Z")
+        );
+        assert_eq!(
+            parse_jump_time(" 9:07:30 ", reference),
+            expect("2026-03-04T09:07:30Z")
+        );
+        assert_eq!(
+            parse_jump_time("2026-03-01T08:00:00Z", reference),
+            expect("2026-03-01T08:00:00Z")
+        );
+        for bad in ["", "25:00", "12:60", "12", "1:2:3:4", "noon"] {
+            assert_eq!(parse_jump_time(bad, reference), None, "{bad}");
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Byte counts are summed into buckets this wide.
+const BUCKET: Duration = Duration::from_secs(10);
+/// Rates are averaged over the last minute.
+pub const GROWTH_WINDOW: Duration = Duration::from_secs(60);
+/// Below this a session file counts as not growing.
+pub const NEAR_ZERO_BYTES_PER_MIN: f64 = 16.0;
+
+/// Bytes appended to a session file over time, for a bytes-per-minute rate.
+#[derive(Debug, Clone)]
+pub struct GrowthMeter {
+    started: Instant,
+    /// (bucket start, bytes), oldest first.
+    buckets: VecDeque<(Instant, u64)>,
+}
+
+impl GrowthMeter {
+    /// Start measuring at `now`; bytes read earlier are history, not growth.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            buckets: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, bytes: u64, now: Instant) {
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(start, _)| now.saturating_duration_since(*start) >= GROWTH_WINDOW)
+        {
+            self.buckets.pop_front();
+        }
+        if bytes == 0 {
+            return;
+        }
+        match self.buckets.back_mut() {
+            Some((start, total)) if now.saturating_duration_since(*start) < BUCKET => {
+                *total += bytes;
+            }
+            _ => self.buckets.push_back((now, bytes)),
+        }
+    }
+
+    /// Bytes per minute over the last [`GROWTH_WINDOW`], or over the time
+    /// measured so far (at least one bucket) when that is shorter.
+    pub fn bytes_per_minute(&self, now: Instant) -> f64 {
+        let span = now
+            .saturating_duration_since(self.started)
+            .clamp(BUCKET, GROWTH_WINDOW);
+        let bytes: u64 = self
+            .buckets
+            .iter()
+            .filter(|(start, _)| now.saturating_duration_since(*start) < GROWTH_WINDOW)
+            .map(|(_, bytes)| bytes)
+            .sum();
+        bytes as f64 * 60.0 / span.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test]
+    fn steady_appends_give_per_minute_rate() {
+        let t0 = Instant::now();
+        let mut meter = GrowthMeter::new(t0);
+        // 500 bytes every 5s for two minutes = 6000 B/min
+        for i in 1..=24 {
+            meter.record(500, t0 + secs(5 * i));
+        }
+        assert_eq!(meter.bytes_per_minute(t0 + secs(120)), 6000.0);
+    }
+
+    #[test]
+    fn young_meter_averages_over_elapsed_time() {
+        let t0 = Instant::now();
+        let mut meter = GrowthMeter::new(t0);
+        meter.record(1000, t0 + secs(5));
+        meter.record(1000, t0 + secs(20));
+        // 2000 bytes in the first 30s
+        assert_eq!(meter.bytes_per_minute(t0 + secs(30)), 4000.0);
+        // Never divides by less than one bucket
+        let fresh = GrowthMeter::new(t0);
+        assert_eq!(fresh.bytes_per_minute(t0), 0.0);
+    }
+
+    #[test]
+    fn old_bytes_leave_the_window() {
+        let t0 = Instant::now();
+        let mut meter = GrowthMeter::new(t0);
+        meter.record(6000, t0 + secs(10));
+        assert_eq!(meter.bytes_per_minute(t0 + secs(60)), 6000.0);
+        assert_eq!(meter.bytes_per_minute(t0 + secs(75)), 0.0);
+        meter.record(0, t0 + secs(200));
+        assert!(meter.buckets.is_empty());
+    }
+
+    #[test]
+    fn zero_growth_is_near_zero() {
+        let t0 = Instant::now();
+        let mut meter = GrowthMeter::new(t0);
+        for i in 1..=30 {
+            meter.record(0, t0 + secs(i));
+        }
+        let rate = meter.bytes_per_minute(t0 + secs(30));
+        assert_eq!(rate, 0.0);
+        assert!(rate < NEAR_ZERO_BYTES_PER_MIN);
+    }
+}
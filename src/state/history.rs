@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::watcher::parser::{ReadRange, ToolUseEvent};
+
+/// Oldest entries are dropped past this many.
+pub const TOOL_HISTORY_CAPACITY: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryEntry {
+    pub tool_name: String,
+    pub display_status: String,
+    #[serde(default)]
+    pub read_range: Option<ReadRange>,
+}
+
+/// The tools an agent started, newest last. Successive chunked Reads of the
+/// same file collapse into one entry with the combined range.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolHistory {
+    entries: VecDeque<HistoryEntry>,
+}
+
+impl ToolHistory {
+    pub fn push(&mut self, tool: &ToolUseEvent) {
+        if let (Some(next), Some(last)) = (&tool.read_range, self.entries.back_mut()) {
+            if let Some(merged) = last.read_range.as_ref().and_then(|r| r.coalesce(next)) {
+                last.display_status = merged.status();
+                last.read_range = Some(merged);
+                return;
+            }
+        }
+        if self.entries.len() == TOOL_HISTORY_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry {
+            tool_name: tool.tool_name.clone(),
+            display_status: tool.display_status.clone(),
+            read_range: tool.read_range.clone(),
+        });
+    }
+
+    /// Entries from newest to oldest.
+    pub fn recent(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watcher::parser::{extract_tool_uses, parse_line};
+    use crate::watcher::redact::Redactor;
+    use crate::watcher::rules::ToolClassifier;
+
+    fn read(file: &str, offset: Option<u64>, limit: Option<u64>) -> ToolUseEvent {
+        let mut input = serde_json::json!({ "file_path": file });
+        if let Some(offset) = offset {
+            input["offset"] = offset.into();
+        }
+        if let Some(limit) = limit {
+            input["limit"] = limit.into();
+        }
+        let line = serde_json::json!({
+            "type": "assistant",
+            "message": {"content": [{"type": "tool_use", "id": "t", "name": "Read", "input": input}]},
+        });
+        let record = parse_line(&line.to_string()).unwrap();
+        extract_tool_uses(&record, &Redactor::default(), &ToolClassifier::default()).remove(0)
+    }
+
+    fn statuses(history: &ToolHistory) -> Vec<&str> {
+        history
+            .recent()
+            .map(|e| e.display_status.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn contiguous_chunks_coalesce() {
+        let mut history = ToolHistory::default();
+        history.push(&read("/logs/big.log", Some(4000), Some(2000)));
+        history.push(&read("/logs/big.log", Some(6000), Some(2000)));
+        history.push(&read("/logs/big.log", Some(8000), None));
+        assert_eq!(statuses(&history), vec!["Reading big.log (from line 4000)"]);
+    }
+
+    #[test]
+    fn gaps_other_files_and_plain_reads_stay_separate() {
+        let mut history = ToolHistory::default();
+        history.push(&read("/logs/big.log", None, Some(100)));
+        history.push(&read("/logs/big.log", Some(500), Some(100)));
+        history.push(&read("/logs/other.log", Some(600), Some(100)));
+        history.push(&read("/logs/other.log", None, None));
+        history.push(&read("/logs/other.log", Some(0), Some(100)));
+        assert_eq!(
+            statuses(&history),
+            vec![
+                "Reading other.log (first 100 lines)",
+                "Reading other.log",
+                "Reading other.log (lines 600\u{2013}700)",
+                "Reading big.log (lines 500\u{2013}600)",
+                "Reading big.log (first 100 lines)",
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_oldest_past_capacity() {
+        let mut history = ToolHistory::default();
+        for i in 0..TOOL_HISTORY_CAPACITY as u64 + 2 {
+            // Every other line, so nothing coalesces
+            history.push(&read("/f", Some(i * 2), Some(1)));
+        }
+        assert_eq!(history.len(), TOOL_HISTORY_CAPACITY);
+        assert_eq!(
+            history
+                .recent()
+                .last()
+                .unwrap()
+                .read_range
+                .as_ref()
+                .unwrap()
+                .start,
+            4
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::{Duration, SystemTime};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Responses slower than this are flagged in the details panel.
+pub const SLOW_RESPONSE: Duration = Duration::from_secs(10);
+
+/// Measures how long the agent takes to start answering a prompt: the gap
+/// between a user prompt and the next assistant record, from record
+/// timestamps. Samples without both timestamps are skipped, not guessed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LatencyTracker {
+    /// Timestamp of the prompt still waiting for a response.
+    #[serde(skip)]
+    pending: Option<SystemTime>,
+    last: Option<Duration>,
+    total: Duration,
+    samples: u32,
+}
+
+impl LatencyTracker {
+    /// A user prompt arrived. One without a timestamp cancels the pending
+    /// measurement so the next response isn't matched to an older prompt.
+    pub fn prompt(&mut self, at: Option<SystemTime>) {
+        self.pending = at;
+    }
+
+    /// An assistant record arrived; completes the pending measurement.
+    pub fn response(&mut self, at: Option<SystemTime>) {
+        let Some(prompt_at) = self.pending.take() else {
+            return;
+        };
+        // A response stamped before its prompt means skewed clocks
+        let Some(gap) = at.and_then(|at| at.duration_since(prompt_at).ok()) else {
+            return;
+        };
+        self.last = Some(gap);
+        self.total += gap;
+        self.samples += 1;
+    }
+
+    /// The turn finished; anything still pending has no response to match.
+    pub fn turn_end(&mut self) {
+        self.pending = None;
+    }
+
+    pub fn last(&self) -> Option<Duration> {
+        self.last
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        (self.samples > 0).then(|| self.total / self.samples)
+    }
+}
+
+/// Format a latency as `2.3s`, or `1m05s` past a minute.
+pub fn format_latency(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+    if secs < 60.0 {
+        format!("{secs:.1}s")
+    } else {
+        format!("{}m{:02}s", d.as_secs() / 60, d.as_secs() % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn at(ms: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn measures_prompt_to_first_response() {
+        let mut tracker = LatencyTracker::default();
+        tracker.prompt(at(1_000));
+        tracker.response(at(3_300));
+        // Later records in the same turn don't add samples
+        tracker.response(at(9_000));
+        tracker.turn_end();
+        assert_eq!(tracker.last(), Some(Duration::from_millis(2_300)));
+        assert_eq!(tracker.average(), Some(Duration::from_millis(2_300)));
+    }
+
+    #[test]
+    fn averages_over_turns() {
+        let mut tracker = LatencyTracker::default();
+        tracker.prompt(at(0));
+        tracker.response(at(1_000));
+        tracker.turn_end();
+        tracker.prompt(at(10_000));
+        tracker.response(at(13_000));
+        tracker.turn_end();
+        assert_eq!(tracker.last(), Some(Duration::from_secs(3)));
+        assert_eq!(tracker.average(), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn missing_timestamps_skip_the_sample() {
+        let mut tracker = LatencyTracker::default();
+        // Prompt without a timestamp: the response isn't matched to anything
+        tracker.prompt(at(0));
+        tracker.prompt(None);
+        tracker.response(at(5_000));
+        assert_eq!(tracker.average(), None);
+
+        // Response without a timestamp consumes the prompt
+        tracker.prompt(at(10_000));
+        tracker.response(None);
+        tracker.response(at(12_000));
+        assert_eq!(tracker.last(), None);
+    }
+
+    #[test]
+    fn turn_end_and_skew_discard_pending_prompt() {
+        let mut tracker = LatencyTracker::default();
+        tracker.prompt(at(0));
+        tracker.turn_end();
+        tracker.response(at(1_000));
+        assert_eq!(tracker.last(), None);
+
+        tracker.prompt(at(5_000));
+        tracker.response(at(4_000));
+        assert_eq!(tracker.average(), None);
+    }
+
+    #[test]
+    fn latency_format() {
+        assert_eq!(format_latency(Duration::from_millis(2_340)), "2.3s");
+        assert_eq!(format_latency(Duration::from_secs(65)), "1m05s");
+    }
+}
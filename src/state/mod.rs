@@ -1,2 +1,17 @@
+pub mod activity;
 pub mod agent;
+pub mod alerts;
+pub mod clock;
+pub mod escalation;
+pub mod events;
+pub mod growth;
+pub mod history;
+pub mod latency;
+pub mod pacing;
+pub mod project;
+pub mod replay;
 pub mod sdd;
+pub mod stats;
+pub mod timer;
+pub mod turns;
+pub mod usage;
@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Loop durations are counted in 1ms buckets up to this; longer ones
+/// share the last bucket.
+const HISTOGRAM_MS: usize = 250;
+
+/// Loops the pacer looks back over before changing the interval.
+pub const PACER_WINDOW: usize = 20;
+/// Overruns in a full window that stretch the interval.
+pub const STRETCH_AFTER_OVERRUNS: usize = 5;
+/// The interval never grows past this many times the tick rate.
+pub const MAX_STRETCH: u32 = 4;
+
+/// How long the main loop's work took (drawing, handling events and
+/// ticking, not the wait for input), over the whole run.
+#[derive(Debug, Clone)]
+pub struct SelfStats {
+    /// Loops by duration, one bucket per millisecond.
+    histogram: [u64; HISTOGRAM_MS + 1],
+    pub loops: u64,
+    /// Loops whose work took longer than the tick interval.
+    pub overruns: u64,
+    pub max: Duration,
+}
+
+impl Default for SelfStats {
+    fn default() -> Self {
+        Self {
+            histogram: [0; HISTOGRAM_MS + 1],
+            loops: 0,
+            overruns: 0,
+            max: Duration::ZERO,
+        }
+    }
+}
+
+impl SelfStats {
+    pub fn record(&mut self, work: Duration, overrun: bool) {
+        let bucket = (work.as_millis() as usize).min(HISTOGRAM_MS);
+        self.histogram[bucket] += 1;
+        self.loops += 1;
+        self.overruns += u64::from(overrun);
+        self.max = self.max.max(work);
+    }
+
+    /// The duration `p` (0–100) percent of loops stayed within, to the
+    /// millisecond; None before the first loop.
+    pub fn percentile(&self, p: u8) -> Option<Duration> {
+        if self.loops == 0 {
+            return None;
+        }
+        let rank = (self.loops * u64::from(p.min(100))).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (ms, count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                // The last bucket holds everything longer
+                return Some(if ms == HISTOGRAM_MS {
+                    self.max
+                } else {
+                    Duration::from_millis(ms as u64)
+                });
+            }
+        }
+        Some(self.max)
+    }
+
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50)
+    }
+
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(95)
+    }
+}
+
+/// Stretches the tick interval while loops keep overrunning it, and eases
+/// back to the tick rate once they fit again. Input still wakes the loop
+/// right away; only the animation gets choppier.
+#[derive(Debug, Clone)]
+pub struct TickPacer {
+    /// The configured tick rate.
+    base: Duration,
+    interval: Duration,
+    /// Work of the last loops since the interval last changed, oldest first.
+    recent: VecDeque<Duration>,
+}
+
+impl TickPacer {
+    pub fn new(base: Duration) -> Self {
+        Self {
+            base,
+            interval: base,
+            recent: VecDeque::with_capacity(PACER_WINDOW),
+        }
+    }
+
+    /// How long to wait for input before the next tick.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The tick rate the interval was stretched from.
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    pub fn is_stretched(&self) -> bool {
+        self.interval > self.base
+    }
+
+    /// Whether `work` took longer than the current interval.
+    pub fn is_overrun(&self, work: Duration) -> bool {
+        work > self.interval
+    }
+
+    /// Feed one loop's work. Returns the new interval when it changed.
+    ///
+    /// With a full window, [`STRETCH_AFTER_OVERRUNS`] or more overruns
+    /// stretch the interval by half, up to [`MAX_STRETCH`] times the tick
+    /// rate. A stretched interval shrinks by a third, not below the tick
+    /// rate, once every loop in the window would have fit the shorter one.
+    pub fn observe(&mut self, work: Duration) -> Option<Duration> {
+        if self.recent.len() == PACER_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(work);
+        if self.recent.len() < PACER_WINDOW {
+            return None;
+        }
+        let overruns = self.recent.iter().filter(|w| self.is_overrun(**w)).count();
+        let next = if overruns >= STRETCH_AFTER_OVERRUNS {
+            (self.interval * 3 / 2).min(self.base * MAX_STRETCH)
+        } else {
+            let shorter = (self.interval * 2 / 3).max(self.base);
+            if self.recent.iter().all(|w| *w <= shorter) {
+                shorter
+            } else {
+                self.interval
+            }
+        };
+        if next == self.interval {
+            return None;
+        }
+        self.interval = next;
+        self.recent.clear();
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    /// Feed `work` `times` times, collecting interval changes.
+    fn feed(pacer: &mut TickPacer, work: Duration, times: usize) -> Vec<Duration> {
+        (0..times).filter_map(|_| pacer.observe(work)).collect()
+    }
+
+    #[test]
+    fn percentiles_come_from_the_histogram() {
+        let mut stats = SelfStats::default();
+        assert_eq!(stats.p50(), None);
+        for i in 1..=100 {
+            stats.record(ms(i), i > 90);
+        }
+        assert_eq!(stats.p50(), Some(ms(50)));
+        assert_eq!(stats.p95(), Some(ms(95)));
+        assert_eq!(stats.percentile(100), Some(ms(100)));
+        assert_eq!((stats.loops, stats.overruns, stats.max), (100, 10, ms(100)));
+
+        // Past the last bucket, the exact max stands in
+        stats.record(ms(900), true);
+        stats.record(ms(700), true);
+        assert_eq!(stats.percentile(100), Some(ms(900)));
+    }
+
+    #[test]
+    fn steady_fast_loops_keep_the_tick_rate() {
+        let mut pacer = TickPacer::new(ms(100));
+        assert!(feed(&mut pacer, ms(30), 200).is_empty());
+        assert_eq!(pacer.interval(), ms(100));
+        assert!(!pacer.is_stretched());
+    }
+
+    #[test]
+    fn occasional_overruns_are_tolerated() {
+        let mut pacer = TickPacer::new(ms(100));
+        let mut changes = Vec::new();
+        for i in 0..200 {
+            // One slow loop in five stays under the threshold
+            let work = if i % 5 == 0 { ms(150) } else { ms(20) };
+            changes.extend(pacer.observe(work));
+        }
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn sustained_overruns_stretch_up_to_the_cap() {
+        let mut pacer = TickPacer::new(ms(100));
+        // Nothing changes before a full window
+        assert!(feed(&mut pacer, ms(500), PACER_WINDOW - 1).is_empty());
+        assert_eq!(pacer.observe(ms(500)), Some(ms(150)));
+        let changes = feed(&mut pacer, ms(500), PACER_WINDOW * 10);
+        assert_eq!(
+            changes,
+            [ms(225), ms(337) + Duration::from_micros(500), ms(400)]
+        );
+        assert_eq!(pacer.interval(), ms(100) * MAX_STRETCH);
+        assert!(pacer.is_overrun(ms(401)));
+    }
+
+    #[test]
+    fn eases_back_once_loops_fit_again() {
+        let mut pacer = TickPacer::new(ms(100));
+        feed(&mut pacer, ms(500), PACER_WINDOW * 10);
+        assert_eq!(pacer.interval(), ms(400));
+
+        // Work that fits 400ms but not the next step down holds
+        assert!(feed(&mut pacer, ms(300), PACER_WINDOW * 3).is_empty());
+
+        let changes = feed(&mut pacer, ms(40), PACER_WINDOW * 10);
+        assert_eq!(changes.last(), Some(&ms(100)));
+        assert!(changes.windows(2).all(|pair| pair[1] < pair[0]));
+        assert!(!pacer.is_stretched());
+    }
+}
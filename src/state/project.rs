@@ -0,0 +1,207 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// The project the dashboard was launched for, taken from the session hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HomeProject {
+    /// Short name for display, e.g. `my-repo`.
+    pub name: String,
+    /// The project's directory name under `~/.claude/projects/`.
+    pub dir_key: String,
+}
+
+impl HomeProject {
+    pub fn from_cwd(cwd: &Path) -> Self {
+        let name = cwd
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| cwd.to_string_lossy().into_owned());
+        Self {
+            name,
+            dir_key: encode_project_dir(cwd),
+        }
+    }
+
+    /// Whether a session file lives in this project's directory.
+    pub fn contains(&self, session_file: &Path) -> bool {
+        session_file
+            .parent()
+            .and_then(|p| p.file_name())
+            .is_some_and(|name| name.to_string_lossy() == self.dir_key)
+    }
+}
+
+/// Claude Code's project directory name for a cwd: every character other
+/// than an ASCII letter or digit becomes `-`.
+pub fn encode_project_dir(cwd: &Path) -> String {
+    cwd.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Readable name of the project a session file belongs to, from its
+/// directory's name. Walks the filesystem; see [`decode_project_name`].
+pub fn session_project_name(session_file: &Path) -> String {
+    session_file
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|key| decode_project_name(&key.to_string_lossy(), list_child_dirs))
+        .unwrap_or_default()
+}
+
+/// Readable name for a project directory under `~/.claude/projects/`.
+///
+/// The encoding is lossy (`/`, `.` and `-` all become `-`), so the original
+/// path is rebuilt by matching encoded names against the directories that
+/// `list_dirs` finds, starting at `/`. The result is the project's own
+/// directory name; if the project no longer exists, it is the part of the
+/// key below the deepest directory that does.
+pub fn decode_project_name<F>(dir_key: &str, list_dirs: F) -> String
+where
+    F: Fn(&Path) -> Vec<String>,
+{
+    let Some(rest) = dir_key.strip_prefix('-') else {
+        return dir_key.to_string();
+    };
+    match resolve(Path::new("/"), rest, &list_dirs) {
+        Resolved::Full(name) | Resolved::Partial(name) => name,
+    }
+}
+
+enum Resolved {
+    /// The whole key matched; holds the last directory's name.
+    Full(String),
+    /// Holds the unmatched tail of the key.
+    Partial(String),
+}
+
+fn resolve<F: Fn(&Path) -> Vec<String>>(dir: &Path, rest: &str, list_dirs: &F) -> Resolved {
+    let mut children: Vec<(String, String)> = list_dirs(dir)
+        .into_iter()
+        .map(|name| (encode_project_dir(Path::new(&name)), name))
+        .collect();
+    // Longest first, so `my-repo` wins over `my` followed by `repo`
+    children.sort_by_key(|(encoded, _)| std::cmp::Reverse(encoded.len()));
+
+    let mut best = rest.to_string();
+    for (encoded, name) in children {
+        if encoded == rest {
+            return Resolved::Full(name);
+        }
+        let Some(tail) = rest
+            .strip_prefix(encoded.as_str())
+            .and_then(|t| t.strip_prefix('-'))
+        else {
+            continue;
+        };
+        match resolve(&dir.join(&name), tail, list_dirs) {
+            full @ Resolved::Full(_) => return full,
+            Resolved::Partial(tail) if tail.len() < best.len() => best = tail,
+            Resolved::Partial(_) => {}
+        }
+    }
+    Resolved::Partial(best)
+}
+
+/// Names of the directories directly inside `dir`. Unreadable is empty.
+pub fn list_child_dirs(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// The JSON a `SessionStart` hook receives on stdin. Only `cwd` is used.
+#[derive(Debug, Deserialize)]
+struct HookInput {
+    cwd: Option<PathBuf>,
+}
+
+/// Extract the session cwd from hook input, if present.
+pub fn cwd_from_hook_input(input: &str) -> Option<PathBuf> {
+    serde_json::from_str::<HookInput>(input)
+        .ok()?
+        .cwd
+        .filter(|cwd| !cwd.as_os_str().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_like_claude_code() {
+        assert_eq!(
+            encode_project_dir(Path::new("/Users/me/src/my.repo")),
+            "-Users-me-src-my-repo"
+        );
+    }
+
+    #[test]
+    fn home_project_matches_its_sessions() {
+        let home = HomeProject::from_cwd(Path::new("/Users/me/my-repo"));
+        assert_eq!(home.name, "my-repo");
+        assert!(home.contains(Path::new(
+            "/home/me/.claude/projects/-Users-me-my-repo/abc.jsonl"
+        )));
+        assert!(!home.contains(Path::new(
+            "/home/me/.claude/projects/-Users-me-other/abc.jsonl"
+        )));
+    }
+
+    /// A fake filesystem listing for `decode_project_name`.
+    fn tree(dirs: &'static [&'static str]) -> impl Fn(&Path) -> Vec<String> {
+        move |dir: &Path| {
+            dirs.iter()
+                .map(Path::new)
+                .filter(|d| d.parent() == Some(dir))
+                .filter_map(|d| d.file_name())
+                .map(|n| n.to_string_lossy().into_owned())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn decodes_mangled_project_dirs() {
+        let fs = tree(&[
+            "/Users",
+            "/Users/me",
+            "/Users/me/src",
+            "/Users/me/src/my",
+            "/Users/me/src/my-repo",
+            "/Users/me/src/my.site",
+            "/Users/me/.config",
+        ]);
+        assert_eq!(decode_project_name("-Users-me-src-my-repo", &fs), "my-repo");
+        assert_eq!(decode_project_name("-Users-me-src-my-site", &fs), "my.site");
+        assert_eq!(decode_project_name("-Users-me--config", &fs), ".config");
+        // Gone from disk: the tail below the deepest existing directory
+        assert_eq!(
+            decode_project_name("-Users-me-src-old-tool", &fs),
+            "old-tool"
+        );
+        // Nothing on disk matches, e.g. mirrored from another machine
+        assert_eq!(
+            decode_project_name("-home-ci-app", tree(&[])),
+            "home-ci-app"
+        );
+        assert_eq!(decode_project_name("plain", &fs), "plain");
+    }
+
+    #[test]
+    fn reads_cwd_from_hook_input() {
+        let input = r#"{"session_id":"abc","transcript_path":"/t.jsonl","cwd":"/Users/me/my-repo","hook_event_name":"SessionStart","source":"startup"}"#;
+        assert_eq!(
+            cwd_from_hook_input(input),
+            Some(PathBuf::from("/Users/me/my-repo"))
+        );
+        assert_eq!(cwd_from_hook_input(r#"{"cwd":""}"#), None);
+        assert_eq!(cwd_from_hook_input("not json"), None);
+    }
+}
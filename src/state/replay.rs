@@ -0,0 +1,190 @@
+use crate::state::sdd::{detect_sdd_phase, SddPhase};
+use crate::watcher::format::TranscriptFormat;
+use crate::watcher::parser::ToolUseEvent;
+use crate::watcher::redact::Redactor;
+use crate::watcher::rules::ToolClassifier;
+use crate::watcher::types::JsonlRecord;
+
+/// Prompt summaries are cut to this many characters.
+const SUMMARY_CHARS: usize = 150;
+
+/// What a session's existing content says about where it stands now, as
+/// opposed to what it did hours ago. Fed every record read while catching
+/// up on a file that already had content when it was found.
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    last_prompt: Option<String>,
+    /// The first text the model wrote since the last turn ended.
+    post_turn_text: Option<String>,
+    /// Phase of the latest Skill call in the turn in progress.
+    turn_phase: Option<SddPhase>,
+    /// Phase the last finished turn ended in.
+    finished_turn_phase: Option<SddPhase>,
+    /// Tool IDs started and not resolved since the last turn ended.
+    open_tools: Vec<String>,
+    in_turn: bool,
+}
+
+/// The present state [`Replay`] found.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReplaySummary {
+    /// The most recent prompt, or else the first text after the last turn
+    /// ended. None when the session has neither.
+    pub prompt_summary: Option<String>,
+    /// The most recent prompt alone.
+    pub last_prompt: Option<String>,
+    /// The phase of the current turn, or of the last finished one. Skill
+    /// calls of earlier turns are history.
+    pub sdd_phase: Option<SddPhase>,
+    /// Tools still unresolved, oldest first.
+    pub open_tools: Vec<String>,
+    /// Whether a turn was started and hasn't ended.
+    pub in_turn: bool,
+}
+
+impl Replay {
+    /// Take in one record, given the tools `format` extracted from it.
+    pub fn observe(
+        &mut self,
+        format: &dyn TranscriptFormat,
+        record: &JsonlRecord,
+        tools: &[ToolUseEvent],
+    ) {
+        if format.is_user_prompt(record) {
+            if let Some(text) = format.prompt_text(record) {
+                self.last_prompt = Some(text);
+            }
+            self.in_turn = true;
+        }
+        if let Some(text) = format.text(record) {
+            self.post_turn_text.get_or_insert(text);
+            self.in_turn = true;
+        }
+        for tool in tools {
+            if let Some(phase) = detect_sdd_phase(tool) {
+                self.turn_phase = Some(phase);
+            }
+            self.open_tools.push(tool.tool_id.clone());
+            self.in_turn = true;
+        }
+        for result in format.tool_results(record) {
+            self.open_tools.retain(|id| *id != result.tool_id);
+        }
+        // Tools from before a compaction never get their results
+        if format.is_compaction(record) {
+            self.open_tools.clear();
+        }
+        if format.is_turn_end(record) {
+            self.post_turn_text = None;
+            self.finished_turn_phase = self.turn_phase.take();
+            self.open_tools.clear();
+            self.in_turn = false;
+        }
+    }
+
+    pub fn finish(self) -> ReplaySummary {
+        let cut = |text: String| -> String { text.chars().take(SUMMARY_CHARS).collect() };
+        let last_prompt = self.last_prompt.map(cut);
+        ReplaySummary {
+            prompt_summary: last_prompt.clone().or(self.post_turn_text.map(cut)),
+            last_prompt,
+            sdd_phase: self.turn_phase.or(self.finished_turn_phase),
+            open_tools: self.open_tools,
+            in_turn: self.in_turn,
+        }
+    }
+}
+
+/// Replay a whole history at once.
+pub fn replay_history<'a>(
+    format: &dyn TranscriptFormat,
+    records: impl IntoIterator<Item = &'a JsonlRecord>,
+    redactor: &Redactor,
+    classifier: &ToolClassifier,
+) -> ReplaySummary {
+    let mut replay = Replay::default();
+    for record in records {
+        let tools = format.tool_uses(record, redactor, classifier);
+        replay.observe(format, record, &tools);
+    }
+    replay.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watcher::format::DEFAULT_FORMAT;
+    use crate::watcher::parser::parse_line;
+
+    fn replay(lines: &[&str]) -> ReplaySummary {
+        let records: Vec<JsonlRecord> = lines.iter().filter_map(|line| parse_line(line)).collect();
+        replay_history(
+            DEFAULT_FORMAT,
+            &records,
+            &Redactor::default(),
+            &ToolClassifier::default(),
+        )
+    }
+
+    const PROMPT_1: &str =
+        r#"{"type":"user","message":{"role":"user","content":"Fix the login bug"}}"#;
+    const SKILL: &str = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Exploring first"},{"type":"tool_use","id":"s1","name":"Skill","input":{"skill":"sdd-explore"}}]}}"#;
+    const SKILL_DONE: &str =
+        r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"s1"}]}}"#;
+    const TURN_END: &str = r#"{"type":"system","subtype":"turn_duration","duration_ms":4000}"#;
+    const PROMPT_2: &str =
+        r#"{"type":"user","message":{"role":"user","content":"Now write the tests"}}"#;
+    const READ: &str = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"r1","name":"Read","input":{"file_path":"/src/login.rs"}}]}}"#;
+    const READ_DONE: &str =
+        r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"r1"}]}}"#;
+    const BASH: &str = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"b1","name":"Bash","input":{"command":"cargo test"}}]}}"#;
+
+    #[test]
+    fn newest_prompt_and_unresolved_tools_win() {
+        let summary = replay(&[
+            PROMPT_1, SKILL, SKILL_DONE, TURN_END, TURN_END, PROMPT_2, READ, READ_DONE, BASH,
+        ]);
+        assert_eq!(
+            summary,
+            ReplaySummary {
+                prompt_summary: Some("Now write the tests".to_string()),
+                last_prompt: Some("Now write the tests".to_string()),
+                // The Skill call was two turns ago
+                sdd_phase: None,
+                open_tools: vec!["b1".to_string()],
+                in_turn: true,
+            }
+        );
+    }
+
+    #[test]
+    fn finished_turn_keeps_its_phase_and_ends_the_turn() {
+        let summary = replay(&[PROMPT_1, SKILL, READ, TURN_END]);
+        assert_eq!(summary.prompt_summary.as_deref(), Some("Fix the login bug"));
+        assert_eq!(summary.sdd_phase, Some(SddPhase::Explore));
+        // A turn end resolves whatever was left open
+        assert!(summary.open_tools.is_empty());
+        assert!(!summary.in_turn);
+    }
+
+    #[test]
+    fn without_prompts_the_text_since_the_turn_ended_stands_in() {
+        let summary = replay(&[SKILL, SKILL_DONE]);
+        assert_eq!(summary.prompt_summary.as_deref(), Some("Exploring first"));
+        assert_eq!(summary.last_prompt, None);
+        assert!(summary.in_turn);
+
+        assert_eq!(replay(&[SKILL, TURN_END]).prompt_summary, None);
+        assert_eq!(replay(&[]), ReplaySummary::default());
+    }
+
+    #[test]
+    fn compaction_drops_tools_started_before_it() {
+        const COMPACTED: &str =
+            r#"{"type":"system","subtype":"compact_boundary","content":"Conversation compacted"}"#;
+        let summary = replay(&[PROMPT_2, BASH, COMPACTED, READ]);
+        assert_eq!(summary.open_tools, ["r1"]);
+        // Still the same turn
+        assert!(summary.in_turn);
+    }
+}
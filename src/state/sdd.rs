@@ -1,6 +1,14 @@
+use std::time::{Duration, Instant};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
 use crate::watcher::parser::ToolUseEvent;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Phases in workflow order, so `Explore < Propose < ... < Archive`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
 pub enum SddPhase {
     Explore,
     Propose,
@@ -42,6 +50,84 @@ impl SddPhase {
     pub fn total() -> usize {
         8
     }
+
+    /// The phase after this one; None after Archive.
+    pub fn next(&self) -> Option<SddPhase> {
+        match self {
+            SddPhase::Explore => Some(SddPhase::Propose),
+            SddPhase::Propose => Some(SddPhase::Spec),
+            SddPhase::Spec => Some(SddPhase::Design),
+            SddPhase::Design => Some(SddPhase::Tasks),
+            SddPhase::Tasks => Some(SddPhase::Apply),
+            SddPhase::Apply => Some(SddPhase::Verify),
+            SddPhase::Verify => Some(SddPhase::Archive),
+            SddPhase::Archive => None,
+        }
+    }
+
+    /// One segment per phase, filled up to this one: `▰▰▰▰▱▱▱▱` for Design,
+    /// or `####----` in ASCII.
+    pub fn progress_bar(&self, ascii: bool) -> String {
+        let (done, todo) = if ascii {
+            ('#', '-')
+        } else {
+            ('\u{25b0}', '\u{25b1}')
+        };
+        let filled = self.index() + 1;
+        std::iter::repeat_n(done, filled)
+            .chain(std::iter::repeat_n(todo, Self::total() - filled))
+            .collect()
+    }
+}
+
+/// Every phase an agent went through, in order, with when it started.
+/// Going back to an earlier phase adds an entry instead of overwriting the
+/// current one, so regressions stay visible.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SddHistory {
+    transitions: Vec<(SddPhase, Instant)>,
+}
+
+impl SddHistory {
+    /// Note a Skill invoking `phase`. Returns whether it is a transition;
+    /// repeating the current phase isn't.
+    pub fn record(&mut self, phase: SddPhase, now: Instant) -> bool {
+        if self.current() == Some(phase) {
+            return false;
+        }
+        self.transitions.push((phase, now));
+        true
+    }
+
+    pub fn transitions(&self) -> &[(SddPhase, Instant)] {
+        &self.transitions
+    }
+
+    pub fn current(&self) -> Option<SddPhase> {
+        self.transitions.last().map(|&(phase, _)| phase)
+    }
+
+    /// Whether entry `i` went back to an earlier phase than the one before.
+    pub fn is_regression(&self, i: usize) -> bool {
+        i > 0 && self.transitions[i].0 < self.transitions[i - 1].0
+    }
+
+    /// Each transition's age at `now`, for a snapshot.
+    pub fn ages(&self, now: Instant) -> Vec<(SddPhase, Duration)> {
+        self.transitions
+            .iter()
+            .map(|&(phase, at)| (phase, now.saturating_duration_since(at)))
+            .collect()
+    }
+
+    /// Rebuild from `ages`, anchored at `now`.
+    pub fn from_ages(ages: &[(SddPhase, Duration)], now: Instant) -> Self {
+        let transitions = ages
+            .iter()
+            .map(|&(phase, age)| (phase, now.checked_sub(age).unwrap_or(now)))
+            .collect();
+        Self { transitions }
+    }
 }
 
 /// Detect SDD phase from a Skill tool invocation.
@@ -69,6 +155,7 @@ pub fn detect_sdd_phase(tool: &ToolUseEvent) -> Option<SddPhase> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Instant;
 
     fn skill_event(skill_name: &str) -> ToolUseEvent {
         ToolUseEvent {
@@ -76,6 +163,10 @@ mod tests {
             tool_name: "Skill".to_string(),
             display_status: format!("Skill: {skill_name}"),
             is_reading: false,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
         }
     }
 
@@ -122,6 +213,10 @@ mod tests {
             tool_name: "Read".to_string(),
             display_status: "Reading file.rs".to_string(),
             is_reading: true,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
         };
         assert_eq!(detect_sdd_phase(&tool), None);
     }
@@ -131,6 +226,56 @@ mod tests {
         assert_eq!(detect_sdd_phase(&skill_event("brainstorming")), None);
     }
 
+    #[test]
+    fn phases_are_ordered_by_workflow() {
+        assert!(SddPhase::Explore < SddPhase::Propose);
+        assert!(SddPhase::Verify < SddPhase::Archive);
+        let mut phase = SddPhase::Explore;
+        let mut seen = vec![phase];
+        while let Some(next) = phase.next() {
+            assert!(next > phase);
+            assert_eq!(next.index(), phase.index() + 1);
+            phase = next;
+            seen.push(phase);
+        }
+        assert_eq!(seen.len(), SddPhase::total());
+        assert_eq!(SddPhase::Archive.next(), None);
+    }
+
+    #[test]
+    fn progress_bar_fills_up_to_the_phase() {
+        assert_eq!(
+            SddPhase::Design.progress_bar(false),
+            "\u{25b0}\u{25b0}\u{25b0}\u{25b0}\u{25b1}\u{25b1}\u{25b1}\u{25b1}"
+        );
+        assert_eq!(SddPhase::Explore.progress_bar(true), "#-------");
+        assert_eq!(SddPhase::Archive.progress_bar(true), "########");
+    }
+
+    #[test]
+    fn history_keeps_regressions() {
+        let t0 = Instant::now();
+        let secs = Duration::from_secs;
+        let mut history = SddHistory::default();
+        assert!(history.record(SddPhase::Explore, t0));
+        assert!(!history.record(SddPhase::Explore, t0 + secs(5)));
+        assert!(history.record(SddPhase::Design, t0 + secs(10)));
+        assert!(history.record(SddPhase::Explore, t0 + secs(20)));
+
+        let phases: Vec<SddPhase> = history.transitions().iter().map(|&(p, _)| p).collect();
+        assert_eq!(
+            phases,
+            [SddPhase::Explore, SddPhase::Design, SddPhase::Explore]
+        );
+        assert_eq!(history.transitions()[1].1, t0 + secs(10));
+        assert_eq!(history.current(), Some(SddPhase::Explore));
+        assert!(!history.is_regression(1));
+        assert!(history.is_regression(2));
+
+        let now = t0 + secs(30);
+        assert_eq!(SddHistory::from_ages(&history.ages(now), now), history);
+    }
+
     #[test]
     fn phase_labels_are_correct() {
         assert_eq!(SddPhase::Apply.label(), "Apply");
@@ -0,0 +1,101 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::watcher::parser::{ToolCategory, ToolUseEvent};
+
+/// Tools started over a session, by the kind of work they do, for the
+/// statistics screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolCounts {
+    /// File tools that only look, like Read, Grep and Glob.
+    pub reads: u64,
+    /// File tools that change files, like Write and Edit.
+    pub writes: u64,
+    pub bash: u64,
+    pub web: u64,
+    pub task: u64,
+    /// MCP and anything else.
+    pub other: u64,
+}
+
+impl ToolCounts {
+    pub fn count(&mut self, tool: &ToolUseEvent) {
+        let counter = match tool.category() {
+            ToolCategory::File if tool.is_reading => &mut self.reads,
+            ToolCategory::File => &mut self.writes,
+            ToolCategory::Shell => &mut self.bash,
+            ToolCategory::Web => &mut self.web,
+            ToolCategory::Task => &mut self.task,
+            ToolCategory::Mcp | ToolCategory::Other => &mut self.other,
+        };
+        *counter += 1;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.reads + self.writes + self.bash + self.web + self.task + self.other
+    }
+
+    /// Add `other`'s counts to these.
+    pub fn add(&mut self, other: &ToolCounts) {
+        self.reads += other.reads;
+        self.writes += other.writes;
+        self.bash += other.bash;
+        self.web += other.web;
+        self.task += other.task;
+        self.other += other.other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    fn tool(name: &str, is_reading: bool) -> ToolUseEvent {
+        ToolUseEvent {
+            tool_id: "t1".to_string(),
+            tool_name: name.to_string(),
+            display_status: String::new(),
+            is_reading,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn tools_are_counted_by_kind() {
+        let mut counts = ToolCounts::default();
+        for (name, is_reading) in [
+            ("Read", true),
+            ("Grep", true),
+            ("Edit", false),
+            ("Write", false),
+            // Observational commands are still Bash
+            ("Bash", true),
+            ("Bash", false),
+            ("WebFetch", true),
+            ("Task", false),
+            ("mcp__github__list_issues", true),
+            ("TodoWrite", false),
+        ] {
+            counts.count(&tool(name, is_reading));
+        }
+        let expected = ToolCounts {
+            reads: 2,
+            writes: 2,
+            bash: 2,
+            web: 1,
+            task: 1,
+            other: 2,
+        };
+        assert_eq!(counts, expected);
+        assert_eq!(counts.total(), 10);
+
+        counts.add(&expected);
+        assert_eq!(counts.total(), 20);
+        assert_eq!(counts.writes, 4);
+    }
+}
@@ -0,0 +1,245 @@
+use std::time::{Duration, Instant};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::agent::AgentStatus;
+
+/// Accumulated time spent in each status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StatusTotals {
+    pub active: Duration,
+    #[serde(default)]
+    pub needs_input: Duration,
+    pub waiting: Duration,
+    pub dormant: Duration,
+}
+
+impl StatusTotals {
+    pub fn get(&self, status: AgentStatus) -> Duration {
+        match status {
+            AgentStatus::Active => self.active,
+            AgentStatus::NeedsInput => self.needs_input,
+            AgentStatus::Waiting => self.waiting,
+            AgentStatus::Dormant => self.dormant,
+        }
+    }
+
+    fn slot(&mut self, status: AgentStatus) -> &mut Duration {
+        match status {
+            AgentStatus::Active => &mut self.active,
+            AgentStatus::NeedsInput => &mut self.needs_input,
+            AgentStatus::Waiting => &mut self.waiting,
+            AgentStatus::Dormant => &mut self.dormant,
+        }
+    }
+
+    pub fn add(&mut self, other: &StatusTotals) {
+        self.active += other.active;
+        self.needs_input += other.needs_input;
+        self.waiting += other.waiting;
+        self.dormant += other.dormant;
+    }
+
+    /// Compact breakdown like `active 34m · waiting 12m · idle 1h02m`.
+    /// Statuses with no recorded time are left out.
+    pub fn summary(&self) -> String {
+        let parts = [
+            ("active", self.active),
+            ("needs input", self.needs_input),
+            ("waiting", self.waiting),
+            ("idle", self.dormant),
+        ];
+        parts
+            .iter()
+            .filter(|(_, d)| !d.is_zero())
+            .map(|(label, d)| format!("{label} {}", format_duration_short(*d)))
+            .collect::<Vec<_>>()
+            .join(" \u{b7} ")
+    }
+}
+
+/// Tracks per-status durations, updated on every status transition.
+#[derive(Debug, Clone)]
+pub struct StatusTimer {
+    current: AgentStatus,
+    since: Instant,
+    totals: StatusTotals,
+}
+
+impl StatusTimer {
+    pub fn new(status: AgentStatus, now: Instant) -> Self {
+        Self {
+            current: status,
+            since: now,
+            totals: StatusTotals::default(),
+        }
+    }
+
+    /// Close the open interval for the current status and start timing `to`.
+    pub fn transition(&mut self, to: AgentStatus, now: Instant) {
+        if to == self.current {
+            return;
+        }
+        self.close_interval(now);
+        self.current = to;
+    }
+
+    /// Totals including the still-open interval, without mutating.
+    pub fn totals(&self, now: Instant) -> StatusTotals {
+        let mut totals = self.totals;
+        *totals.slot(self.current) += now.saturating_duration_since(self.since);
+        totals
+    }
+
+    /// Fold the open interval into the totals and return them.
+    /// Called when the agent is removed.
+    pub fn finalize(&mut self, now: Instant) -> StatusTotals {
+        self.close_interval(now);
+        self.totals
+    }
+
+    /// Credit time up to `gap_start` and drop the interval until `now`,
+    /// e.g. while the machine was suspended.
+    pub fn skip_gap(&mut self, gap_start: Instant, now: Instant) {
+        self.close_interval(gap_start);
+        self.since = now;
+    }
+
+    /// Rebuild a timer in `current` since `since`, with `closed` holding the
+    /// time of every finished interval (see [`StatusTimer::closed_totals`]).
+    pub fn resume(current: AgentStatus, since: Instant, closed: StatusTotals) -> Self {
+        Self {
+            current,
+            since,
+            totals: closed,
+        }
+    }
+
+    /// Totals of finished intervals only, excluding the open one.
+    pub fn closed_totals(&self) -> StatusTotals {
+        self.totals
+    }
+
+    /// How long the agent has been in its current status.
+    pub fn time_in_status(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.since)
+    }
+
+    fn close_interval(&mut self, now: Instant) {
+        *self.totals.slot(self.current) += now.saturating_duration_since(self.since);
+        self.since = now;
+    }
+}
+
+/// Format a duration as `45s`, `34m`, or `1h02m`.
+pub fn format_duration_short(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test]
+    fn accumulates_across_transitions() {
+        let t0 = Instant::now();
+        let mut timer = StatusTimer::new(AgentStatus::Waiting, t0);
+        timer.transition(AgentStatus::Active, t0 + secs(10));
+        timer.transition(AgentStatus::Waiting, t0 + secs(40));
+        let totals = timer.totals(t0 + secs(50));
+        assert_eq!(totals.waiting, secs(20));
+        assert_eq!(totals.active, secs(30));
+        assert_eq!(totals.dormant, Duration::ZERO);
+    }
+
+    #[test]
+    fn same_status_transition_is_noop() {
+        let t0 = Instant::now();
+        let mut timer = StatusTimer::new(AgentStatus::Active, t0);
+        timer.transition(AgentStatus::Active, t0 + secs(5));
+        assert_eq!(timer.time_in_status(t0 + secs(8)), secs(8));
+        assert_eq!(timer.totals(t0 + secs(8)).active, secs(8));
+    }
+
+    #[test]
+    fn rapid_flapping_loses_no_time() {
+        let t0 = Instant::now();
+        let mut timer = StatusTimer::new(AgentStatus::Waiting, t0);
+        let mut now = t0;
+        for i in 0..1000 {
+            now += Duration::from_millis(7);
+            let to = if i % 2 == 0 {
+                AgentStatus::Active
+            } else {
+                AgentStatus::Waiting
+            };
+            timer.transition(to, now);
+        }
+        let totals = timer.totals(now);
+        assert_eq!(totals.active + totals.waiting, now - t0);
+        assert_eq!(totals.active, Duration::from_millis(3500));
+        assert_eq!(totals.waiting, Duration::from_millis(3500));
+    }
+
+    #[test]
+    fn finalize_closes_open_interval() {
+        let t0 = Instant::now();
+        let mut timer = StatusTimer::new(AgentStatus::Active, t0);
+        timer.transition(AgentStatus::Dormant, t0 + secs(60));
+        let totals = timer.finalize(t0 + secs(360));
+        assert_eq!(totals.active, secs(60));
+        assert_eq!(totals.dormant, secs(300));
+        // Finalizing twice at the same instant adds nothing
+        assert_eq!(timer.finalize(t0 + secs(360)), totals);
+    }
+
+    #[test]
+    fn skipped_gap_is_not_credited() {
+        let t0 = Instant::now();
+        let mut timer = StatusTimer::new(AgentStatus::Active, t0);
+        timer.skip_gap(t0 + secs(20), t0 + secs(3600));
+        let totals = timer.totals(t0 + secs(3610));
+        assert_eq!(totals.active, secs(30));
+    }
+
+    #[test]
+    fn resumed_timer_continues_open_interval() {
+        let t0 = Instant::now();
+        let mut timer = StatusTimer::new(AgentStatus::Waiting, t0);
+        timer.transition(AgentStatus::Active, t0 + secs(10));
+        let resumed =
+            StatusTimer::resume(AgentStatus::Active, t0 + secs(10), timer.closed_totals());
+        assert_eq!(resumed.totals(t0 + secs(25)), timer.totals(t0 + secs(25)));
+    }
+
+    #[test]
+    fn summary_format() {
+        let totals = StatusTotals {
+            active: secs(34 * 60 + 5),
+            needs_input: secs(90),
+            waiting: secs(12 * 60),
+            dormant: secs(3720),
+        };
+        assert_eq!(
+            totals.summary(),
+            "active 34m \u{b7} needs input 1m \u{b7} waiting 12m \u{b7} idle 1h02m"
+        );
+        let only_active = StatusTotals {
+            active: secs(42),
+            ..Default::default()
+        };
+        assert_eq!(only_active.summary(), "active 42s");
+    }
+}
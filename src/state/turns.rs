@@ -0,0 +1,126 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::timer;
+
+/// Completed turns kept per agent; older ones are dropped.
+pub const TURN_SUMMARIES_KEPT: usize = 5;
+
+/// What happened in one finished turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TurnSummary {
+    /// 1-based position of the turn in the session.
+    pub number: u32,
+    /// From the turn's `turn_duration` record, when it had one.
+    pub duration: Option<Duration>,
+    pub tools: u32,
+    /// Distinct files the turn's tools read or changed.
+    pub files: u32,
+    /// Some tool result in the turn reported an error.
+    pub errored: bool,
+}
+
+impl TurnSummary {
+    /// `#12 42s · 6 tools · 2 files`, without the outcome mark.
+    pub fn row(&self) -> String {
+        let duration = self
+            .duration
+            .map(timer::format_duration_short)
+            .unwrap_or_else(|| "?".to_string());
+        let plural = |n: u32, word: &str| {
+            if n == 1 {
+                format!("{n} {word}")
+            } else {
+                format!("{n} {word}s")
+            }
+        };
+        format!(
+            "#{} {duration} \u{b7} {} \u{b7} {}",
+            self.number,
+            plural(self.tools, "tool"),
+            plural(self.files, "file"),
+        )
+    }
+}
+
+/// The turn in progress.
+#[derive(Debug, Clone, Default)]
+pub struct TurnStats {
+    pub tools: u32,
+    pub files: BTreeSet<String>,
+    pub errored: bool,
+}
+
+/// The last few finished turns, newest last.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TurnHistory {
+    turns: VecDeque<TurnSummary>,
+    /// Turns finished this session, including dropped ones.
+    completed: u32,
+    /// The `turn_duration` of every turn finished this session.
+    #[serde(default)]
+    total_duration: Duration,
+}
+
+impl TurnHistory {
+    /// Close `stats` as the next turn and keep its summary.
+    pub fn finish(&mut self, stats: TurnStats, duration: Option<Duration>) -> &TurnSummary {
+        self.completed += 1;
+        self.total_duration += duration.unwrap_or_default();
+        if self.turns.len() == TURN_SUMMARIES_KEPT {
+            self.turns.pop_front();
+        }
+        self.turns.push_back(TurnSummary {
+            number: self.completed,
+            duration,
+            tools: stats.tools,
+            files: stats.files.len() as u32,
+            errored: stats.errored,
+        });
+        self.turns.back().expect("just pushed")
+    }
+
+    /// Summaries from newest to oldest.
+    pub fn recent(&self) -> impl Iterator<Item = &TurnSummary> {
+        self.turns.iter().rev()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    /// Turns finished this session, including ones no longer kept.
+    pub fn completed(&self) -> u32 {
+        self.completed
+    }
+
+    /// Time spent in turns this session, as far as their records said.
+    pub fn total_duration(&self) -> Duration {
+        self.total_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_row_format() {
+        let summary = TurnSummary {
+            number: 12,
+            duration: Some(Duration::from_millis(42_300)),
+            tools: 6,
+            files: 1,
+            errored: false,
+        };
+        assert_eq!(summary.row(), "#12 42s \u{b7} 6 tools \u{b7} 1 file");
+        let unknown = TurnSummary {
+            duration: None,
+            ..summary
+        };
+        assert!(unknown.row().starts_with("#12 ? "));
+    }
+}
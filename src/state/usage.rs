@@ -0,0 +1,115 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::watcher::parser::UsageDelta;
+
+/// Running token totals for one agent, from the `usage` of its assistant
+/// records. Records repeating an earlier message's usage replace it instead
+/// of adding to it, so multi-block messages count once.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TokenUsage {
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub total_cache_read_tokens: u64,
+    /// Usage of the current turn, or the last one between turns.
+    pub turn_input_tokens: u64,
+    pub turn_output_tokens: u64,
+    /// Model of the latest message that named one.
+    pub model: Option<String>,
+    /// The latest message's usage, taken back out if it is repeated.
+    #[serde(skip)]
+    last_message: Option<UsageDelta>,
+}
+
+impl TokenUsage {
+    pub fn record(&mut self, delta: UsageDelta) {
+        if let Some(last) = self.last_message.take() {
+            if last.message_id.is_some() && last.message_id == delta.message_id {
+                self.total_input_tokens -= last.input_tokens;
+                self.total_output_tokens -= last.output_tokens;
+                self.total_cache_read_tokens -= last.cache_read_tokens;
+                self.turn_input_tokens = self.turn_input_tokens.saturating_sub(last.input_tokens);
+                self.turn_output_tokens =
+                    self.turn_output_tokens.saturating_sub(last.output_tokens);
+            }
+        }
+        self.total_input_tokens += delta.input_tokens;
+        self.total_output_tokens += delta.output_tokens;
+        self.total_cache_read_tokens += delta.cache_read_tokens;
+        self.turn_input_tokens += delta.input_tokens;
+        self.turn_output_tokens += delta.output_tokens;
+        if delta.model.is_some() {
+            self.model.clone_from(&delta.model);
+        }
+        self.last_message = Some(delta);
+    }
+
+    /// A new prompt arrived; the turn counts start over.
+    pub fn start_turn(&mut self) {
+        self.turn_input_tokens = 0;
+        self.turn_output_tokens = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_input_tokens == 0 && self.total_output_tokens == 0
+    }
+}
+
+/// Format a token count as `812`, `41.2k` or `1.3M`.
+pub fn format_tokens(n: u64) -> String {
+    if n < 1_000 {
+        n.to_string()
+    } else if n < 1_000_000 {
+        format!("{:.1}k", n as f64 / 1_000.0)
+    } else {
+        format!("{:.1}M", n as f64 / 1_000_000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(id: &str, input: u64, output: u64) -> UsageDelta {
+        UsageDelta {
+            message_id: Some(id.to_string()),
+            model: None,
+            input_tokens: input,
+            output_tokens: output,
+            cache_read_tokens: 0,
+        }
+    }
+
+    #[test]
+    fn repeated_message_usage_counts_once() {
+        let mut usage = TokenUsage::default();
+        usage.record(delta("m1", 10, 5));
+        // Second block of the same message, output grew while streaming
+        usage.record(delta("m1", 10, 80));
+        usage.record(delta("m2", 3, 20));
+        assert_eq!(
+            (usage.total_input_tokens, usage.total_output_tokens),
+            (13, 100)
+        );
+    }
+
+    #[test]
+    fn turn_counts_restart_but_totals_keep_going() {
+        let mut usage = TokenUsage::default();
+        usage.record(delta("m1", 100, 50));
+        usage.start_turn();
+        usage.record(delta("m2", 7, 3));
+        assert_eq!((usage.turn_input_tokens, usage.turn_output_tokens), (7, 3));
+        assert_eq!(
+            (usage.total_input_tokens, usage.total_output_tokens),
+            (107, 53)
+        );
+    }
+
+    #[test]
+    fn token_format() {
+        assert_eq!(format_tokens(812), "812");
+        assert_eq!(format_tokens(41_234), "41.2k");
+        assert_eq!(format_tokens(1_300_000), "1.3M");
+    }
+}
@@ -0,0 +1,390 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::api::AppSnapshot;
+use crate::app::App;
+use crate::schema::SCHEMA_VERSION;
+use crate::state::agent::{AgentState, AgentStatus};
+use crate::state::sdd::SddPhase;
+
+/// How often the running instance rewrites the status file.
+pub const WRITE_INTERVAL_SECS: u64 = 3;
+
+/// Status files older than this are considered left over from a dead instance.
+const STALE_AFTER_SECS: u64 = 30;
+
+/// Compact per-status agent counts shared with shell prompts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StatusCounts {
+    pub schema_version: u32,
+    pub active: u32,
+    pub waiting: u32,
+    pub needs_input: u32,
+    pub dormant: u32,
+    /// Unix timestamp (seconds) of the last write.
+    pub updated_at: u64,
+}
+
+impl Default for StatusCounts {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            active: 0,
+            waiting: 0,
+            needs_input: 0,
+            dormant: 0,
+            updated_at: 0,
+        }
+    }
+}
+
+impl StatusCounts {
+    pub fn from_snapshot(snapshot: &AppSnapshot, now: SystemTime) -> Self {
+        Self::from_statuses(snapshot.agents.iter().map(|agent| agent.status), now)
+    }
+
+    /// Count agents by status, without copying them into a snapshot.
+    pub fn from_statuses(statuses: impl IntoIterator<Item = AgentStatus>, now: SystemTime) -> Self {
+        let mut counts = StatusCounts {
+            updated_at: unix_secs(now),
+            ..Default::default()
+        };
+        for status in statuses {
+            match status {
+                AgentStatus::NeedsInput => counts.needs_input += 1,
+                AgentStatus::Active => counts.active += 1,
+                AgentStatus::Waiting => counts.waiting += 1,
+                AgentStatus::Dormant => counts.dormant += 1,
+            }
+        }
+        counts
+    }
+
+    pub fn is_stale(&self, now: SystemTime) -> bool {
+        unix_secs(now).saturating_sub(self.updated_at) > STALE_AFTER_SECS
+    }
+
+    /// Add `other`'s counts to these, keeping the later write time.
+    pub fn add(&mut self, other: &StatusCounts) {
+        self.active += other.active;
+        self.waiting += other.waiting;
+        self.needs_input += other.needs_input;
+        self.dormant += other.dormant;
+        self.updated_at = self.updated_at.max(other.updated_at);
+    }
+}
+
+/// One agent as printed by `--status` for scripts and status bars.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentSummary {
+    pub id: u32,
+    pub project: String,
+    pub status: AgentStatus,
+    /// Status of the tool in flight, e.g. `Reading auth.rs`.
+    pub tool: Option<String>,
+    pub sdd_phase: Option<SddPhase>,
+    pub prompt_summary: String,
+    pub sub_agents: usize,
+}
+
+impl AgentSummary {
+    pub fn new(agent: &AgentState) -> Self {
+        Self {
+            id: agent.id,
+            project: agent.project_name.clone(),
+            status: agent.status,
+            tool: agent.current_tool_display().map(str::to_string),
+            sdd_phase: agent.sdd_phase,
+            prompt_summary: agent.prompt_summary.clone(),
+            sub_agents: agent.sub_agents.len(),
+        }
+    }
+}
+
+/// What `--status` prints.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StatusReport {
+    pub schema_version: u32,
+    pub agents: Vec<AgentSummary>,
+}
+
+impl StatusReport {
+    pub fn new(app: &App) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            agents: agent_summaries(app),
+        }
+    }
+}
+
+/// Every tracked agent in ID order.
+pub fn agent_summaries(app: &App) -> Vec<AgentSummary> {
+    let mut agents: Vec<AgentSummary> = app.agents.values().map(AgentSummary::new).collect();
+    agents.sort_by_key(|agent| agent.id);
+    agents
+}
+
+fn unix_secs(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Write `value` as JSON via a temp file + rename so readers never see a
+/// partial file.
+pub fn write_atomic<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let json = serde_json::to_vec(value).map_err(io::Error::other)?;
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = Path::new(&tmp_name);
+    fs::write(tmp, json)?;
+    fs::rename(tmp, path)
+}
+
+/// Read the status file. Returns None if it is missing or unparsable.
+pub fn read(path: &Path) -> Option<StatusCounts> {
+    let data = fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// The counts for shell prompts: those of the all-projects instance at
+/// `global` while it runs, since it sees every agent, or else the fresh
+/// per-project ones added up. None when no instance is running.
+pub fn read_combined(global: &Path, projects: &[PathBuf], now: SystemTime) -> Option<StatusCounts> {
+    let fresh = |path: &Path| read(path).filter(|counts| !counts.is_stale(now));
+    if let Some(counts) = fresh(global) {
+        return Some(counts);
+    }
+    projects
+        .iter()
+        .filter_map(|path| fresh(path))
+        .reduce(|mut total, counts| {
+            total.add(&counts);
+            total
+        })
+}
+
+/// Format a single whitespace-free token like `●2○1⚠1` for shell prompts.
+///
+/// Dormant agents are left out; returns None when nothing is worth showing.
+pub fn format_segment(counts: &StatusCounts, color: bool) -> Option<String> {
+    let parts = [
+        (counts.needs_input, "\u{26a0}", "31"),
+        (counts.active, "\u{25cf}", "32"),
+        (counts.waiting, "\u{25cb}", "33"),
+    ];
+    let mut out = String::new();
+    for (count, symbol, ansi) in parts {
+        if count == 0 {
+            continue;
+        }
+        if color {
+            out.push_str(&format!("\x1b[{ansi}m{symbol}{count}\x1b[0m"));
+        } else {
+            out.push_str(&format!("{symbol}{count}"));
+        }
+    }
+    if out.is_empty() {
+        None
+    } else {
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::time::{Duration, Instant};
+
+    use crate::state::agent::AgentState;
+    use crate::watcher::parser::ToolUseEvent;
+
+    fn counts(active: u32, waiting: u32, needs_input: u32) -> StatusCounts {
+        StatusCounts {
+            active,
+            waiting,
+            needs_input,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn counts_from_snapshot() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        app.agents
+            .insert(1, AgentState::new(1, PathBuf::from("/tmp/1.jsonl")));
+        let mut asking = AgentState::new(2, PathBuf::from("/tmp/2.jsonl"));
+        asking.add_tool(ToolUseEvent {
+            tool_id: "t1".to_string(),
+            tool_name: "AskUserQuestion".to_string(),
+            display_status: "Waiting for answer".to_string(),
+            is_reading: false,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
+        });
+        app.agents.insert(2, asking);
+        let snapshot = app.app_snapshot(Instant::now());
+        let c = StatusCounts::from_snapshot(&snapshot, UNIX_EPOCH + Duration::from_secs(42));
+        assert_eq!(c.waiting, 1);
+        assert_eq!(c.needs_input, 1);
+        assert_eq!(c.active, 0);
+        assert_eq!(c.updated_at, 42);
+    }
+
+    #[test]
+    fn summaries_are_sorted_and_name_the_tool() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        let mut reading = AgentState::new(2, PathBuf::from("/c/projects/-src-app/2.jsonl"));
+        reading.add_tool(ToolUseEvent {
+            tool_id: "t1".to_string(),
+            tool_name: "Read".to_string(),
+            display_status: "Reading main.rs".to_string(),
+            is_reading: true,
+            read_range: None,
+            file_path: None,
+            rule: None,
+            started_at: Instant::now(),
+        });
+        app.agents.insert(2, reading);
+        app.agents.insert(
+            1,
+            AgentState::new(1, PathBuf::from("/c/projects/-src-app/1.jsonl")),
+        );
+
+        let summaries = agent_summaries(&app);
+        assert_eq!(summaries.iter().map(|a| a.id).collect::<Vec<_>>(), [1, 2]);
+        let json = serde_json::to_value(&summaries[1]).unwrap();
+        assert_eq!(json["status"], "active");
+        assert_eq!(json["tool"], "Reading main.rs");
+        assert_eq!(json["sub_agents"], 0);
+        assert!(serde_json::to_value(&summaries[0]).unwrap()["tool"].is_null());
+    }
+
+    #[test]
+    fn segment_plain() {
+        assert_eq!(
+            format_segment(&counts(2, 1, 1), false).as_deref(),
+            Some("\u{26a0}1\u{25cf}2\u{25cb}1")
+        );
+        assert_eq!(
+            format_segment(&counts(0, 3, 0), false).as_deref(),
+            Some("\u{25cb}3")
+        );
+    }
+
+    #[test]
+    fn segment_colored_wraps_each_part() {
+        let s = format_segment(&counts(1, 0, 0), true).unwrap();
+        assert_eq!(s, "\x1b[32m\u{25cf}1\x1b[0m");
+        assert!(!s.contains(' '));
+    }
+
+    #[test]
+    fn segment_empty_when_nothing_to_show() {
+        let mut c = counts(0, 0, 0);
+        c.dormant = 4;
+        assert!(format_segment(&c, false).is_none());
+    }
+
+    #[test]
+    fn staleness() {
+        let c = StatusCounts {
+            updated_at: 100,
+            ..Default::default()
+        };
+        assert!(!c.is_stale(UNIX_EPOCH + Duration::from_secs(110)));
+        assert!(c.is_stale(UNIX_EPOCH + Duration::from_secs(200)));
+    }
+
+    #[test]
+    fn prompts_add_up_per_project_instances_unless_one_sees_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = UNIX_EPOCH + Duration::from_secs(1000);
+        let at = |mut c: StatusCounts, secs| {
+            c.updated_at = secs;
+            c
+        };
+        let global = dir.path().join("all.json");
+        let projects: Vec<PathBuf> = ["a.json", "b.json", "old.json"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        write_atomic(&projects[0], &at(counts(1, 0, 1), 990)).unwrap();
+        write_atomic(&projects[1], &at(counts(2, 1, 0), 995)).unwrap();
+        // Left behind by a crash
+        write_atomic(&projects[2], &at(counts(9, 9, 9), 100)).unwrap();
+        assert_eq!(read_combined(&global, &[], now), None);
+        assert_eq!(
+            read_combined(&global, &projects, now),
+            Some(at(counts(3, 1, 1), 995))
+        );
+
+        write_atomic(&global, &at(counts(4, 0, 0), 998)).unwrap();
+        assert_eq!(
+            read_combined(&global, &projects, now),
+            Some(at(counts(4, 0, 0), 998))
+        );
+    }
+
+    #[test]
+    fn write_then_read_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        let c = counts(3, 2, 1);
+        write_atomic(&path, &c).unwrap();
+        assert_eq!(read(&path), Some(c));
+    }
+
+    #[test]
+    fn readers_never_see_partial_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        write_atomic(&path, &counts(0, 0, 0)).unwrap();
+
+        // The writer keeps rewriting for as long as the reader reads, so
+        // every one of the reads races a write
+        let done = Arc::new(AtomicBool::new(false));
+        let start = Arc::new(Barrier::new(2));
+        let writer = {
+            let path = path.clone();
+            let done = done.clone();
+            let start = start.clone();
+            std::thread::spawn(move || {
+                start.wait();
+                let mut i = 0;
+                loop {
+                    i += 1;
+                    write_atomic(&path, &counts(i, i * 1000, i * 100_000)).unwrap();
+                    if done.load(Ordering::SeqCst) {
+                        return i;
+                    }
+                }
+            })
+        };
+
+        start.wait();
+        for _ in 0..500 {
+            let data = fs::read(&path).unwrap();
+            let parsed: Result<StatusCounts, _> = serde_json::from_slice(&data);
+            assert!(
+                parsed.is_ok(),
+                "partial read: {:?}",
+                String::from_utf8(data)
+            );
+        }
+        done.store(true, Ordering::SeqCst);
+        // The last write is the one left
+        let n = writer.join().unwrap();
+        assert_eq!(read(&path), Some(counts(n, n * 1000, n * 100_000)));
+    }
+}
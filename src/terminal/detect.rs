@@ -1,4 +1,9 @@
 use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TerminalKind {
@@ -6,6 +11,7 @@ pub enum TerminalKind {
     WezTerm,
     Kitty,
     Tmux,
+    WindowsTerminal,
     Unknown,
 }
 
@@ -16,103 +22,489 @@ impl TerminalKind {
             TerminalKind::WezTerm => "WezTerm",
             TerminalKind::Kitty => "Kitty",
             TerminalKind::Tmux => "tmux",
+            TerminalKind::WindowsTerminal => "Windows Terminal",
             TerminalKind::Unknown => "Unknown",
         }
     }
 }
 
-/// Detect terminal from env vars. Priority: Zellij > WezTerm > Kitty > tmux > Unknown
+/// Detect terminal from env vars. Priority: Zellij > WezTerm > Kitty > tmux >
+/// Windows Terminal > Unknown
 pub fn detect_terminal() -> TerminalKind {
-    if env::var("ZELLIJ").is_ok() || env::var("ZELLIJ_SESSION_NAME").is_ok() {
+    detect_terminal_from(|key| env::var_os(key))
+}
+
+/// Like [`detect_terminal`], reading env vars through `env`. Multiplexers
+/// win over Windows Terminal, which sets `WT_SESSION` for everything
+/// running inside it.
+pub fn detect_terminal_from<E>(env: E) -> TerminalKind
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    let set = |key: &str| env(key).is_some();
+    if set("ZELLIJ") || set("ZELLIJ_SESSION_NAME") {
         return TerminalKind::Zellij;
     }
-    if env::var("WEZTERM_PANE").is_ok() || env::var("WEZTERM_EXECUTABLE").is_ok() {
+    if set("WEZTERM_PANE") || set("WEZTERM_EXECUTABLE") {
         return TerminalKind::WezTerm;
     }
-    if env::var("KITTY_PID").is_ok() || env::var("KITTY_WINDOW_ID").is_ok() {
+    if set("KITTY_PID") || set("KITTY_WINDOW_ID") {
         return TerminalKind::Kitty;
     }
-    if env::var("TMUX").is_ok() {
+    if set("TMUX") {
         return TerminalKind::Tmux;
     }
+    if set("WT_SESSION") {
+        return TerminalKind::WindowsTerminal;
+    }
     TerminalKind::Unknown
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SplitCommand {
     pub program: String,
     pub args: Vec<String>,
 }
 
+impl SplitCommand {
+    fn new<'a>(program: &str, args: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.into_iter().map(str::to_string).collect(),
+        }
+    }
+
+    /// The command as one shell-quoted line, for logs and diagnostics.
+    pub fn command_line(&self) -> String {
+        let mut words = vec![self.program.clone()];
+        words.extend(self.args.iter().cloned());
+        shell_join(&words)
+    }
+}
+
+/// How much of the terminal the split takes without `--percent`.
+pub const DEFAULT_SPLIT_PERCENT: u8 = 35;
+
+/// Which side of the current pane the dashboard opens on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SplitDirection {
+    #[default]
+    Right,
+    Down,
+}
+
+/// Where the split goes and how much of the terminal it takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitOptions {
+    /// Share of the terminal's width (or height, splitting down), 1-99.
+    pub percent: u8,
+    pub direction: SplitDirection,
+    /// Working directory of the new pane; the terminal's choice without.
+    pub cwd: Option<PathBuf>,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        Self {
+            percent: DEFAULT_SPLIT_PERCENT,
+            direction: SplitDirection::Right,
+            cwd: None,
+        }
+    }
+}
+
 /// Build split command for a given terminal kind.
 pub fn build_split_command(kind: TerminalKind, binary_path: &str) -> Option<SplitCommand> {
-    match kind {
-        TerminalKind::WezTerm => Some(SplitCommand {
-            program: "wezterm".to_string(),
-            args: vec![
-                "cli".to_string(),
-                "split-pane".to_string(),
-                "--right".to_string(),
-                "--percent".to_string(),
-                "35".to_string(),
-                "--".to_string(),
-                binary_path.to_string(),
-                "--attach".to_string(),
+    build_split_command_with_args(kind, binary_path, &[], &SplitOptions::default())
+}
+
+/// Like [`build_split_command`], passing `extra_args` to the attached
+/// instance and placing the split as `options` say. Zellij sizes its
+/// panes itself, so only the direction and directory apply there.
+pub fn build_split_command_with_args(
+    kind: TerminalKind,
+    binary_path: &str,
+    extra_args: &[String],
+    options: &SplitOptions,
+) -> Option<SplitCommand> {
+    let attach: Vec<String> = [binary_path.to_string(), "--attach".to_string()]
+        .into_iter()
+        .chain(extra_args.iter().cloned())
+        .collect();
+    let percent = options.percent.to_string();
+    let down = options.direction == SplitDirection::Down;
+    let cwd = options.cwd.as_deref().map(|dir| dir.to_string_lossy());
+    let mut cmd = match kind {
+        TerminalKind::WezTerm => SplitCommand::new(
+            "wezterm",
+            [
+                "cli",
+                "split-pane",
+                if down { "--bottom" } else { "--right" },
+                "--percent",
+                &percent,
+                "--",
             ],
-        }),
-        TerminalKind::Zellij => Some(SplitCommand {
-            program: "zellij".to_string(),
-            args: vec![
-                "action".to_string(),
-                "new-pane".to_string(),
-                "--direction".to_string(),
-                "right".to_string(),
-                "--".to_string(),
-                binary_path.to_string(),
-                "--attach".to_string(),
+        ),
+        TerminalKind::Zellij => SplitCommand::new(
+            "zellij",
+            [
+                "action",
+                "new-pane",
+                "--direction",
+                if down { "down" } else { "right" },
+                "--",
             ],
-        }),
-        TerminalKind::Tmux => Some(SplitCommand {
-            program: "tmux".to_string(),
-            args: vec![
-                "split-window".to_string(),
-                "-h".to_string(),
-                "-l".to_string(),
-                "35%".to_string(),
-                format!("{} --attach", binary_path),
+        ),
+        // The command goes as separate arguments, so tmux runs it without
+        // a shell and paths with spaces stay whole
+        TerminalKind::Tmux => SplitCommand::new(
+            "tmux",
+            [
+                "split-window",
+                if down { "-v" } else { "-h" },
+                "-l",
+                &format!("{percent}%"),
+                "--",
             ],
-        }),
-        TerminalKind::Kitty => Some(SplitCommand {
-            program: "kitty".to_string(),
-            args: vec![
-                "@".to_string(),
-                "launch".to_string(),
-                "--location=vsplit".to_string(),
-                binary_path.to_string(),
-                "--attach".to_string(),
+        ),
+        TerminalKind::Kitty => SplitCommand::new(
+            "kitty",
+            [
+                "@",
+                "launch",
+                if down {
+                    "--location=hsplit"
+                } else {
+                    "--location=vsplit"
+                },
+                &format!("--bias={percent}"),
             ],
-        }),
-        TerminalKind::Unknown => None,
+        ),
+        // `-w 0` targets the window the hook runs in
+        TerminalKind::WindowsTerminal => {
+            let size = format!("{:.2}", f32::from(options.percent) / 100.0);
+            let mut cmd = SplitCommand::new(
+                "wt.exe",
+                [
+                    "-w",
+                    "0",
+                    "split-pane",
+                    if down { "--horizontal" } else { "--vertical" },
+                    "--size",
+                    &size,
+                ],
+            );
+            if let Some(cwd) = &cwd {
+                cmd.args.extend(["-d".to_string(), wt_escape(cwd)]);
+            }
+            cmd.args.extend(attach.iter().map(|arg| wt_escape(arg)));
+            return Some(cmd);
+        }
+        TerminalKind::Unknown => return None,
+    };
+    if let Some(cwd) = cwd {
+        let flag = match kind {
+            TerminalKind::Tmux => vec!["-c".to_string(), cwd.into_owned()],
+            TerminalKind::Kitty => vec![format!("--cwd={cwd}")],
+            _ => vec!["--cwd".to_string(), cwd.into_owned()],
+        };
+        // Ahead of the `--` that ends the options, where there is one
+        let at = cmd.args.len() - usize::from(cmd.args.last().is_some_and(|arg| arg == "--"));
+        cmd.args.splice(at..at, flag);
+    }
+    cmd.args.extend(attach);
+    Some(cmd)
+}
+
+/// The command that moves focus back to the pane the launcher ran in, once
+/// the split has taken it, so typing carries on where it was.
+pub fn build_focus_return_command(
+    kind: TerminalKind,
+    direction: SplitDirection,
+) -> Option<SplitCommand> {
+    build_focus_return_command_from(kind, direction, |key| env::var_os(key))
+}
+
+/// Like [`build_focus_return_command`], reading env vars through `env`.
+/// WezTerm is pointed back at `$WEZTERM_PANE` by id: the new pane may not
+/// sit beside the old one, so a direction could land anywhere. Without the
+/// id focus stays where the split put it.
+pub fn build_focus_return_command_from<E>(
+    kind: TerminalKind,
+    direction: SplitDirection,
+    env: E,
+) -> Option<SplitCommand>
+where
+    E: Fn(&str) -> Option<OsString>,
+{
+    let down = direction == SplitDirection::Down;
+    Some(match kind {
+        TerminalKind::WezTerm => {
+            let pane = env("WEZTERM_PANE")?;
+            SplitCommand::new(
+                "wezterm",
+                ["cli", "activate-pane", "--pane-id", pane.to_str()?],
+            )
+        }
+        TerminalKind::Zellij => SplitCommand::new("zellij", ["action", "focus-previous-pane"]),
+        TerminalKind::Tmux => SplitCommand::new("tmux", ["last-pane"]),
+        // `recent:1` is the window active before the current one
+        TerminalKind::Kitty => {
+            SplitCommand::new("kitty", ["@", "focus-window", "--match", "recent:1"])
+        }
+        TerminalKind::WindowsTerminal => SplitCommand::new(
+            "wt.exe",
+            ["-w", "0", "move-focus", if down { "up" } else { "left" }],
+        ),
+        TerminalKind::Unknown => return None,
+    })
+}
+
+/// Join arguments into one shell command line, quoting where needed.
+fn shell_join(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `wt` splits its command line into subcommands at `;`, so literal ones
+/// are escaped.
+fn wt_escape(arg: &str) -> String {
+    arg.replace(';', "\\;")
+}
+
+pub(crate) fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "/._-=:+,@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Stderr fragments that mean the multiplexer isn't ready yet, e.g. when the
+/// session hook fires while the session is still being created.
+const TMUX_TRANSIENT: &[&str] = &[
+    "no current session",
+    "no server running",
+    "error connecting to",
+    "lost server",
+];
+const ZELLIJ_TRANSIENT: &[&str] = &[
+    "There is no active session",
+    "No active zellij sessions found",
+    "Session not found",
+];
+const WEZTERM_TRANSIENT: &[&str] = &[
+    "failed to connect",
+    "Connection refused",
+    "no running wezterm instance",
+];
+const KITTY_TRANSIENT: &[&str] = &["Failed to connect", "Connection refused"];
+
+/// Stderr patterns that mark a failed split command as worth retrying.
+pub fn transient_failure_patterns(kind: TerminalKind) -> &'static [&'static str] {
+    match kind {
+        TerminalKind::Tmux => TMUX_TRANSIENT,
+        TerminalKind::Zellij => ZELLIJ_TRANSIENT,
+        TerminalKind::WezTerm => WEZTERM_TRANSIENT,
+        TerminalKind::Kitty => KITTY_TRANSIENT,
+        TerminalKind::WindowsTerminal | TerminalKind::Unknown => &[],
+    }
+}
+
+/// Whether a failed split command's stderr matches a transient pattern.
+pub fn is_transient_failure(kind: TerminalKind, stderr: &str) -> bool {
+    transient_failure_patterns(kind)
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
+
+/// Whether `program` resolves to a file in one of the `path_var` directories.
+/// Absolute or relative paths containing a separator are checked directly.
+pub fn program_on_path(program: &str, path_var: Option<&OsStr>) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    let Some(path_var) = path_var else {
+        return false;
+    };
+    env::split_paths(path_var).any(|dir| dir.join(program).is_file())
+}
+
+/// How long a precondition probe may take before it counts as stuck.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Command that succeeds only when kitty remote control is enabled.
+pub fn kitty_remote_control_probe() -> SplitCommand {
+    SplitCommand {
+        program: "kitty".to_string(),
+        args: vec!["@".to_string(), "ls".to_string()],
+    }
+}
+
+/// A harmless `zellij action`, which hangs while zellij waits for the
+/// user to grant permissions and fails outside a usable session.
+pub fn zellij_action_probe() -> SplitCommand {
+    SplitCommand {
+        program: "zellij".to_string(),
+        args: vec!["action".to_string(), "query-tab-names".to_string()],
+    }
+}
+
+/// The probe to run before splitting in `kind`, for terminals whose split
+/// fails silently or hangs when not set up.
+pub fn split_probe(kind: TerminalKind) -> Option<SplitCommand> {
+    match kind {
+        TerminalKind::Kitty => Some(kitty_remote_control_probe()),
+        TerminalKind::Zellij => Some(zellij_action_probe()),
+        _ => None,
+    }
+}
+
+/// What running a probe command showed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    Ok,
+    /// Exited unsuccessfully or could not start, with its stderr or the
+    /// spawn error.
+    Failed(String),
+    /// Still running at the timeout, and killed.
+    TimedOut,
+}
+
+/// What to tell the user when `outcome` means splitting in `kind` won't
+/// work; None when it will.
+pub fn probe_remedy(kind: TerminalKind, outcome: &ProbeOutcome) -> Option<String> {
+    let secs = PROBE_TIMEOUT.as_secs();
+    let remedy = match (kind, outcome) {
+        (_, ProbeOutcome::Ok) => return None,
+        (TerminalKind::Kitty, ProbeOutcome::Failed(stderr)) => format!(
+            "kitty remote control is off (`kitty @ ls`: {}). Add `allow_remote_control yes` \
+             to kitty.conf and restart kitty",
+            or_no_output(stderr)
+        ),
+        (TerminalKind::Kitty, ProbeOutcome::TimedOut) => format!(
+            "`kitty @ ls` didn't answer within {secs}s, so kitty is probably asking \
+             whether to allow remote control. Set `allow_remote_control yes` in \
+             kitty.conf and restart kitty"
+        ),
+        (TerminalKind::Zellij, ProbeOutcome::Failed(stderr)) => format!(
+            "zellij refused `zellij action` ({}). Check that $ZELLIJ_SESSION_NAME names \
+             a running session with `zellij list-sessions`",
+            or_no_output(stderr)
+        ),
+        (TerminalKind::Zellij, ProbeOutcome::TimedOut) => format!(
+            "`zellij action` didn't answer within {secs}s, so zellij is probably waiting \
+             on a permission prompt. Grant it in the zellij session, then run the \
+             launcher again"
+        ),
+        (_, ProbeOutcome::Failed(stderr)) => format!("probe failed: {}", or_no_output(stderr)),
+        (_, ProbeOutcome::TimedOut) => format!("probe didn't answer within {secs}s"),
+    };
+    Some(remedy)
+}
+
+fn or_no_output(stderr: &str) -> &str {
+    match stderr.trim() {
+        "" => "no output",
+        trimmed => trimmed,
     }
 }
 
+/// Run a probe command for at most `timeout`. The child is killed on
+/// timeout.
+pub fn run_probe(cmd: &SplitCommand, timeout: Duration) -> ProbeOutcome {
+    let child = Command::new(&cmd.program)
+        .args(&cmd.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return ProbeOutcome::Failed(format!("{}: {e}", cmd.program)),
+    };
+    // Drained as it comes, so a chatty probe can't block on a full pipe
+    let stderr = child.stderr.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = pipe.read_to_string(&mut text);
+            text
+        })
+    });
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return ProbeOutcome::Ok,
+            Ok(Some(_)) => {
+                let text = stderr
+                    .and_then(|reader| reader.join().ok())
+                    .unwrap_or_default();
+                return ProbeOutcome::Failed(text.trim().to_string());
+            }
+            Ok(None) if Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return ProbeOutcome::TimedOut;
+            }
+            Err(e) => return ProbeOutcome::Failed(e.to_string()),
+        }
+    }
+}
+
+/// Run a probe command, returning true only if it exits successfully
+/// within `timeout`.
+pub fn probe_succeeds(cmd: &SplitCommand, timeout: Duration) -> bool {
+    run_probe(cmd, timeout) == ProbeOutcome::Ok
+}
+
 /// Build fallback command (new terminal tab).
 pub fn build_fallback_command(binary_path: &str) -> SplitCommand {
-    if cfg!(target_os = "macos") {
+    build_fallback_command_with_args(binary_path, &[])
+}
+
+/// Like [`build_fallback_command`], passing `extra_args` to the attached instance.
+pub fn build_fallback_command_with_args(binary_path: &str, extra_args: &[String]) -> SplitCommand {
+    fallback_command_for(env::consts::OS, binary_path, extra_args)
+}
+
+/// The fallback for `os` (as in `std::env::consts::OS`): Terminal.app on
+/// macOS, a new console window on Windows, xterm elsewhere.
+fn fallback_command_for(os: &str, binary_path: &str, extra_args: &[String]) -> SplitCommand {
+    if os == "windows" {
+        // `start` takes its first quoted argument as the window title; an
+        // empty one is passed as `""`
+        SplitCommand {
+            program: "cmd.exe".to_string(),
+            args: ["/c", "start", "", binary_path, "--attach"]
+                .iter()
+                .map(|s| s.to_string())
+                .chain(extra_args.iter().cloned())
+                .collect(),
+        }
+    } else if os == "macos" {
         SplitCommand {
             program: "open".to_string(),
-            args: vec![
-                "-a".to_string(),
-                "Terminal".to_string(),
-                binary_path.to_string(),
-                "--args".to_string(),
-                "--attach".to_string(),
-            ],
+            args: ["-a", "Terminal", binary_path, "--args", "--attach"]
+                .iter()
+                .map(|s| s.to_string())
+                .chain(extra_args.iter().cloned())
+                .collect(),
         }
     } else {
+        let attach: Vec<String> = [binary_path.to_string(), "--attach".to_string()]
+            .into_iter()
+            .chain(extra_args.iter().cloned())
+            .collect();
         SplitCommand {
             program: "xterm".to_string(),
-            args: vec!["-e".to_string(), format!("{} --attach", binary_path)],
+            args: vec!["-e".to_string(), shell_join(&attach)],
         }
     }
 }
@@ -121,6 +513,43 @@ pub fn build_fallback_command(binary_path: &str) -> SplitCommand {
 mod tests {
     use super::*;
 
+    #[cfg(unix)]
+    #[test]
+    fn probes_report_success_failure_and_timeouts() {
+        let sh = |script: &str| SplitCommand {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+        };
+        let timeout = Duration::from_millis(200);
+        assert_eq!(run_probe(&sh("exit 0"), timeout), ProbeOutcome::Ok);
+        assert_eq!(
+            run_probe(
+                &sh("echo 'Remote control is disabled' >&2; exit 1"),
+                timeout
+            ),
+            ProbeOutcome::Failed("Remote control is disabled".to_string())
+        );
+        assert_eq!(run_probe(&sh("sleep 5"), timeout), ProbeOutcome::TimedOut);
+        // More than a pipe holds doesn't stall the probe
+        let chatty = sh("head -c 200000 /dev/zero | tr '\\0' x >&2; exit 1");
+        assert!(
+            matches!(run_probe(&chatty, Duration::from_secs(5)), ProbeOutcome::Failed(e) if e.len() == 200_000)
+        );
+        let missing = SplitCommand {
+            program: "/nonexistent/kitty".to_string(),
+            args: Vec::new(),
+        };
+        assert!(
+            matches!(run_probe(&missing, timeout), ProbeOutcome::Failed(e) if e.starts_with("/nonexistent/kitty: "))
+        );
+        assert!(!probe_succeeds(&missing, timeout));
+
+        assert_eq!(split_probe(TerminalKind::Tmux), None);
+        assert_eq!(probe_remedy(TerminalKind::Kitty, &ProbeOutcome::Ok), None);
+        let remedy = probe_remedy(TerminalKind::Kitty, &ProbeOutcome::Failed(String::new()));
+        assert!(remedy.unwrap().contains("(`kitty @ ls`: no output)"));
+    }
+
     #[test]
     fn build_split_wezterm() {
         let cmd = build_split_command(TerminalKind::WezTerm, "/usr/bin/pixel-agents-tui");
@@ -152,15 +581,296 @@ mod tests {
         assert_eq!(cmd.unwrap().program, "kitty");
     }
 
+    #[test]
+    fn build_split_windows_terminal() {
+        let extra = vec!["--home-project".to_string(), r"C:\src\a;b".to_string()];
+        let cmd = build_split_command_with_args(
+            TerminalKind::WindowsTerminal,
+            r"C:\bin\pixel-agents-tui.exe",
+            &extra,
+            &SplitOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(cmd.program, "wt.exe");
+        assert_eq!(
+            cmd.args,
+            [
+                "-w",
+                "0",
+                "split-pane",
+                "--vertical",
+                "--size",
+                "0.35",
+                r"C:\bin\pixel-agents-tui.exe",
+                "--attach",
+                "--home-project",
+                r"C:\src\a\;b",
+            ]
+        );
+    }
+
+    #[test]
+    fn detection_priority() {
+        let env = |vars: &'static [&'static str]| {
+            move |key: &str| vars.contains(&key).then(|| OsString::from("1"))
+        };
+        assert_eq!(
+            detect_terminal_from(env(&["WT_SESSION"])),
+            TerminalKind::WindowsTerminal
+        );
+        // tmux or Zellij running inside Windows Terminal get the split
+        assert_eq!(
+            detect_terminal_from(env(&["WT_SESSION", "TMUX"])),
+            TerminalKind::Tmux
+        );
+        assert_eq!(
+            detect_terminal_from(env(&["TMUX", "ZELLIJ"])),
+            TerminalKind::Zellij
+        );
+        assert_eq!(detect_terminal_from(env(&[])), TerminalKind::Unknown);
+    }
+
     #[test]
     fn unknown_terminal_returns_none() {
         let cmd = build_split_command(TerminalKind::Unknown, "/usr/bin/pixel-agents-tui");
         assert!(cmd.is_none());
     }
 
+    #[test]
+    fn transient_patterns_are_per_terminal() {
+        assert!(is_transient_failure(
+            TerminalKind::Tmux,
+            "no current session\n"
+        ));
+        assert!(is_transient_failure(
+            TerminalKind::Zellij,
+            "There is no active session!"
+        ));
+        assert!(!is_transient_failure(
+            TerminalKind::Zellij,
+            "no current session"
+        ));
+        assert!(!is_transient_failure(
+            TerminalKind::Tmux,
+            "unknown option -- z"
+        ));
+        assert!(!is_transient_failure(TerminalKind::Unknown, "anything"));
+    }
+
+    #[test]
+    fn program_on_path_searches_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("tmux"), "").unwrap();
+        let path_var = env::join_paths([Path::new("/nonexistent"), dir.path()]).unwrap();
+        assert!(program_on_path("tmux", Some(&path_var)));
+        assert!(!program_on_path("zellij", Some(&path_var)));
+        assert!(!program_on_path("tmux", None));
+    }
+
+    #[test]
+    fn extra_args_reach_the_attached_instance() {
+        let extra = vec!["--home-project".to_string(), "/src/my repo".to_string()];
+        for kind in [
+            TerminalKind::WezTerm,
+            TerminalKind::Zellij,
+            TerminalKind::Kitty,
+            TerminalKind::Tmux,
+        ] {
+            let cmd = build_split_command_with_args(
+                kind,
+                "/opt/my tools/pat",
+                &extra,
+                &SplitOptions::default(),
+            )
+            .unwrap();
+            // Each argument stays whole, spaces and all, with no quoting
+            assert_eq!(
+                &cmd.args[cmd.args.len() - 4..],
+                [
+                    "/opt/my tools/pat",
+                    "--attach",
+                    "--home-project",
+                    "/src/my repo"
+                ],
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn split_size_and_direction_reach_every_terminal() {
+        let split = |kind, percent, direction| {
+            let options = SplitOptions {
+                percent,
+                direction,
+                cwd: None,
+            };
+            let cmd = build_split_command_with_args(kind, "/bin/pat", &[], &options).unwrap();
+            // Everything before the attached command
+            let attach = cmd.args.iter().position(|arg| arg == "/bin/pat").unwrap();
+            cmd.args[..attach].join(" ")
+        };
+        let right = SplitDirection::Right;
+        let down = SplitDirection::Down;
+        assert_eq!(
+            split(TerminalKind::Tmux, 35, right),
+            "split-window -h -l 35% --"
+        );
+        assert_eq!(
+            split(TerminalKind::Tmux, 50, down),
+            "split-window -v -l 50% --"
+        );
+        assert_eq!(
+            split(TerminalKind::WezTerm, 40, down),
+            "cli split-pane --bottom --percent 40 --"
+        );
+        assert_eq!(
+            split(TerminalKind::Kitty, 25, right),
+            "@ launch --location=vsplit --bias=25"
+        );
+        assert_eq!(
+            split(TerminalKind::Kitty, 25, down),
+            "@ launch --location=hsplit --bias=25"
+        );
+        // Zellij sizes panes itself
+        assert_eq!(
+            split(TerminalKind::Zellij, 25, down),
+            "action new-pane --direction down --"
+        );
+        assert_eq!(
+            split(TerminalKind::WindowsTerminal, 5, down),
+            "-w 0 split-pane --horizontal --size 0.05"
+        );
+        assert_eq!(
+            build_split_command(TerminalKind::WezTerm, "/bin/pat"),
+            build_split_command_with_args(
+                TerminalKind::WezTerm,
+                "/bin/pat",
+                &[],
+                &SplitOptions {
+                    percent: 35,
+                    direction: right,
+                    cwd: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn split_opens_in_the_given_directory() {
+        let options = SplitOptions {
+            cwd: Some(PathBuf::from("/src/my repo")),
+            ..SplitOptions::default()
+        };
+        let split = |kind| {
+            let cmd = build_split_command_with_args(kind, "/bin/pat", &[], &options).unwrap();
+            let attach = cmd.args.iter().position(|arg| arg == "/bin/pat").unwrap();
+            cmd.args[..attach].to_vec()
+        };
+        assert_eq!(
+            split(TerminalKind::Tmux),
+            [
+                "split-window",
+                "-h",
+                "-l",
+                "35%",
+                "-c",
+                "/src/my repo",
+                "--"
+            ]
+        );
+        assert_eq!(
+            split(TerminalKind::WezTerm)[4..],
+            ["35", "--cwd", "/src/my repo", "--"]
+        );
+        assert_eq!(
+            split(TerminalKind::Zellij)[4..],
+            ["--cwd", "/src/my repo", "--"]
+        );
+        assert_eq!(
+            split(TerminalKind::Kitty).last().unwrap(),
+            "--cwd=/src/my repo"
+        );
+        assert_eq!(
+            split(TerminalKind::WindowsTerminal)[5..],
+            ["0.35", "-d", "/src/my repo"]
+        );
+    }
+
+    #[test]
+    fn focus_returns_to_the_pane_split_from() {
+        let focus = |kind, direction| {
+            build_focus_return_command_from(kind, direction, |key| {
+                (key == "WEZTERM_PANE").then(|| "7".into())
+            })
+            .unwrap()
+            .command_line()
+        };
+        assert_eq!(
+            focus(TerminalKind::Tmux, SplitDirection::Right),
+            "tmux last-pane"
+        );
+        assert_eq!(
+            focus(TerminalKind::WezTerm, SplitDirection::Right),
+            "wezterm cli activate-pane --pane-id 7"
+        );
+        assert_eq!(
+            build_focus_return_command_from(TerminalKind::WezTerm, SplitDirection::Down, |_| None),
+            None
+        );
+        assert_eq!(
+            focus(TerminalKind::Zellij, SplitDirection::Down),
+            "zellij action focus-previous-pane"
+        );
+        assert_eq!(
+            focus(TerminalKind::Kitty, SplitDirection::Right),
+            "kitty @ focus-window --match recent:1"
+        );
+        assert_eq!(
+            focus(TerminalKind::WindowsTerminal, SplitDirection::Down),
+            "wt.exe -w 0 move-focus up"
+        );
+        assert_eq!(
+            build_focus_return_command(TerminalKind::Unknown, SplitDirection::Right),
+            None
+        );
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("plain/path-1.2"), "plain/path-1.2");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
     #[test]
     fn fallback_provides_command() {
         let cmd = build_fallback_command("/usr/bin/pixel-agents-tui");
         assert!(!cmd.program.is_empty());
     }
+
+    #[test]
+    fn fallback_per_platform() {
+        let extra = vec!["--welcome".to_string()];
+        let windows = fallback_command_for("windows", r"C:\bin\pat.exe", &extra);
+        assert_eq!(windows.program, "cmd.exe");
+        assert_eq!(
+            windows.args,
+            [
+                "/c",
+                "start",
+                "",
+                r"C:\bin\pat.exe",
+                "--attach",
+                "--welcome"
+            ]
+        );
+        assert_eq!(
+            fallback_command_for("macos", "/bin/pat", &extra).program,
+            "open"
+        );
+        let linux = fallback_command_for("linux", "/bin/pat", &extra);
+        assert_eq!(linux.program, "xterm");
+        assert_eq!(linux.args, ["-e", "/bin/pat --attach --welcome"]);
+    }
 }
@@ -0,0 +1,658 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::detect::{
+    is_transient_failure, probe_remedy, run_probe, split_probe, ProbeOutcome, SplitCommand,
+    TerminalKind, PROBE_TIMEOUT,
+};
+use crate::schema::SCHEMA_VERSION;
+use crate::singleton::PidFileState;
+
+/// How many times a transiently failing split command is retried.
+pub const SPLIT_RETRIES: u32 = 3;
+
+/// Pause between split attempts while the multiplexer finishes starting.
+pub const SPLIT_RETRY_DELAY: Duration = Duration::from_millis(300);
+
+/// Result of running a split command to completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunResult {
+    pub success: bool,
+    pub stderr: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitOutcome {
+    Launched {
+        attempts: u32,
+    },
+    /// Gave up, either on a non-transient error or after all retries.
+    Failed {
+        attempts: u32,
+        reason: String,
+    },
+}
+
+/// Run a split command and wait for it. Split commands return as soon as
+/// the pane exists, so waiting lets us see the multiplexer's error.
+pub fn run_split_command(cmd: &SplitCommand) -> io::Result<RunResult> {
+    let output = Command::new(&cmd.program)
+        .args(&cmd.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    Ok(RunResult {
+        success: output.status.success(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
+
+/// Run `cmd` via `run`, retrying up to [`SPLIT_RETRIES`] times when the
+/// failure matches the terminal's transient patterns. Each attempt is
+/// logged to `log`.
+pub fn spawn_split_with_retry<R, S>(
+    kind: TerminalKind,
+    cmd: &SplitCommand,
+    mut run: R,
+    mut sleep: S,
+    log: &mut dyn Write,
+) -> SplitOutcome
+where
+    R: FnMut(&SplitCommand) -> io::Result<RunResult>,
+    S: FnMut(Duration),
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let reason = match run(cmd) {
+            Ok(result) if result.success => {
+                let _ = writeln!(log, "split attempt {attempts}: {} ok", cmd.program);
+                return SplitOutcome::Launched { attempts };
+            }
+            Ok(result) => result.stderr.trim().to_string(),
+            Err(e) => e.to_string(),
+        };
+        let _ = writeln!(
+            log,
+            "split attempt {attempts}: {} failed: {reason}",
+            cmd.program
+        );
+        if attempts > SPLIT_RETRIES || !is_transient_failure(kind, &reason) {
+            return SplitOutcome::Failed { attempts, reason };
+        }
+        sleep(SPLIT_RETRY_DELAY);
+    }
+}
+
+/// Runs the launcher's external commands, so the launch flow can be
+/// exercised without spawning anything.
+pub trait ProcessRunner {
+    /// Run a split command to completion.
+    fn run(&mut self, cmd: &SplitCommand) -> io::Result<RunResult>;
+    /// Start a command without waiting for it, e.g. a new terminal window.
+    fn spawn(&mut self, cmd: &SplitCommand) -> io::Result<()>;
+    /// Run a precondition probe for at most `timeout`.
+    fn probe(&mut self, cmd: &SplitCommand, timeout: Duration) -> ProbeOutcome;
+}
+
+/// Runs commands for real.
+pub struct SystemRunner;
+
+impl ProcessRunner for SystemRunner {
+    fn run(&mut self, cmd: &SplitCommand) -> io::Result<RunResult> {
+        run_split_command(cmd)
+    }
+
+    fn spawn(&mut self, cmd: &SplitCommand) -> io::Result<()> {
+        Command::new(&cmd.program).args(&cmd.args).spawn()?;
+        Ok(())
+    }
+
+    fn probe(&mut self, cmd: &SplitCommand, timeout: Duration) -> ProbeOutcome {
+        run_probe(cmd, timeout)
+    }
+}
+
+/// Why the launcher could not open the dashboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchError {
+    /// No supported terminal was detected and the fallback window failed.
+    NoTerminal { detail: String },
+    /// The split failed and so did the fallback window.
+    Spawn { detail: String },
+    /// The terminal isn't set up to split; `detail` says how to fix it.
+    SplitBlocked { detail: String },
+}
+
+impl LaunchError {
+    /// Stable identifier for `--diagnostics-json`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LaunchError::NoTerminal { .. } => "no_terminal",
+            LaunchError::Spawn { .. } => "spawn_failed",
+            LaunchError::SplitBlocked { .. } => "split_blocked",
+        }
+    }
+
+    pub fn detail(&self) -> &str {
+        match self {
+            LaunchError::NoTerminal { detail }
+            | LaunchError::Spawn { detail }
+            | LaunchError::SplitBlocked { detail } => detail,
+        }
+    }
+}
+
+impl std::fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LaunchError::NoTerminal { detail } => {
+                write!(f, "no supported terminal found: {detail}")
+            }
+            LaunchError::Spawn { detail } => write!(f, "could not open the dashboard: {detail}"),
+            LaunchError::SplitBlocked { detail } => write!(f, "cannot split: {detail}"),
+        }
+    }
+}
+
+impl std::error::Error for LaunchError {}
+
+/// Everything the launcher decided before running anything.
+#[derive(Debug, Clone)]
+pub struct LaunchPlan {
+    pub kind: TerminalKind,
+    /// None when the terminal has no split support.
+    pub split: Option<SplitCommand>,
+    /// Run after a split, to give focus back to the pane launched from.
+    pub focus_return: Option<SplitCommand>,
+    pub fallback: SplitCommand,
+    pub pid_state: PidFileState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchOutcome {
+    /// An instance is already running; nothing was started.
+    AlreadyRunning(u32),
+    Split {
+        attempts: u32,
+    },
+    /// Opened in a new window, after the split failed with `split_error`.
+    Fallback {
+        split_error: Option<String>,
+    },
+    Failed(LaunchError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchReport {
+    pub outcome: LaunchOutcome,
+    /// The last command run, if any.
+    pub command_attempted: Option<SplitCommand>,
+}
+
+/// Open the dashboard as `plan` says: split the current terminal, retrying
+/// transient failures, and hand focus back to the pane launched from; or,
+/// failing that, fall back to a new window. Kitty and zellij are
+/// probed first; when they aren't set up to split, the launch stops with
+/// the fix instead of failing silently or hanging on a prompt.
+pub fn launch<S>(
+    plan: &LaunchPlan,
+    runner: &mut dyn ProcessRunner,
+    sleep: S,
+    log: &mut dyn Write,
+) -> LaunchReport
+where
+    S: FnMut(Duration),
+{
+    if let PidFileState::Running(pid) = plan.pid_state {
+        return LaunchReport {
+            outcome: LaunchOutcome::AlreadyRunning(pid),
+            command_attempted: None,
+        };
+    }
+
+    if let (Some(probe), Some(_)) = (split_probe(plan.kind), &plan.split) {
+        let outcome = runner.probe(&probe, PROBE_TIMEOUT);
+        if let Some(detail) = probe_remedy(plan.kind, &outcome) {
+            let _ = writeln!(log, "{} not ready: {detail}", plan.kind.label());
+            return LaunchReport {
+                outcome: LaunchOutcome::Failed(LaunchError::SplitBlocked { detail }),
+                command_attempted: Some(probe),
+            };
+        }
+    }
+
+    let split_error = match &plan.split {
+        Some(split) => {
+            match spawn_split_with_retry(plan.kind, split, |c| runner.run(c), sleep, log) {
+                SplitOutcome::Launched { attempts } => {
+                    // Best effort: the dashboard is up either way
+                    if let Some(focus) = &plan.focus_return {
+                        let reason = match runner.run(focus) {
+                            Ok(result) if result.success => None,
+                            Ok(result) => Some(result.stderr.trim().to_string()),
+                            Err(e) => Some(e.to_string()),
+                        };
+                        if let Some(reason) = reason {
+                            let _ = writeln!(
+                                log,
+                                "returning focus: {} failed: {reason}",
+                                focus.command_line()
+                            );
+                        }
+                    }
+                    return LaunchReport {
+                        outcome: LaunchOutcome::Split { attempts },
+                        command_attempted: Some(split.clone()),
+                    };
+                }
+                SplitOutcome::Failed { reason, .. } => {
+                    let _ = writeln!(log, "falling back to {}", plan.fallback.program);
+                    Some(reason)
+                }
+            }
+        }
+        None => None,
+    };
+
+    let outcome = match runner.spawn(&plan.fallback) {
+        Ok(()) => LaunchOutcome::Fallback { split_error },
+        Err(e) => {
+            let fallback = format!("{}: {e}", plan.fallback.program);
+            LaunchOutcome::Failed(match split_error {
+                Some(reason) => LaunchError::Spawn {
+                    detail: format!("split failed ({reason}); {fallback}"),
+                },
+                None => LaunchError::NoTerminal { detail: fallback },
+            })
+        }
+    };
+    LaunchReport {
+        outcome,
+        command_attempted: Some(plan.fallback.clone()),
+    }
+}
+
+/// The single JSON object `--diagnostics-json` writes when the launcher exits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Diagnostics {
+    pub schema_version: u32,
+    /// `already_running`, `launched`, `launched_fallback` or `failed`.
+    pub outcome: &'static str,
+    pub terminal_kind: String,
+    pub command_attempted: Option<String>,
+    /// `no_terminal`, `spawn_failed` or `split_blocked` on failure;
+    /// `split_failed` when the fallback window opened instead of a split.
+    pub error_kind: Option<&'static str>,
+    pub error_detail: Option<String>,
+    pub pid_file_state: &'static str,
+    pub duration_ms: u64,
+}
+
+impl Diagnostics {
+    pub fn new(plan: &LaunchPlan, report: &LaunchReport, elapsed: Duration) -> Self {
+        let (outcome, error_kind, error_detail) = match &report.outcome {
+            LaunchOutcome::AlreadyRunning(_) => ("already_running", None, None),
+            LaunchOutcome::Split { .. } => ("launched", None, None),
+            LaunchOutcome::Fallback { split_error } => (
+                "launched_fallback",
+                split_error.as_ref().map(|_| "split_failed"),
+                split_error.clone(),
+            ),
+            LaunchOutcome::Failed(e) => ("failed", Some(e.kind()), Some(e.detail().to_string())),
+        };
+        Self {
+            schema_version: SCHEMA_VERSION,
+            outcome,
+            terminal_kind: plan.kind.label().to_ascii_lowercase(),
+            command_attempted: report.command_attempted.as_ref().map(|c| c.command_line()),
+            error_kind,
+            error_detail,
+            pid_file_state: plan.pid_state.label(),
+            duration_ms: elapsed.as_millis() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tmux_split() -> SplitCommand {
+        SplitCommand {
+            program: "tmux".to_string(),
+            args: vec!["split-window".to_string()],
+        }
+    }
+
+    fn failure(stderr: &str) -> io::Result<RunResult> {
+        Ok(RunResult {
+            success: false,
+            stderr: stderr.to_string(),
+        })
+    }
+
+    /// Answers split runs from a script and records everything.
+    struct FakeRunner {
+        runs: Vec<io::Result<RunResult>>,
+        ran: Vec<String>,
+        spawn_ok: bool,
+        spawned: Vec<String>,
+        probe: ProbeOutcome,
+    }
+
+    impl ProcessRunner for FakeRunner {
+        fn run(&mut self, cmd: &SplitCommand) -> io::Result<RunResult> {
+            self.ran.push(cmd.command_line());
+            self.runs.remove(0)
+        }
+
+        fn spawn(&mut self, cmd: &SplitCommand) -> io::Result<()> {
+            self.spawned.push(cmd.program.clone());
+            if self.spawn_ok {
+                Ok(())
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "No such file or directory",
+                ))
+            }
+        }
+
+        fn probe(&mut self, _cmd: &SplitCommand, timeout: Duration) -> ProbeOutcome {
+            assert_eq!(timeout, PROBE_TIMEOUT);
+            self.probe.clone()
+        }
+    }
+
+    fn plan(kind: TerminalKind, pid_state: PidFileState) -> LaunchPlan {
+        LaunchPlan {
+            kind,
+            split: (kind != TerminalKind::Unknown).then(tmux_split),
+            focus_return: None,
+            fallback: SplitCommand {
+                program: "xterm".to_string(),
+                args: vec!["-e".to_string(), "pixel-agents-tui --attach".to_string()],
+            },
+            pid_state,
+        }
+    }
+
+    fn diagnostics_json(plan: &LaunchPlan, runner: &mut FakeRunner) -> serde_json::Value {
+        let report = launch(plan, runner, |_| {}, &mut io::sink());
+        let diagnostics = Diagnostics::new(plan, &report, Duration::from_millis(42));
+        serde_json::to_value(diagnostics).unwrap()
+    }
+
+    #[test]
+    fn diagnostics_for_successful_split() {
+        let mut runner = FakeRunner {
+            runs: vec![Ok(RunResult {
+                success: true,
+                stderr: String::new(),
+            })],
+            ran: Vec::new(),
+            spawn_ok: true,
+            spawned: Vec::new(),
+            probe: ProbeOutcome::Ok,
+        };
+        let json = diagnostics_json(
+            &plan(TerminalKind::Tmux, PidFileState::Missing),
+            &mut runner,
+        );
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "schema_version": 1,
+                "outcome": "launched",
+                "terminal_kind": "tmux",
+                "command_attempted": "tmux split-window",
+                "error_kind": null,
+                "error_detail": null,
+                "pid_file_state": "missing",
+                "duration_ms": 42,
+            })
+        );
+        assert!(runner.spawned.is_empty());
+    }
+
+    #[test]
+    fn diagnostics_for_stale_pid_and_running_instance() {
+        let mut runner = FakeRunner {
+            runs: vec![Ok(RunResult {
+                success: true,
+                stderr: String::new(),
+            })],
+            ran: Vec::new(),
+            spawn_ok: true,
+            spawned: Vec::new(),
+            probe: ProbeOutcome::Ok,
+        };
+        // A stale PID file doesn't stop the launch but is reported
+        let json = diagnostics_json(
+            &plan(TerminalKind::Tmux, PidFileState::Stale(4242)),
+            &mut runner,
+        );
+        assert_eq!(json["outcome"], "launched");
+        assert_eq!(json["pid_file_state"], "stale");
+
+        let json = diagnostics_json(
+            &plan(TerminalKind::Tmux, PidFileState::Running(4242)),
+            &mut runner,
+        );
+        assert_eq!(json["outcome"], "already_running");
+        assert_eq!(json["pid_file_state"], "running");
+        assert_eq!(json["command_attempted"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn diagnostics_for_spawn_failure() {
+        let mut runner = FakeRunner {
+            runs: vec![failure("can't find pane: %9")],
+            ran: Vec::new(),
+            spawn_ok: false,
+            spawned: Vec::new(),
+            probe: ProbeOutcome::Ok,
+        };
+        let json = diagnostics_json(
+            &plan(TerminalKind::Tmux, PidFileState::Missing),
+            &mut runner,
+        );
+        assert_eq!(json["outcome"], "failed");
+        assert_eq!(json["error_kind"], "spawn_failed");
+        assert_eq!(
+            json["error_detail"],
+            "split failed (can't find pane: %9); xterm: No such file or directory"
+        );
+        assert_eq!(
+            json["command_attempted"],
+            "xterm -e 'pixel-agents-tui --attach'"
+        );
+        assert_eq!(runner.spawned, vec!["xterm"]);
+    }
+
+    #[test]
+    fn diagnostics_for_missing_terminal() {
+        let mut runner = FakeRunner {
+            runs: Vec::new(),
+            ran: Vec::new(),
+            spawn_ok: false,
+            spawned: Vec::new(),
+            probe: ProbeOutcome::Ok,
+        };
+        let json = diagnostics_json(
+            &plan(TerminalKind::Unknown, PidFileState::Missing),
+            &mut runner,
+        );
+        assert_eq!(json["outcome"], "failed");
+        assert_eq!(json["terminal_kind"], "unknown");
+        assert_eq!(json["error_kind"], "no_terminal");
+        assert_eq!(json["error_detail"], "xterm: No such file or directory");
+
+        // With a working fallback the same path succeeds
+        runner.spawn_ok = true;
+        let json = diagnostics_json(
+            &plan(TerminalKind::Unknown, PidFileState::Missing),
+            &mut runner,
+        );
+        assert_eq!(json["outcome"], "launched_fallback");
+        assert_eq!(json["error_kind"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn kitty_and_zellij_are_probed_before_splitting() {
+        for kind in [TerminalKind::Kitty, TerminalKind::Zellij] {
+            let probe = split_probe(kind).unwrap();
+            let mut runner = FakeRunner {
+                runs: vec![Ok(RunResult {
+                    success: true,
+                    stderr: String::new(),
+                })],
+                ran: Vec::new(),
+                spawn_ok: true,
+                spawned: Vec::new(),
+                probe: ProbeOutcome::Ok,
+            };
+            let plan = plan(kind, PidFileState::Missing);
+            let report = launch(&plan, &mut runner, |_| {}, &mut io::sink());
+            assert_eq!(report.outcome, LaunchOutcome::Split { attempts: 1 });
+
+            for outcome in [
+                ProbeOutcome::Failed("Remote control is disabled".to_string()),
+                ProbeOutcome::TimedOut,
+            ] {
+                runner.probe = outcome;
+                let mut log = Vec::new();
+                let report = launch(&plan, &mut runner, |_| {}, &mut log);
+                // Neither the split nor the fallback window is tried
+                assert_eq!(report.command_attempted.as_ref(), Some(&probe));
+                assert!(runner.runs.is_empty() && runner.spawned.is_empty());
+                let LaunchOutcome::Failed(error) = report.outcome else {
+                    panic!("{kind:?} launched despite a failed probe");
+                };
+                assert_eq!(error.kind(), "split_blocked");
+                assert!(String::from_utf8(log).unwrap().contains("not ready"));
+                let remedy = error.detail();
+                match (kind, &runner.probe) {
+                    (TerminalKind::Kitty, _) => {
+                        assert!(remedy.contains("`allow_remote_control yes`"), "{remedy}");
+                    }
+                    (_, ProbeOutcome::TimedOut) => {
+                        assert!(remedy.contains("permission prompt"), "{remedy}");
+                    }
+                    _ => assert!(remedy.contains("zellij list-sessions"), "{remedy}"),
+                }
+                if let ProbeOutcome::Failed(stderr) = &runner.probe {
+                    assert!(remedy.contains(stderr.as_str()), "{remedy}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn focus_returns_after_a_split_but_not_a_fallback() {
+        let ok = || {
+            Ok(RunResult {
+                success: true,
+                stderr: String::new(),
+            })
+        };
+        let mut runner = FakeRunner {
+            runs: vec![ok(), failure("no last pane")],
+            ran: Vec::new(),
+            spawn_ok: true,
+            spawned: Vec::new(),
+            probe: ProbeOutcome::Ok,
+        };
+        let mut plan = plan(TerminalKind::Tmux, PidFileState::Missing);
+        plan.focus_return = Some(SplitCommand {
+            program: "tmux".to_string(),
+            args: vec!["last-pane".to_string()],
+        });
+        let mut log = Vec::new();
+        let report = launch(&plan, &mut runner, |_| {}, &mut log);
+        // A focus that didn't come back is no reason to open another window
+        assert_eq!(report.outcome, LaunchOutcome::Split { attempts: 1 });
+        assert_eq!(runner.ran, ["tmux split-window", "tmux last-pane"]);
+        assert!(runner.spawned.is_empty());
+        assert!(String::from_utf8(log)
+            .unwrap()
+            .contains("returning focus: tmux last-pane failed: no last pane"));
+
+        runner.runs = vec![failure("can't find pane: %9")];
+        runner.ran.clear();
+        let report = launch(&plan, &mut runner, |_| {}, &mut io::sink());
+        assert!(matches!(report.outcome, LaunchOutcome::Fallback { .. }));
+        assert_eq!(runner.ran, ["tmux split-window"]);
+        assert_eq!(runner.spawned, ["xterm"]);
+    }
+
+    #[test]
+    fn retries_transient_failures_until_success() {
+        let mut calls = 0;
+        let mut slept = Vec::new();
+        let mut log = Vec::new();
+        let outcome = spawn_split_with_retry(
+            TerminalKind::Tmux,
+            &tmux_split(),
+            |_| {
+                calls += 1;
+                if calls < 3 {
+                    failure("no current session")
+                } else {
+                    Ok(RunResult {
+                        success: true,
+                        stderr: String::new(),
+                    })
+                }
+            },
+            |d| slept.push(d),
+            &mut log,
+        );
+        assert_eq!(outcome, SplitOutcome::Launched { attempts: 3 });
+        assert_eq!(calls, 3);
+        assert_eq!(slept, vec![SPLIT_RETRY_DELAY; 2]);
+        let log = String::from_utf8(log).unwrap();
+        assert_eq!(log.lines().count(), 3);
+        assert!(log.contains("split attempt 3: tmux ok"));
+    }
+
+    #[test]
+    fn gives_up_after_bounded_retries() {
+        let mut calls = 0;
+        let outcome = spawn_split_with_retry(
+            TerminalKind::Tmux,
+            &tmux_split(),
+            |_| {
+                calls += 1;
+                failure("no server running on /tmp/tmux-1000/default")
+            },
+            |_| {},
+            &mut io::sink(),
+        );
+        assert_eq!(calls, SPLIT_RETRIES + 1);
+        assert!(matches!(outcome, SplitOutcome::Failed { attempts, .. } if attempts == calls));
+    }
+
+    #[test]
+    fn permanent_failures_are_not_retried() {
+        let mut calls = 0;
+        let outcome = spawn_split_with_retry(
+            TerminalKind::Tmux,
+            &tmux_split(),
+            |_| {
+                calls += 1;
+                Err(io::Error::new(io::ErrorKind::NotFound, "not found"))
+            },
+            |_| panic!("should not sleep"),
+            &mut io::sink(),
+        );
+        assert_eq!(calls, 1);
+        assert!(matches!(outcome, SplitOutcome::Failed { attempts: 1, .. }));
+    }
+}
@@ -1,5 +1,15 @@
 pub mod detect;
+pub mod launch;
 
 pub use detect::{
-    build_fallback_command, build_split_command, detect_terminal, SplitCommand, TerminalKind,
+    build_fallback_command, build_fallback_command_with_args, build_focus_return_command,
+    build_focus_return_command_from, build_split_command, build_split_command_with_args,
+    detect_terminal, detect_terminal_from, is_transient_failure, kitty_remote_control_probe,
+    probe_remedy, probe_succeeds, program_on_path, run_probe, split_probe,
+    transient_failure_patterns, zellij_action_probe, ProbeOutcome, SplitCommand, SplitDirection,
+    SplitOptions, TerminalKind, DEFAULT_SPLIT_PERCENT, PROBE_TIMEOUT,
+};
+pub use launch::{
+    launch, run_split_command, spawn_split_with_retry, Diagnostics, LaunchError, LaunchOutcome,
+    LaunchPlan, LaunchReport, ProcessRunner, RunResult, SplitOutcome, SystemRunner,
 };
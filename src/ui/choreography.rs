@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::state::agent::{AgentState, AgentStatus};
+
+/// How long a walk between the office entrance and a desk takes.
+pub const WALK_DURATION: Duration = Duration::from_secs(1);
+
+/// Where an agent's character is, relative to its desk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgentPosition {
+    AtDesk,
+    /// Walking on the spot while getting up from, or back to, dormancy.
+    Stirring,
+    /// On the way in; 0 is the entrance at the office's left edge, 1 the
+    /// desk.
+    Entering(f32),
+    /// On the way out; 0 is the desk, 1 past the entrance and out of view.
+    Leaving(f32),
+}
+
+impl AgentPosition {
+    pub fn is_walking(&self) -> bool {
+        *self != AgentPosition::AtDesk
+    }
+
+    /// Somewhere between the entrance and the desk, rather than at it.
+    pub fn is_away(&self) -> bool {
+        matches!(self, AgentPosition::Entering(_) | AgentPosition::Leaving(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WalkKind {
+    Entering,
+    Stirring,
+    Leaving,
+}
+
+/// A walk `walked` ticks into its `ticks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Walk {
+    kind: WalkKind,
+    walked: u32,
+    ticks: u32,
+}
+
+impl Walk {
+    fn new(kind: WalkKind, ticks: u32) -> Self {
+        Self {
+            kind,
+            walked: 0,
+            ticks: ticks.max(1),
+        }
+    }
+
+    fn step(&mut self) {
+        self.walked = (self.walked + 1).min(self.ticks);
+    }
+
+    fn is_done(&self) -> bool {
+        self.walked >= self.ticks
+    }
+
+    fn position(&self) -> AgentPosition {
+        let fraction = self.walked as f32 / self.ticks as f32;
+        match self.kind {
+            WalkKind::Entering => AgentPosition::Entering(fraction),
+            WalkKind::Stirring => AgentPosition::Stirring,
+            WalkKind::Leaving => AgentPosition::Leaving(fraction),
+        }
+    }
+}
+
+/// An agent whose session is gone, still walking out of the office.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepartingAgent {
+    pub id: u32,
+    /// Where its desk was: the office groups desks by project.
+    pub session_file: PathBuf,
+    walk: Walk,
+}
+
+impl DepartingAgent {
+    pub fn position(&self) -> AgentPosition {
+        self.walk.position()
+    }
+
+    /// Take one step out; true once out of view.
+    pub fn step(&mut self) -> bool {
+        self.walk.step();
+        self.walk.is_done()
+    }
+}
+
+/// Where each agent's character is in the office. Agents are placed by
+/// comparing each tick's agents with the last: new ones walk in from the
+/// entrance, ones that disappeared walk out, and ones going dormant or
+/// waking up walk on the spot.
+#[derive(Debug, Default)]
+pub struct OfficeLayout {
+    /// Agents not simply sitting at their desk.
+    walks: HashMap<u32, Walk>,
+    /// Every agent seen last tick: its session file, and whether dormant.
+    seen: HashMap<u32, (PathBuf, bool)>,
+}
+
+impl OfficeLayout {
+    /// Take one step of every walk and start walks for what changed since
+    /// the last call. Walks last `ticks`. Returns the agents that left.
+    pub fn update(&mut self, agents: &HashMap<u32, AgentState>, ticks: u32) -> Vec<DepartingAgent> {
+        for walk in self.walks.values_mut() {
+            walk.step();
+        }
+        self.walks
+            .retain(|id, walk| !walk.is_done() && agents.contains_key(id));
+
+        let mut departed = Vec::new();
+        self.seen.retain(|&id, (session_file, _)| {
+            if agents.contains_key(&id) {
+                return true;
+            }
+            departed.push(DepartingAgent {
+                id,
+                session_file: std::mem::take(session_file),
+                walk: Walk::new(WalkKind::Leaving, ticks),
+            });
+            false
+        });
+        departed.sort_by_key(|agent| agent.id);
+
+        for (&id, agent) in agents {
+            let dormant = agent.status == AgentStatus::Dormant;
+            match self.seen.get_mut(&id) {
+                None => {
+                    self.seen.insert(id, (agent.session_file.clone(), dormant));
+                    self.walks.insert(id, Walk::new(WalkKind::Entering, ticks));
+                }
+                Some((_, was_dormant)) if *was_dormant != dormant => {
+                    *was_dormant = dormant;
+                    // Still walking in, the stir would go unseen
+                    self.walks
+                        .entry(id)
+                        .or_insert_with(|| Walk::new(WalkKind::Stirring, ticks));
+                }
+                Some(_) => {}
+            }
+        }
+        departed
+    }
+
+    pub fn position(&self, id: u32) -> AgentPosition {
+        self.walks
+            .get(&id)
+            .map_or(AgentPosition::AtDesk, Walk::position)
+    }
+}
+
+/// Ticks a walk of [`WALK_DURATION`] takes at `tick_rate`.
+pub fn walk_ticks(tick_rate: Duration) -> u32 {
+    let ticks = WALK_DURATION.as_millis() / tick_rate.as_millis().max(1);
+    (ticks as u32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agents(ids: &[u32]) -> HashMap<u32, AgentState> {
+        ids.iter()
+            .map(|&id| {
+                let path = PathBuf::from(format!("/tmp/{id}.jsonl"));
+                (id, AgentState::new(id, path))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn new_agents_walk_in_to_their_desk() {
+        let mut office = OfficeLayout::default();
+        let present = agents(&[1]);
+        assert!(office.update(&present, 4).is_empty());
+        assert_eq!(office.position(1), AgentPosition::Entering(0.0));
+        let mut last = 0.0;
+        for _ in 0..3 {
+            office.update(&present, 4);
+            let AgentPosition::Entering(fraction) = office.position(1) else {
+                panic!("arrived early: {:?}", office.position(1));
+            };
+            assert!(fraction > last);
+            last = fraction;
+        }
+        office.update(&present, 4);
+        assert_eq!(office.position(1), AgentPosition::AtDesk);
+        // Agents that are simply there stay put
+        office.update(&present, 4);
+        assert_eq!(office.position(1), AgentPosition::AtDesk);
+    }
+
+    #[test]
+    fn removed_agents_leave_and_dormancy_changes_stir() {
+        let mut office = OfficeLayout::default();
+        let mut present = agents(&[1, 2]);
+        for _ in 0..3 {
+            office.update(&present, 2);
+        }
+
+        present
+            .get_mut(&1)
+            .unwrap()
+            .set_status(AgentStatus::Dormant);
+        present.remove(&2);
+        let mut departed = office.update(&present, 2);
+        assert_eq!(office.position(1), AgentPosition::Stirring);
+        assert_eq!(departed.len(), 1);
+        let leaving = &mut departed[0];
+        assert_eq!(leaving.id, 2);
+        assert_eq!(leaving.session_file, PathBuf::from("/tmp/2.jsonl"));
+        assert_eq!(leaving.position(), AgentPosition::Leaving(0.0));
+        assert!(!leaving.step());
+        assert_eq!(leaving.position(), AgentPosition::Leaving(0.5));
+        assert!(leaving.step());
+
+        office.update(&present, 2);
+        office.update(&present, 2);
+        assert_eq!(office.position(1), AgentPosition::AtDesk);
+        // Coming back is a new arrival
+        let departed = office.update(&agents(&[1, 2]), 2);
+        assert!(departed.is_empty());
+        assert!(office.position(2).is_walking());
+    }
+
+    #[test]
+    fn walks_take_about_a_second_of_ticks() {
+        assert_eq!(walk_ticks(Duration::from_millis(100)), 10);
+        assert_eq!(walk_ticks(Duration::from_millis(250)), 4);
+        assert_eq!(walk_ticks(Duration::from_secs(5)), 1);
+    }
+}
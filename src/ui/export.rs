@@ -0,0 +1,371 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier};
+use ratatui::Terminal;
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::App;
+use crate::ui::layout;
+
+/// SVG cell size in pixels.
+const CELL_WIDTH: u16 = 9;
+const CELL_HEIGHT: u16 = 18;
+const SVG_FOREGROUND: &str = "#d0d0d0";
+const SVG_BACKGROUND: &str = "#1e1e1e";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ExportFormat {
+    #[default]
+    #[value(name = "txt")]
+    Text,
+    Svg,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Svg => "svg",
+        }
+    }
+}
+
+/// Render the office panel off-screen at the size it has in a frame of
+/// `frame_area`.
+pub fn render_office_buffer(app: &App, frame_area: Rect) -> Buffer {
+    let area = layout::office_area(frame_area, app.config.office_width_percent);
+    let backend = TestBackend::new(area.width, area.height);
+    let Ok(mut terminal) = Terminal::new(backend);
+    let Ok(_) = terminal.draw(|frame| layout::render_office(frame, app, frame.area()));
+    terminal.backend().buffer().clone()
+}
+
+/// The visible symbols of each row, skipping cells hidden behind wide
+/// characters.
+fn visible_rows(buffer: &Buffer) -> Vec<Vec<(u16, &ratatui::buffer::Cell)>> {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let mut row = Vec::new();
+            let mut skip = 0;
+            for x in area.left()..area.right() {
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                let cell = &buffer[(x, y)];
+                skip = cell.symbol().width().saturating_sub(1);
+                row.push((x - area.left(), cell));
+            }
+            row
+        })
+        .collect()
+}
+
+/// Plain-text dump of the buffer, one line per row.
+pub fn buffer_to_text(buffer: &Buffer) -> String {
+    let mut out = String::new();
+    for row in visible_rows(buffer) {
+        let line: String = row.iter().map(|(_, cell)| cell.symbol()).collect();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// SVG rendering of the buffer: one `<text>` per row, with a `<tspan>` per
+/// run of cells sharing a foreground color and weight.
+pub fn buffer_to_svg(buffer: &Buffer) -> String {
+    let width = buffer.area.width * CELL_WIDTH;
+    let height = buffer.area.height * CELL_HEIGHT;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect width="100%" height="100%" fill="{SVG_BACKGROUND}"/>"#
+    );
+    let _ = writeln!(
+        out,
+        r#"<g font-family="monospace" font-size="15" xml:space="preserve">"#
+    );
+    for (y, row) in visible_rows(buffer).into_iter().enumerate() {
+        let baseline = (y as u16 + 1) * CELL_HEIGHT - 4;
+        let mut runs: Vec<(u16, Color, bool, String)> = Vec::new();
+        for (x, cell) in row {
+            let bold = cell.modifier.contains(Modifier::BOLD);
+            match runs.last_mut() {
+                Some((_, fg, b, text)) if *fg == cell.fg && *b == bold => {
+                    text.push_str(cell.symbol());
+                }
+                _ => runs.push((x, cell.fg, bold, cell.symbol().to_string())),
+            }
+        }
+        let _ = write!(out, r#"<text y="{baseline}">"#);
+        for (x, fg, bold, text) in runs {
+            if text.trim().is_empty() {
+                continue;
+            }
+            let weight = if bold { r#" font-weight="bold""# } else { "" };
+            let _ = write!(
+                out,
+                r#"<tspan x="{}" fill="{}"{weight}>{}</tspan>"#,
+                x * CELL_WIDTH,
+                svg_color(fg),
+                escape_xml(&text)
+            );
+        }
+        out.push_str("</text>\n");
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The 16 base colors of the xterm palette, in ANSI index order.
+const ANSI_16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Hex color for a terminal color, using the xterm palette.
+fn svg_color(color: Color) -> String {
+    let (r, g, b) = match color {
+        Color::Reset => return SVG_FOREGROUND.to_string(),
+        Color::Black => ANSI_16[0],
+        Color::Red => ANSI_16[1],
+        Color::Green => ANSI_16[2],
+        Color::Yellow => ANSI_16[3],
+        Color::Blue => ANSI_16[4],
+        Color::Magenta => ANSI_16[5],
+        Color::Cyan => ANSI_16[6],
+        Color::Gray => ANSI_16[7],
+        Color::DarkGray => ANSI_16[8],
+        Color::LightRed => ANSI_16[9],
+        Color::LightGreen => ANSI_16[10],
+        Color::LightYellow => ANSI_16[11],
+        Color::LightBlue => ANSI_16[12],
+        Color::LightMagenta => ANSI_16[13],
+        Color::LightCyan => ANSI_16[14],
+        Color::White => ANSI_16[15],
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(i) => indexed_rgb(i),
+    };
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// 256-color index to RGB: base colors, the 6x6x6 cube, then the gray ramp.
+fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match i {
+        0..=15 => ANSI_16[i as usize],
+        16..=231 => {
+            let i = i - 16;
+            (
+                LEVELS[(i / 36) as usize],
+                LEVELS[((i / 6) % 6) as usize],
+                LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let gray = 8 + (i - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Render the office and write it to `dir` as
+/// `pixel-agents-<unix timestamp>.<ext>`, or `-2`, `-3`... after the
+/// timestamp when exports in the same second already took that name.
+/// Returns the written path.
+pub fn export_office(
+    app: &App,
+    frame_area: Rect,
+    format: ExportFormat,
+    dir: &Path,
+    now: SystemTime,
+) -> io::Result<PathBuf> {
+    let buffer = render_office_buffer(app, frame_area);
+    let contents = match format {
+        ExportFormat::Text => buffer_to_text(&buffer),
+        ExportFormat::Svg => buffer_to_svg(&buffer),
+    };
+    let stamp = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    fs::create_dir_all(dir)?;
+    for n in 1u32.. {
+        let suffix = if n == 1 {
+            String::new()
+        } else {
+            format!("-{n}")
+        };
+        let path = dir.join(format!(
+            "pixel-agents-{stamp}{suffix}.{}",
+            format.extension()
+        ));
+        // create_new, so two exports can't both pick a free name
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                io::Write::write_all(&mut file, contents.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("ran out of export names")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::state::agent::AgentState;
+
+    fn app_with_agents(n: u32) -> App {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        for id in 1..=n {
+            let path = PathBuf::from(format!("/tmp/{id}.jsonl"));
+            app.agents.insert(id, AgentState::new(id, path));
+        }
+        app.refresh_display_caches();
+        app
+    }
+
+    #[test]
+    fn text_export_round_trips_buffer() {
+        let app = app_with_agents(3);
+        let buffer = render_office_buffer(&app, Rect::new(0, 0, 120, 40));
+        let text = buffer_to_text(&buffer);
+        assert_eq!(text.lines().count(), buffer.area.height as usize);
+
+        // Re-rendering the text into a buffer reproduces every symbol
+        let lines: Vec<&str> = text.lines().collect();
+        let mut rebuilt = Buffer::empty(buffer.area);
+        for (y, line) in lines.iter().enumerate() {
+            rebuilt.set_string(0, y as u16, line, ratatui::style::Style::new());
+        }
+        for y in 0..buffer.area.height {
+            for x in 0..buffer.area.width {
+                assert_eq!(
+                    rebuilt[(x, y)].symbol(),
+                    buffer[(x, y)].symbol(),
+                    "mismatch at ({x}, {y})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn text_export_skips_cells_hidden_by_wide_symbols() {
+        let buffer = Buffer::with_lines(["a\u{1f980}b  "]);
+        assert_eq!(buffer_to_text(&buffer), "a\u{1f980}b\n");
+    }
+
+    #[test]
+    fn svg_is_well_formed_and_labels_agents() {
+        let app = app_with_agents(2);
+        let buffer = render_office_buffer(&app, Rect::new(0, 0, 120, 40));
+        let svg = buffer_to_svg(&buffer);
+        let doc = roxmltree::Document::parse(&svg).expect("well-formed SVG");
+        let text: String = doc
+            .descendants()
+            .filter(|n| n.is_text())
+            .filter_map(|n| n.text())
+            .collect();
+        assert!(text.contains("\u{25c9}1"));
+        assert!(text.contains("\u{25c9}2"));
+    }
+
+    #[test]
+    fn svg_escapes_markup() {
+        let buffer = Buffer::with_lines(["<a & b>"]);
+        let svg = buffer_to_svg(&buffer);
+        assert!(svg.contains("&lt;a &amp; b&gt;"));
+        assert!(roxmltree::Document::parse(&svg).is_ok());
+    }
+
+    #[test]
+    fn indexed_colors_map_to_xterm_palette() {
+        assert_eq!(svg_color(Color::Indexed(1)), "#cd0000");
+        assert_eq!(svg_color(Color::Indexed(196)), "#ff0000");
+        assert_eq!(svg_color(Color::Indexed(232)), "#080808");
+    }
+
+    #[test]
+    fn export_writes_timestamped_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_agents(1);
+        let now = UNIX_EPOCH + Duration::from_secs(1_760_000_000);
+        let path = export_office(
+            &app,
+            Rect::new(0, 0, 100, 30),
+            ExportFormat::Svg,
+            dir.path(),
+            now,
+        )
+        .unwrap();
+        assert_eq!(path, dir.path().join("pixel-agents-1760000000.svg"));
+        assert!(fs::read_to_string(path).unwrap().starts_with("<svg"));
+
+        // More in the same second get their own files
+        for expected in [
+            "pixel-agents-1760000000-2.svg",
+            "pixel-agents-1760000000-3.svg",
+        ] {
+            let path = export_office(
+                &app,
+                Rect::new(0, 0, 100, 30),
+                ExportFormat::Svg,
+                dir.path(),
+                now,
+            )
+            .unwrap();
+            assert_eq!(path, dir.path().join(expected));
+        }
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+}
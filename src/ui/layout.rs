@@ -1,158 +1,922 @@
-use ratatui::layout::{Constraint, Layout, Rect};
-use ratatui::style::{Color, Modifier, Style};
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant, SystemTime};
+
+use ratatui::layout::{Constraint, Layout, Margin, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::symbols::scrollbar;
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{
+    Block, Borders, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    Table,
+};
 use ratatui::Frame;
+use unicode_width::UnicodeWidthStr;
 
-use crate::app::{App, PanelFocus};
+use crate::app::{App, FooterSegment, PanelFocus, SidebarFilter, ToastLevel, ViewMode};
+use crate::config::KeyMap;
+use crate::diff::{DiffView, Sign};
 use crate::state::agent::AgentStatus;
+use crate::state::clock;
+use crate::state::events;
+use crate::state::latency;
 use crate::state::sdd::SddPhase;
-use crate::ui::sprites;
+use crate::state::stats::ToolCounts;
+use crate::state::timer;
+use crate::state::usage;
+use crate::ui::choreography::AgentPosition;
+use crate::ui::office::{self, CellPolicy, GridParams, GridPlan, Section};
+use crate::ui::sprites::{self, AnimState};
+use crate::ui::text::{self, elide_path, format_bytes};
+use crate::ui::theme::Theme;
+
+/// Tools listed on the details panel's "Recent:" row.
+const RECENT_TOOLS_SHOWN: usize = 3;
+
+/// Characters of the latest failure shown on the details panel's "Errors:" row.
+const ERROR_EXCERPT_CHARS: usize = 40;
+
+/// How long each frame of a sprite at its desk shows.
+const DESK_FRAME: Duration = Duration::from_millis(500);
+/// How long each step of a walking sprite shows.
+const WALK_FRAME: Duration = Duration::from_millis(100);
+
+/// Columns a character sprite takes.
+const SPRITE_WIDTH: i32 = 3;
+
+/// Share of the sidebar column the event log takes when shown.
+const EVENT_LOG_PERCENT: u16 = 40;
+
+/// Smallest frame the dashboard lays out; anything smaller gets a notice.
+pub const MIN_FRAME_WIDTH: u16 = 40;
+pub const MIN_FRAME_HEIGHT: u16 = 15;
 
 /// Main render entry point. Splits the frame into header, body (office + sidebar), and footer.
 pub fn render(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    if area.width < MIN_FRAME_WIDTH || area.height < MIN_FRAME_HEIGHT {
+        render_too_small(frame, &app.theme, area);
+        return;
+    }
+    let [header_area, office_area, sidebar_area, footer_area] =
+        split_frame(frame.area(), app.config.office_width_percent);
+
+    render_header(frame, app, header_area);
+    match app.view_mode {
+        ViewMode::Office => {
+            render_office(frame, app, office_area);
+            if app.show_event_log {
+                let [details_area, log_area] = split_sidebar(sidebar_area);
+                render_sidebar(frame, app, details_area);
+                render_event_log(frame, app, log_area);
+            } else {
+                render_sidebar(frame, app, sidebar_area);
+            }
+        }
+        ViewMode::Stats => render_stats(frame, app, office_area.union(sidebar_area)),
+    }
+    render_footer(frame, app, footer_area);
+    if app.show_welcome {
+        render_welcome(frame, app);
+    }
+    if let Some(view) = &app.diff_view {
+        render_diff(frame, app, view);
+    }
+}
+
+/// What a frame too small for the dashboard shows instead: the size it has
+/// and the size it needs, centered.
+fn render_too_small(frame: &mut Frame, theme: &Theme, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            theme.text.add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            format!(
+                "{}x{}, need {MIN_FRAME_WIDTH}x{MIN_FRAME_HEIGHT}",
+                area.width, area.height
+            ),
+            theme.muted,
+        )),
+    ];
+    let area = centered_rect(area, MIN_FRAME_WIDTH, lines.len() as u16);
+    frame.render_widget(
+        Paragraph::new(lines).alignment(ratatui::layout::Alignment::Center),
+        area,
+    );
+}
+
+/// Header, office, sidebar, and footer areas for a frame of the given size,
+/// with `office_percent` of the body width going to the office.
+fn split_frame(area: Rect, office_percent: u16) -> [Rect; 4] {
     let [header_area, body_area, footer_area] = Layout::vertical([
         Constraint::Length(3),
         Constraint::Min(10),
         Constraint::Length(3),
     ])
-    .areas(frame.area());
+    .areas(area);
+
+    let [office_area, sidebar_area] = Layout::horizontal([
+        Constraint::Percentage(office_percent),
+        Constraint::Percentage(100 - office_percent),
+    ])
+    .areas(body_area);
 
-    let [office_area, sidebar_area] =
-        Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
-            .areas(body_area);
+    [header_area, office_area, sidebar_area, footer_area]
+}
 
-    render_header(frame, app, header_area);
-    render_office(frame, app, office_area);
-    render_sidebar(frame, app, sidebar_area);
-    render_footer(frame, app, footer_area);
+/// Text rows of the event log panel in a frame of the given size.
+pub fn event_log_rows(area: Rect, office_percent: u16) -> u16 {
+    let sidebar_area = split_frame(area, office_percent)[2];
+    let [_, log_area] = split_sidebar(sidebar_area);
+    log_area.height.saturating_sub(2)
+}
+
+/// Details and event log areas when the log shares the sidebar column.
+fn split_sidebar(area: Rect) -> [Rect; 2] {
+    Layout::vertical([
+        Constraint::Percentage(100 - EVENT_LOG_PERCENT),
+        Constraint::Percentage(EVENT_LOG_PERCENT),
+    ])
+    .areas(area)
+}
+
+/// Where the office panel sits in a frame of the given size.
+pub fn office_area(area: Rect, office_percent: u16) -> Rect {
+    split_frame(area, office_percent)[1]
 }
 
 /// Render the header bar with title, agent count, and global SDD phase.
 fn render_header(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let agent_count = app.agents.len();
 
-    // Find the most advanced SDD phase across all agents
+    // Each agent's SDD phase, or the one agent's progress
     let sdd_display = global_sdd_display(app);
 
     let title_span = Span::styled(
         " \u{25c9} Pixel Agents TUI ",
-        Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
-    );
-    let count_span = Span::styled(
-        format!("   {agent_count} agents"),
-        Style::new().fg(Color::White),
+        theme.accent.add_modifier(Modifier::BOLD),
     );
-    let sdd_span = Span::styled(format!("   {sdd_display}"), Style::new().fg(Color::Yellow));
+    let count_span = Span::styled(format!("   {agent_count} agents"), theme.text);
+    let sdd_span = Span::styled(format!("   {sdd_display}"), theme.highlight);
 
-    let header_line = Line::from(vec![title_span, count_span, sdd_span]);
+    let mut header_spans = vec![title_span, count_span, sdd_span];
+    // Agents escalated for waiting too long count along with questions
+    let attention = app.attention_count();
+    if attention > 0 {
+        let what = if attention == app.needs_input_count() {
+            "needs input"
+        } else {
+            "needs attention"
+        };
+        header_spans.push(Span::styled(
+            format!("   \u{26a0} {attention} {what}"),
+            theme
+                .status(AgentStatus::NeedsInput)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    let (tokens_in, tokens_out) = app.total_tokens();
+    if tokens_in + tokens_out > 0 {
+        header_spans.push(Span::styled(
+            format!(
+                "   {} in / {} out tokens",
+                usage::format_tokens(tokens_in),
+                usage::format_tokens(tokens_out)
+            ),
+            theme.text,
+        ));
+    }
+    if app.scale_mode() {
+        header_spans.push(Span::styled(
+            "   scale mode",
+            theme.notice.add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(warning) = &app.watcher_warning {
+        header_spans.push(Span::styled(
+            format!("   \u{26a0} {warning}"),
+            theme.highlight,
+        ));
+    }
+    if let Some(skew) = app.clock_skew {
+        header_spans.push(Span::styled(
+            format!("   \u{26a0} clock skew ({})", skew.label()),
+            theme.highlight,
+        ));
+    }
+    if let Some(banner) = app.connection_banner(Instant::now()) {
+        header_spans.push(Span::styled(
+            format!("   \u{26a0} {banner}"),
+            theme.bad.add_modifier(Modifier::BOLD),
+        ));
+    }
+    let header_line = Line::from(header_spans);
     let header = Paragraph::new(header_line).block(
         Block::default()
             .borders(Borders::ALL)
             .title(" pixel-agents-tui ")
-            .title_style(Style::new().fg(Color::Cyan)),
+            .title_style(theme.accent),
     );
 
     frame.render_widget(header, area);
 }
 
+/// The bordered frame every body panel uses. Focus shows in three ways so
+/// it doesn't rest on color alone: accent border, a `▸` before the bold
+/// title, and (see [`dim_unless_focused`]) undimmed content.
+fn panel_block<'a>(title: &str, focused: bool, theme: &Theme) -> Block<'a> {
+    let (title, title_style, border_style) = if focused {
+        (
+            format!(" \u{25b8} {title} "),
+            Style::new().add_modifier(Modifier::BOLD),
+            theme.accent,
+        )
+    } else {
+        (format!(" {title} "), Style::new(), theme.border)
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_style(title_style)
+        .border_style(border_style)
+}
+
+/// Dim an unfocused panel's content once it has been drawn.
+fn dim_unless_focused(frame: &mut Frame, inner: Rect, focused: bool) {
+    if !focused {
+        frame
+            .buffer_mut()
+            .set_style(inner, Style::new().add_modifier(Modifier::DIM));
+    }
+}
+
 /// Render the office view with desks and animated agent characters.
-fn render_office(frame: &mut Frame, app: &App, area: Rect) {
+pub fn render_office(frame: &mut Frame, app: &App, area: Rect) {
     let focused = app.focus == PanelFocus::Office;
-    let border_style = if focused {
-        Style::new().fg(Color::Cyan)
+    let inner = area.inner(Margin::new(1, 1));
+    let plan = office_plan(app, inner);
+    let title = if plan.page_info.count > 1 {
+        format!(
+            "Office (page {}/{})",
+            plan.page_info.page + 1,
+            plan.page_info.count
+        )
     } else {
-        Style::new().fg(Color::DarkGray)
+        "Office".to_string()
     };
+    let block = panel_block(&title, focused, &app.theme);
+    frame.render_widget(block, area);
+    render_office_content(frame, app, inner, &plan);
+    dim_unless_focused(frame, inner, focused);
+}
 
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title(" Office ")
-        .border_style(border_style);
+/// Which frame of an animation stepping every `step` is showing.
+fn anim_frame(app: &App, step: Duration) -> usize {
+    (app.anim_elapsed.as_millis() / step.as_millis()) as usize
+}
 
-    let inner = block.inner(area);
-    frame.render_widget(block, area);
+fn render_office_content(frame: &mut Frame, app: &App, inner: Rect, plan: &GridPlan) {
+    let theme = &app.theme;
+    if app.agents.is_empty() && app.departing.is_empty() {
+        render_empty_state(frame, app, inner);
+        return;
+    }
+
+    for (label, line) in &plan.dividers {
+        let divider = Span::styled(divider_text(label, line.width), theme.muted);
+        frame.render_widget(Paragraph::new(Line::from(divider)), *line);
+    }
+    let frame_idx = anim_frame(app, DESK_FRAME);
+    for &(id, cell) in &plan.desks {
+        render_desk(frame, app, id, cell, frame_idx);
+    }
+    // Walkers cross other desks, so they go on top
+    for &(id, cell) in &plan.desks {
+        render_walker(frame, app, id, cell, inner);
+    }
+    for &(id, cell) in &plan.bench {
+        render_bench_entry(frame, app, id, cell);
+    }
+}
 
-    let ids = app.sorted_agent_ids();
-    let frame_idx = (app.tick_count / 5) as usize; // animate every 5 ticks
+/// Lay out the office for the current page in `inner`.
+fn office_plan(app: &App, inner: Rect) -> GridPlan {
+    let policy = if app.scale_mode() {
+        CellPolicy::Bench
+    } else {
+        CellPolicy::Desks {
+            max_columns: app.config.desks_per_row,
+        }
+    };
+    office::grid_layout(&GridParams {
+        inner,
+        sections: &office_sections(app),
+        policy,
+        page: app.office_page,
+    })
+}
 
-    // Layout: 3 desks per row. Each desk cell is ~10 chars wide, ~6 lines tall.
-    let desks_per_row: usize = 3;
-    let cell_width: u16 = 10;
-    let cell_height: u16 = 6;
+/// The office's inner area in a frame of the given size.
+fn office_inner(app: &App, area: Rect) -> Rect {
+    office_area(area, app.config.office_width_percent).inner(Margin::new(1, 1))
+}
 
-    for (i, &id) in ids.iter().enumerate() {
-        let col = i % desks_per_row;
-        let row = i / desks_per_row;
+/// How many pages of desks the office has in a frame of the given size.
+pub fn office_page_count(app: &App, area: Rect) -> usize {
+    office_plan(app, office_inner(app, area)).page_info.count
+}
 
-        let x = inner.x + (col as u16) * cell_width + 1;
-        let y = inner.y + (row as u16) * cell_height;
+/// The page of the office showing agent `id`'s desk.
+pub fn office_page_of(app: &App, area: Rect, id: u32) -> Option<usize> {
+    office_plan(app, office_inner(app, area))
+        .agent_pages
+        .get(&id)
+        .copied()
+}
 
-        // Check if this desk fits within the inner area
-        if x + cell_width > inner.x + inner.width || y + cell_height > inner.y + inner.height {
-            continue;
-        }
+/// Group the agents for the office. With a known home project its agents
+/// come first, other projects' agents in a second section; otherwise there
+/// is one unlabeled section.
+/// Agents walking out keep their desk until they're gone, unless scale
+/// mode's bench or a filter would hide them anyway.
+pub(crate) fn office_sections(app: &App) -> Vec<Section> {
+    let mut ids = app.filtered_agent_ids();
+    let unfiltered = app.sidebar_filter == SidebarFilter::All && app.search_query.is_empty();
+    if unfiltered && !app.scale_mode() {
+        ids.extend(app.departing.iter().map(|agent| agent.id));
+        ids.sort_unstable();
+        ids.dedup();
+    }
+    let Some(home) = &app.home_project else {
+        return vec![Section { label: None, ids }];
+    };
 
-        let color = sprites::agent_color(id);
-        let anim = app.agent_anim_state(id);
-        let sprite = sprites::sprite_frame(anim, frame_idx);
+    let session_file = |id: &u32| {
+        app.agents.get(id).map(|a| &a.session_file).or_else(|| {
+            app.departing
+                .iter()
+                .find(|agent| agent.id == *id)
+                .map(|agent| &agent.session_file)
+        })
+    };
+    let (home_ids, other_ids): (Vec<u32>, Vec<u32>) = ids
+        .into_iter()
+        .partition(|id| session_file(id).is_some_and(|file| home.contains(file)));
+    vec![
+        Section {
+            label: Some(home.name.clone()),
+            ids: home_ids,
+        },
+        Section {
+            label: Some("other projects".to_string()),
+            ids: other_ids,
+        },
+    ]
+}
 
-        // Render desk (2 lines)
-        let desk = sprites::DESK;
-        for (dy, desk_line) in desk.iter().enumerate() {
-            let desk_span = Span::styled(*desk_line, Style::new().fg(Color::White));
-            let desk_paragraph = Paragraph::new(Line::from(desk_span));
-            let desk_rect = Rect::new(x + 1, y + dy as u16, desk_line.chars().count() as u16, 1);
-            if desk_rect.y < inner.y + inner.height {
-                frame.render_widget(desk_paragraph, desk_rect);
-            }
-        }
+/// `─ label ────` filling exactly `width` columns, truncating the label
+/// with `…` when it doesn't fit.
+pub(crate) fn divider_text(label: &str, width: u16) -> String {
+    let width = width as usize;
+    // "─ " before and " " + at least one "─" after the label
+    if width < 5 {
+        return "\u{2500}".repeat(width);
+    }
+    let room = width - 4;
+    let label: String = if label.chars().count() > room {
+        label
+            .chars()
+            .take(room - 1)
+            .chain(std::iter::once('\u{2026}'))
+            .collect()
+    } else {
+        label.to_string()
+    };
+    let used = label.chars().count() + 3;
+    format!("\u{2500} {label} {}", "\u{2500}".repeat(width - used))
+}
 
-        // Render character sprite (3 lines) below desk
-        for (dy, sprite_line) in sprite.iter().enumerate() {
-            let sprite_span = Span::styled(*sprite_line, Style::new().fg(color));
-            let sprite_paragraph = Paragraph::new(Line::from(sprite_span));
-            let sprite_rect = Rect::new(
-                x + 2,
-                y + 2 + dy as u16,
-                sprite_line.chars().count() as u16,
-                1,
-            );
-            if sprite_rect.y < inner.y + inner.height {
-                frame.render_widget(sprite_paragraph, sprite_rect);
-            }
+/// Render one desk with its character, label, and sub-agent icons.
+/// The caller makes sure the whole `cell` fits.
+fn render_desk(frame: &mut Frame, app: &App, id: u32, cell: Rect, frame_idx: usize) {
+    let theme = &app.theme;
+    let Rect { x, y, .. } = cell;
+    // Nothing drawn for the desk may leave its cell or the frame
+    let cell = cell.intersection(frame.area());
+    let clip = |rect: Rect| rect.intersection(cell);
+    // An agent waiting on an answer stands out in red, blinking
+    let asking = app.agents.get(&id).is_some_and(|a| a.is_asking_user());
+    let color = if asking {
+        theme.status(AgentStatus::NeedsInput)
+    } else {
+        theme.agent_color(id)
+    };
+    let anim = app.agent_anim_state(id);
+    let sprite = sprites::sprite_frame(anim, frame_idx);
+
+    // Render desk (2 lines)
+    for (dy, desk_line) in sprites::DESK.iter().enumerate() {
+        let desk_span = Span::styled(*desk_line, theme.furniture);
+        let desk_rect = clip(Rect::new(
+            x + 1,
+            y + dy as u16,
+            desk_line.chars().count() as u16,
+            1,
+        ));
+        frame.render_widget(Paragraph::new(Line::from(desk_span)), desk_rect);
+    }
+
+    // Render character sprite (3 lines) below desk, unless out walking
+    let sprite: &[&str] = if app.agent_position(id).is_away() {
+        &[]
+    } else {
+        &sprite
+    };
+    for (dy, sprite_line) in sprite.iter().enumerate() {
+        let sprite_span = Span::styled(*sprite_line, color);
+        let sprite_rect = clip(Rect::new(
+            x + 2,
+            y + 2 + dy as u16,
+            sprite_line.chars().count() as u16,
+            1,
+        ));
+        frame.render_widget(Paragraph::new(Line::from(sprite_span)), sprite_rect);
+    }
+
+    // Render agent label below sprite; the selected agent's is marked
+    let label_y = y + 5;
+    let mut label_style = color;
+    if asking {
+        label_style = label_style.add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK);
+    }
+    if app.selected_agent == Some(id) {
+        label_style = label_style.add_modifier(Modifier::REVERSED);
+        frame.render_widget(
+            Paragraph::new(Span::styled("\u{25b8}", theme.accent)),
+            clip(Rect::new(x + 1, label_y, 1, 1)),
+        );
+    }
+    let mut label = vec![Span::styled(format!("\u{25c9}{id}"), label_style)];
+    // Its latest tool just failed
+    if app.agents.get(&id).is_some_and(|a| a.last_result_failed) {
+        label.push(Span::styled("!", theme.bad.add_modifier(Modifier::BOLD)));
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(label)),
+        clip(Rect::new(x + 2, label_y, 4, 1)),
+    );
+
+    // Sub-agent mini-sprites beside the label, one icon per sub-agent
+    if let Some(agent) = app.agents.get(&id) {
+        let icons: Vec<Span> = agent
+            .sub_agents
+            .iter()
+            .map(|sub| {
+                Span::styled(
+                    sprites::sub_agent_icon(&sub.agent_type, app.ascii_icons),
+                    theme.sub_agent_color(&sub.agent_type),
+                )
+            })
+            .collect();
+        if !icons.is_empty() {
+            let icons_rect = clip(Rect::new(x + 6, label_y, cell.width.saturating_sub(6), 1));
+            frame.render_widget(Paragraph::new(Line::from(icons)), icons_rect);
         }
+    }
+}
 
-        // Render agent label below sprite
-        let label_text = format!("\u{25c9}{id}");
-        let label_span = Span::styled(label_text, Style::new().fg(color));
-        let label_paragraph = Paragraph::new(Line::from(label_span));
-        let label_y = y + 5;
-        if label_y < inner.y + inner.height {
-            let label_rect = Rect::new(x + 2, label_y, 4, 1);
-            frame.render_widget(label_paragraph, label_rect);
+/// Render agent `id`'s character on its way between the entrance, at the
+/// left edge of `inner`, and its desk in `cell`. Nothing when seated.
+fn render_walker(frame: &mut Frame, app: &App, id: u32, cell: Rect, inner: Rect) {
+    let desk_x = i32::from(cell.x) + 2;
+    let entrance_x = i32::from(inner.x);
+    let sprite_x = match app.agent_position(id) {
+        AgentPosition::Entering(walked) => {
+            entrance_x + ((desk_x - entrance_x) as f32 * walked) as i32
         }
+        // Out through the entrance, until the whole sprite is past it
+        AgentPosition::Leaving(walked) => {
+            let exit_x = entrance_x - SPRITE_WIDTH;
+            desk_x - ((desk_x - exit_x) as f32 * walked) as i32
+        }
+        AgentPosition::AtDesk | AgentPosition::Stirring => return,
+    };
+    let area = inner.intersection(frame.area());
+    let sprite = sprites::sprite_frame(AnimState::Walking, anim_frame(app, WALK_FRAME));
+    for (dy, sprite_line) in sprite.iter().enumerate() {
+        // Columns left of the entrance aren't drawn
+        let hidden = (entrance_x - sprite_x).max(0);
+        let visible: String = sprite_line.chars().skip(hidden as usize).collect();
+        let rect = Rect::new(
+            sprite_x.max(entrance_x) as u16,
+            cell.y + 2 + dy as u16,
+            visible.chars().count() as u16,
+            1,
+        );
+        frame.render_widget(
+            Paragraph::new(Span::styled(visible, app.theme.agent_color(id))),
+            rect.intersection(area),
+        );
+    }
+}
+
+/// The statistics screen: what each agent's session did so far, one row
+/// per agent, then a row adding them all up. The selected agent's row is
+/// highlighted.
+fn render_stats(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let block = panel_block("Statistics", true, theme);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    if app.agents.is_empty() {
+        render_empty_state(frame, app, inner);
+        return;
+    }
+
+    let mut rows = Vec::new();
+    let mut all_counts = ToolCounts::default();
+    let mut all_turns = 0;
+    let mut all_turn_time = Duration::ZERO;
+    let mut all_files = BTreeSet::new();
+    for id in app.sorted_agent_ids() {
+        let Some(agent) = app.agents.get(&id) else {
+            continue;
+        };
+        let files = agent.touched_file_names();
+        let style = if app.selected_agent == Some(id) {
+            theme.text.add_modifier(Modifier::REVERSED)
+        } else {
+            theme.text
+        };
+        rows.push(
+            stats_row(
+                format!("#{id} {}", agent.project_name),
+                &agent.tool_counts,
+                agent.turns.completed(),
+                agent.turns.total_duration(),
+                &files,
+            )
+            .style(style),
+        );
+        all_counts.add(&agent.tool_counts);
+        all_turns += agent.turns.completed();
+        all_turn_time += agent.turns.total_duration();
+        all_files.extend(files);
+    }
+    rows.push(
+        stats_row(
+            "All".to_string(),
+            &all_counts,
+            all_turns,
+            all_turn_time,
+            &all_files,
+        )
+        .style(theme.text.add_modifier(Modifier::BOLD)),
+    );
+
+    let header = Row::new(STATS_COLUMNS.map(|(title, _)| title))
+        .style(theme.muted.add_modifier(Modifier::BOLD));
+    let table = Table::new(rows, STATS_COLUMNS.map(|(_, width)| width))
+        .header(header)
+        .column_spacing(1);
+    frame.render_widget(table, inner);
+}
+
+/// The statistics table's columns: title and width.
+const STATS_COLUMNS: [(&str, Constraint); 11] = [
+    ("Agent", Constraint::Length(20)),
+    ("Tools", Constraint::Length(5)),
+    ("Read", Constraint::Length(5)),
+    ("Write", Constraint::Length(5)),
+    ("Bash", Constraint::Length(5)),
+    ("Web", Constraint::Length(5)),
+    ("Task", Constraint::Length(5)),
+    ("Other", Constraint::Length(5)),
+    ("Turns", Constraint::Length(5)),
+    ("Turn time", Constraint::Length(9)),
+    ("Files", Constraint::Fill(1)),
+];
+
+/// One line of the statistics table; `files` lists file names, counted
+/// first.
+fn stats_row<'a>(
+    label: String,
+    counts: &ToolCounts,
+    turns: u32,
+    turn_time: Duration,
+    files: &BTreeSet<&str>,
+) -> Row<'a> {
+    let names: Vec<&str> = files.iter().copied().collect();
+    let files = if names.is_empty() {
+        "0".to_string()
+    } else {
+        format!("{}: {}", names.len(), names.join(", "))
+    };
+    Row::new([
+        Cell::from(label),
+        Cell::from(counts.total().to_string()),
+        Cell::from(counts.reads.to_string()),
+        Cell::from(counts.writes.to_string()),
+        Cell::from(counts.bash.to_string()),
+        Cell::from(counts.web.to_string()),
+        Cell::from(counts.task.to_string()),
+        Cell::from(counts.other.to_string()),
+        Cell::from(turns.to_string()),
+        Cell::from(timer::format_duration_short(turn_time)),
+        Cell::from(files),
+    ])
+}
+
+/// Shown in the office when no sessions are active: lists the watched roots
+/// so relocated or misconfigured Claude directories are easy to spot.
+fn render_empty_state(frame: &mut Frame, app: &App, inner: Rect) {
+    let theme = &app.theme;
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "No active sessions",
+            theme.text.add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    // Paths are indented by two columns
+    let path_width = (inner.width as usize).saturating_sub(2);
+    if let Some(socket) = app.remote_socket() {
+        lines.push(Line::from(Span::styled("Mirroring:", theme.muted)));
+        lines.push(Line::from(Span::styled(
+            format!("  {}", elide_path(&socket.to_string_lossy(), path_width)),
+            theme.text,
+        )));
+        frame.render_widget(Paragraph::new(lines), inner);
+        return;
+    }
+    lines.push(Line::from(Span::styled("Watching:", theme.muted)));
+    for (i, root) in app.watched_roots().into_iter().enumerate() {
+        let note = if i == 0 { "" } else { " (discovered)" };
+        let projects = root.join("projects");
+        let shown = elide_path(
+            &projects.to_string_lossy(),
+            path_width.saturating_sub(note.len()),
+        );
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {shown}"), theme.text),
+            Span::styled(note, theme.muted),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// One agent on the dense, static bench of scale mode: its status glyph
+/// and id, colored by status; no animation.
+fn render_bench_entry(frame: &mut Frame, app: &App, id: u32, cell: Rect) {
+    let theme = &app.theme;
+    let Some(agent) = app.agents.get(&id) else {
+        return;
+    };
+    let text = Span::styled(
+        format!("{}{id}", agent.status.symbol()),
+        theme.status(agent.status),
+    );
+    frame.render_widget(Paragraph::new(Line::from(text)), cell);
+}
+
+/// `Agent Details`, followed by the filter and search narrowing the list.
+fn sidebar_title(app: &App) -> String {
+    let mut narrowed = Vec::new();
+    if app.sidebar_filter != SidebarFilter::All {
+        narrowed.push(format!("filter: {}", app.sidebar_filter.label()));
+    }
+    if !app.search_query.is_empty() {
+        narrowed.push(format!("search: \"{}\"", app.search_query));
+    }
+    if narrowed.is_empty() {
+        "Agent Details".to_string()
+    } else {
+        format!("Agent Details ({})", narrowed.join(", "))
     }
 }
 
 /// Render the sidebar with a scrollable agent detail list.
 fn render_sidebar(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let focused = app.focus == PanelFocus::Sidebar;
-    let border_style = if focused {
-        Style::new().fg(Color::Cyan)
-    } else {
-        Style::new().fg(Color::DarkGray)
-    };
+    let block = panel_block(&sidebar_title(app), focused, &app.theme);
 
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title(" Agent Details ")
-        .border_style(border_style);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let lines = sidebar_lines(app, inner.width);
+    let lines_len = lines.len();
+    let view = SidebarView::new(
+        lines_len,
+        inner.height as usize,
+        app.sidebar_scroll as usize,
+    );
+
+    let hint = |text: String| Line::styled(text, theme.muted);
+    let mut visible_lines = Vec::with_capacity(inner.height as usize);
+    if view.above > 0 {
+        visible_lines.push(hint(format!("\u{2191} {} more", view.above)));
+    }
+    visible_lines.extend(lines.into_iter().skip(view.scroll).take(view.rows));
+    if view.below > 0 {
+        visible_lines.push(hint(format!("\u{2193} {} more", view.below)));
+    }
+
+    let paragraph = Paragraph::new(visible_lines);
+    frame.render_widget(paragraph, inner);
+    dim_unless_focused(frame, inner, focused);
+
+    // Where the view is, on the right border
+    if view.above + view.below > 0 {
+        let max_scroll = SidebarView::max_scroll(lines_len, inner.height as usize);
+        let mut state = ScrollbarState::new(max_scroll + 1)
+            .position(view.scroll)
+            .viewport_content_length(view.rows);
+        let symbols = scrollbar::Set {
+            thumb: if app.ascii_icons { "#" } else { "\u{2588}" },
+            ..scrollbar::VERTICAL
+        };
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .symbols(symbols)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(if focused { theme.accent } else { theme.border })
+            .thumb_style(theme.text);
+        frame.render_stateful_widget(scrollbar, area.inner(Margin::new(0, 1)), &mut state);
+    }
+}
+
+/// What a scrolled sidebar shows: which lines, and how many are hidden on
+/// either side. Hidden lines are counted on a row of their own, taken from
+/// the content, so the last line is still reachable with both showing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SidebarView {
+    /// First content line shown, after capping the requested scroll.
+    pub scroll: usize,
+    /// Content lines shown.
+    pub rows: usize,
+    pub above: usize,
+    pub below: usize,
+}
 
+impl SidebarView {
+    /// Below this many rows there's no room for indicators.
+    const MIN_HEIGHT_FOR_HINTS: usize = 3;
+
+    pub(crate) fn new(total: usize, height: usize, scroll: usize) -> Self {
+        if total <= height || height < Self::MIN_HEIGHT_FOR_HINTS {
+            let scroll = scroll.min(total.saturating_sub(height));
+            let rows = height.min(total - scroll);
+            return Self {
+                scroll,
+                rows,
+                above: 0,
+                below: 0,
+            };
+        }
+        // At the bottom the top indicator takes a row
+        let scroll = scroll.min(Self::max_scroll(total, height));
+        let mut rows = height - usize::from(scroll > 0);
+        if scroll + rows < total {
+            rows -= 1;
+        }
+        Self {
+            scroll,
+            rows,
+            above: scroll,
+            below: total - scroll - rows,
+        }
+    }
+
+    /// The furthest scroll that still shows something new.
+    pub(crate) fn max_scroll(total: usize, height: usize) -> usize {
+        if total <= height || height < Self::MIN_HEIGHT_FOR_HINTS {
+            total.saturating_sub(height)
+        } else {
+            total - (height - 1)
+        }
+    }
+}
+
+/// How much the sidebar has to show and the rows it has to show it in,
+/// for scrolling it without rendering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SidebarExtent {
+    /// Lines for the current agent set, the selected agent's details
+    /// included.
+    pub lines: usize,
+    /// Rows inside the panel's borders.
+    pub height: usize,
+}
+
+impl SidebarExtent {
+    pub fn max_scroll(&self) -> usize {
+        SidebarView::max_scroll(self.lines, self.height)
+    }
+
+    /// Lines a page up or down moves: what a scrolled view shows between
+    /// its two hidden-line rows.
+    pub fn page(&self) -> usize {
+        self.height.saturating_sub(2).max(1)
+    }
+
+    /// The scroll nearest `scroll` that shows `line`.
+    pub fn scroll_showing(&self, line: usize, scroll: usize) -> usize {
+        let view = SidebarView::new(self.lines, self.height, scroll);
+        if line < view.scroll {
+            return line;
+        }
+        (view.scroll..=self.max_scroll())
+            .find(|&scroll| {
+                let view = SidebarView::new(self.lines, self.height, scroll);
+                line < view.scroll + view.rows
+            })
+            .unwrap_or(view.scroll)
+    }
+}
+
+/// The sidebar's extent in a frame of the given size.
+pub fn sidebar_extent(app: &App, area: Rect) -> SidebarExtent {
+    let mut sidebar_area = split_frame(area, app.config.office_width_percent)[2];
+    if app.show_event_log {
+        sidebar_area = split_sidebar(sidebar_area)[0];
+    }
+    let inner = sidebar_area.inner(Margin::new(1, 1));
+    SidebarExtent {
+        lines: sidebar_lines(app, inner.width).len(),
+        height: inner.height as usize,
+    }
+}
+
+/// The furthest the sidebar scrolls in a frame of the given size.
+pub fn sidebar_max_scroll(app: &App, area: Rect) -> u16 {
+    sidebar_extent(app, area).max_scroll() as u16
+}
+
+/// Render the selected agent's recent events, newest at the bottom.
+/// `event_log_scroll` counts lines back from the newest.
+fn render_event_log(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let focused = app.focus == PanelFocus::EventLog;
+    let agent = app.selected_agent.and_then(|id| app.agents.get(&id));
+    let title = match agent {
+        Some(agent) => format!("Events: Agent #{}", agent.id),
+        None => "Events".to_string(),
+    };
+    let mut block = panel_block(&title, focused, &app.theme);
+    if let Some(input) = &app.time_input {
+        block = block.title_bottom(Line::styled(
+            format!(" jump to (UTC): {input}_ "),
+            theme.highlight,
+        ));
+    }
     let inner = block.inner(area);
     frame.render_widget(block, area);
+    render_event_log_content(frame, app, inner);
+    dim_unless_focused(frame, inner, focused);
+}
+
+fn render_event_log_content(frame: &mut Frame, app: &App, inner: Rect) {
+    let theme = &app.theme;
+    let agent = app.selected_agent.and_then(|id| app.agents.get(&id));
+
+    let Some(agent) = agent else {
+        let hint = Line::styled("Select an agent to see its events", theme.muted);
+        frame.render_widget(Paragraph::new(hint), inner);
+        return;
+    };
+
+    let now = SystemTime::now();
+    let lines: Vec<Line> = agent
+        .log
+        .entries()
+        .map(|event| {
+            let age = clock::clamped_age(now, event.at);
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>4} ", timer::format_duration_short(age)),
+                    theme.muted,
+                ),
+                Span::styled(format!("{:<5}", event.kind.label()), theme.accent),
+                Span::raw(event.text.as_str()),
+            ])
+        })
+        .collect();
+
+    let height = inner.height as usize;
+    let top = events::view_top(lines.len(), height, app.event_log_scroll as usize);
+    let visible: Vec<Line> = lines.into_iter().skip(top).take(height).collect();
+    frame.render_widget(Paragraph::new(visible), inner);
+}
 
-    let ids = app.sorted_agent_ids();
+/// Build every sidebar line for the current agent set (before scrolling).
+/// In scale mode each agent gets exactly one row and nothing auto-expands.
+pub fn sidebar_lines(app: &App, width: u16) -> Vec<Line<'_>> {
+    let theme = &app.theme;
+    let ids = app.filtered_agent_ids();
+    let scale_mode = app.scale_mode();
     let mut lines: Vec<Line> = Vec::new();
 
     for &id in &ids {
@@ -162,165 +926,1949 @@ fn render_sidebar(frame: &mut Frame, app: &App, area: Rect) {
         };
 
         let is_selected = app.selected_agent == Some(id);
-        let color = sprites::agent_color(id);
-        let status_symbol = agent.status.symbol();
-        let status_label = agent.status.label();
+        let display = agent.display();
+        let color = theme.agent_color(id);
 
         // Agent header line
         let marker = if is_selected { "\u{25b8} " } else { "  " };
         let header_style = if is_selected {
-            Style::new().fg(color).add_modifier(Modifier::BOLD)
+            color.add_modifier(Modifier::BOLD)
         } else {
-            Style::new().fg(color)
-        };
-        let status_color = match agent.status {
-            AgentStatus::Active => Color::Green,
-            AgentStatus::Waiting => Color::Yellow,
-            AgentStatus::Dormant => Color::DarkGray,
+            color
         };
+        let rule = app.current_rule(id);
+        let rule_color = rule.and_then(|(_, rule)| rule.color);
+        let mut status_style =
+            rule_color.map_or_else(|| theme.status(agent.status), |c| theme.custom(c));
+        if agent.status == AgentStatus::NeedsInput {
+            status_style = status_style.add_modifier(Modifier::BOLD);
+        }
+        let escalation = theme.escalation(agent.escalation);
+        if let Some(style) = escalation {
+            status_style = style;
+        }
 
-        lines.push(Line::from(vec![
+        let mut header = vec![
             Span::styled(marker, header_style),
-            Span::styled(format!("Agent #{id} "), header_style),
-            Span::styled("[", Style::new().fg(Color::White)),
-            Span::styled(
-                format!("{status_symbol} {status_label}"),
-                Style::new().fg(status_color),
-            ),
-            Span::styled("]", Style::new().fg(Color::White)),
-        ]));
+            Span::styled(display.header.as_str(), header_style),
+            Span::styled("[", theme.text),
+            Span::styled(display.status_text.as_str(), status_style),
+            Span::styled("]", theme.text),
+        ];
+        let now = Instant::now();
+        if let Some(style) = escalation {
+            let waited = agent.status_timer.time_in_status(now);
+            header.push(Span::styled(
+                format!(" for {}", timer::format_duration_short(waited)),
+                style,
+            ));
+        }
+        if let Some(rate) = app.growth_rate(id, now).filter(|r| *r >= 1.0) {
+            header.push(Span::styled(
+                format!(" ~{}/min", format_bytes(rate)),
+                theme.muted,
+            ));
+        }
+        if app.looks_stalled(id, now) {
+            header.push(Span::styled(" stalled?", theme.highlight));
+        }
+        if !is_selected && app.is_unseen(id) {
+            header.push(Span::styled(" \u{2022}new", theme.muted));
+        }
+        lines.push(Line::from(header));
 
         // Expanded details for selected agent
-        if is_selected {
-            // Current tool
-            if let Some(tool_display) = agent.current_tool_display() {
-                let truncated: String = tool_display.chars().take(40).collect();
-                lines.push(Line::from(vec![
-                    Span::styled("   Tool: ", Style::new().fg(Color::DarkGray)),
-                    Span::styled(truncated, Style::new().fg(Color::White)),
-                ]));
+        if is_selected && !scale_mode {
+            let label = theme.muted;
+            let value = theme.text;
+            let mut details: Vec<Vec<Span>> = Vec::new();
+
+            // Current tool, with how long it has been running, colored by
+            // its category unless a rule picked a color
+            if let Some(tool) = agent.active_tools.last() {
+                let truncated: String = tool.display_status.chars().take(40).collect();
+                let style = rule_color
+                    .map_or_else(|| theme.tool_category(tool.category()), |c| theme.custom(c));
+                let mut spans = vec![
+                    Span::styled("Tool: ", label),
+                    Span::styled(truncated, style),
+                ];
+                if let Some(elapsed) = agent.current_tool_elapsed(now) {
+                    spans.push(Span::styled(
+                        format!(" ({})", timer::format_duration_short(elapsed)),
+                        theme.muted,
+                    ));
+                }
+                details.push(spans);
             }
 
-            // Prompt summary
-            if !agent.prompt_summary.is_empty() {
-                let prompt: String = agent.prompt_summary.chars().take(35).collect();
-                lines.push(Line::from(vec![
-                    Span::styled("   Prompt: ", Style::new().fg(Color::DarkGray)),
-                    Span::styled(format!("\"{prompt}...\""), Style::new().fg(Color::White)),
-                ]));
+            // The status rule that classified the current tool
+            if let Some((index, rule)) = rule {
+                details.push(vec![
+                    Span::styled("Rule: ", label),
+                    Span::styled(format!("#{} {}", index + 1, rule.matcher.describe()), value),
+                ]);
             }
 
-            // SDD phase
+            // What the user last asked, or else how the model opened
+            if !display.request_line.is_empty() {
+                details.push(vec![
+                    Span::styled("Ask: ", label),
+                    Span::styled(format!("\"{}...\"", display.request_line), value),
+                ]);
+            } else if !display.summary_line.is_empty() {
+                details.push(vec![
+                    Span::styled("Prompt: ", label),
+                    Span::styled(format!("\"{}...\"", display.summary_line), value),
+                ]);
+            }
+            if agent.queued_prompts > 0 {
+                details.push(vec![
+                    Span::styled("Queued: ", label),
+                    Span::styled(agent.queued_prompts.to_string(), theme.highlight),
+                ]);
+            }
+
+            // SDD phase, and the way there when it wasn't straight
             if let Some(ref phase) = agent.sdd_phase {
                 let phase_display = format!(
-                    "{} ({}/{})",
+                    "{} {} ({}/{})",
+                    phase.progress_bar(app.ascii_icons),
                     phase.label(),
                     phase.index() + 1,
                     SddPhase::total()
                 );
-                lines.push(Line::from(vec![
-                    Span::styled("   SDD: ", Style::new().fg(Color::DarkGray)),
-                    Span::styled(phase_display, Style::new().fg(Color::Yellow)),
-                ]));
+                details.push(vec![
+                    Span::styled("SDD: ", label),
+                    Span::styled(phase_display, theme.highlight),
+                ]);
+            }
+            let history = &agent.sdd_history;
+            if history.transitions().len() > 1 {
+                let arrow = if app.ascii_icons { " > " } else { " \u{203a} " };
+                let mut spans = vec![Span::styled("Phases: ", label)];
+                for (i, (phase, _)) in history.transitions().iter().enumerate() {
+                    if i > 0 {
+                        spans.push(Span::styled(arrow, theme.muted));
+                    }
+                    // Steps back stand out
+                    let style = if history.is_regression(i) {
+                        theme.bad
+                    } else {
+                        value
+                    };
+                    spans.push(Span::styled(phase.label(), style));
+                }
+                details.push(spans);
+            }
+
+            // Time spent per status
+            let time_summary = agent.status_timer.totals(Instant::now()).summary();
+            if !time_summary.is_empty() {
+                details.push(vec![
+                    Span::styled("Time: ", label),
+                    Span::styled(time_summary, value),
+                ]);
+            }
+
+            if let Some(duration) = agent.last_turn_duration() {
+                details.push(vec![
+                    Span::styled("Last turn: ", label),
+                    Span::styled(latency::format_latency(duration), value),
+                ]);
+            }
+
+            // Prompt-to-response latency, flagged when slow
+            if let (Some(last), Some(avg)) = (agent.latency.last(), agent.latency.average()) {
+                let style = if last > latency::SLOW_RESPONSE {
+                    theme.highlight.add_modifier(Modifier::BOLD)
+                } else {
+                    value
+                };
+                details.push(vec![
+                    Span::styled("Latency: ", label),
+                    Span::styled(latency::format_latency(last), style),
+                    Span::styled(format!(" (avg {})", latency::format_latency(avg)), value),
+                ]);
+            }
+
+            // Tokens over the session, with cache reads when there are any
+            if !agent.usage.is_empty() {
+                let usage = &agent.usage;
+                let mut spans = vec![
+                    Span::styled("Tokens: ", label),
+                    Span::styled(
+                        format!(
+                            "{} in / {} out",
+                            usage::format_tokens(usage.total_input_tokens),
+                            usage::format_tokens(usage.total_output_tokens)
+                        ),
+                        value,
+                    ),
+                ];
+                if usage.total_cache_read_tokens > 0 {
+                    spans.push(Span::styled(
+                        format!(
+                            " ({} cached)",
+                            usage::format_tokens(usage.total_cache_read_tokens)
+                        ),
+                        theme.muted,
+                    ));
+                }
+                details.push(spans);
+            }
+
+            // Last few turns, newest first
+            for (i, turn) in agent.turns.recent().enumerate() {
+                let (mark, style) = match (turn.errored, app.ascii_icons) {
+                    (false, false) => ("\u{2713}", theme.good),
+                    (true, false) => ("\u{2717}", theme.bad),
+                    (false, true) => ("ok", theme.good),
+                    (true, true) => ("err", theme.bad),
+                };
+                let heading = if i == 0 { "Turns: " } else { "       " };
+                details.push(vec![
+                    Span::styled(heading, label),
+                    Span::styled(format!("{} \u{b7} ", turn.row()), value),
+                    Span::styled(mark, style),
+                ]);
+            }
+
+            // Session file, for finding it on disk
+            if !agent.session_id.is_empty() {
+                details.push(vec![
+                    Span::styled("Session: ", label),
+                    Span::styled(agent.session_id.as_str(), value),
+                ]);
+            }
+
+            // Distinct tools used this session
+            if !agent.tools_seen.is_empty() {
+                details.push(vec![
+                    Span::styled("Tools used: ", label),
+                    Span::styled(agent.tools_seen.len().to_string(), value),
+                ]);
+            }
+
+            // Latest tools, chunked reads of one file collapsed
+            if !agent.tool_history.is_empty() {
+                let recent: Vec<&str> = agent
+                    .tool_history
+                    .recent()
+                    .take(RECENT_TOOLS_SHOWN)
+                    .map(|e| e.display_status.as_str())
+                    .collect();
+                details.push(vec![
+                    Span::styled("Recent: ", label),
+                    Span::styled(recent.join(" \u{2190} "), value),
+                ]);
+            }
+
+            // Failed tool results, with the latest one's first line
+            if let Some((tool, snippet)) = &agent.last_error {
+                let first_line = snippet
+                    .lines()
+                    .map(str::trim)
+                    .find(|l| !l.is_empty())
+                    .unwrap_or("");
+                let mut excerpt: String = first_line.chars().take(ERROR_EXCERPT_CHARS).collect();
+                if excerpt.len() < first_line.len() {
+                    excerpt.push_str("...");
+                }
+                details.push(vec![
+                    Span::styled("Errors: ", label),
+                    Span::styled(
+                        format!("{} (last: {tool} \u{2014} {excerpt})", agent.error_count),
+                        theme.bad,
+                    ),
+                ]);
+            }
+
+            // Tool events per 5s bucket, squeezed to fit
+            if !agent.activity.is_quiet() {
+                let heading = "Activity (5m): ";
+                let counts: Vec<u32> = agent.activity.counts().collect();
+                let room = (width as usize).saturating_sub(3 + heading.len());
+                details.push(vec![
+                    Span::styled(heading, label),
+                    Span::styled(
+                        text::sparkline(&counts, room, app.ascii_icons),
+                        theme.accent,
+                    ),
+                ]);
             }
 
             // Sub-agents
+            let mut subs: Vec<Vec<Span>> = Vec::new();
             if !agent.sub_agents.is_empty() {
-                lines.push(Line::from(Span::styled(
-                    "   Sub-agents:",
-                    Style::new().fg(Color::DarkGray),
-                )));
+                details.push(vec![Span::styled("Sub-agents:", label)]);
                 for sub in &agent.sub_agents {
-                    let sub_color = sprites::sub_agent_color(id);
+                    let sub_color = theme.sub_agent_color(&sub.agent_type);
+                    let sub_icon = sprites::sub_agent_icon(&sub.agent_type, app.ascii_icons);
                     let sub_tool = sub
                         .active_tools
                         .last()
                         .map(|t| t.display_status.as_str())
                         .unwrap_or(&sub.agent_type);
-                    lines.push(Line::from(vec![
-                        Span::styled("   \u{2514}\u{2500} ", Style::new().fg(Color::DarkGray)),
-                        Span::styled(
-                            format!("{}: {sub_tool}", sub.agent_type),
-                            Style::new().fg(sub_color),
-                        ),
-                    ]));
+                    subs.push(vec![Span::styled(
+                        format!("{sub_icon} {}: {sub_tool}", sub.agent_type),
+                        sub_color,
+                    )]);
                 }
             }
 
+            // Flatten into rows with a gutter linking the parent to its sub-agents
+            let prefixes = tree_prefixes(details.len(), subs.len());
+            let gutter_style = if subs.is_empty() { label } else { color };
+            for (prefix, mut row) in prefixes.into_iter().zip(details.into_iter().chain(subs)) {
+                row.insert(0, Span::styled(prefix, gutter_style));
+                lines.push(Line::from(row));
+            }
+
             // Separator after expanded agent
             lines.push(Line::from(Span::styled(
-                "\u{2500}".repeat(inner.width as usize),
-                Style::new().fg(Color::DarkGray),
+                "\u{2500}".repeat(width as usize),
+                theme.muted,
             )));
         }
     }
 
-    // Apply scroll offset
-    let scroll_offset = app.sidebar_scroll as usize;
-    let visible_lines: Vec<Line> = lines.into_iter().skip(scroll_offset).collect();
-
-    let paragraph = Paragraph::new(visible_lines);
-    frame.render_widget(paragraph, inner);
+    lines
 }
 
-/// Render the footer with keybindings and FPS counter.
-fn render_footer(frame: &mut Frame, _app: &App, area: Rect) {
-    let fps = 10; // Target FPS from the app design
-
-    let keys = vec![
-        Span::styled(" [q]", Style::new().fg(Color::Yellow)),
-        Span::styled("uit  ", Style::new().fg(Color::DarkGray)),
-        Span::styled("[1-9]", Style::new().fg(Color::Yellow)),
-        Span::styled("select  ", Style::new().fg(Color::DarkGray)),
-        Span::styled("[Tab]", Style::new().fg(Color::Yellow)),
-        Span::styled("focus  ", Style::new().fg(Color::DarkGray)),
-        Span::styled("[\u{2191}\u{2193}]", Style::new().fg(Color::Yellow)),
-        Span::styled("scroll", Style::new().fg(Color::DarkGray)),
-    ];
+/// Gutter prefixes for an expanded agent block: one per detail row, then one
+/// per sub-agent row. A continuous `│` gutter runs down the detail rows only
+/// when there are sub-agents to link to; otherwise rows are plainly indented.
+pub(crate) fn tree_prefixes(detail_rows: usize, sub_rows: usize) -> Vec<&'static str> {
+    if sub_rows == 0 {
+        return vec!["   "; detail_rows];
+    }
+    let mut prefixes = vec![" \u{2502} "; detail_rows];
+    prefixes.extend(std::iter::repeat_n(" \u{251c}\u{2500} ", sub_rows - 1));
+    prefixes.push(" \u{2514}\u{2500} ");
+    prefixes
+}
 
-    // Calculate space needed for right-aligned FPS
-    let fps_text = format!("{fps} FPS ");
-    let key_line = Line::from(keys);
+/// Separator between footer segments.
+const SEGMENT_SEPARATOR: &str = " \u{b7} ";
 
-    // We'll put keys on the left and FPS on the right via two separate paragraphs
-    let block = Block::default().borders(Borders::ALL);
-    let inner = block.inner(area);
-    frame.render_widget(block, area);
+/// The segments that fit in `width` columns, still rightmost first. The
+/// lowest priority segments are dropped first; on a tie, the leftmost goes.
+/// A last segment too wide on its own, such as a long toast, is cut short.
+pub fn fit_footer_segments(segments: &[FooterSegment], width: usize) -> Vec<FooterSegment> {
+    let mut kept: Vec<FooterSegment> = segments.to_vec();
+    while kept.len() > 1 && segments_width(&kept) > width {
+        let drop = (0..kept.len())
+            .rev()
+            .min_by_key(|&i| kept[i].priority)
+            .unwrap_or(0);
+        kept.remove(drop);
+    }
+    if let [only] = kept.as_mut_slice() {
+        // Room left after the trailing space
+        only.text = text::elide_right(&only.text, width.saturating_sub(1));
+    }
+    kept.retain(|segment| !segment.text.is_empty());
+    kept
+}
 
-    // Keys on the left
-    let keys_paragraph = Paragraph::new(key_line);
-    frame.render_widget(keys_paragraph, inner);
+/// Columns taken by `segments`, including separators and a trailing space.
+fn segments_width(segments: &[FooterSegment]) -> usize {
+    let text: usize = segments.iter().map(|s| s.text.width()).sum();
+    text + SEGMENT_SEPARATOR.width() * segments.len().saturating_sub(1) + 1
+}
 
-    // FPS on the right
-    let fps_span = Span::styled(fps_text.clone(), Style::new().fg(Color::DarkGray));
-    let fps_paragraph =
-        Paragraph::new(Line::from(fps_span)).alignment(ratatui::layout::Alignment::Right);
-    frame.render_widget(fps_paragraph, inner);
+/// The most important keys and what they do, as currently bound. The
+/// scroll hint names the panel the arrow keys currently move.
+fn key_hints(keys: &KeyMap, focus: PanelFocus) -> [(String, &'static str); 5] {
+    let scroll = match focus {
+        PanelFocus::EventLog => "scroll events",
+        PanelFocus::Office | PanelFocus::Sidebar => "scroll details",
+    };
+    [
+        (keys.quit.to_string(), "quit"),
+        ("1-9".to_string(), "select"),
+        (keys.focus.to_string(), "focus"),
+        (keys.filter.to_string(), "filter"),
+        (format!("{}{}", keys.scroll_up, keys.scroll_down), scroll),
+    ]
 }
 
-/// Determine the global SDD display string from all agents.
-fn global_sdd_display(app: &App) -> String {
-    let mut best_phase: Option<&SddPhase> = None;
-    for agent in app.agents.values() {
-        if let Some(ref phase) = agent.sdd_phase {
-            match best_phase {
-                None => best_phase = Some(phase),
-                Some(current) => {
-                    if phase.index() > current.index() {
-                        best_phase = Some(phase);
-                    }
-                }
-            }
+/// Render the footer: key hints on the left, status segments on the right.
+fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let mut key_spans = vec![Span::raw(" ")];
+    for (i, (key, action)) in key_hints(&app.config.keys, app.focus)
+        .into_iter()
+        .enumerate()
+    {
+        if i > 0 {
+            key_spans.push(Span::raw("  "));
         }
+        key_spans.push(Span::styled(format!("[{key}]"), theme.highlight));
+        key_spans.push(Span::styled(action, theme.muted));
+    }
+    let mut key_line = Line::from(key_spans);
+    // The search being typed and the kill prompt take the key hints'
+    // place until done
+    if let Some(input) = &app.search_input {
+        key_line = Line::from(vec![
+            Span::styled(format!(" /{input}_  "), theme.highlight),
+            Span::styled("[Enter]", theme.highlight),
+            Span::styled(" apply  ", theme.muted),
+            Span::styled("[Esc]", theme.highlight),
+            Span::styled(" cancel", theme.muted),
+        ]);
+    } else if let Some(id) = app.kill_prompt {
+        key_line = Line::from(vec![
+            Span::styled(format!(" Kill Agent #{id}'s session? "), theme.bad),
+            Span::styled("[y]", theme.highlight),
+            Span::styled(" yes  ", theme.muted),
+            Span::styled("[any key]", theme.highlight),
+            Span::styled(" no", theme.muted),
+        ]);
+    } else if app.view_mode == ViewMode::Stats {
+        let keys = &app.config.keys;
+        key_line = Line::from(vec![
+            Span::styled(format!(" [{}]", keys.quit), theme.highlight),
+            Span::styled("quit  ", theme.muted),
+            Span::styled(format!("[{}/Esc]", keys.stats), theme.highlight),
+            Span::styled("back to the office", theme.muted),
+        ]);
+    }
+
+    let block = Block::default().borders(Borders::ALL);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let segments = app.footer_segments(Instant::now());
+    let fitted = fit_footer_segments(&segments, inner.width as usize);
+    let mut spans = Vec::new();
+    for (i, segment) in fitted.iter().rev().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(SEGMENT_SEPARATOR, theme.muted));
+        }
+        let style = match segment.level {
+            Some(ToastLevel::Info) => theme.good,
+            Some(ToastLevel::Warning) => theme.highlight,
+            Some(ToastLevel::Error) => theme.bad,
+            None => theme.muted,
+        };
+        spans.push(Span::styled(segment.text.clone(), style));
+    }
+    spans.push(Span::raw(" "));
+    let right_width = if fitted.is_empty() {
+        0
+    } else {
+        segments_width(&fitted) as u16
+    };
+
+    // Key hints get whatever the segments leave
+    let [keys_area, segments_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(right_width)]).areas(inner);
+    frame.render_widget(Paragraph::new(key_line), keys_area);
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).alignment(ratatui::layout::Alignment::Right),
+        segments_area,
+    );
+}
+
+/// A `width` x `height` rect centered in `area`, shrunk to fit.
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// First-run overlay: what the panels show, what the status symbols mean,
+/// and the keys to get around, as currently bound.
+fn render_welcome(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let label = theme.text.add_modifier(Modifier::BOLD);
+    let text = theme.text;
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Office  ", label),
+            Span::styled("one character per Claude Code session", text),
+        ]),
+        Line::from(vec![
+            Span::styled("Sidebar ", label),
+            Span::styled("each agent's status, tool and details", text),
+        ]),
+        Line::from(vec![
+            Span::styled("Footer  ", label),
+            Span::styled("keys on the left, notices on the right", text),
+        ]),
+        Line::default(),
+    ];
+    let statuses = [
+        AgentStatus::Active,
+        AgentStatus::NeedsInput,
+        AgentStatus::Waiting,
+        AgentStatus::Dormant,
+    ];
+    let mut status_spans = Vec::new();
+    for status in statuses {
+        status_spans.push(Span::styled(
+            format!("{} {}   ", status.symbol(), status.label()),
+            theme.status(status),
+        ));
     }
+    lines.push(Line::from(status_spans));
+    lines.push(Line::default());
+    for (key, action) in key_hints(&app.config.keys, app.focus) {
+        lines.push(Line::from(vec![
+            Span::styled(format!("[{key}]"), theme.highlight),
+            Span::styled(format!(" {action}"), text),
+        ]));
+    }
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(
+        "press any key to start",
+        theme.accent,
+    )));
+
+    let area = centered_rect(frame.area(), 50, lines.len() as u16 + 2);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.accent)
+        .title(" Welcome to Pixel Agents ");
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// What changed since the bookmark: one row per change, green for what
+/// was added or moved forward, red for what went away or back.
+fn render_diff(frame: &mut Frame, app: &App, view: &DiffView) {
+    let theme = &app.theme;
+    let lines: Vec<Line> = if view.changes.is_empty() {
+        vec![Line::from(Span::styled("nothing changed", theme.muted))]
+    } else {
+        view.changes
+            .iter()
+            .map(|change| {
+                let sign = change.sign();
+                let style = match sign {
+                    Sign::Plus => theme.good,
+                    Sign::Minus => theme.bad,
+                    Sign::Changed => theme.highlight,
+                };
+                Line::from(Span::styled(
+                    format!("{} {}", sign.symbol(app.ascii_icons), change.describe()),
+                    style,
+                ))
+            })
+            .collect()
+    };
 
-    match best_phase {
-        Some(phase) => format!(
+    let keys = &app.config.keys;
+    let area = centered_rect(frame.area(), 60, lines.len() as u16 + 2);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.accent)
+        .title(format!(
+            " Since bookmark ({} ago) ",
+            timer::format_duration_short(view.age)
+        ))
+        .title_bottom(format!(
+            " {}{} scroll \u{b7} Esc close \u{b7} {} clear ",
+            keys.scroll_up, keys.scroll_down, keys.bookmark
+        ));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((view.scroll as u16, 0)),
+        area,
+    );
+}
+
+/// SDD progress for the header: `SDD: Apply (6/8)` when one agent has a
+/// phase, `SDD: 1:Apply 2:Spec` when several do.
+fn global_sdd_display(app: &App) -> String {
+    let phases: Vec<(u32, SddPhase)> = app
+        .sorted_agent_ids()
+        .into_iter()
+        .filter_map(|id| Some((id, app.agents.get(&id)?.sdd_phase?)))
+        .collect();
+
+    match phases.as_slice() {
+        [] => String::new(),
+        [(_, phase)] => format!(
             "SDD: {} ({}/{})",
             phase.label(),
             phase.index() + 1,
             SddPhase::total()
         ),
-        None => String::new(),
+        _ => {
+            let per_agent: Vec<String> = phases
+                .iter()
+                .map(|(id, phase)| format!("{id}:{}", phase.label()))
+                .collect();
+            format!("SDD: {}", per_agent.join(" "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use crossterm::event::KeyCode;
+    use ratatui::backend::TestBackend;
+    use ratatui::buffer::Buffer;
+    use ratatui::style::Color;
+    use ratatui::Terminal;
+
+    use crate::app::FooterSegmentKind;
+    use crate::config::Config;
+    use crate::state::agent::AgentState;
+    use crate::state::clock::ClockSkew;
+    use crate::state::project::HomeProject;
+    use crate::state::timer::{StatusTimer, StatusTotals};
+    use crate::ui::sprites::AnimState;
+    use crate::watcher::parser::{is_reading_tool, ToolResultEvent, ToolUseEvent, UsageDelta};
+    use crate::watcher::rules::{RuleMatcher, RuleSpec};
+
+    fn app_with_agents(n: u32) -> App {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        for id in 1..=n {
+            let path = PathBuf::from(format!("/tmp/{id}.jsonl"));
+            app.agents.insert(id, AgentState::new(id, path));
+        }
+        app
+    }
+
+    fn render_to_string(app: &App, width: u16, height: u16) -> String {
+        let buffer = render_to_buffer(app, width, height);
+        let mut out = String::new();
+        for y in 0..buffer.area.height {
+            for x in 0..buffer.area.width {
+                out.push_str(buffer[(x, y)].symbol());
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render_to_buffer(app: &App, width: u16, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| render(frame, app)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// The sidebar's lines as plain text.
+    fn sidebar_text(app: &App, width: u16) -> Vec<String> {
+        sidebar_lines(app, width)
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    /// A tool use as the parser hands it over; tests adjust the rest.
+    fn tool_use(name: &str, path: Option<&str>) -> ToolUseEvent {
+        ToolUseEvent {
+            tool_id: name.to_string(),
+            tool_name: name.to_string(),
+            display_status: String::new(),
+            is_reading: is_reading_tool(name),
+            read_range: None,
+            file_path: path.map(str::to_string),
+            rule: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Top edge of a desk sprite, which only the office draws.
+    const DESK_TOP: &str = "\u{2554}\u{2550}\u{2550}\u{2550}\u{2557}";
+
+    /// Whether the first cell of `needle` on screen is dimmed.
+    fn is_dimmed(buffer: &Buffer, needle: &str) -> bool {
+        let first = needle.chars().next().unwrap().to_string();
+        for y in 0..buffer.area.height {
+            let row: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            if let Some(col) = row.find(needle) {
+                // Byte offset to column: every cell before it is one symbol
+                let x = row[..col].chars().count() as u16;
+                assert_eq!(buffer[(x, y)].symbol(), first);
+                return buffer[(x, y)].modifier.contains(Modifier::DIM);
+            }
+        }
+        panic!("{needle:?} not on screen");
+    }
+
+    #[test]
+    fn focus_shows_in_title_and_dims_the_other_panel() {
+        let mut app = app_with_agents(1);
+        let buffer = render_to_buffer(&app, 100, 30);
+        let screen = render_to_string(&app, 100, 30);
+        assert!(screen.contains("\u{25b8} Agent Details"));
+        assert!(!screen.contains("\u{25b8} Office"));
+        assert!(!is_dimmed(&buffer, "Agent #1"));
+        assert!(is_dimmed(&buffer, DESK_TOP));
+        assert!(screen.contains("scroll details"));
+
+        app.toggle_focus();
+        let buffer = render_to_buffer(&app, 100, 30);
+        let screen = render_to_string(&app, 100, 30);
+        assert!(screen.contains("\u{25b8} Office"));
+        assert!(!screen.contains("\u{25b8} Agent Details"));
+        assert!(is_dimmed(&buffer, "Agent #1"));
+        assert!(!is_dimmed(&buffer, DESK_TOP));
+    }
+
+    #[test]
+    fn footer_names_the_panel_the_arrows_scroll() {
+        let mut app = app_with_agents(1);
+        app.toggle_event_log();
+        app.toggle_focus();
+        assert_eq!(app.focus, PanelFocus::EventLog);
+        assert!(render_to_string(&app, 120, 30).contains("scroll events"));
+    }
+
+    #[test]
+    fn tree_prefixes_without_sub_agents() {
+        assert_eq!(tree_prefixes(3, 0), vec!["   ", "   ", "   "]);
+        assert!(tree_prefixes(0, 0).is_empty());
+    }
+
+    #[test]
+    fn tree_prefixes_with_one_sub_agent() {
+        assert_eq!(
+            tree_prefixes(2, 1),
+            vec![" \u{2502} ", " \u{2502} ", " \u{2514}\u{2500} "]
+        );
+    }
+
+    #[test]
+    fn tree_prefixes_with_three_sub_agents() {
+        assert_eq!(
+            tree_prefixes(2, 3),
+            vec![
+                " \u{2502} ",
+                " \u{2502} ",
+                " \u{251c}\u{2500} ",
+                " \u{251c}\u{2500} ",
+                " \u{2514}\u{2500} ",
+            ]
+        );
+    }
+
+    #[test]
+    fn sub_agent_rows_follow_their_parent() {
+        let mut app = app_with_agents(3);
+        let parent = app.agents.get_mut(&2).unwrap();
+        for i in 0..3 {
+            parent.add_tool(ToolUseEvent {
+                tool_id: format!("task{i}"),
+                display_status: "Subtask: explore".to_string(),
+                ..tool_use("Task", None)
+            });
+        }
+        app.selected_agent = Some(2);
+        app.refresh_display_caches();
+
+        let rendered = sidebar_text(&app, 40);
+        let parent_row = rendered
+            .iter()
+            .position(|l| l.contains("Agent #2"))
+            .unwrap();
+        let next_agent = rendered
+            .iter()
+            .position(|l| l.contains("Agent #3"))
+            .unwrap();
+        let sub_rows: Vec<usize> = rendered
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.contains("task: task"))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(sub_rows.len(), 3);
+        assert!(sub_rows.iter().all(|&i| i > parent_row && i < next_agent));
+        assert!(rendered[sub_rows[2]].starts_with(" \u{2514}\u{2500} "));
+    }
+
+    #[test]
+    fn new_badge_shows_for_unseen_agents_only() {
+        let mut app = app_with_agents(2);
+        app.select_agent(1);
+        app.select_agent(2);
+        let text = render_to_string(&app, 100, 20);
+        assert!(!text.contains("\u{2022}new"));
+
+        app.agents.get_mut(&1).unwrap().last_activity = Instant::now() + Duration::from_secs(1);
+        let text = render_to_string(&app, 100, 20);
+        let badged: Vec<&str> = text.lines().filter(|l| l.contains("\u{2022}new")).collect();
+        assert_eq!(badged.len(), 1);
+        assert!(badged[0].contains("Agent #1"));
+    }
+
+    #[test]
+    fn filter_shows_in_sidebar_title() {
+        let mut app = app_with_agents(1);
+        app.cycle_sidebar_filter();
+        assert!(render_to_string(&app, 100, 20).contains("Agent Details (filter: unseen)"));
+    }
+
+    #[test]
+    fn search_narrows_the_sidebar_and_the_office() {
+        let mut app = app_with_agents(3);
+        for (id, prompt) in [(1, "Fix auth"), (2, "Write docs"), (3, "Auth tokens")] {
+            app.agents.get_mut(&id).unwrap().set_prompt_summary(prompt);
+        }
+        app.open_search_input();
+        for c in "AUTH".chars() {
+            app.search_key(KeyCode::Char(c));
+        }
+        let screen = render_to_string(&app, 120, 30);
+        assert!(screen.contains("/AUTH_  [Enter] apply  [Esc] cancel"));
+        assert!(!screen.contains("[q]quit"));
+
+        app.search_key(KeyCode::Enter);
+        app.toggle_status_filter(SidebarFilter::Waiting);
+        let screen = render_to_string(&app, 120, 30);
+        assert!(screen.contains("Agent Details (filter: waiting, search: \"auth\")"));
+        assert!(screen.contains("[q]quit"));
+        assert!(screen.contains("Agent #1 \u{b7}") && screen.contains("Agent #3 \u{b7}"));
+        assert!(!screen.contains("Agent #2 \u{b7}"));
+        assert!(screen.contains("\u{25c9}3"));
+        assert!(!screen.contains("\u{25c9}2"));
+    }
+
+    #[test]
+    fn footer_shows_toast_then_key_hints() {
+        let mut app = app_with_agents(1);
+        app.reload_config(crate::config::parse("[keys]\nquit = \"Q\""));
+        let text = render_to_string(&app, 100, 20);
+        assert!(text.contains("config reloaded"));
+
+        app.toast = None;
+        let text = render_to_string(&app, 100, 20);
+        assert!(text.contains("[Q]quit"));
+    }
+
+    #[test]
+    fn welcome_overlay_shows_until_first_run_is_recorded() {
+        use crate::persist::{self, PersistedState};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(persist::STATE_FILE_NAME);
+        let mut app = app_with_agents(1);
+        app.reload_config(crate::config::parse("[keys]\nquit = \"Q\""));
+
+        // No state file yet: the overlay is up, with the remapped key
+        app.show_welcome = !persist::load(&path).state.first_run_done;
+        let text = render_to_string(&app, 100, 30);
+        assert!(text.contains("Welcome to Pixel Agents"));
+        assert!(text.contains("[Q] quit"));
+        assert!(text.contains("press any key to start"));
+
+        let state = PersistedState {
+            first_run_done: true,
+            ..PersistedState::default()
+        };
+        persist::save(&path, &state).unwrap();
+        app.show_welcome = !persist::load(&path).state.first_run_done;
+        assert!(!render_to_string(&app, 100, 30).contains("Welcome to Pixel Agents"));
+    }
+
+    #[test]
+    fn event_log_panel_shows_selected_agents_events() {
+        let mut app = app_with_agents(1);
+        app.toggle_event_log();
+        assert!(render_to_string(&app, 100, 30).contains("Select an agent"));
+
+        app.selected_agent = Some(1);
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.log_text("first thought");
+        agent.log_text("second thought");
+        let screen = render_to_string(&app, 100, 30);
+        assert!(screen.contains("Events: Agent #1"));
+        let first = screen.find("first thought").unwrap();
+        let second = screen.find("second thought").unwrap();
+        assert!(first < second, "newest event goes last");
+
+        app.toggle_event_log();
+        assert!(!render_to_string(&app, 100, 30).contains("Events: Agent #1"));
+    }
+
+    #[test]
+    fn office_paginates_desks_that_do_not_fit() {
+        let mut app = app_with_agents(14);
+        app.scale_threshold = 50;
+        // 100x30: the office's inner area is 53x22, so 3 rows of 3 desks
+        let area = Rect::new(0, 0, 100, 30);
+        assert_eq!(office_page_count(&app, area), 2);
+        assert_eq!(office_page_of(&app, area, 9), Some(0));
+        assert_eq!(office_page_of(&app, area, 14), Some(1));
+
+        let screen = render_to_string(&app, 100, 30);
+        assert!(screen.contains("Office (page 1/2)"));
+        assert!(screen.contains("\u{25c9}9"));
+        assert!(!screen.contains("\u{25c9}14"));
+
+        app.next_office_page(2);
+        app.next_office_page(2);
+        let screen = render_to_string(&app, 100, 30);
+        assert!(screen.contains("Office (page 2/2)"));
+        assert!(screen.contains("\u{25c9}14"));
+        assert!(!screen.contains("\u{25c9}9 "));
+
+        // Everything fits on one page: no indicator
+        assert!(render_to_string(&app, 200, 60).contains("Office "));
+        assert!(!render_to_string(&app, 200, 60).contains("page 1"));
+    }
+
+    #[test]
+    fn narrow_office_wraps_desks_instead_of_dropping_them() {
+        let mut app = app_with_agents(3);
+        app.config.desks_per_row = 6;
+        // 40 columns: the office's inner area is 20 wide, room for one desk
+        let screen = render_to_string(&app, 40, 40);
+        for id in 1..=3 {
+            assert!(screen.contains(&format!("\u{25c9}{id}")), "agent {id}");
+        }
+    }
+
+    #[test]
+    fn selected_desk_is_marked() {
+        let mut app = app_with_agents(2);
+        assert!(!render_to_string(&app, 100, 30).contains("\u{25b8}\u{25c9}"));
+        app.select_agent(2);
+        let buffer = render_to_buffer(&app, 100, 30);
+        let screen = render_to_string(&app, 100, 30);
+        assert!(screen.contains("\u{25b8}\u{25c9}2"));
+        let (y, line) = screen
+            .lines()
+            .enumerate()
+            .find(|(_, l)| l.contains("\u{25c9}2"))
+            .unwrap();
+        let x = line[..line.find("\u{25c9}2").unwrap()].chars().count();
+        let cell = &buffer[(x as u16, y as u16)];
+        assert!(cell.modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn sidebar_view_accounts_for_indicator_rows() {
+        // 20 lines in 8 rows
+        assert_eq!(
+            SidebarView::new(20, 8, 0),
+            SidebarView {
+                scroll: 0,
+                rows: 7,
+                above: 0,
+                below: 13
+            }
+        );
+        assert_eq!(
+            SidebarView::new(20, 8, 5),
+            SidebarView {
+                scroll: 5,
+                rows: 6,
+                above: 5,
+                below: 9
+            }
+        );
+        // The last line is reachable, and scrolling further is capped
+        assert_eq!(SidebarView::max_scroll(20, 8), 13);
+        assert_eq!(
+            SidebarView::new(20, 8, 99),
+            SidebarView {
+                scroll: 13,
+                rows: 7,
+                above: 13,
+                below: 0
+            }
+        );
+        // Content that fits never scrolls; tiny views get no indicators
+        assert_eq!(
+            SidebarView::new(5, 8, 3),
+            SidebarView {
+                scroll: 0,
+                rows: 5,
+                above: 0,
+                below: 0
+            }
+        );
+        assert_eq!(
+            SidebarView::new(20, 2, 99),
+            SidebarView {
+                scroll: 18,
+                rows: 2,
+                above: 0,
+                below: 0
+            }
+        );
+    }
+
+    #[test]
+    fn sidebar_scroll_stops_at_the_end_of_the_expanded_details() {
+        let mut app = app_with_agents(12);
+        let area = Rect::new(0, 0, 100, 16);
+        // One more details line than the others get
+        app.agents.get_mut(&1).unwrap().queued_prompts = 2;
+        app.select_agent(1);
+        let long = sidebar_extent(&app, area);
+        app.select_agent(2);
+        let short = sidebar_extent(&app, area);
+        assert_eq!(long.lines, short.lines + 1);
+        app.selected_agent = None;
+        assert_eq!(sidebar_extent(&app, area).lines, 12);
+
+        app.select_agent(1);
+        for _ in 0..long.lines * 2 {
+            app.scroll_down(long, 0);
+        }
+        assert_eq!(app.sidebar_scroll as usize, long.max_scroll());
+        assert_eq!(sidebar_max_scroll(&app, area) as usize, long.max_scroll());
+
+        // Shorter details end sooner, and Up moves away from that end
+        app.select_agent(2);
+        app.scroll_up(short, 0);
+        assert_eq!(app.sidebar_scroll as usize, short.max_scroll() - 1);
+        app.scroll_to_edge(true, short, 0);
+        assert_eq!(app.sidebar_scroll as usize, short.max_scroll());
+    }
+
+    #[test]
+    fn sidebar_scrollbar_follows_the_view() {
+        // The thumb's rows on the sidebar's right border
+        let thumb_rows = |app: &App| -> Vec<u16> {
+            let buffer = render_to_buffer(app, 100, 16);
+            (0..16)
+                .filter(|&y| buffer[(99, y)].symbol() == "\u{2588}")
+                .collect()
+        };
+        assert!(thumb_rows(&app_with_agents(2)).is_empty());
+
+        let mut app = app_with_agents(12);
+        app.select_agent(1);
+        let top = thumb_rows(&app);
+        assert!(!top.is_empty());
+        app.sidebar_scroll = sidebar_max_scroll(&app, Rect::new(0, 0, 100, 16));
+        let bottom = thumb_rows(&app);
+        assert!(bottom[0] > top[0], "{top:?} then {bottom:?}");
+
+        app.ascii_icons = true;
+        let buffer = render_to_buffer(&app, 100, 16);
+        assert!((0..16).any(|y| buffer[(99, y)].symbol() == "#"));
+    }
+
+    #[test]
+    fn sidebar_shows_hidden_line_counts_when_scrolled() {
+        let mut app = app_with_agents(12);
+        app.select_agent(1);
+        let area = Rect::new(0, 0, 100, 16);
+        let inner = split_frame(area, 55)[2].inner(Margin::new(1, 1));
+        let total = sidebar_lines(&app, inner.width).len();
+        let height = inner.height as usize;
+        assert!(total > height + 2, "needs content to scroll");
+        let max = sidebar_max_scroll(&app, area);
+        assert_eq!(max as usize, total - (height - 1));
+
+        let top = render_to_string(&app, 100, 16);
+        // The footer's scroll hint has the arrows too, but not before a space
+        assert!(!top.contains("\u{2191} "));
+        assert!(top.contains(&format!("\u{2193} {} more", total - (height - 1))));
+
+        app.sidebar_scroll = 2;
+        let mid = render_to_string(&app, 100, 16);
+        assert!(mid.contains("\u{2191} 2 more"));
+        assert!(mid.contains(&format!("\u{2193} {} more", total - 2 - (height - 2))));
+
+        app.sidebar_scroll = 200;
+        let bottom = render_to_string(&app, 100, 16);
+        assert!(bottom.contains(&format!("\u{2191} {max} more")));
+        assert!(!bottom.contains("\u{2193} "));
+        assert!(bottom.contains("Agent #12"));
+    }
+
+    #[test]
+    fn office_width_follows_config() {
+        let area = Rect::new(0, 0, 100, 30);
+        assert_eq!(office_area(area, 55).width, 55);
+        assert_eq!(office_area(area, 70).width, 70);
+
+        let mut app = app_with_agents(4);
+        app.apply_config(
+            crate::config::parse("desks_per_row = 2\n[keys]\nfocus = \"enter\"").unwrap(),
+        );
+        let text = render_to_string(&app, 120, 40);
+        assert!(text.contains("[Enter]focus"));
+    }
+
+    /// Agents with sub-agents and a selection, so desks draw everything.
+    fn busy_app() -> App {
+        let mut app = app_with_agents(12);
+        app.selected_agent = Some(1);
+        for agent in app.agents.values_mut() {
+            agent.add_tool(skill("t1", "sdd-apply"));
+            let mut task = skill("t2", "task");
+            task.tool_name = "Task".to_string();
+            agent.add_tool(task);
+        }
+        app
+    }
+
+    #[test]
+    fn renders_common_sizes() {
+        let app = busy_app();
+        let screen = render_to_string(&app, 80, 24);
+        assert!(screen.contains("Office"));
+        assert!(screen.contains("Agent Details"));
+        assert!(!screen.contains("too small"));
+
+        let screen = render_to_string(&app, 40, 15);
+        assert!(screen.contains("Office"));
+        assert!(!screen.contains("too small"));
+
+        let screen = render_to_string(&app, 20, 8);
+        assert!(screen.contains("Terminal too small"));
+        assert!(screen.contains("20x8, need 40x15"));
+        assert!(!screen.contains("Office"));
+    }
+
+    #[test]
+    fn any_size_renders_without_panicking() {
+        let app = busy_app();
+        for width in (0..=130).step_by(7) {
+            for height in (0..=45).step_by(4) {
+                render_to_buffer(&app, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn mono_theme_draws_no_colors() {
+        let mut app = busy_app();
+        app.show_toast("saved".to_string(), ToastLevel::Warning);
+        let colored = render_to_buffer(&app, 100, 30);
+        assert!(colored.content().iter().any(|cell| cell.fg != Color::Reset));
+
+        app.theme = Theme::mono();
+        let buffer = render_to_buffer(&app, 100, 30);
+        for cell in buffer.content() {
+            assert_eq!((cell.fg, cell.bg), (Color::Reset, Color::Reset));
+        }
+        // Focus and the selection still show through modifiers
+        assert!(buffer
+            .content()
+            .iter()
+            .any(|cell| cell.modifier.contains(Modifier::BOLD)));
+    }
+
+    #[test]
+    fn centered_rect_shrinks_to_fit() {
+        let area = Rect::new(10, 5, 40, 10);
+        assert_eq!(centered_rect(area, 20, 4), Rect::new(20, 8, 20, 4));
+        assert_eq!(centered_rect(area, 60, 20), area);
+    }
+
+    fn segment(kind: FooterSegmentKind, text: &str, priority: u8) -> FooterSegment {
+        FooterSegment {
+            kind,
+            text: text.to_string(),
+            priority,
+            level: None,
+        }
+    }
+
+    #[test]
+    fn footer_segments_drop_lowest_priority_first() {
+        // Rightmost first: "config reloaded · scanned 12s ago · tick 3ms · 10 FPS "
+        let segments = [
+            segment(FooterSegmentKind::Fps, "10 FPS", 2),
+            segment(FooterSegmentKind::TickTime, "tick 3ms", 1),
+            segment(FooterSegmentKind::ScanStaleness, "scanned 12s ago", 3),
+            segment(FooterSegmentKind::Toast, "config reloaded", 4),
+        ];
+        let kinds = |width| {
+            fit_footer_segments(&segments, width)
+                .iter()
+                .map(|s| s.kind)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            kinds(60),
+            segments.iter().map(|s| s.kind).collect::<Vec<_>>()
+        );
+        // Too narrow for tick time, still room for FPS
+        assert_eq!(
+            kinds(45),
+            vec![
+                FooterSegmentKind::Fps,
+                FooterSegmentKind::ScanStaleness,
+                FooterSegmentKind::Toast
+            ]
+        );
+        assert_eq!(kinds(16), vec![FooterSegmentKind::Toast]);
+        // A toast wider than the footer is cut rather than dropped
+        let fitted = fit_footer_segments(&segments, 5);
+        assert_eq!(fitted.len(), 1);
+        assert_eq!(fitted[0].text, "con\u{2026}");
+        assert!(kinds(1).is_empty());
+    }
+
+    #[test]
+    fn footer_segments_follow_app_state_and_config() {
+        let mut app = app_with_agents(1);
+        let now = Instant::now();
+        app.tick_interval = Some(Duration::from_millis(100));
+        app.tick_cost = Some(Duration::from_millis(3));
+        app.last_scan_at = Some(now);
+        let texts = |app: &App, now| {
+            app.footer_segments(now)
+                .into_iter()
+                .map(|s| s.text)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(&app, now), vec!["10 FPS", "tick 3ms"]);
+        assert_eq!(
+            texts(&app, now + Duration::from_secs(12)),
+            vec!["10 FPS", "tick 3ms", "scanned 12s ago"]
+        );
+
+        app.apply_config(crate::config::parse("[footer]\nhide = [\"tick_time\"]").unwrap());
+        assert_eq!(texts(&app, now), vec!["10 FPS"]);
+        let screen = render_to_string(&app, 100, 20);
+        assert!(screen.contains("10 FPS"));
+        assert!(!screen.contains("tick "));
+    }
+
+    fn grouped_app() -> App {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        for (id, project) in [(1, "-src-other"), (2, "-src-my-repo"), (3, "-src-my-repo")] {
+            let path = PathBuf::from(format!("/c/projects/{project}/{id}.jsonl"));
+            app.agents.insert(id, AgentState::new(id, path));
+        }
+        app.home_project = Some(HomeProject::from_cwd(std::path::Path::new("/src/my-repo")));
+        app.refresh_display_caches();
+        app
+    }
+
+    #[test]
+    fn office_is_flat_without_home_project() {
+        let app = app_with_agents(4);
+        assert_eq!(
+            office_sections(&app),
+            vec![Section {
+                label: None,
+                ids: vec![1, 2, 3, 4]
+            }]
+        );
+        assert!(!render_to_string(&app, 100, 30).contains("other projects"));
+    }
+
+    #[test]
+    fn office_groups_home_project_first() {
+        let app = grouped_app();
+        assert_eq!(
+            office_sections(&app),
+            vec![
+                Section {
+                    label: Some("my-repo".to_string()),
+                    ids: vec![2, 3]
+                },
+                Section {
+                    label: Some("other projects".to_string()),
+                    ids: vec![1]
+                },
+            ]
+        );
+        let text = render_to_string(&app, 100, 30);
+        let home = text.find("\u{2500} my-repo \u{2500}").unwrap();
+        let other = text.find("\u{2500} other projects \u{2500}").unwrap();
+        assert!(home < other);
+    }
+
+    #[test]
+    fn grouped_office_skips_empty_sections() {
+        let mut app = grouped_app();
+        app.agents.remove(&1);
+        let text = render_to_string(&app, 100, 30);
+        assert!(text.contains("\u{2500} my-repo \u{2500}"));
+        assert!(!text.contains("other projects"));
+    }
+
+    #[test]
+    fn divider_fills_and_clamps_to_width() {
+        assert_eq!(
+            divider_text("repo", 12),
+            "\u{2500} repo \u{2500}\u{2500}\u{2500}\u{2500}\u{2500}"
+        );
+        assert_eq!(divider_text("repo", 12).chars().count(), 12);
+        let clamped = divider_text("a-very-long-project-name", 10);
+        assert_eq!(clamped.chars().count(), 10);
+        assert!(clamped.contains('\u{2026}'));
+        assert_eq!(divider_text("repo", 3), "\u{2500}\u{2500}\u{2500}");
+        assert_eq!(divider_text("repo", 0), "");
+    }
+
+    #[test]
+    fn scale_mode_sidebar_is_one_line_per_agent() {
+        let mut app = app_with_agents(100);
+        app.selected_agent = Some(1);
+        assert!(app.scale_mode());
+        assert_eq!(sidebar_lines(&app, 40).len(), 100);
+    }
+
+    #[test]
+    fn empty_state_lists_discovered_roots() {
+        let mut app = App::new(PathBuf::from("/home/me/.claude"));
+        app.extra_roots.push(PathBuf::from("/data/claude"));
+        let screen = render_to_string(&app, 120, 30);
+        assert!(screen.contains("No active sessions"));
+        assert!(screen.contains("/home/me/.claude/projects"));
+        assert!(screen.contains("/data/claude/projects (discovered)"));
+    }
+
+    #[test]
+    fn empty_state_elides_long_roots_but_keeps_the_tail() {
+        let deep = "/home/me/some/very/deeply/nested/directory/for/claude/data";
+        let app = App::new(PathBuf::from(deep));
+        let screen = render_to_string(&app, 40, 30);
+        assert!(screen.contains("/\u{2026}/data/projects"));
+        assert!(!screen.contains("nested"));
+    }
+
+    #[test]
+    fn sidebar_shows_growth_rate_next_to_status() {
+        use crate::state::growth::GrowthMeter;
+
+        let mut app = app_with_agents(2);
+        let now = Instant::now();
+        let mut meter = GrowthMeter::new(now);
+        meter.record(700, now);
+        app.growth.insert(1, meter);
+        app.growth.insert(2, GrowthMeter::new(now));
+        let lines = sidebar_text(&app, 60);
+        assert!(lines[0].contains("] ~4.1 KB/min"), "{lines:?}");
+        // No growth: nothing shown
+        assert!(!lines[1].contains("/min"));
+    }
+
+    #[test]
+    fn details_show_latency_and_flag_slow_responses() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut app = app_with_agents(2);
+        let at = |ms| Some(UNIX_EPOCH + Duration::from_millis(ms));
+        let quick = &mut app.agents.get_mut(&1).unwrap().latency;
+        quick.prompt(at(0));
+        quick.response(at(2_300));
+        let slow = &mut app.agents.get_mut(&2).unwrap().latency;
+        slow.prompt(at(0));
+        slow.response(at(14_000));
+
+        for (id, text, flagged) in [(1, "2.3s", false), (2, "14.0s", true)] {
+            app.selected_agent = Some(id);
+            let lines = sidebar_lines(&app, 60);
+            let spans = lines
+                .iter()
+                .flat_map(|l| l.spans.iter())
+                .skip_while(|s| s.content != "Latency: ")
+                .collect::<Vec<_>>();
+            assert_eq!(spans[1].content, text);
+            assert_eq!(spans[1].style.fg == Some(Color::Yellow), flagged);
+        }
+    }
+
+    #[test]
+    fn tokens_show_in_details_and_header() {
+        let mut app = app_with_agents(2);
+        for (id, input, output) in [(1, 41_200, 8_300), (2, 800, 200)] {
+            app.agents.get_mut(&id).unwrap().usage.record(UsageDelta {
+                input_tokens: input,
+                output_tokens: output,
+                ..UsageDelta::default()
+            });
+        }
+        app.selected_agent = Some(1);
+        let lines = sidebar_text(&app, 60);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("Tokens: 41.2k in / 8.3k out")));
+        assert!(render_to_string(&app, 120, 30).contains("42.0k in / 8.5k out tokens"));
+    }
+
+    #[test]
+    fn agent_asking_a_question_stands_out() {
+        let mut app = app_with_agents(2);
+        let agent = app.agents.get_mut(&2).unwrap();
+        agent.add_tool(ToolUseEvent {
+            tool_id: "q1".to_string(),
+            display_status: "Waiting for answer".to_string(),
+            ..tool_use("AskUserQuestion", None)
+        });
+        agent.refresh_display();
+        let buffer = render_to_buffer(&app, 120, 30);
+        let screen = render_to_string(&app, 120, 30);
+        assert!(screen.contains("\u{26a0} 1 needs input"));
+        assert!(screen.contains("[\u{26a0} needs input]"));
+        let (y, line) = screen
+            .lines()
+            .enumerate()
+            .find(|(_, l)| l.contains("\u{25c9}2"))
+            .unwrap();
+        let x = line[..line.find("\u{25c9}2").unwrap()].chars().count();
+        let cell = &buffer[(x as u16, y as u16)];
+        assert_eq!(cell.fg, Color::LightRed);
+        assert!(cell.modifier.contains(Modifier::SLOW_BLINK));
+
+        let agent = app.agents.get_mut(&2).unwrap();
+        agent.remove_tool("q1");
+        agent.refresh_display();
+        assert!(!render_to_string(&app, 120, 30).contains("needs input"));
+    }
+
+    #[test]
+    fn selected_agent_shows_its_activity_sparkline() {
+        let mut app = app_with_agents(2);
+        let now = Instant::now();
+        for id in [1, 2] {
+            let activity = &mut app.agents.get_mut(&id).unwrap().activity;
+            activity.record(1, now);
+            activity.record(4, now + Duration::from_secs(5));
+            activity.advance(now + Duration::from_secs(10));
+        }
+        app.select_agent(1);
+        let screen = render_to_string(&app, 120, 30);
+        let row = screen
+            .lines()
+            .find(|line| line.contains("Activity (5m): "))
+            .unwrap();
+        // Buckets summed in pairs to fit, the busiest pair tallest
+        assert!(row.contains("\u{2581}\u{2583}\u{2588} "), "{row}");
+        assert_eq!(screen.matches("Activity (5m)").count(), 1);
+    }
+
+    /// Where the first row of desks starts, and the columns of the
+    /// character bodies on the row below it, seated or walking.
+    fn desks_and_bodies(buffer: &Buffer) -> (Vec<u16>, Vec<u16>) {
+        let symbol = |x: u16, y: u16| buffer[(x, y)].symbol().to_string();
+        let area = buffer.area;
+        let columns = || 1..area.width - 1;
+        let desk_y = (0..area.height)
+            .find(|&y| {
+                columns().any(|x| symbol(x, y) == "\u{2554}" && symbol(x + 1, y) == "\u{2550}")
+            })
+            .unwrap();
+        let desks = columns()
+            .filter(|&x| symbol(x, desk_y) == "\u{2554}" && symbol(x + 1, desk_y) == "\u{2550}")
+            .collect();
+        let bodies = columns()
+            .filter(|&x| {
+                symbol(x - 1, desk_y + 3) == "\u{2554}"
+                    && symbol(x, desk_y + 3) == "\u{2551}"
+                    && symbol(x + 1, desk_y + 3) == "\u{2557}"
+            })
+            .map(|x| x - 1)
+            .collect();
+        (desks, bodies)
+    }
+
+    #[test]
+    fn characters_walk_from_the_entrance_to_their_desk_and_back() {
+        let mut app = app_with_agents(2);
+        for _ in 0..5 {
+            app.office.update(&app.agents, 4);
+        }
+        app.agents
+            .insert(3, AgentState::new(3, PathBuf::from("/tmp/3.jsonl")));
+        let office_x = office_inner(&app, Rect::new(0, 0, 100, 30)).x;
+
+        // Just in through the entrance, at the office's left edge
+        app.office.update(&app.agents, 4);
+        let (desks, bodies) = desks_and_bodies(&render_to_buffer(&app, 100, 30));
+        let [first, second, third] = desks[..] else {
+            panic!("{desks:?}");
+        };
+        assert_eq!(bodies, [office_x, first + 1, second + 1]);
+        // On the way, over the other desks
+        app.office.update(&app.agents, 4);
+        let (_, bodies) = desks_and_bodies(&render_to_buffer(&app, 100, 30));
+        let walker = bodies[0];
+        assert!(walker > office_x && walker < third + 1, "{bodies:?}");
+        // Seated
+        for _ in 0..3 {
+            app.office.update(&app.agents, 4);
+        }
+        let (_, bodies) = desks_and_bodies(&render_to_buffer(&app, 100, 30));
+        assert_eq!(bodies, [first + 1, second + 1, third + 1]);
+
+        // Gone, the desk stays until its agent is out of view
+        app.agents.remove(&3);
+        app.departing = app.office.update(&app.agents, 4);
+        let (desks, bodies) = desks_and_bodies(&render_to_buffer(&app, 100, 30));
+        assert_eq!(desks, [first, second, third]);
+        assert_eq!(bodies, [first + 1, second + 1, third + 1]);
+        app.departing[0].step();
+        app.departing[0].step();
+        app.departing[0].step();
+        // Nearly out, passing the first desk on the way
+        let (_, bodies) = desks_and_bodies(&render_to_buffer(&app, 100, 30));
+        assert!(bodies.len() == 2 && bodies[0] < second, "{bodies:?}");
+        app.departing.clear();
+        let (desks, _) = desks_and_bodies(&render_to_buffer(&app, 100, 30));
+        assert_eq!(desks, [first, second]);
+    }
+
+    #[test]
+    fn stats_screen_tables_each_agent_and_the_total() {
+        let mut app = app_with_agents(2);
+        let agent = app.agents.get_mut(&1).unwrap();
+        for (id, name, file) in [
+            ("t1", "Read", Some("/src/auth.rs")),
+            ("t2", "Edit", Some("/src/auth.rs")),
+            ("t3", "Bash", None),
+        ] {
+            agent.add_tool(ToolUseEvent {
+                tool_id: id.to_string(),
+                ..tool_use(name, file)
+            });
+        }
+        agent.finish_turn(Some(Duration::from_secs(90)));
+        app.agents
+            .get_mut(&2)
+            .unwrap()
+            .add_tool(tool_use("WebFetch", None));
+        app.selected_agent = Some(2);
+
+        app.toggle_stats();
+        let buffer = render_to_buffer(&app, 120, 20);
+        let screen = render_to_string(&app, 120, 20);
+        assert!(screen.contains("Statistics"));
+        assert!(!screen.contains(DESK_TOP));
+        let row = |start: &str| {
+            screen
+                .lines()
+                .find(|line| line.trim_start_matches('\u{2502}').starts_with(start))
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+                .unwrap_or_else(|| panic!("no {start:?} row in:\n{screen}"))
+        };
+        assert!(row("Agent").contains("Tools Read Write Bash Web Task Other Turns Turn time Files"));
+        assert!(row("#1 ").contains(" 3 1 1 1 0 0 0 1 1m 1: auth.rs"));
+        assert!(row("#2 ").contains(" 1 0 0 0 1 0 0 0 0s 0"));
+        assert!(row("All").contains(" 4 1 1 1 1 0 0 1 1m 1: auth.rs"));
+        // The selected agent's row stands out
+        let (x, y) = (0..20)
+            .flat_map(|y| (0..120).map(move |x| (x, y)))
+            .find(|&(x, y)| buffer[(x, y)].symbol() == "#" && buffer[(x + 1, y)].symbol() == "2")
+            .unwrap();
+        assert!(buffer[(x, y)].modifier.contains(Modifier::REVERSED));
+        assert!(screen.contains("back to the office"));
+
+        app.toggle_stats();
+        assert!(render_to_string(&app, 120, 20).contains(DESK_TOP));
+    }
+
+    #[test]
+    fn failed_tools_show_a_count_and_a_desk_marker() {
+        let mut app = app_with_agents(2);
+        let agent = app.agents.get_mut(&1).unwrap();
+        for (id, snippet) in [
+            ("t1", "denied"),
+            ("t2", "\nExit code 1\nerror[E0308]: mismatched types"),
+        ] {
+            agent.add_tool(ToolUseEvent {
+                tool_id: id.to_string(),
+                display_status: "Running: cargo test".to_string(),
+                ..tool_use("Bash", None)
+            });
+            agent.note_tool_result(&ToolResultEvent {
+                tool_id: id.into(),
+                is_error: true,
+                snippet: snippet.into(),
+            });
+            agent.remove_tool(id);
+        }
+        app.select_agent(1);
+        let buffer = render_to_buffer(&app, 120, 30);
+        let screen = render_to_string(&app, 120, 30);
+        let row = screen.lines().find(|l| l.contains("Errors: ")).unwrap();
+        assert!(
+            row.contains("Errors: 2 (last: Bash \u{2014} Exit code 1)"),
+            "{row}"
+        );
+        assert_eq!(screen.matches("Errors: ").count(), 1);
+
+        // The marker follows the label, in red, only for the failing agent
+        assert!(screen.contains("\u{25c9}1!"));
+        assert!(!screen.contains("\u{25c9}2!"));
+        let (x, y) = (0..30)
+            .flat_map(|y| (0..120).map(move |x| (x, y)))
+            .find(|&(x, y)| buffer[(x, y)].symbol() == "1" && buffer[(x + 1, y)].symbol() == "!")
+            .unwrap();
+        assert_eq!(buffer[(x + 1, y)].fg, Color::Red);
+
+        // A success clears the marker but not the count
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.note_tool_result(&ToolResultEvent {
+            tool_id: "t3".into(),
+            is_error: false,
+            snippet: String::new(),
+        });
+        let screen = render_to_string(&app, 120, 30);
+        assert!(!screen.contains("\u{25c9}1!"));
+        assert!(screen.contains("Errors: 2 "));
+
+        // Only a cut excerpt ends in dots
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.note_tool_result(&ToolResultEvent {
+            tool_id: "t4".into(),
+            is_error: true,
+            snippet: format!("{}tail", "e".repeat(ERROR_EXCERPT_CHARS)),
+        });
+        let screen = render_to_string(&app, 200, 30);
+        let cut = format!(
+            "Errors: 3 (last: tool \u{2014} {}...)",
+            "e".repeat(ERROR_EXCERPT_CHARS)
+        );
+        assert!(screen.contains(&cut), "{screen}");
+    }
+
+    #[test]
+    fn kill_prompt_replaces_the_key_hints() {
+        let mut app = app_with_agents(2);
+        app.select_agent(2);
+        app.open_kill_prompt();
+        let screen = render_to_string(&app, 120, 30);
+        assert!(screen.contains("Kill Agent #2's session? [y] yes  [any key] no"));
+        assert!(!screen.contains("[q]quit"));
+    }
+
+    #[test]
+    fn long_waits_escalate_the_row_and_the_header() {
+        let mut app = app_with_agents(3);
+        let now = Instant::now();
+        for (id, mins) in [(1, 6), (2, 16)] {
+            app.agents.get_mut(&id).unwrap().status_timer = StatusTimer::resume(
+                AgentStatus::Waiting,
+                now - Duration::from_secs(mins * 60),
+                StatusTotals::default(),
+            );
+        }
+        app.update_escalations(now);
+        let buffer = render_to_buffer(&app, 120, 30);
+        let screen = render_to_string(&app, 120, 30);
+        assert!(screen.contains("\u{26a0} 2 needs attention"));
+        let style_of = |needle: &str| {
+            let (y, line) = screen
+                .lines()
+                .enumerate()
+                .find(|(_, l)| l.contains(needle))
+                .unwrap();
+            let x = line[..line.find(needle).unwrap()].chars().count();
+            let cell = &buffer[(x as u16 + 1, y as u16)];
+            (cell.fg, cell.modifier.contains(Modifier::BOLD))
+        };
+        assert_eq!(style_of("for 6m"), (Color::Yellow, false));
+        assert_eq!(style_of("for 16m"), (Color::Indexed(208), true));
+        // Agent 3 only just started waiting
+        assert_eq!(screen.matches(" for ").count(), 2);
+
+        app.agents
+            .get_mut(&2)
+            .unwrap()
+            .set_status(AgentStatus::Active);
+        app.agents
+            .get_mut(&1)
+            .unwrap()
+            .set_status(AgentStatus::Active);
+        app.update_escalations(Instant::now());
+        assert!(!render_to_string(&app, 120, 30).contains("\u{26a0}"));
+    }
+
+    #[test]
+    fn details_show_tool_elapsed_and_last_turn() {
+        let mut app = app_with_agents(1);
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.finish_turn(Some(Duration::from_millis(12_400)));
+        agent.add_tool(ToolUseEvent {
+            display_status: "Running: cargo test".to_string(),
+            ..tool_use("Bash", None)
+        });
+        agent.refresh_display();
+        app.selected_agent = Some(1);
+        assert!(sidebar_text(&app, 60)
+            .iter()
+            .any(|l| l.contains("Last turn: 12.4s")));
+        assert!(sidebar_text(&app, 60)
+            .iter()
+            .any(|l| l.contains("Tool: Running: cargo test (0s)")));
+
+        // No new records, but the clock moved on
+        app.agents.get_mut(&1).unwrap().active_tools[0].started_at -= Duration::from_secs(48);
+        assert!(sidebar_text(&app, 60)
+            .iter()
+            .any(|l| l.contains("Tool: Running: cargo test (48s)")));
+    }
+
+    #[test]
+    fn tool_line_is_colored_by_category() {
+        let mut app = app_with_agents(1);
+        app.selected_agent = Some(1);
+        let tool_style = |app: &App, name: &str| {
+            let agent = app.agents.get(&1).unwrap();
+            assert_eq!(agent.active_tools.last().unwrap().tool_name, name);
+            sidebar_lines(app, 60)
+                .iter()
+                .find(|l| l.spans.iter().any(|s| s.content == "Tool: "))
+                .map(|l| l.spans[2].style)
+                .unwrap()
+        };
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.add_tool(ToolUseEvent {
+            display_status: "MCP(github): get issue".to_string(),
+            ..tool_use("mcp__github__get_issue", None)
+        });
+        assert_eq!(
+            tool_style(&app, "mcp__github__get_issue").fg,
+            Some(Color::Magenta)
+        );
+
+        app.agents
+            .get_mut(&1)
+            .unwrap()
+            .add_tool(tool_use("Bash", None));
+        assert_eq!(tool_style(&app, "Bash").fg, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn details_name_the_matching_rule_in_its_color() {
+        let mut app = app_with_agents(1);
+        app.apply_config(Config {
+            rules: vec![RuleSpec {
+                matcher: RuleMatcher::Command("^cargo test".to_string()),
+                label: "Testing".to_string(),
+                anim: AnimState::Testing,
+                color: Some(Color::Magenta),
+            }],
+            ..Config::default()
+        });
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.add_tool(ToolUseEvent {
+            display_status: "Testing".to_string(),
+            rule: Some(0),
+            ..tool_use("Bash", None)
+        });
+        agent.refresh_display();
+        app.selected_agent = Some(1);
+        let lines = sidebar_lines(&app, 60);
+        assert!(sidebar_text(&app, 60)
+            .iter()
+            .any(|l| l.contains("Rule: #1 command /^cargo test/")));
+        let status = lines[0]
+            .spans
+            .iter()
+            .find(|s| s.content.contains("active"))
+            .unwrap();
+        assert_eq!(status.style.fg, Some(Color::Magenta));
+        assert_eq!(app.agent_anim_state(1), AnimState::Testing);
+
+        // Without the rule the agent falls back to the defaults
+        app.apply_config(Config::default());
+        assert!(!sidebar_text(&app, 60).iter().any(|l| l.contains("Rule: ")));
+        assert_eq!(app.agent_anim_state(1), AnimState::Typing);
+    }
+
+    fn skill(id: &str, name: &str) -> ToolUseEvent {
+        ToolUseEvent {
+            tool_id: id.to_string(),
+            display_status: format!("Skill: {name}"),
+            ..tool_use("Skill", None)
+        }
+    }
+
+    #[test]
+    fn details_show_sdd_progress_and_steps_back() {
+        let mut app = app_with_agents(1);
+        app.ascii_icons = true;
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.add_tool(skill("t1", "sdd-design"));
+        app.selected_agent = Some(1);
+        assert!(sidebar_text(&app, 60)
+            .iter()
+            .any(|l| l.contains("SDD: ####---- Design (4/8)")));
+        // A single phase has no trail to show
+        assert!(!sidebar_text(&app, 60)
+            .iter()
+            .any(|l| l.contains("Phases: ")));
+
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.add_tool(skill("t2", "sdd-explore"));
+        let lines = sidebar_lines(&app, 60);
+        let phases = lines
+            .iter()
+            .find(|l| l.spans.iter().any(|s| s.content == "Phases: "))
+            .unwrap();
+        let labels: Vec<&str> = phases.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert!(labels.join("").contains("Phases: Design > Explore"));
+        let explore = phases
+            .spans
+            .iter()
+            .find(|s| s.content == "Explore")
+            .unwrap();
+        assert_eq!(explore.style.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn header_lists_each_agents_phase() {
+        let mut app = app_with_agents(3);
+        app.agents
+            .get_mut(&1)
+            .unwrap()
+            .add_tool(skill("t1", "sdd-apply"));
+        assert_eq!(global_sdd_display(&app), "SDD: Apply (6/8)");
+        app.agents
+            .get_mut(&3)
+            .unwrap()
+            .add_tool(skill("t1", "sdd-spec"));
+        assert_eq!(global_sdd_display(&app), "SDD: 1:Apply 3:Spec");
+        assert_eq!(global_sdd_display(&app_with_agents(2)), "");
+    }
+
+    #[test]
+    fn diff_overlay_colors_each_change() {
+        let mut app = app_with_agents(1);
+        let now = Instant::now();
+        app.toggle_bookmark(now);
+        app.agents.remove(&1);
+        app.agents
+            .insert(2, AgentState::new(2, PathBuf::from("/tmp/2.jsonl")));
+        app.toggle_bookmark(now);
+
+        let buffer = render_to_buffer(&app, 100, 30);
+        let screen = render_to_string(&app, 100, 30);
+        assert!(screen.contains("Since bookmark (0s ago)"));
+        assert!(screen.contains("Esc close"));
+        let color_of = |needle: &str| {
+            let y = screen.lines().position(|l| l.contains(needle)).unwrap() as u16;
+            let x = screen
+                .lines()
+                .nth(y as usize)
+                .unwrap()
+                .split(needle)
+                .next()
+                .unwrap()
+                .chars()
+                .count() as u16;
+            buffer[(x, y)].fg
+        };
+        assert_eq!(color_of("\u{2212} #1 gone"), Color::Red);
+        assert_eq!(color_of("+ #2 new agent"), Color::Green);
+
+        app.diff_view.as_mut().unwrap().changes.clear();
+        assert!(render_to_string(&app, 100, 30).contains("nothing changed"));
+    }
+
+    #[test]
+    fn details_list_recent_turns_newest_first() {
+        let mut app = app_with_agents(1);
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.finish_turn(Some(Duration::from_secs(42)));
+        agent.note_tool_result(&ToolResultEvent {
+            tool_id: "t1".into(),
+            is_error: true,
+            snippet: "Exit code 1".into(),
+        });
+        agent.finish_turn(Some(Duration::from_secs(3)));
+        app.selected_agent = Some(1);
+        let lines = sidebar_text(&app, 60);
+        let first = lines
+            .iter()
+            .position(|l| l.contains("Turns: #2 3s"))
+            .unwrap();
+        assert!(lines[first].ends_with("\u{2717}"));
+        assert!(lines[first + 1].contains("#1 42s \u{b7} 0 tools \u{b7} 0 files \u{b7} \u{2713}"));
+    }
+
+    #[test]
+    fn details_show_the_latest_ask_and_queued_prompts() {
+        let mut app = app_with_agents(1);
+        app.select_agent(1);
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.set_prompt_summary("I'll start by reading the module");
+        agent.refresh_display();
+        let lines = sidebar_text(&app, 60);
+        assert!(lines.contains(&"   Prompt: \"I'll start by reading the module...\"".to_string()));
+        assert!(!lines.iter().any(|l| l.contains("Queued:")));
+
+        let agent = app.agents.get_mut(&1).unwrap();
+        agent.note_prompt("refactor the auth module to use the new session store");
+        agent.set_status(AgentStatus::Active);
+        agent.note_prompt("refactor the auth module, then the tests");
+        agent.note_prompt("refactor the auth module, then the docs");
+        agent.refresh_display();
+        let lines = sidebar_text(&app, 60);
+        assert!(lines.contains(&"   Ask: \"refactor the auth module, then the ...\"".to_string()));
+        assert!(!lines.iter().any(|l| l.contains("Prompt:")));
+        assert!(lines.contains(&"   Queued: 2".to_string()));
+    }
+
+    #[test]
+    fn sidebar_shows_project_and_session_id() {
+        let mut app = App::new(PathBuf::from("/tmp"));
+        let path = PathBuf::from("/c/projects/my-repo/0f3c9a2e-5b1d.jsonl");
+        app.agents.insert(1, AgentState::new(1, path));
+        app.selected_agent = Some(1);
+        app.refresh_display_caches();
+        let lines = sidebar_text(&app, 60);
+        assert!(lines[0].contains("Agent #1 \u{b7} my-repo ["), "{lines:?}");
+        assert!(lines.iter().any(|l| l.contains("Session: 0f3c9a2e-5b1d")));
+    }
+
+    #[test]
+    fn watcher_warning_shows_in_header() {
+        let mut app = app_with_agents(1);
+        app.watcher_warning = Some("another instance (pid 4242) is watching this directory".into());
+        let text = render_to_string(&app, 160, 20);
+        let header = text.lines().nth(1).unwrap();
+        assert!(header.contains("another instance (pid 4242)"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_source_shows_connection_banner() {
+        use crate::app::StateSource;
+        use crate::remote::RemoteClient;
+
+        let mut app = App::new(PathBuf::new());
+        app.source = StateSource::Remote(RemoteClient::connect(PathBuf::from(
+            "/nonexistent/pixel.sock",
+        )));
+        let screen = render_to_string(&app, 160, 20);
+        let header = screen.lines().nth(1).unwrap();
+        assert!(header.contains("connecting to /nonexistent/pixel.sock"));
+        assert!(screen.contains("Mirroring:"));
+        assert!(!screen.contains("Watching:"));
+    }
+
+    #[test]
+    fn clock_skew_shows_in_header() {
+        let mut app = app_with_agents(1);
+        assert!(!render_to_string(&app, 120, 30).contains("clock skew"));
+        app.clock_skew = Some(ClockSkew::Ahead(Duration::from_secs(250)));
+        assert!(render_to_string(&app, 120, 30).contains("\u{26a0} clock skew (+4m)"));
+    }
+
+    #[test]
+    fn scale_mode_shows_in_header() {
+        let app = app_with_agents(100);
+        let screen = render_to_string(&app, 100, 30);
+        assert!(screen.contains("scale mode"));
+
+        let small = app_with_agents(3);
+        let screen = render_to_string(&small, 100, 30);
+        assert!(!screen.contains("scale mode"));
     }
 }
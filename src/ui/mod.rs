@@ -1,2 +1,7 @@
+pub mod choreography;
+pub mod export;
 pub mod layout;
+pub mod office;
 pub mod sprites;
+pub mod text;
+pub mod theme;
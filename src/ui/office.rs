@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+
+use ratatui::layout::Rect;
+
+/// Columns and lines one desk takes in the office grid. Desks start one
+/// column in from the left edge.
+pub const DESK_CELL_WIDTH: u16 = 10;
+pub const DESK_CELL_HEIGHT: u16 = 6;
+/// Columns of one single-line bench entry.
+pub const BENCH_CELL_WIDTH: u16 = 6;
+
+/// A run of agents shown together, under a divider when labeled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub label: Option<String>,
+    pub ids: Vec<u32>,
+}
+
+/// How big each agent's cell is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellPolicy {
+    /// Full desks, at most `max_columns` to a row. Falls back to the bench
+    /// when not even one desk fits.
+    Desks { max_columns: usize },
+    /// One line per agent, as many to a row as fit; sections aren't shown.
+    Bench,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridParams<'a> {
+    pub inner: Rect,
+    pub sections: &'a [Section],
+    pub policy: CellPolicy,
+    /// Requested page; clamped to the pages there are.
+    pub page: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageInfo {
+    pub page: usize,
+    pub count: usize,
+}
+
+/// Where everything in the office goes for one page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridPlan {
+    /// Desk cells on the shown page.
+    pub desks: Vec<(u32, Rect)>,
+    /// Bench entries on the shown page.
+    pub bench: Vec<(u32, Rect)>,
+    /// Section dividers on the shown page, one line each.
+    pub dividers: Vec<(String, Rect)>,
+    pub page_info: PageInfo,
+    /// The page every agent is on.
+    pub agent_pages: BTreeMap<u32, usize>,
+}
+
+/// One line or row of cells before pagination.
+enum Row<'a> {
+    Divider(&'a str),
+    Cells(&'a [u32]),
+}
+
+/// Lay out the office: cells for the agents of `params.sections`, in order,
+/// split into pages that fit `params.inner`. Nothing is placed when the
+/// area can't hold a single bench entry.
+pub fn grid_layout(params: &GridParams) -> GridPlan {
+    let inner = params.inner;
+    let desk_columns = (inner.width.saturating_sub(1) / DESK_CELL_WIDTH) as usize;
+    let desks_fit = desk_columns > 0 && inner.height >= DESK_CELL_HEIGHT;
+    let (columns, cell_width, cell_height, left, bench) = match params.policy {
+        CellPolicy::Desks { max_columns } if desks_fit => (
+            desk_columns.min(max_columns.max(1)),
+            DESK_CELL_WIDTH,
+            DESK_CELL_HEIGHT,
+            1,
+            false,
+        ),
+        _ => (
+            (inner.width / BENCH_CELL_WIDTH) as usize,
+            BENCH_CELL_WIDTH,
+            1,
+            0,
+            true,
+        ),
+    };
+
+    let mut plan = GridPlan {
+        desks: Vec::new(),
+        bench: Vec::new(),
+        dividers: Vec::new(),
+        page_info: PageInfo { page: 0, count: 1 },
+        agent_pages: BTreeMap::new(),
+    };
+    if columns == 0 || inner.height == 0 {
+        return plan;
+    }
+
+    // The bench is one flat run; desks keep their sections
+    let flat: Vec<u32>;
+    let mut rows = Vec::new();
+    if bench {
+        flat = params.sections.iter().flat_map(|s| s.ids.clone()).collect();
+        rows.extend(flat.chunks(columns).map(Row::Cells));
+    } else {
+        for section in params.sections.iter().filter(|s| !s.ids.is_empty()) {
+            if let Some(label) = &section.label {
+                rows.push(Row::Divider(label));
+            }
+            rows.extend(section.ids.chunks(columns).map(Row::Cells));
+        }
+    }
+
+    // Pages are filled top-down; a divider moves on with its first row
+    let mut placed: Vec<(usize, u16, Row)> = Vec::new();
+    let (mut page, mut y) = (0, 0);
+    let mut rows = rows.into_iter().peekable();
+    while let Some(row) = rows.next() {
+        let height = match row {
+            Row::Divider(_) => 1,
+            Row::Cells(_) => cell_height,
+        };
+        let needed = match (&row, rows.peek()) {
+            (Row::Divider(_), Some(Row::Cells(_))) => height + cell_height,
+            _ => height,
+        };
+        if y > 0 && y + needed > inner.height {
+            page += 1;
+            y = 0;
+        }
+        placed.push((page, y, row));
+        y += height;
+    }
+    plan.page_info = PageInfo {
+        page: params.page.min(page),
+        count: page + 1,
+    };
+
+    for (row_page, y, row) in placed {
+        let shown = row_page == plan.page_info.page;
+        match row {
+            Row::Divider(label) if shown => plan.dividers.push((
+                label.to_string(),
+                Rect::new(inner.x, inner.y + y, inner.width, 1),
+            )),
+            Row::Divider(_) => {}
+            Row::Cells(ids) => {
+                for (col, &id) in ids.iter().enumerate() {
+                    plan.agent_pages.insert(id, row_page);
+                    if !shown {
+                        continue;
+                    }
+                    let x = inner.x + left + col as u16 * cell_width;
+                    let cell = Rect::new(x, inner.y + y, cell_width, cell_height);
+                    if bench {
+                        plan.bench.push((id, cell));
+                    } else {
+                        plan.desks.push((id, cell));
+                    }
+                }
+            }
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat(n: u32) -> Vec<Section> {
+        vec![Section {
+            label: None,
+            ids: (1..=n).collect(),
+        }]
+    }
+
+    fn grouped(home: u32, other: u32) -> Vec<Section> {
+        vec![
+            Section {
+                label: Some("home".to_string()),
+                ids: (1..=home).collect(),
+            },
+            Section {
+                label: Some("other projects".to_string()),
+                ids: (home + 1..=home + other).collect(),
+            },
+        ]
+    }
+
+    fn plan(
+        width: u16,
+        height: u16,
+        sections: &[Section],
+        policy: CellPolicy,
+        page: usize,
+    ) -> GridPlan {
+        grid_layout(&GridParams {
+            inner: Rect::new(3, 2, width, height),
+            sections,
+            policy,
+            page,
+        })
+    }
+
+    fn contains(outer: Rect, inner: Rect) -> bool {
+        inner.x >= outer.x
+            && inner.y >= outer.y
+            && inner.right() <= outer.right()
+            && inner.bottom() <= outer.bottom()
+    }
+
+    /// Every size from tiny to roomy, flat and grouped, desks and bench.
+    fn all_inputs() -> Vec<(u16, u16, Vec<Section>, CellPolicy)> {
+        let mut inputs = Vec::new();
+        for width in [0, 5, 6, 10, 11, 21, 33, 53, 80] {
+            for height in [0, 1, 5, 6, 7, 13, 22, 40] {
+                for sections in [
+                    flat(0),
+                    flat(1),
+                    flat(7),
+                    flat(40),
+                    grouped(2, 9),
+                    grouped(0, 4),
+                ] {
+                    for policy in [
+                        CellPolicy::Desks { max_columns: 3 },
+                        CellPolicy::Desks { max_columns: 12 },
+                        CellPolicy::Bench,
+                    ] {
+                        inputs.push((width, height, sections.clone(), policy));
+                    }
+                }
+            }
+        }
+        inputs
+    }
+
+    fn every_page(
+        width: u16,
+        height: u16,
+        sections: &[Section],
+        policy: CellPolicy,
+    ) -> Vec<GridPlan> {
+        let count = plan(width, height, sections, policy, 0).page_info.count;
+        (0..count)
+            .map(|page| plan(width, height, sections, policy, page))
+            .collect()
+    }
+
+    #[test]
+    fn cells_stay_inside_and_never_overlap() {
+        for (width, height, sections, policy) in all_inputs() {
+            let inner = Rect::new(3, 2, width, height);
+            for plan in every_page(width, height, &sections, policy) {
+                let mut rects: Vec<Rect> = plan.desks.iter().map(|(_, r)| *r).collect();
+                rects.extend(plan.bench.iter().map(|(_, r)| *r));
+                rects.extend(plan.dividers.iter().map(|(_, r)| *r));
+                for (i, a) in rects.iter().enumerate() {
+                    assert!(contains(inner, *a), "{a:?} outside {inner:?}");
+                    for b in &rects[i + 1..] {
+                        assert!(!a.intersects(*b), "{a:?} overlaps {b:?} in {inner:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_agent_is_on_exactly_one_page() {
+        for (width, height, sections, policy) in all_inputs() {
+            let ids: Vec<u32> = sections.iter().flat_map(|s| s.ids.clone()).collect();
+            let pages = every_page(width, height, &sections, policy);
+            if width < BENCH_CELL_WIDTH || height == 0 {
+                // No room for anything
+                assert!(pages[0].agent_pages.is_empty());
+                continue;
+            }
+            for id in ids {
+                let page = pages[0].agent_pages[&id];
+                let shown_on: Vec<usize> = pages
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| {
+                        p.desks
+                            .iter()
+                            .chain(&p.bench)
+                            .any(|(shown, _)| *shown == id)
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+                assert_eq!(shown_on, vec![page], "agent {id} in {width}x{height}");
+            }
+        }
+    }
+
+    #[test]
+    fn plans_are_stable() {
+        for (width, height, sections, policy) in all_inputs() {
+            assert_eq!(
+                plan(width, height, &sections, policy, 1),
+                plan(width, height, &sections, policy, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn desks_fill_rows_then_pages() {
+        // 53x22 holds three rows of three desks
+        let first = plan(53, 22, &flat(14), CellPolicy::Desks { max_columns: 3 }, 0);
+        assert_eq!(first.page_info, PageInfo { page: 0, count: 2 });
+        assert_eq!(first.desks.len(), 9);
+        assert_eq!(first.desks[0], (1, Rect::new(4, 2, 10, 6)));
+        assert_eq!(first.desks[4], (5, Rect::new(14, 8, 10, 6)));
+        let second = plan(53, 22, &flat(14), CellPolicy::Desks { max_columns: 3 }, 7);
+        assert_eq!(second.page_info.page, 1);
+        assert_eq!(second.desks.first().unwrap().0, 10);
+    }
+
+    #[test]
+    fn dividers_move_with_their_first_row() {
+        // 2 home desks, then "other projects" would be left at the bottom
+        let plan = plan(
+            33,
+            13,
+            &grouped(3, 3),
+            CellPolicy::Desks { max_columns: 3 },
+            0,
+        );
+        assert_eq!(plan.dividers.len(), 1);
+        assert_eq!(plan.dividers[0].0, "home");
+        assert_eq!(plan.page_info.count, 2);
+        assert_eq!(plan.agent_pages[&4], 1);
+    }
+
+    #[test]
+    fn too_small_for_desks_uses_the_bench() {
+        let plan = plan(40, 4, &flat(10), CellPolicy::Desks { max_columns: 3 }, 0);
+        assert!(plan.desks.is_empty());
+        assert!(plan.dividers.is_empty());
+        assert_eq!(plan.bench.len(), 10);
+        assert_eq!(plan.bench[6], (7, Rect::new(3, 3, 6, 1)));
+    }
+}
@@ -1,11 +1,40 @@
-use ratatui::style::Color;
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AnimState {
     Idle,
     Typing,
     Reading,
     Walking,
+    /// Waiting on an answer from the user.
+    Asking,
+    /// Running tests; only reached through a `[[rules]]` entry.
+    Testing,
+}
+
+impl AnimState {
+    pub const ALL: [AnimState; 6] = [
+        AnimState::Idle,
+        AnimState::Typing,
+        AnimState::Reading,
+        AnimState::Walking,
+        AnimState::Asking,
+        AnimState::Testing,
+    ];
+
+    /// Name used for `anim` in the config's `[[rules]]`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnimState::Idle => "idle",
+            AnimState::Typing => "typing",
+            AnimState::Reading => "reading",
+            AnimState::Walking => "walking",
+            AnimState::Asking => "asking",
+            AnimState::Testing => "testing",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.name() == name)
+    }
 }
 
 /// Get sprite frame (3 lines) for a given animation state and frame index.
@@ -15,6 +44,8 @@ pub fn sprite_frame(state: AnimState, frame: usize) -> [&'static str; 3] {
         AnimState::Typing => TYPING_FRAMES[frame % TYPING_FRAMES.len()],
         AnimState::Reading => READING_FRAMES[frame % READING_FRAMES.len()],
         AnimState::Walking => WALKING_FRAMES[frame % WALKING_FRAMES.len()],
+        AnimState::Asking => ASKING_FRAMES[frame % ASKING_FRAMES.len()],
+        AnimState::Testing => TESTING_FRAMES[frame % TESTING_FRAMES.len()],
     }
 }
 
@@ -71,26 +102,100 @@ const WALKING_FRAMES: &[[&str; 3]] = &[
     ],
 ];
 
+const ASKING_FRAMES: &[[&str; 3]] = &[
+    [
+        " \u{25c9}?",
+        "\u{2554}\u{2551}\u{2557}",
+        "\u{255a}\u{2569}\u{255d}",
+    ],
+    [
+        " \u{25c9} ",
+        "\u{2554}\u{2551}\u{2557}",
+        "\u{255a}\u{2569}\u{255d}",
+    ],
+];
+
+const TESTING_FRAMES: &[[&str; 3]] = &[
+    [
+        " \u{25c9} ",
+        "\u{2554}\u{2551}\u{2557}",
+        "\u{255a}\u{2569}\u{255d}",
+    ],
+    [
+        " \u{25c9}\u{2713}",
+        "\u{2554}\u{2551}\u{2557}",
+        "\u{255a}\u{2569}\u{255d}",
+    ],
+    [
+        " \u{25c9}\u{2717}",
+        "\u{2554}\u{2551}\u{2557}",
+        "\u{255a}\u{2569}\u{255d}",
+    ],
+];
+
 pub const DESK: [&str; 2] = [
     "\u{2554}\u{2550}\u{2550}\u{2550}\u{2557}",
     "\u{255a}\u{2550}\u{2550}\u{2550}\u{255d}",
 ];
 
-pub const AGENT_COLORS: &[Color] = &[
-    Color::Cyan,
-    Color::Magenta,
-    Color::Yellow,
-    Color::Green,
-    Color::Blue,
-    Color::Red,
-];
+/// The well-known sub-agent types, and any other by a stable hash of its
+/// name, so a custom type keeps its color across runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubAgentKind {
+    Explore,
+    Plan,
+    General,
+    Custom(u32),
+}
+
+impl SubAgentKind {
+    /// Classify a Task `subagent_type`.
+    pub fn of(agent_type: &str) -> Self {
+        match agent_type {
+            "Explore" => SubAgentKind::Explore,
+            "Plan" => SubAgentKind::Plan,
+            // "task" is the placeholder used when no type is known
+            "general-purpose" | "task" | "" => SubAgentKind::General,
+            custom => SubAgentKind::Custom(fnv1a(custom)),
+        }
+    }
+
+    pub fn icon(self, ascii: bool) -> &'static str {
+        match (self, ascii) {
+            (SubAgentKind::Explore, false) => "\u{1f50d}",
+            (SubAgentKind::Explore, true) => "?",
+            (SubAgentKind::Plan, false) => "\u{1f5fa}",
+            (SubAgentKind::Plan, true) => "#",
+            (SubAgentKind::General, false) => "\u{2699}",
+            (SubAgentKind::General, true) => "*",
+            (SubAgentKind::Custom(_), false) => "\u{25c7}",
+            (SubAgentKind::Custom(_), true) => "+",
+        }
+    }
+}
+
+pub fn sub_agent_icon(agent_type: &str, ascii: bool) -> &'static str {
+    SubAgentKind::of(agent_type).icon(ascii)
+}
 
-pub fn agent_color(id: u32) -> Color {
-    AGENT_COLORS[(id as usize).saturating_sub(1) % AGENT_COLORS.len()]
+/// FNV-1a, used instead of `DefaultHasher` so colors stay stable across builds.
+fn fnv1a(s: &str) -> u32 {
+    s.bytes().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    })
 }
 
-pub fn sub_agent_color(_parent_id: u32) -> Color {
-    Color::DarkGray
+/// Whether the locale advertises UTF-8; if not, prefer ASCII icons.
+pub fn locale_is_utf8() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|k| std::env::var(k).ok())
+        .find(|v| !v.is_empty())
+        .map(|v| {
+            let v = v.to_ascii_lowercase();
+            v.contains("utf-8") || v.contains("utf8")
+        })
+        .unwrap_or(false)
 }
 
 #[cfg(test)]
@@ -99,17 +204,20 @@ mod tests {
 
     #[test]
     fn sprite_frames_have_3_lines() {
-        for state in [
-            AnimState::Idle,
-            AnimState::Typing,
-            AnimState::Reading,
-            AnimState::Walking,
-        ] {
+        for state in AnimState::ALL {
             let frame = sprite_frame(state, 0);
             assert_eq!(frame.len(), 3);
         }
     }
 
+    #[test]
+    fn anim_state_names_round_trip() {
+        for state in AnimState::ALL {
+            assert_eq!(AnimState::from_name(state.name()), Some(state));
+        }
+        assert_eq!(AnimState::from_name("dancing"), None);
+    }
+
     #[test]
     fn sprite_frames_cycle() {
         let f0 = sprite_frame(AnimState::Typing, 0);
@@ -118,9 +226,32 @@ mod tests {
     }
 
     #[test]
-    fn agent_colors_cycle() {
-        assert_eq!(agent_color(1), Color::Cyan);
-        assert_eq!(agent_color(7), Color::Cyan);
+    fn known_sub_agent_types() {
+        assert_eq!(SubAgentKind::of("Explore"), SubAgentKind::Explore);
+        assert_eq!(sub_agent_icon("Explore", false), "\u{1f50d}");
+        assert_eq!(SubAgentKind::of("Plan"), SubAgentKind::Plan);
+        assert_eq!(sub_agent_icon("Plan", false), "\u{1f5fa}");
+        assert_eq!(SubAgentKind::of("general-purpose"), SubAgentKind::General);
+        assert_eq!(sub_agent_icon("general-purpose", false), "\u{2699}");
+    }
+
+    #[test]
+    fn ascii_icons_are_single_ascii_chars() {
+        for t in ["Explore", "Plan", "general-purpose", "code-reviewer"] {
+            let icon = sub_agent_icon(t, true);
+            assert_eq!(icon.len(), 1);
+            assert!(icon.is_ascii());
+        }
+    }
+
+    #[test]
+    fn custom_type_hash_is_stable() {
+        // Pinned so a hash change (which would recolor users' agents) is noticed
+        assert_eq!(fnv1a("code-reviewer"), 0x4320_0e00);
+        assert_eq!(
+            SubAgentKind::of("code-reviewer"),
+            SubAgentKind::Custom(0x4320_0e00)
+        );
     }
 
     #[test]
@@ -0,0 +1,246 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const ELLIPSIS: &str = "\u{2026}";
+
+/// Longest prefix of `s` at most `width` columns wide.
+fn prefix_within(s: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in s.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &s[..i];
+        }
+    }
+    s
+}
+
+/// Longest suffix of `s` at most `width` columns wide.
+fn suffix_within(s: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in s.char_indices().rev() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &s[i + c.len_utf8()..];
+        }
+    }
+    s
+}
+
+/// Human-readable byte count: `512 B`, `4.2 KB`, `1.3 MB`.
+pub fn format_bytes(bytes: f64) -> String {
+    const KB: f64 = 1024.0;
+    if bytes < KB {
+        format!("{bytes:.0} B")
+    } else if bytes < KB * KB {
+        format!("{:.1} KB", bytes / KB)
+    } else {
+        format!("{:.1} MB", bytes / (KB * KB))
+    }
+}
+
+/// Fit `s` in `width` columns by dropping its end: `config relo…`.
+pub fn elide_right(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    format!("{}{ELLIPSIS}", prefix_within(s, width - 1))
+}
+
+/// Fit `s` in `width` columns by dropping its start: `…rser.rs`.
+pub fn elide_left(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    format!("{ELLIPSIS}{}", suffix_within(s, width - 1))
+}
+
+/// Fit `s` in `width` columns by dropping its middle, keeping slightly more
+/// of the end: `src/wat…parser.rs`.
+pub fn elide_middle(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let budget = width - 1;
+    let head = prefix_within(s, budget / 2);
+    let tail = suffix_within(s, budget - head.width());
+    format!("{head}{ELLIPSIS}{tail}")
+}
+
+/// Fit a path in `width` columns. Whole middle components go first
+/// (`/home/…/watcher/parser.rs`), then the leading ones
+/// (`…/watcher/parser.rs`); the file name is only cut when nothing else is
+/// left to drop.
+pub fn elide_path(path: &str, width: usize) -> String {
+    if path.width() <= width {
+        return path.to_string();
+    }
+    let segments: Vec<&str> = path.split('/').collect();
+    let n = segments.len();
+    let last = segments[n - 1];
+
+    // First component, ellipsis, and as many trailing components as fit
+    for keep in (1..n.saturating_sub(1)).rev() {
+        let candidate = format!(
+            "{}/{ELLIPSIS}/{}",
+            segments[0],
+            segments[n - keep..].join("/")
+        );
+        if candidate.width() <= width {
+            return candidate;
+        }
+    }
+    // Without the first component
+    for keep in (1..n).rev() {
+        let candidate = format!("{ELLIPSIS}/{}", segments[n - keep..].join("/"));
+        if candidate.width() <= width {
+            return candidate;
+        }
+    }
+    if last.width() <= width {
+        return last.to_string();
+    }
+    elide_middle(last, width)
+}
+
+/// Block characters for sparklines, lowest first.
+const SPARK_BLOCKS: [char; 8] = [
+    '\u{2581}', '\u{2582}', '\u{2583}', '\u{2584}', '\u{2585}', '\u{2586}', '\u{2587}', '\u{2588}',
+];
+/// Stand-ins for terminals without Unicode.
+const SPARK_ASCII: [char; 8] = ['_', '.', ',', ':', '-', '=', '+', '#'];
+
+/// One character per count, at most `width` of them: adjacent counts are
+/// summed until they fit. The largest sum gets the tallest block, and only
+/// zero gets the lowest one.
+pub fn sparkline(counts: &[u32], width: usize, ascii: bool) -> String {
+    let blocks = if ascii { SPARK_ASCII } else { SPARK_BLOCKS };
+    let per_char = counts.len().div_ceil(width.max(1)).max(1);
+    let sums: Vec<u32> = counts
+        .chunks(per_char)
+        .map(|chunk| chunk.iter().sum())
+        .collect();
+    let max = sums.iter().copied().max().unwrap_or(0).max(1);
+    let top = (blocks.len() - 1) as u32;
+    sums.iter()
+        .map(|&sum| blocks[(sum * top).div_ceil(max) as usize])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: &[&str] = &[
+        "/home/me/work/pixel-agents-tui/src/watcher/parser.rs",
+        "~/.claude/projects/-Users-me-repo/6f1c2e9a.jsonl",
+        "relative/dir/file.txt",
+        "/tmp/pixel-agents-1760000000.svg",
+        "single-segment-file-name.rs",
+        "/",
+        "",
+        "/home/\u{9805}\u{76ee}/\u{30d5}\u{30a1}\u{30a4}\u{30eb}.rs",
+        "/a/b/\u{1f980}\u{1f980}\u{1f980}.rs",
+    ];
+
+    #[test]
+    fn byte_counts() {
+        assert_eq!(format_bytes(0.0), "0 B");
+        assert_eq!(format_bytes(512.4), "512 B");
+        assert_eq!(format_bytes(4300.0), "4.2 KB");
+        assert_eq!(format_bytes(1_363_149.0), "1.3 MB");
+    }
+
+    #[test]
+    fn sparkline_scales_to_the_busiest_bucket() {
+        assert_eq!(
+            sparkline(&[0, 1, 7, 14, 3], 10, false),
+            "\u{2581}\u{2582}\u{2585}\u{2588}\u{2583}"
+        );
+        assert_eq!(sparkline(&[0, 1, 7, 14, 3], 10, true), "_.-#,");
+        assert_eq!(sparkline(&[0; 4], 10, false), "\u{2581}".repeat(4));
+        // Pairs summed to fit
+        assert_eq!(sparkline(&[1, 1, 0, 0, 2, 2], 3, true), "-_#");
+        assert_eq!(sparkline(&[0; 60], 25, true).len(), 20);
+    }
+
+    #[test]
+    fn elide_path_examples() {
+        let path = "/home/me/work/src/watcher/parser.rs";
+        assert_eq!(elide_path(path, 60), path);
+        assert_eq!(elide_path(path, 28), "/\u{2026}/src/watcher/parser.rs");
+        assert_eq!(elide_path(path, 20), "/\u{2026}/watcher/parser.rs");
+        assert_eq!(elide_path(path, 12), "/\u{2026}/parser.rs");
+        assert_eq!(elide_path(path, 11), "\u{2026}/parser.rs");
+        assert_eq!(elide_path(path, 9), "parser.rs");
+        assert_eq!(elide_path(path, 6), "pa\u{2026}.rs");
+        assert_eq!(elide_path("~/a/b/c/file.rs", 12), "~/\u{2026}/file.rs");
+    }
+
+    #[test]
+    fn elide_left_and_middle_examples() {
+        assert_eq!(elide_left("src/watcher/parser.rs", 10), "\u{2026}parser.rs");
+        assert_eq!(
+            elide_right("src/watcher/parser.rs", 10),
+            "src/watch\u{2026}"
+        );
+        assert_eq!(
+            elide_middle("src/watcher/parser.rs", 10),
+            "src/\u{2026}er.rs"
+        );
+        assert_eq!(elide_middle("short", 10), "short");
+        assert_eq!(elide_left("abc", 0), "");
+        assert_eq!(elide_middle("abc", 1), "\u{2026}");
+    }
+
+    #[test]
+    fn never_exceed_width() {
+        for s in SAMPLES {
+            for width in 0..=s.width() + 2 {
+                for (name, out) in [
+                    ("elide_left", elide_left(s, width)),
+                    ("elide_right", elide_right(s, width)),
+                    ("elide_middle", elide_middle(s, width)),
+                    ("elide_path", elide_path(s, width)),
+                ] {
+                    assert!(
+                        out.width() <= width,
+                        "{name}({s:?}, {width}) = {out:?} is too wide"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn elide_path_keeps_file_name_when_it_fits() {
+        for s in SAMPLES {
+            let last = s.rsplit('/').next().unwrap();
+            for width in last.width()..=s.width() + 2 {
+                let out = elide_path(s, width);
+                assert!(
+                    out.ends_with(last),
+                    "elide_path({s:?}, {width}) = {out:?} lost {last:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unchanged_when_it_fits() {
+        for s in SAMPLES {
+            assert_eq!(elide_left(s, s.width()), *s);
+            assert_eq!(elide_right(s, s.width()), *s);
+            assert_eq!(elide_middle(s, s.width()), *s);
+            assert_eq!(elide_path(s, s.width()), *s);
+        }
+    }
+}
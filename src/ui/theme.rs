@@ -0,0 +1,391 @@
+use std::ffi::OsStr;
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::state::agent::AgentStatus;
+use crate::ui::sprites::SubAgentKind;
+use crate::watcher::parser::ToolCategory;
+
+/// The built-in themes, in the order `cycle_theme` goes through them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThemeName {
+    /// Bright colors for dark terminal backgrounds.
+    #[default]
+    Dark,
+    /// Deeper colors that stay readable on light backgrounds.
+    Light,
+    /// No colors at all, only bold and dim.
+    Mono,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 3] = [ThemeName::Dark, ThemeName::Light, ThemeName::Mono];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeName::Dark => "dark",
+            ThemeName::Light => "light",
+            ThemeName::Mono => "mono",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|&theme| theme == self)
+            .unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// Every style the UI draws with. Render code asks the theme by role
+/// instead of naming colors, so a theme can also drop them altogether.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: ThemeName,
+    /// Titles, focused borders and the selection marker.
+    pub accent: Style,
+    /// Borders of unfocused panels.
+    pub border: Style,
+    /// Main text and values.
+    pub text: Style,
+    /// Labels, hints and anything secondary.
+    pub muted: Style,
+    /// SDD phases, key hints and warnings.
+    pub highlight: Style,
+    /// Successes and additions.
+    pub good: Style,
+    /// Failures, removals and errors.
+    pub bad: Style,
+    /// Rare modes worth noticing, like scale mode.
+    pub notice: Style,
+    /// Agents left waiting on the user past the last escalation threshold.
+    pub urgent: Style,
+    /// Desks.
+    pub furniture: Style,
+    /// Active, needs input, waiting, dormant.
+    statuses: [Style; 4],
+    agents: [Style; 6],
+    /// Explore, Plan and general-purpose sub-agents.
+    sub_agent_kinds: [Style; 3],
+    /// Custom sub-agent types, picked by a stable hash of the name.
+    sub_agents: [Style; 6],
+    /// File, shell, web, MCP, Task and other tools.
+    tool_categories: [Style; 6],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn named(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Self::dark(),
+            ThemeName::Light => Self::light(),
+            ThemeName::Mono => Self::mono(),
+        }
+    }
+
+    pub fn dark() -> Self {
+        let fg = |color| Style::new().fg(color);
+        Self {
+            name: ThemeName::Dark,
+            accent: fg(Color::Cyan),
+            border: fg(Color::DarkGray),
+            text: fg(Color::White),
+            muted: fg(Color::DarkGray),
+            highlight: fg(Color::Yellow),
+            good: fg(Color::Green),
+            bad: fg(Color::Red),
+            notice: fg(Color::Magenta),
+            urgent: fg(Color::Indexed(208)).add_modifier(Modifier::BOLD),
+            furniture: fg(Color::White),
+            statuses: [
+                fg(Color::Green),
+                fg(Color::LightRed),
+                fg(Color::Yellow),
+                fg(Color::DarkGray),
+            ],
+            agents: [
+                fg(Color::Cyan),
+                fg(Color::Magenta),
+                fg(Color::Yellow),
+                fg(Color::Green),
+                fg(Color::Blue),
+                fg(Color::Red),
+            ],
+            sub_agent_kinds: [fg(Color::Blue), fg(Color::Yellow), fg(Color::Gray)],
+            // Avoids the colors of the well-known kinds
+            sub_agents: [
+                fg(Color::Cyan),
+                fg(Color::Magenta),
+                fg(Color::Green),
+                fg(Color::LightBlue),
+                fg(Color::LightMagenta),
+                fg(Color::LightCyan),
+            ],
+            tool_categories: [
+                fg(Color::White),
+                fg(Color::Yellow),
+                fg(Color::Blue),
+                fg(Color::Magenta),
+                fg(Color::Green),
+                fg(Color::Gray),
+            ],
+        }
+    }
+
+    /// Yellows and light tones turn into darker indexed colors, which
+    /// wash out less on a white background.
+    pub fn light() -> Self {
+        let fg = |color| Style::new().fg(color);
+        let amber = Color::Indexed(130);
+        let green = Color::Indexed(28);
+        let teal = Color::Indexed(30);
+        Self {
+            name: ThemeName::Light,
+            accent: fg(Color::Blue),
+            border: fg(Color::Gray),
+            text: fg(Color::Black),
+            muted: fg(Color::DarkGray),
+            highlight: fg(amber),
+            good: fg(green),
+            bad: fg(Color::Red),
+            notice: fg(Color::Magenta),
+            urgent: fg(Color::Indexed(166)).add_modifier(Modifier::BOLD),
+            furniture: fg(Color::DarkGray),
+            statuses: [fg(green), fg(Color::Red), fg(amber), fg(Color::Gray)],
+            agents: [
+                fg(teal),
+                fg(Color::Magenta),
+                fg(amber),
+                fg(green),
+                fg(Color::Blue),
+                fg(Color::Red),
+            ],
+            sub_agent_kinds: [fg(Color::Blue), fg(amber), fg(Color::DarkGray)],
+            sub_agents: [
+                fg(teal),
+                fg(Color::Magenta),
+                fg(green),
+                fg(Color::Indexed(25)),
+                fg(Color::Indexed(90)),
+                fg(Color::Indexed(31)),
+            ],
+            tool_categories: [
+                fg(Color::Black),
+                fg(amber),
+                fg(Color::Blue),
+                fg(Color::Magenta),
+                fg(green),
+                fg(Color::DarkGray),
+            ],
+        }
+    }
+
+    /// The terminal's own foreground everywhere; emphasis comes from bold
+    /// and dim only. Also what `NO_COLOR` gets.
+    pub fn mono() -> Self {
+        let plain = Style::new();
+        let bold = Style::new().add_modifier(Modifier::BOLD);
+        let dim = Style::new().add_modifier(Modifier::DIM);
+        Self {
+            name: ThemeName::Mono,
+            accent: bold,
+            border: dim,
+            text: plain,
+            muted: dim,
+            highlight: bold,
+            good: plain,
+            bad: bold,
+            notice: bold,
+            urgent: bold.add_modifier(Modifier::REVERSED),
+            furniture: plain,
+            statuses: [plain, bold, plain, dim],
+            agents: [plain; 6],
+            sub_agent_kinds: [plain; 3],
+            sub_agents: [plain; 6],
+            tool_categories: [plain; 6],
+        }
+    }
+
+    /// This theme with its accent replaced by `[theme] accent`. The
+    /// monochrome theme keeps its own.
+    pub fn with_accent(mut self, accent: Option<Color>) -> Self {
+        if let (Some(color), false) = (accent, self.name == ThemeName::Mono) {
+            self.accent = Style::new().fg(color);
+        }
+        self
+    }
+
+    pub fn status(&self, status: AgentStatus) -> Style {
+        match status {
+            AgentStatus::Active => self.statuses[0],
+            AgentStatus::NeedsInput => self.statuses[1],
+            AgentStatus::Waiting => self.statuses[2],
+            AgentStatus::Dormant => self.statuses[3],
+        }
+    }
+
+    /// How an agent waiting on the user past `level` escalation thresholds
+    /// stands out: highlighted at the first, urgent from the second on.
+    pub fn escalation(&self, level: usize) -> Option<Style> {
+        match level {
+            0 => None,
+            1 => Some(self.highlight),
+            _ => Some(self.urgent),
+        }
+    }
+
+    /// Agent `id`'s color, cycling through the palette.
+    pub fn agent_color(&self, id: u32) -> Style {
+        self.agents[(id as usize).saturating_sub(1) % self.agents.len()]
+    }
+
+    /// The color of a sub-agent type (Task `subagent_type`).
+    pub fn sub_agent_color(&self, agent_type: &str) -> Style {
+        match SubAgentKind::of(agent_type) {
+            SubAgentKind::Explore => self.sub_agent_kinds[0],
+            SubAgentKind::Plan => self.sub_agent_kinds[1],
+            SubAgentKind::General => self.sub_agent_kinds[2],
+            SubAgentKind::Custom(hash) => self.sub_agents[hash as usize % self.sub_agents.len()],
+        }
+    }
+
+    /// The color of a tool's category, for the sidebar's Tool line.
+    pub fn tool_category(&self, category: ToolCategory) -> Style {
+        match category {
+            ToolCategory::File => self.tool_categories[0],
+            ToolCategory::Shell => self.tool_categories[1],
+            ToolCategory::Web => self.tool_categories[2],
+            ToolCategory::Mcp => self.tool_categories[3],
+            ToolCategory::Task => self.tool_categories[4],
+            ToolCategory::Other => self.tool_categories[5],
+        }
+    }
+
+    /// A color the user picked, e.g. a `[[rules]]` status color. The
+    /// monochrome theme drops it.
+    pub fn custom(&self, color: Color) -> Style {
+        if self.name == ThemeName::Mono {
+            Style::new()
+        } else {
+            Style::new().fg(color)
+        }
+    }
+}
+
+/// Whether `NO_COLOR` asks for no colors: set to anything but empty.
+pub fn no_color_requested(value: Option<&OsStr>) -> bool {
+    value.is_some_and(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_style(theme: &Theme) -> Vec<Style> {
+        let mut styles = vec![
+            theme.accent,
+            theme.border,
+            theme.text,
+            theme.muted,
+            theme.highlight,
+            theme.good,
+            theme.bad,
+            theme.notice,
+            theme.urgent,
+            theme.furniture,
+        ];
+        styles.extend(theme.statuses);
+        styles.extend(theme.agents);
+        styles.extend(theme.sub_agent_kinds);
+        styles.extend(theme.sub_agents);
+        styles.extend(theme.tool_categories);
+        styles
+    }
+
+    #[test]
+    fn color_themes_have_a_full_palette() {
+        for theme in [Theme::dark(), Theme::light()] {
+            assert!(every_style(&theme).iter().all(|style| style.fg.is_some()));
+            for palette in [
+                &theme.statuses[..],
+                &theme.agents[..],
+                &theme.tool_categories[..],
+            ] {
+                for (i, style) in palette.iter().enumerate() {
+                    assert!(!palette[..i].contains(style), "{:?}", theme.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mono_theme_emits_no_colors() {
+        let theme = Theme::mono().with_accent(Some(Color::Magenta));
+        for style in every_style(&theme) {
+            assert_eq!((style.fg, style.bg), (None, None));
+        }
+        assert_eq!(theme.custom(Color::Red), Style::new());
+        assert_eq!(theme.sub_agent_color("code-reviewer"), Style::new());
+        // Still tells the statuses that matter apart
+        assert!(theme
+            .status(AgentStatus::NeedsInput)
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert!(theme
+            .status(AgentStatus::Dormant)
+            .add_modifier
+            .contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn agent_colors_cycle() {
+        let theme = Theme::dark();
+        assert_eq!(theme.agent_color(1).fg, Some(Color::Cyan));
+        assert_eq!(theme.agent_color(7), theme.agent_color(1));
+    }
+
+    #[test]
+    fn sub_agent_colors_by_kind() {
+        let theme = Theme::dark();
+        assert_eq!(theme.sub_agent_color("Explore").fg, Some(Color::Blue));
+        assert_eq!(theme.sub_agent_color("Plan").fg, Some(Color::Yellow));
+        assert_eq!(
+            theme.sub_agent_color("general-purpose").fg,
+            Some(Color::Gray)
+        );
+        let custom = theme.sub_agent_color("code-reviewer");
+        assert_eq!(theme.sub_agent_color("code-reviewer"), custom);
+        assert!(theme.sub_agents.contains(&custom));
+    }
+
+    #[test]
+    fn themes_cycle_and_parse() {
+        assert_eq!(ThemeName::Dark.next(), ThemeName::Light);
+        assert_eq!(ThemeName::Mono.next(), ThemeName::Dark);
+        for name in ThemeName::ALL {
+            assert_eq!(ThemeName::from_name(name.name()), Some(name));
+            assert_eq!(Theme::named(name).name, name);
+        }
+        assert_eq!(ThemeName::from_name("solarized"), None);
+    }
+
+    #[test]
+    fn accent_override_and_no_color() {
+        let theme = Theme::light().with_accent(Some(Color::Red));
+        assert_eq!(theme.accent.fg, Some(Color::Red));
+        assert_eq!(Theme::dark().with_accent(None), Theme::dark());
+        assert!(no_color_requested(Some(OsStr::new("1"))));
+        assert!(!no_color_requested(Some(OsStr::new(""))));
+        assert!(!no_color_requested(None));
+    }
+}
@@ -0,0 +1,191 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+
+use crate::watcher::discovery;
+use crate::watcher::format::{self, TranscriptFormat};
+use crate::watcher::redact::Redactor;
+use crate::watcher::rules::ToolClassifier;
+use crate::watcher::types::JsonlRecord;
+
+/// Whether `path` is a gzip-compressed session archive (`.jsonl.gz`).
+/// The live watcher skips these; only whole-file reads decompress them.
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "gz")
+        && path
+            .file_stem()
+            .map(Path::new)
+            .and_then(|stem| stem.extension())
+            .is_some_and(|e| e == "jsonl")
+}
+
+/// Every session file under `claude_dir/projects/`, however old, archives
+/// included. Sub-agent transcripts are left out. Sorted by path.
+pub fn list_sessions(claude_dir: &Path) -> Vec<PathBuf> {
+    let mut sessions = Vec::new();
+    let mut dirs = vec![claude_dir.join("projects")];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                dirs.push(path);
+            } else if (path.extension().is_some_and(|e| e == "jsonl") || is_compressed(&path))
+                && !discovery::is_sidechain_file(&path)
+            {
+                sessions.push(path);
+            }
+        }
+    }
+    sessions.sort();
+    sessions
+}
+
+/// Open a session file for a single pass, decompressing archives on the fly.
+pub fn open_session(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    if is_compressed(path) {
+        Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Feed every parsable record of a plain or compressed session file to `f`,
+/// with its dialect. Lines are sniffed and parsed as the live watcher does.
+pub fn for_each_record<F>(path: &Path, mut f: F) -> io::Result<()>
+where
+    F: FnMut(&dyn TranscriptFormat, JsonlRecord),
+{
+    let mut reader = open_session(path)?;
+    let mut line = String::new();
+    let mut dialect = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if dialect.is_none() {
+            dialect = format::sniff(&line);
+        }
+        if let Some(dialect) = dialect {
+            if let Some(record) = dialect.parse_line(&line) {
+                f(dialect, record);
+            }
+        }
+    }
+}
+
+/// Whole-session counts for batch reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    pub records: u64,
+    pub prompts: u64,
+    pub tool_uses: u64,
+    pub turns: u64,
+}
+
+/// Count a session file's records, prompts, tool uses and finished turns.
+pub fn session_stats(path: &Path) -> io::Result<SessionStats> {
+    let redactor = Redactor::default();
+    let classifier = ToolClassifier::default();
+    let mut stats = SessionStats::default();
+    for_each_record(path, |dialect, record| {
+        stats.records += 1;
+        if dialect.is_user_prompt(&record) {
+            stats.prompts += 1;
+        }
+        stats.tool_uses += dialect.tool_use_refs(&record, &redactor, &classifier).len() as u64;
+        if dialect.is_turn_end(&record) {
+            stats.turns += 1;
+        }
+    })?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    const SESSION: &str = concat!(
+        r#"{"type":"user","message":{"content":"run the tests"}}"#,
+        "\n",
+        r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test"}}]}}"#,
+        "\n",
+        r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1"}]}}"#,
+        "\n",
+        "not json\n",
+        r#"{"type":"system","subtype":"turn_duration","duration_ms":1200}"#,
+        "\n",
+    );
+
+    #[test]
+    fn detects_archives_by_full_extension() {
+        assert!(is_compressed(Path::new("/p/abc.jsonl.gz")));
+        assert!(!is_compressed(Path::new("/p/abc.jsonl")));
+        assert!(!is_compressed(Path::new("/p/backup.tar.gz")));
+    }
+
+    #[test]
+    fn lists_sessions_and_archives_but_not_sidechains() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("-src-app");
+        std::fs::create_dir_all(project.join("s").join("subagents")).unwrap();
+        for name in [
+            "s.jsonl",
+            "old.jsonl.gz",
+            "notes.txt",
+            "s/subagents/agent-1.jsonl",
+        ] {
+            std::fs::write(project.join(name), "").unwrap();
+        }
+        assert_eq!(
+            list_sessions(dir.path()),
+            [project.join("old.jsonl.gz"), project.join("s.jsonl")]
+        );
+    }
+
+    #[test]
+    fn compressed_session_gives_identical_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("abc.jsonl");
+        std::fs::write(&plain, SESSION).unwrap();
+        let archived = dir.path().join("abc.jsonl.gz");
+        let mut encoder = GzEncoder::new(File::create(&archived).unwrap(), Compression::default());
+        encoder.write_all(SESSION.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let expected = SessionStats {
+            records: 4,
+            prompts: 1,
+            tool_uses: 1,
+            turns: 1,
+        };
+        assert_eq!(session_stats(&plain).unwrap(), expected);
+        assert_eq!(session_stats(&archived).unwrap(), expected);
+    }
+
+    #[test]
+    fn other_dialects_are_counted_too() {
+        let fixture =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/role-based-session.jsonl");
+        let stats = session_stats(&fixture).unwrap();
+        assert_eq!((stats.prompts, stats.turns, stats.tool_uses), (2, 1, 3));
+    }
+
+    #[test]
+    fn corrupt_archive_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.jsonl.gz");
+        std::fs::write(&path, SESSION).unwrap();
+        assert!(session_stats(&path).is_err());
+    }
+}
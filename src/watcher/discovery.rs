@@ -1,115 +1,662 @@
 use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
-const DORMANCY_TIMEOUT: Duration = Duration::from_secs(300);
+use serde::{Deserialize, Serialize};
 
-/// Scan ~/.claude/projects/ for active .jsonl files (modified within 5 minutes).
-/// Returns an empty vec if the directory doesn't exist.
-pub fn scan_sessions(claude_dir: &Path) -> Vec<PathBuf> {
-    let projects_dir = claude_dir.join("projects");
-    if !projects_dir.exists() {
-        return Vec::new();
+use crate::persist::{self, LoadOutcome, Migration, Versioned};
+use crate::watcher::parser;
+use crate::watcher::types::JsonlRecord;
+
+/// Sessions untouched for longer than this are no longer tracked.
+pub const DORMANCY_TIMEOUT: Duration = Duration::from_secs(300);
+/// How far into a sidechain to look for its opening prompt.
+const SIDECHAIN_OPENING_LINES: usize = 10;
+
+/// Slack on the age check for filesystems that keep whole-second mtimes,
+/// so a session right at the edge doesn't flap in and out between scans.
+const MTIME_GRANULARITY: Duration = Duration::from_secs(1);
+
+/// What a scan needs to know about one directory entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStat {
+    pub is_dir: bool,
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl FileStat {
+    fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
     }
+}
 
-    let now = SystemTime::now();
+/// The filesystem calls scans make. On a network mount each call is a
+/// round trip, which is what `--remote-friendly` economizes on.
+pub trait FsProbe {
+    /// Paths of the entries in `dir`, without their attributes.
+    fn read_dir(&mut self, dir: &Path) -> Vec<PathBuf>;
+    /// One entry's attributes.
+    fn stat(&mut self, path: &Path) -> Option<FileStat>;
+    /// Every entry of `dir` with its attributes, as one listing. sshfs
+    /// answers these from the attributes its directory read brought back.
+    /// Only `is_dir` is filled in for directories.
+    fn list_dir(&mut self, dir: &Path) -> Vec<(PathBuf, FileStat)>;
+}
+
+/// The local filesystem.
+pub struct RealFs;
+
+impl FsProbe for RealFs {
+    fn read_dir(&mut self, dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default()
+    }
+
+    fn stat(&mut self, path: &Path) -> Option<FileStat> {
+        path.metadata().ok().map(|m| FileStat::from_metadata(&m))
+    }
+
+    fn list_dir(&mut self, dir: &Path) -> Vec<(PathBuf, FileStat)> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                // The listing's own entry type spares directories a stat
+                let stat = if entry.file_type().ok()?.is_dir() {
+                    FileStat {
+                        is_dir: true,
+                        len: 0,
+                        modified: None,
+                    }
+                } else {
+                    FileStat::from_metadata(&entry.metadata().ok()?)
+                };
+                Some((entry.path(), stat))
+            })
+            .collect()
+    }
+}
+
+/// A session file found by a scan, with its size at the time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionFile {
+    pub path: PathBuf,
+    pub len: u64,
+}
+
+/// Scan ~/.claude/projects/ for active .jsonl files (modified within `max_age`).
+/// Returns an empty vec if the directory doesn't exist.
+pub fn scan_sessions(claude_dir: &Path, max_age: Duration) -> Vec<PathBuf> {
+    scan_sessions_with(&mut RealFs, claude_dir, max_age)
+        .into_iter()
+        .map(|session| session.path)
+        .collect()
+}
+
+/// Like `scan_sessions`, but with one directory listing per directory
+/// instead of a stat per entry, and the sizes included so the caller can
+/// tell which files grew.
+pub fn scan_sessions_batched(claude_dir: &Path, max_age: Duration) -> Vec<SessionFile> {
+    scan_sessions_batched_with(&mut RealFs, claude_dir, max_age)
+}
+
+/// Keep the sessions of the project whose directory under
+/// `~/.claude/projects/` is `dir_key` (see [`encode_project_dir`]), or every
+/// session for `None`.
+///
+/// [`encode_project_dir`]: crate::state::project::encode_project_dir
+pub fn filter_to_project(sessions: Vec<PathBuf>, dir_key: Option<&str>) -> Vec<PathBuf> {
+    sessions
+        .into_iter()
+        .filter(|path| in_project(path, dir_key))
+        .collect()
+}
+
+/// Whether a session file belongs to the project `dir_key`; always true for
+/// `None`, which stands for all projects.
+pub fn in_project(session: &Path, dir_key: Option<&str>) -> bool {
+    dir_key.is_none_or(|key| project_key(session) == Some(OsStr::new(key)))
+}
+
+fn scan_sessions_with<P: FsProbe>(
+    probe: &mut P,
+    claude_dir: &Path,
+    max_age: Duration,
+) -> Vec<SessionFile> {
     let mut sessions = Vec::new();
+    walk_for_jsonl(
+        probe,
+        &claude_dir.join("projects"),
+        SystemTime::now(),
+        max_age,
+        &mut sessions,
+    );
+    sessions
+}
 
-    walk_for_jsonl(&projects_dir, now, &mut sessions);
+fn scan_sessions_batched_with<P: FsProbe>(
+    probe: &mut P,
+    claude_dir: &Path,
+    max_age: Duration,
+) -> Vec<SessionFile> {
+    let now = SystemTime::now();
+    let mut sessions = Vec::new();
+    let mut dirs = vec![claude_dir.join("projects")];
+    while let Some(dir) = dirs.pop() {
+        for (path, stat) in probe.list_dir(&dir) {
+            if stat.is_dir {
+                dirs.push(path);
+            } else if is_session_candidate(&path) && is_recent(stat.modified, now, max_age) {
+                sessions.push(SessionFile {
+                    path,
+                    len: stat.len,
+                });
+            }
+        }
+    }
     sessions
 }
 
-/// Recursively walk a directory, collecting .jsonl files modified within DORMANCY_TIMEOUT.
-fn walk_for_jsonl(dir: &Path, now: SystemTime, out: &mut Vec<PathBuf>) {
-    let entries = match std::fs::read_dir(dir) {
-        Ok(entries) => entries,
-        Err(_) => return,
-    };
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.is_dir() {
-            walk_for_jsonl(&path, now, out);
-        } else if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
-            if let Ok(metadata) = path.metadata() {
-                if let Ok(modified) = metadata.modified() {
-                    if let Ok(elapsed) = now.duration_since(modified) {
-                        if elapsed <= DORMANCY_TIMEOUT {
-                            out.push(path);
-                        }
-                    }
+/// Recursively walk a directory, collecting .jsonl files modified within `max_age`.
+fn walk_for_jsonl<P: FsProbe>(
+    probe: &mut P,
+    dir: &Path,
+    now: SystemTime,
+    max_age: Duration,
+    out: &mut Vec<SessionFile>,
+) {
+    for path in probe.read_dir(dir) {
+        let Some(stat) = probe.stat(&path) else {
+            continue;
+        };
+        if stat.is_dir {
+            walk_for_jsonl(probe, &path, now, max_age, out);
+        } else if is_session_candidate(&path) && is_recent(stat.modified, now, max_age) {
+            out.push(SessionFile {
+                path,
+                len: stat.len,
+            });
+        }
+    }
+}
+
+fn is_session_candidate(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("jsonl") && !is_sidechain_file(path)
+}
+
+/// Modified within `max_age` of `now`. An mtime in the future (a remote
+/// clock running ahead) counts as just now.
+fn is_recent(modified: Option<SystemTime>, now: SystemTime, max_age: Duration) -> bool {
+    modified.is_some_and(|modified| {
+        now.duration_since(modified).unwrap_or_default() <= max_age + MTIME_GRANULARITY
+    })
+}
+
+/// Whether `path` is a Task sub-agent's transcript rather than a session:
+/// `<session>/subagents/*.jsonl`, or `agent-*.jsonl` beside the sessions in
+/// older Claude Code versions.
+pub fn is_sidechain_file(path: &Path) -> bool {
+    let in_subagents_dir = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .is_some_and(|name| name == "subagents");
+    let agent_named = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with("agent-"));
+    in_subagents_dir || agent_named
+}
+
+/// Active sidechain transcripts that may belong to `session_file`'s
+/// sub-agents, sorted by path. Which Task each one runs is decided from its
+/// opening prompt; see `sidechain_opening`.
+pub fn sidechain_files(session_file: &Path) -> Vec<PathBuf> {
+    let now = SystemTime::now();
+    let mut files = Vec::new();
+    let subagents_dir = session_file.with_extension("").join("subagents");
+    let siblings = session_file.parent().map(Path::to_path_buf);
+    for dir in std::iter::once(subagents_dir).chain(siblings) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        files.extend(entries.flatten().map(|e| e.path()).filter(|path| {
+            path.extension().is_some_and(|e| e == "jsonl")
+                && is_sidechain_file(path)
+                && path
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .is_ok_and(|modified| {
+                        now.duration_since(modified)
+                            .is_ok_and(|elapsed| elapsed <= DORMANCY_TIMEOUT)
+                    })
+        }));
+    }
+    files.sort();
+    files
+}
+
+/// The prompt a sidechain transcript opens with, once it has been written.
+pub fn sidechain_opening(path: &Path) -> Option<String> {
+    let reader = BufReader::new(File::open(path).ok()?);
+    reader
+        .lines()
+        .take(SIDECHAIN_OPENING_LINES)
+        .map_while(Result::ok)
+        .filter_map(|line| parser::parse_line(&line))
+        .find(|record| matches!(record, JsonlRecord::User { .. }))
+        .and_then(|record| parser::sidechain_prompt(&record))
+}
+
+/// Find the most recently modified .jsonl file under `claude_dir/projects`,
+/// regardless of age.
+pub fn newest_session_file(claude_dir: &Path) -> Option<PathBuf> {
+    fn walk(dir: &Path, best: &mut Option<(SystemTime, PathBuf)>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, best);
+            } else if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
+                let Ok(modified) = path.metadata().and_then(|m| m.modified()) else {
+                    continue;
+                };
+                if best.as_ref().is_none_or(|(t, _)| modified > *t) {
+                    *best = Some((modified, path));
                 }
             }
         }
     }
+
+    let mut best = None;
+    walk(&claude_dir.join("projects"), &mut best);
+    best.map(|(_, path)| path)
+}
+
+/// The project directory name a session file sits in.
+fn project_key(path: &Path) -> Option<&OsStr> {
+    path.parent().and_then(Path::file_name)
+}
+
+/// File inside the state directory that keeps session IDs between runs.
+pub const SESSIONS_FILE_NAME: &str = "sessions.json";
+/// Format version of [`SESSIONS_FILE_NAME`] written by this build.
+pub const SESSIONS_VERSION: u64 = 1;
+/// Sessions gone for longer than this lose their ID for good.
+pub const SESSION_ID_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The ID a session had, as kept in [`SESSIONS_FILE_NAME`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SavedSession {
+    path: PathBuf,
+    id: u32,
+    /// Unix seconds when the session was last tracked.
+    last_seen: u64,
+    /// The session carries on in another file, under `id`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    retired: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedSessions {
+    version: u64,
+    sessions: Vec<SavedSession>,
+}
+
+impl Default for SavedSessions {
+    fn default() -> Self {
+        Self {
+            version: SESSIONS_VERSION,
+            sessions: Vec::new(),
+        }
+    }
+}
+
+impl Versioned for SavedSessions {
+    const LABEL: &'static str = "sessions file";
+    const MIGRATIONS: &'static [Migration] = &[];
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
 }
 
 /// Track known sessions with incremental IDs, detect new/removed sessions.
+///
+/// A session that stops being tracked keeps its ID for
+/// [`SESSION_ID_RETENTION`], so one that went dormant and came back is
+/// still the same agent. With a state file, that memory outlives the
+/// process as well.
 pub struct SessionTracker {
     known: HashMap<PathBuf, u32>,
+    /// Sessions no longer tracked, with their ID and when they left.
+    gone: HashMap<PathBuf, (u32, u64)>,
+    /// Files of sessions resumed in another file, never tracked again, with
+    /// the ID the session carries on under and when it moved.
+    retired: HashMap<PathBuf, (u32, u64)>,
     next_id: u32,
+    state_path: Option<PathBuf>,
+    /// The state file is from a newer version or can't be read; leave it be.
+    read_only: bool,
+    /// What went wrong loading the state file, until taken.
+    warning: Option<String>,
 }
 
 impl Default for SessionTracker {
     fn default() -> Self {
-        Self::new()
+        Self::new(None)
     }
 }
 
 impl SessionTracker {
-    pub fn new() -> Self {
-        Self {
+    /// A tracker that remembers IDs in `state_path`, picking up those of a
+    /// previous run. The file is loaded like the state file (see
+    /// [`persist::load_versioned`]): a corrupt one is moved to `.bak`, and
+    /// one from a newer version is never saved over.
+    pub fn new(state_path: Option<PathBuf>) -> Self {
+        let loaded = match state_path.as_deref() {
+            Some(path) => persist::load_versioned(path),
+            None => LoadOutcome {
+                state: SavedSessions::default(),
+                read_only: false,
+                warning: None,
+            },
+        };
+        let saved: SavedSessions = loaded.state;
+        let (retired, gone): (Vec<SavedSession>, Vec<SavedSession>) = saved
+            .sessions
+            .into_iter()
+            .partition(|session| session.retired);
+        let entries = |sessions: Vec<SavedSession>| {
+            sessions
+                .into_iter()
+                .map(|session| (session.path, (session.id, session.last_seen)))
+                .collect()
+        };
+        let mut tracker = Self {
             known: HashMap::new(),
+            gone: entries(gone),
+            retired: entries(retired),
             next_id: 1,
-        }
+            state_path,
+            read_only: loaded.read_only,
+            warning: loaded.warning,
+        };
+        tracker.prune(unix_now());
+        tracker.next_id = tracker
+            .gone
+            .values()
+            .map(|&(id, _)| id + 1)
+            .max()
+            .unwrap_or(1);
+        tracker
     }
 
     /// Update the tracker with the current list of active session paths.
     ///
+    /// Sessions seen before get their old IDs back. Others discovered in the
+    /// same call get IDs in a fixed order, not in the order of `current`: by
+    /// project directory name, then by path. A batch of sessions started
+    /// together (say, one per git worktree) therefore gets the same IDs
+    /// however the filesystem lists them.
+    ///
     /// Returns a tuple of:
-    /// - `new_sessions`: Vec of (id, path) for newly discovered sessions
-    /// - `removed_ids`: Vec of IDs for sessions no longer present
+    /// - `new_sessions`: Vec of (id, path) for newly discovered sessions, in
+    ///   ID order
+    /// - `removed_ids`: Vec of IDs for sessions no longer present, ascending
     pub fn update(&mut self, current: &[PathBuf]) -> (Vec<(u32, PathBuf)>, Vec<u32>) {
         let current_set: std::collections::HashSet<&PathBuf> = current.iter().collect();
+        let now = unix_now();
 
-        // Find removed sessions
-        let removed: Vec<u32> = self
+        // Find removed sessions, remembering their IDs
+        let removed_paths: Vec<PathBuf> = self
             .known
+            .keys()
+            .filter(|path| !current_set.contains(path))
+            .cloned()
+            .collect();
+        let mut removed = Vec::new();
+        for path in removed_paths {
+            if let Some(id) = self.known.remove(&path) {
+                self.gone.insert(path, (id, now));
+                removed.push(id);
+            }
+        }
+        removed.sort_unstable();
+
+        // Find new sessions and assign IDs
+        let mut fresh: Vec<&PathBuf> = current
             .iter()
-            .filter(|(path, _)| !current_set.contains(path))
-            .map(|(_, &id)| id)
+            .filter(|path| !self.known.contains_key(*path) && !self.retired.contains_key(*path))
             .collect();
+        fresh.sort_by(|a, b| (project_key(a), a).cmp(&(project_key(b), b)));
+        fresh.dedup();
+        let mut new_sessions: Vec<(u32, PathBuf)> = fresh
+            .into_iter()
+            .map(|path| (self.assign(path), path.clone()))
+            .collect();
+        new_sessions.sort_unstable();
 
-        // Remove them from known
-        self.known.retain(|path, _| current_set.contains(path));
+        if !new_sessions.is_empty() || !removed.is_empty() {
+            self.save();
+        }
+        (new_sessions, removed)
+    }
 
-        // Find new sessions and assign IDs
-        let mut new_sessions = Vec::new();
-        for path in current {
-            if !self.known.contains_key(path) {
-                let id = self.next_id;
-                self.next_id += 1;
-                self.known.insert(path.clone(), id);
-                new_sessions.push((id, path.clone()));
-            }
+    /// Start tracking one session, e.g. on a file-created event. Returns
+    /// its ID, or None if it was already known.
+    pub fn add(&mut self, path: &Path) -> Option<u32> {
+        if self.known.contains_key(path) || self.retired.contains_key(path) {
+            return None;
         }
+        let id = self.assign(path);
+        self.save();
+        Some(id)
+    }
 
-        (new_sessions, removed)
+    /// Stop tracking one session, e.g. on a file-removed event.
+    pub fn remove(&mut self, path: &Path) -> Option<u32> {
+        let id = self.known.remove(path)?;
+        self.gone.insert(path.to_path_buf(), (id, unix_now()));
+        self.save();
+        Some(id)
+    }
+
+    /// Track `path`, a session resumed from the one tracked as `into`,
+    /// under `into`'s ID. The earlier file is retired: its session lives
+    /// on in `path`. Returns the ID `path` had, now free, or None unless
+    /// both sessions are tracked.
+    pub fn resume(&mut self, path: &Path, into: u32) -> Option<u32> {
+        let earlier = self
+            .known
+            .iter()
+            .find(|&(_, &id)| id == into)
+            .map(|(path, _)| path.clone())?;
+        let freed = *self.known.get(path)?;
+        if freed == into {
+            return None;
+        }
+        self.known.remove(&earlier);
+        self.known.insert(path.to_path_buf(), into);
+        self.retired.insert(earlier, (into, unix_now()));
+        // The ID was most likely just handed out; the next session gets it
+        if freed + 1 == self.next_id {
+            self.next_id = freed;
+        }
+        self.save();
+        Some(freed)
+    }
+
+    /// What went wrong loading the state file, once.
+    pub fn take_warning(&mut self) -> Option<String> {
+        self.warning.take()
     }
 
     /// Look up the ID for a given session path.
     pub fn get_id(&self, path: &Path) -> Option<u32> {
         self.known.get(path).copied()
     }
+
+    /// Write every ID still remembered to the state file, if there is one.
+    /// Tracked sessions count as seen now. Best effort: a tracker that
+    /// can't save still numbers sessions, just not across restarts.
+    pub fn save(&mut self) {
+        let now = unix_now();
+        self.prune(now);
+        let Some(path) = self.state_path.as_ref().filter(|_| !self.read_only) else {
+            return;
+        };
+        let mut sessions: Vec<SavedSession> = self
+            .known
+            .iter()
+            .map(|(path, &id)| (path, id, now, false))
+            .chain(
+                self.gone
+                    .iter()
+                    .map(|(path, &(id, left))| (path, id, left, false)),
+            )
+            .chain(
+                self.retired
+                    .iter()
+                    .map(|(path, &(id, moved))| (path, id, moved, true)),
+            )
+            .map(|(path, id, last_seen, retired)| SavedSession {
+                path: path.clone(),
+                id,
+                last_seen,
+                retired,
+            })
+            .collect();
+        sessions.sort_unstable_by_key(|session| session.id);
+        let _ = persist::save(
+            path,
+            &SavedSessions {
+                version: SESSIONS_VERSION,
+                sessions,
+            },
+        );
+    }
+
+    /// Track `path` under its old ID if it has one, else the next one.
+    fn assign(&mut self, path: &Path) -> u32 {
+        let id = match self.gone.remove(path) {
+            Some((id, _)) => id,
+            None => {
+                self.next_id += 1;
+                self.next_id - 1
+            }
+        };
+        self.known.insert(path.to_path_buf(), id);
+        id
+    }
+
+    /// Forget sessions gone for longer than [`SESSION_ID_RETENTION`].
+    fn prune(&mut self, now: u64) {
+        let cutoff = now.saturating_sub(SESSION_ID_RETENTION.as_secs());
+        self.gone.retain(|_, &mut (_, left)| left >= cutoff);
+        self.retired.retain(|_, &mut (_, moved)| moved >= cutoff);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::project::encode_project_dir;
+
+    #[test]
+    fn project_filter_matches_the_mangled_directory_name() {
+        let projects = Path::new("/home/u/.claude/projects");
+        let sessions = vec![
+            projects.join("-home-u-my-repo").join("a.jsonl"),
+            projects.join("-home-u-my-repo-v2").join("b.jsonl"),
+            projects.join("-home-u-other").join("c.jsonl"),
+        ];
+        // `.` and `/` both become `-`
+        let key = encode_project_dir(Path::new("/home/u/my.repo"));
+        assert_eq!(key, "-home-u-my-repo");
+        assert_eq!(
+            filter_to_project(sessions.clone(), Some(&key)),
+            vec![sessions[0].clone()]
+        );
+        assert!(filter_to_project(sessions, Some("-home-u-missing")).is_empty());
+    }
+
+    #[test]
+    fn project_filter_defaults_to_all_projects() {
+        let sessions = vec![
+            PathBuf::from("/c/projects/-a/s.jsonl"),
+            PathBuf::from("/c/projects/-b/s.jsonl"),
+        ];
+        assert_eq!(filter_to_project(sessions.clone(), None), sessions);
+        assert!(in_project(Path::new("s.jsonl"), None));
+        assert!(!in_project(Path::new("s.jsonl"), Some("-a")));
+    }
+
+    #[test]
+    fn finds_sidechains_of_a_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        let subagents = project.join("abc").join("subagents");
+        std::fs::create_dir_all(&subagents).unwrap();
+        let session = project.join("abc.jsonl");
+        for path in [
+            session.clone(),
+            project.join("agent-old.jsonl"),
+            subagents.join("agent-new.jsonl"),
+        ] {
+            std::fs::write(path, "").unwrap();
+        }
+
+        assert_eq!(
+            sidechain_files(&session),
+            vec![
+                project
+                    .join("abc")
+                    .join("subagents")
+                    .join("agent-new.jsonl"),
+                project.join("agent-old.jsonl"),
+            ]
+        );
+        // Sidechains never show up as sessions of their own
+        assert_eq!(scan_sessions(dir.path(), DORMANCY_TIMEOUT), vec![session]);
+    }
+
+    #[test]
+    fn reads_the_opening_prompt_of_a_sidechain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent-1.jsonl");
+        assert_eq!(sidechain_opening(&path), None);
+        std::fs::write(
+            &path,
+            concat!(
+                "not json\n",
+                r#"{"type":"user","isSidechain":true,"message":{"content":"Find the auth code"}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            sidechain_opening(&path).as_deref(),
+            Some("Find the auth code")
+        );
+    }
 
     #[test]
     fn tracker_assigns_incremental_ids() {
-        let mut tracker = SessionTracker::new();
+        let mut tracker = SessionTracker::default();
         let paths = vec![PathBuf::from("/tmp/a.jsonl"), PathBuf::from("/tmp/b.jsonl")];
         let (new, removed) = tracker.update(&paths);
         assert_eq!(new.len(), 2);
@@ -118,9 +665,54 @@ mod tests {
         assert!(removed.is_empty());
     }
 
+    #[test]
+    fn simultaneous_sessions_get_ids_in_a_fixed_order() {
+        let paths: Vec<PathBuf> = [
+            "/c/projects/-src-wt-b/s2.jsonl",
+            "/c/projects/-src-wt-a/s9.jsonl",
+            "/c/projects/-src-wt-c/s1.jsonl",
+            "/c/projects/-src-wt-a/s3.jsonl",
+            "/d/projects/-src-wt-b/s0.jsonl",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let assign = |order: &[PathBuf]| {
+            let mut tracker = SessionTracker::default();
+            tracker.update(&[PathBuf::from("/c/projects/-src-main/s.jsonl")]);
+            let (mut new, _) = tracker.update(order);
+            new.sort();
+            new
+        };
+
+        let expected = assign(&paths);
+        let ids: Vec<(u32, &str)> = expected
+            .iter()
+            .map(|(id, p)| (*id, p.to_str().unwrap()))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                (2, "/c/projects/-src-wt-a/s3.jsonl"),
+                (3, "/c/projects/-src-wt-a/s9.jsonl"),
+                (4, "/c/projects/-src-wt-b/s2.jsonl"),
+                (5, "/d/projects/-src-wt-b/s0.jsonl"),
+                (6, "/c/projects/-src-wt-c/s1.jsonl"),
+            ]
+        );
+        // Every rotation, reversed or not, lands on the same IDs
+        for shift in 0..paths.len() {
+            let mut shuffled = paths.clone();
+            shuffled.rotate_left(shift);
+            assert_eq!(assign(&shuffled), expected);
+            shuffled.reverse();
+            assert_eq!(assign(&shuffled), expected);
+        }
+    }
+
     #[test]
     fn tracker_detects_removed_sessions() {
-        let mut tracker = SessionTracker::new();
+        let mut tracker = SessionTracker::default();
         let paths = vec![PathBuf::from("/tmp/a.jsonl"), PathBuf::from("/tmp/b.jsonl")];
         tracker.update(&paths);
         let (new, removed) = tracker.update(&[PathBuf::from("/tmp/a.jsonl")]);
@@ -130,7 +722,7 @@ mod tests {
 
     #[test]
     fn tracker_detects_new_sessions() {
-        let mut tracker = SessionTracker::new();
+        let mut tracker = SessionTracker::default();
         tracker.update(&[PathBuf::from("/tmp/a.jsonl")]);
         let (new, _) =
             tracker.update(&[PathBuf::from("/tmp/a.jsonl"), PathBuf::from("/tmp/c.jsonl")]);
@@ -138,17 +730,293 @@ mod tests {
         assert_eq!(new[0].0, 2);
     }
 
+    #[test]
+    fn tracker_adds_and_removes_single_sessions() {
+        let mut tracker = SessionTracker::default();
+        tracker.update(&[PathBuf::from("/tmp/a.jsonl")]);
+        assert_eq!(tracker.add(Path::new("/tmp/b.jsonl")), Some(2));
+        assert_eq!(tracker.add(Path::new("/tmp/b.jsonl")), None);
+        assert_eq!(tracker.remove(Path::new("/tmp/a.jsonl")), Some(1));
+        assert_eq!(tracker.remove(Path::new("/tmp/a.jsonl")), None);
+        // A later full scan agrees with the incremental changes
+        let (new, removed) = tracker.update(&[PathBuf::from("/tmp/b.jsonl")]);
+        assert!(new.is_empty() && removed.is_empty());
+    }
+
+    #[test]
+    fn reappearing_sessions_get_their_old_ids() {
+        let mut tracker = SessionTracker::default();
+        let (a, b, c) = (
+            PathBuf::from("/tmp/a.jsonl"),
+            PathBuf::from("/tmp/b.jsonl"),
+            PathBuf::from("/tmp/c.jsonl"),
+        );
+        tracker.update(&[a.clone(), b.clone()]);
+        // `b` goes dormant and drops out of the scan while `c` starts
+        let (new, removed) = tracker.update(&[a.clone(), c.clone()]);
+        assert_eq!((new, removed), (vec![(3, c.clone())], vec![2]));
+        let (new, _) = tracker.update(&[a.clone(), b.clone(), c.clone()]);
+        assert_eq!(new, vec![(2, b.clone())]);
+        // Same through single-file events
+        assert_eq!(tracker.remove(&a), Some(1));
+        assert_eq!(tracker.add(&a), Some(1));
+        assert_eq!(tracker.add(Path::new("/tmp/d.jsonl")), Some(4));
+    }
+
+    #[test]
+    fn ids_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("state").join(SESSIONS_FILE_NAME);
+        let paths: Vec<PathBuf> = ["/c/projects/-a/s1.jsonl", "/c/projects/-b/s2.jsonl"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let mut tracker = SessionTracker::new(Some(state.clone()));
+        tracker.update(&[paths[0].clone()]);
+        tracker.update(&paths);
+        tracker.update(&[paths[1].clone()]);
+        drop(tracker);
+
+        // Listed in the other order, and including one never seen before
+        let mut tracker = SessionTracker::new(Some(state.clone()));
+        let newcomer = PathBuf::from("/c/projects/-a/s0.jsonl");
+        let (new, _) = tracker.update(&[paths[1].clone(), newcomer.clone(), paths[0].clone()]);
+        assert_eq!(
+            new,
+            vec![(1, paths[0].clone()), (2, paths[1].clone()), (3, newcomer)]
+        );
+
+        assert!(std::fs::read_to_string(&state)
+            .unwrap()
+            .contains(r#""version":1"#));
+    }
+
+    #[test]
+    fn unreadable_sessions_files_are_kept_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join(SESSIONS_FILE_NAME);
+        let path = PathBuf::from("/c/projects/-a/s1.jsonl");
+
+        // A corrupt file is moved to .bak before a fresh one is written
+        std::fs::write(&state, "{ not json").unwrap();
+        let mut tracker = SessionTracker::new(Some(state.clone()));
+        assert!(tracker.take_warning().unwrap().contains(".bak"));
+        assert_eq!(tracker.take_warning(), None);
+        let (new, _) = tracker.update(std::slice::from_ref(&path));
+        assert_eq!(new, vec![(1, path.clone())]);
+        let mut backup = state.clone().into_os_string();
+        backup.push(".bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), "{ not json");
+        assert!(state.exists());
+
+        // A newer version's IDs are used but never saved over
+        let newer = r#"{"version":9,"sessions":[{"path":"/c/projects/-a/s1.jsonl","id":5,"last_seen":4102444800}],"extra":true}"#;
+        std::fs::write(&state, newer).unwrap();
+        let mut tracker = SessionTracker::new(Some(state.clone()));
+        assert!(tracker.take_warning().unwrap().contains("version 9"));
+        let (new, _) = tracker.update(&[path.clone(), PathBuf::from("/c/projects/-b/s2.jsonl")]);
+        assert_eq!(new[0], (5, path));
+        assert_eq!(std::fs::read_to_string(&state).unwrap(), newer);
+    }
+
+    #[test]
+    fn resumed_sessions_keep_the_earlier_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join(SESSIONS_FILE_NAME);
+        let earlier = PathBuf::from("/c/projects/-a/s1.jsonl");
+        let other = PathBuf::from("/c/projects/-b/s2.jsonl");
+        let resumed = PathBuf::from("/c/projects/-a/s3.jsonl");
+        let mut tracker = SessionTracker::new(Some(state.clone()));
+        tracker.update(&[earlier.clone(), other.clone()]);
+        assert_eq!(tracker.add(&resumed), Some(3));
+
+        assert_eq!(tracker.resume(&resumed, 1), Some(3));
+        assert_eq!(tracker.get_id(&resumed), Some(1));
+        assert_eq!(tracker.get_id(&earlier), None);
+        // Not again, and not for unknown sessions
+        assert_eq!(tracker.resume(&resumed, 1), None);
+        assert_eq!(tracker.resume(&resumed, 7), None);
+        // The earlier file stays put on disk for a while; it's no new session
+        let (new, removed) = tracker.update(&[earlier.clone(), other.clone(), resumed.clone()]);
+        assert!(new.is_empty() && removed.is_empty());
+        assert_eq!(tracker.add(&earlier), None);
+        // The freed ID goes to the next session
+        let newcomer = PathBuf::from("/c/projects/-c/s4.jsonl");
+        assert_eq!(tracker.add(&newcomer), Some(3));
+        drop(tracker);
+
+        let mut tracker = SessionTracker::new(Some(state));
+        let (new, _) = tracker.update(&[earlier, other, resumed.clone(), newcomer]);
+        assert_eq!(new.len(), 3);
+        assert!(new.contains(&(1, resumed)));
+    }
+
+    #[test]
+    fn ids_gone_past_retention_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join(SESSIONS_FILE_NAME);
+        let now = unix_now();
+        let day = SESSION_ID_RETENTION.as_secs();
+        let saved = |sessions: &[(&str, u32, u64)]| SavedSessions {
+            version: SESSIONS_VERSION,
+            sessions: sessions
+                .iter()
+                .map(|&(path, id, last_seen)| SavedSession {
+                    path: PathBuf::from(path),
+                    retired: false,
+                    id,
+                    last_seen,
+                })
+                .collect(),
+        };
+        persist::save(
+            &state,
+            &saved(&[
+                ("/tmp/recent.jsonl", 4, now - 3600),
+                ("/tmp/stale.jsonl", 7, now - day - 60),
+            ]),
+        )
+        .unwrap();
+
+        let mut tracker = SessionTracker::new(Some(state.clone()));
+        let (new, _) = tracker.update(&[
+            PathBuf::from("/tmp/recent.jsonl"),
+            PathBuf::from("/tmp/stale.jsonl"),
+        ]);
+        assert_eq!(
+            new,
+            vec![
+                (4, PathBuf::from("/tmp/recent.jsonl")),
+                (5, PathBuf::from("/tmp/stale.jsonl")),
+            ]
+        );
+        // The file drops the stale entry and marks tracked sessions as seen
+        let written: SavedSessions =
+            serde_json::from_slice(&std::fs::read(&state).unwrap()).unwrap();
+        assert_eq!(written.sessions.len(), 2);
+        assert!(written
+            .sessions
+            .iter()
+            .all(|session| session.last_seen >= now && session.id != 7));
+
+        // Once everything has aged out, numbering starts over
+        persist::save(&state, &saved(&[("/tmp/stale.jsonl", 7, now - 2 * day)])).unwrap();
+        let mut tracker = SessionTracker::new(Some(state));
+        assert_eq!(tracker.add(Path::new("/tmp/other.jsonl")), Some(1));
+    }
+
     #[test]
     fn tracker_get_id() {
-        let mut tracker = SessionTracker::new();
+        let mut tracker = SessionTracker::default();
         tracker.update(&[PathBuf::from("/tmp/a.jsonl")]);
         assert_eq!(tracker.get_id(&PathBuf::from("/tmp/a.jsonl")), Some(1));
         assert_eq!(tracker.get_id(&PathBuf::from("/tmp/nope.jsonl")), None);
     }
 
+    #[test]
+    fn newest_session_file_picks_latest() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("projects").join("p");
+        std::fs::create_dir_all(&project).unwrap();
+        let old = project.join("old.jsonl");
+        let new = project.join("new.jsonl");
+        std::fs::write(&old, "").unwrap();
+        std::fs::write(&new, "").unwrap();
+        let past = SystemTime::now() - Duration::from_secs(3600);
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+        assert_eq!(newest_session_file(dir.path()), Some(new));
+        assert_eq!(newest_session_file(Path::new("/nonexistent")), None);
+    }
+
+    /// Counts the calls a scan makes.
+    #[derive(Default)]
+    struct CountingFs {
+        read_dirs: usize,
+        stats: usize,
+        listings: usize,
+    }
+
+    impl FsProbe for CountingFs {
+        fn read_dir(&mut self, dir: &Path) -> Vec<PathBuf> {
+            self.read_dirs += 1;
+            RealFs.read_dir(dir)
+        }
+
+        fn stat(&mut self, path: &Path) -> Option<FileStat> {
+            self.stats += 1;
+            RealFs.stat(path)
+        }
+
+        fn list_dir(&mut self, dir: &Path) -> Vec<(PathBuf, FileStat)> {
+            self.listings += 1;
+            RealFs.list_dir(dir)
+        }
+    }
+
+    /// `projects/` with `projects` project directories of `files` sessions each.
+    fn claude_dir_with(projects: usize, files: usize) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for p in 0..projects {
+            let project = dir.path().join("projects").join(format!("p{p}"));
+            std::fs::create_dir_all(&project).unwrap();
+            for f in 0..files {
+                std::fs::write(project.join(format!("s{f}.jsonl")), "{}\n").unwrap();
+            }
+        }
+        dir
+    }
+
+    #[test]
+    fn batched_scan_makes_one_call_per_directory() {
+        for (projects, files) in [(1, 1), (3, 5), (4, 40)] {
+            let dir = claude_dir_with(projects, files);
+            let mut standard = CountingFs::default();
+            let found = scan_sessions_with(&mut standard, dir.path(), DORMANCY_TIMEOUT);
+            assert_eq!(found.len(), projects * files);
+            assert_eq!(standard.stats, projects * (files + 1));
+
+            let mut batched = CountingFs::default();
+            let mut found_batched =
+                scan_sessions_batched_with(&mut batched, dir.path(), DORMANCY_TIMEOUT);
+            // projects/ and each project directory, nothing per file
+            assert_eq!(batched.listings, projects + 1);
+            assert_eq!(batched.stats + batched.read_dirs, 0);
+            found_batched.sort_by(|a, b| a.path.cmp(&b.path));
+            let mut found = found;
+            found.sort_by(|a, b| a.path.cmp(&b.path));
+            assert_eq!(found_batched, found);
+        }
+    }
+
+    #[test]
+    fn coarse_and_future_mtimes_count_as_recent() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let max_age = Duration::from_secs(300);
+        // Whole-second mtime just past the window
+        assert!(is_recent(
+            Some(now - max_age - Duration::from_millis(600)),
+            now,
+            max_age
+        ));
+        assert!(!is_recent(
+            Some(now - max_age - Duration::from_secs(2)),
+            now,
+            max_age
+        ));
+        // Remote clock ahead of ours
+        assert!(is_recent(Some(now + Duration::from_secs(90)), now, max_age));
+        assert!(!is_recent(None, now, max_age));
+    }
+
     #[test]
     fn scan_sessions_handles_missing_dir() {
-        let sessions = scan_sessions(Path::new("/nonexistent/path"));
+        let sessions = scan_sessions(Path::new("/nonexistent/path"), DORMANCY_TIMEOUT);
         assert!(sessions.is_empty());
+        assert!(scan_sessions_batched(Path::new("/nonexistent/path"), DORMANCY_TIMEOUT).is_empty());
     }
 }
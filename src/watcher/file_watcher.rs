@@ -1,11 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
 
-use crate::watcher::parser;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::watcher::archive;
+use crate::watcher::format::{self, TranscriptFormat};
 use crate::watcher::types::JsonlRecord;
 
+/// Lines longer than this (a record stuffed with base64 images, say) are
+/// skipped without being held in memory.
+pub const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// What one read call picked up.
+#[derive(Debug, Default)]
+pub struct ReadBatch {
+    pub records: Vec<JsonlRecord>,
+    /// Bytes consumed from the file, including unparsable and oversized
+    /// lines.
+    pub bytes_read: u64,
+    /// Whether the read reached the end of the file, or a last line still
+    /// being written (not cut off by a limit).
+    pub at_eof: bool,
+}
+
 pub struct IncrementalReader {
     offsets: HashMap<PathBuf, u64>,
 }
@@ -24,14 +45,46 @@ impl IncrementalReader {
     }
 
     pub fn read_new_lines(&mut self, path: &Path) -> Vec<JsonlRecord> {
+        self.read_new_lines_limited(path, usize::MAX)
+    }
+
+    /// Like `read_new_lines`, but stops after `max_lines` lines so the rest of
+    /// the file is picked up on a later call.
+    pub fn read_new_lines_limited(&mut self, path: &Path, max_lines: usize) -> Vec<JsonlRecord> {
+        self.read_batch(path, max_lines).records
+    }
+
+    /// Read up to `max_lines` new lines, reporting how many bytes they took.
+    /// Lines are parsed as Claude Code records.
+    pub fn read_batch(&mut self, path: &Path, max_lines: usize) -> ReadBatch {
+        self.read_batch_as(path, max_lines, &mut Some(format::DEFAULT_FORMAT))
+    }
+
+    /// Like `read_batch`, parsing lines in `format`. While `format` is
+    /// None, each line is sniffed and the first one in a known dialect
+    /// sets it; lines before that are skipped.
+    ///
+    /// Only lines ending in a newline are consumed: a last line the writer
+    /// is still appending to is left for the next call. Lines over
+    /// [`MAX_LINE_BYTES`] are consumed unparsed.
+    pub fn read_batch_as(
+        &mut self,
+        path: &Path,
+        max_lines: usize,
+        format: &mut Option<&'static dyn TranscriptFormat>,
+    ) -> ReadBatch {
+        // Archives are never appended to; see `archive` for reading them
+        if archive::is_compressed(path) {
+            return ReadBatch::default();
+        }
         let file = match File::open(path) {
             Ok(f) => f,
-            Err(_) => return Vec::new(),
+            Err(_) => return ReadBatch::default(),
         };
 
         let file_len = match file.metadata() {
             Ok(m) => m.len(),
-            Err(_) => return Vec::new(),
+            Err(_) => return ReadBatch::default(),
         };
 
         let canonical = path.to_path_buf();
@@ -46,34 +99,180 @@ impl IncrementalReader {
 
         let mut reader = BufReader::new(file);
         if reader.seek(SeekFrom::Start(offset)).is_err() {
-            return Vec::new();
+            return ReadBatch::default();
         }
 
         let mut records = Vec::new();
         let mut current_offset = offset;
-        let mut line_buf = String::new();
+        let mut line_buf = Vec::new();
+        let mut lines_read = 0;
+        let mut at_eof = false;
 
-        loop {
+        while lines_read < max_lines {
             line_buf.clear();
-            match reader.read_line(&mut line_buf) {
-                Ok(0) => break, // EOF
-                Ok(bytes_read) => {
-                    current_offset += bytes_read as u64;
-                    if let Some(record) = parser::parse_line(&line_buf) {
-                        records.push(record);
-                    }
-                }
+            let limit = MAX_LINE_BYTES as u64 + 1;
+            let bytes_read = match reader.by_ref().take(limit).read_until(b'\n', &mut line_buf) {
+                Ok(n) => n,
                 Err(_) => break,
+            };
+            if line_buf.ends_with(b"\n") {
+                lines_read += 1;
+                current_offset += bytes_read as u64;
+                let Ok(line) = std::str::from_utf8(&line_buf) else {
+                    continue;
+                };
+                if format.is_none() {
+                    *format = format::sniff(line);
+                }
+                if let Some(record) = format.and_then(|f| f.parse_line(line)) {
+                    records.push(record);
+                }
+            } else if bytes_read > MAX_LINE_BYTES {
+                // Too long to parse; the rest of it, once written, fails
+                // to parse on its own
+                lines_read += 1;
+                current_offset += bytes_read as u64 + skip_line(&mut reader);
+            } else {
+                // End of file, possibly mid-line
+                at_eof = true;
+                break;
             }
         }
 
         self.offsets.insert(canonical, current_offset);
-        records
+        ReadBatch {
+            records,
+            bytes_read: current_offset - offset,
+            at_eof,
+        }
     }
 
     pub fn remove(&mut self, path: &Path) {
         self.offsets.remove(path);
     }
+
+    /// Whether a read offset is kept for `path`.
+    pub fn is_tracking(&self, path: &Path) -> bool {
+        self.offsets.contains_key(path)
+    }
+
+    /// Forget the offsets of every file not in `paths`.
+    pub fn retain(&mut self, paths: &HashSet<&Path>) {
+        self.offsets
+            .retain(|path, _| paths.contains(path.as_path()));
+    }
+}
+
+/// Consume up to and including the next newline, or to the end of the
+/// file, returning the bytes skipped.
+fn skip_line(reader: &mut impl BufRead) -> u64 {
+    let mut skipped = 0;
+    loop {
+        let (consumed, done) = match reader.fill_buf() {
+            Ok([]) | Err(_) => return skipped,
+            Ok(buf) => match buf.iter().position(|&b| b == b'\n') {
+                Some(end) => (end + 1, true),
+                None => (buf.len(), false),
+            },
+        };
+        reader.consume(consumed);
+        skipped += consumed as u64;
+        if done {
+            return skipped;
+        }
+    }
+}
+
+/// A filesystem change to a session file, as reported by [`FileWatcher`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsChange {
+    Created(PathBuf),
+    Modified(PathBuf),
+    Removed(PathBuf),
+    /// Events were lost (e.g. the kernel queue overflowed); rescan everything.
+    Rescan,
+}
+
+/// Watches the `projects` directories of the Claude data roots and reports
+/// changes to `.jsonl` files, so only files that changed need reading.
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl FileWatcher {
+    /// Watch `<root>/projects` recursively for every root that has one.
+    /// Fails if none does or a watch can't be set up (e.g. the inotify
+    /// watch limit is reached); callers then fall back to polling.
+    pub fn new(roots: &[PathBuf]) -> notify::Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        let mut watching = false;
+        for dir in roots.iter().map(|root| root.join("projects")) {
+            if dir.is_dir() {
+                watcher.watch(&dir, RecursiveMode::Recursive)?;
+                watching = true;
+            }
+        }
+        if !watching {
+            return Err(notify::Error::path_not_found());
+        }
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Changes reported since the last call, in order.
+    pub fn drain(&self) -> Vec<FsChange> {
+        let mut changes = Vec::new();
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) => changes.extend(changes_from_event(&event)),
+                Err(_) => changes.push(FsChange::Rescan),
+            }
+        }
+        changes
+    }
+}
+
+/// Translate one notify event into session file changes. Non-`.jsonl`
+/// paths and read-only access are ignored.
+pub fn changes_from_event(event: &Event) -> Vec<FsChange> {
+    if event.need_rescan() {
+        return vec![FsChange::Rescan];
+    }
+    let sessions = || {
+        event
+            .paths
+            .iter()
+            .filter(|p| p.extension().is_some_and(|e| e == "jsonl"))
+            .cloned()
+    };
+    match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            sessions().map(FsChange::Created).collect()
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            sessions().map(FsChange::Removed).collect()
+        }
+        // Both paths, in from/to order
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => event
+            .paths
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.extension().is_some_and(|e| e == "jsonl"))
+            .map(|(i, p)| {
+                if i == 0 {
+                    FsChange::Removed(p.clone())
+                } else {
+                    FsChange::Created(p.clone())
+                }
+            })
+            .collect(),
+        EventKind::Modify(_) => sessions().map(FsChange::Modified).collect(),
+        _ => Vec::new(),
+    }
 }
 
 #[cfg(test)]
@@ -81,6 +280,56 @@ mod tests {
     use super::*;
     use std::io::Write;
 
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+
+    #[test]
+    fn maps_notify_events_to_session_changes() {
+        let event = |kind, paths: &[&str]| Event {
+            kind,
+            paths: paths.iter().map(PathBuf::from).collect(),
+            attrs: Default::default(),
+        };
+        assert_eq!(
+            changes_from_event(&event(
+                EventKind::Create(CreateKind::File),
+                &["/p/a.jsonl", "/p/notes.txt"]
+            )),
+            vec![FsChange::Created(PathBuf::from("/p/a.jsonl"))]
+        );
+        assert_eq!(
+            changes_from_event(&event(
+                EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+                &["/p/a.jsonl"]
+            )),
+            vec![FsChange::Modified(PathBuf::from("/p/a.jsonl"))]
+        );
+        assert_eq!(
+            changes_from_event(&event(EventKind::Remove(RemoveKind::File), &["/p/a.jsonl"])),
+            vec![FsChange::Removed(PathBuf::from("/p/a.jsonl"))]
+        );
+        assert_eq!(
+            changes_from_event(&event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &["/p/a.jsonl", "/p/b.jsonl"]
+            )),
+            vec![
+                FsChange::Removed(PathBuf::from("/p/a.jsonl")),
+                FsChange::Created(PathBuf::from("/p/b.jsonl"))
+            ]
+        );
+        assert!(changes_from_event(&event(
+            EventKind::Access(notify::event::AccessKind::Any),
+            &["/p/a.jsonl"]
+        ))
+        .is_empty());
+    }
+
+    #[test]
+    fn watcher_needs_a_projects_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(FileWatcher::new(&[dir.path().to_path_buf()]).is_err());
+    }
+
     #[test]
     fn reads_new_lines_incrementally() {
         let dir = tempfile::tempdir().unwrap();
@@ -110,6 +359,59 @@ mod tests {
         assert!(matches!(records[0], JsonlRecord::System { .. }));
     }
 
+    #[test]
+    fn limited_read_resumes_where_it_stopped() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("test.jsonl");
+        {
+            let mut f = File::create(&file_path).unwrap();
+            for _ in 0..5 {
+                writeln!(
+                    f,
+                    r#"{{"type":"system","subtype":"turn_duration","duration_ms":1}}"#
+                )
+                .unwrap();
+            }
+        }
+        let mut reader = IncrementalReader::new();
+        assert_eq!(reader.read_new_lines_limited(&file_path, 2).len(), 2);
+        assert_eq!(reader.read_new_lines_limited(&file_path, 2).len(), 2);
+        assert_eq!(reader.read_new_lines_limited(&file_path, 2).len(), 1);
+        assert!(reader.read_new_lines(&file_path).is_empty());
+    }
+
+    #[test]
+    fn read_batch_reports_bytes_and_eof() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("test.jsonl");
+        let line = r#"{"type":"system","subtype":"turn_duration","duration_ms":1}"#;
+        std::fs::write(&file_path, format!("{line}\n{line}\nnot json\n")).unwrap();
+        let line_len = line.len() as u64 + 1;
+
+        let mut reader = IncrementalReader::new();
+        let batch = reader.read_batch(&file_path, 1);
+        assert_eq!(
+            (batch.records.len(), batch.bytes_read, batch.at_eof),
+            (1, line_len, false)
+        );
+        // Unparsable lines still count as bytes
+        let batch = reader.read_batch(&file_path, usize::MAX);
+        assert_eq!(batch.records.len(), 1);
+        assert_eq!(batch.bytes_read, line_len + 9);
+        assert!(batch.at_eof);
+        let batch = reader.read_batch(&file_path, usize::MAX);
+        assert_eq!((batch.bytes_read, batch.at_eof), (0, true));
+    }
+
+    #[test]
+    fn skips_compressed_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("test.jsonl.gz");
+        std::fs::write(&file_path, "{\"type\":\"system\"}\n").unwrap();
+        let mut reader = IncrementalReader::new();
+        assert!(reader.read_new_lines(&file_path).is_empty());
+    }
+
     #[test]
     fn handles_missing_file() {
         let mut reader = IncrementalReader::new();
@@ -148,4 +450,63 @@ mod tests {
         reader.remove(&path);
         assert!(!reader.offsets.contains_key(&path));
     }
+
+    #[test]
+    fn retain_forgets_other_files() {
+        let mut reader = IncrementalReader::new();
+        let kept = PathBuf::from("/tmp/kept.jsonl");
+        let gone = PathBuf::from("/tmp/gone.jsonl");
+        reader.offsets.insert(kept.clone(), 10);
+        reader.offsets.insert(gone.clone(), 20);
+        reader.retain(&HashSet::from([kept.as_path()]));
+        assert!(reader.is_tracking(&kept));
+        assert!(!reader.is_tracking(&gone));
+    }
+
+    #[test]
+    fn partial_last_line_waits_for_its_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("test.jsonl");
+        let line = r#"{"type":"system","subtype":"turn_duration","duration_ms":1}"#;
+        let (head, tail) = line.split_at(20);
+        std::fs::write(&file_path, format!("{line}\n{head}")).unwrap();
+
+        let mut reader = IncrementalReader::new();
+        let batch = reader.read_batch(&file_path, usize::MAX);
+        assert_eq!(batch.records.len(), 1);
+        assert_eq!(batch.bytes_read, line.len() as u64 + 1);
+        assert!(batch.at_eof);
+        // Still mid-append: nothing new
+        assert!(reader.read_new_lines(&file_path).is_empty());
+
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&file_path)
+            .unwrap();
+        writeln!(f, "{tail}").unwrap();
+        let batch = reader.read_batch(&file_path, usize::MAX);
+        assert_eq!(batch.records.len(), 1);
+        assert_eq!(batch.bytes_read, line.len() as u64 + 1);
+    }
+
+    #[test]
+    fn oversized_lines_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("test.jsonl");
+        let line = r#"{"type":"system","subtype":"turn_duration","duration_ms":1}"#;
+        let huge = format!(
+            r#"{{"type":"assistant","message":{{"content":[{{"type":"text","text":"{}"}}]}}}}"#,
+            "A".repeat(MAX_LINE_BYTES)
+        );
+        std::fs::write(&file_path, format!("{huge}\n{line}\n")).unwrap();
+
+        let mut reader = IncrementalReader::new();
+        let batch = reader.read_batch(&file_path, usize::MAX);
+        assert_eq!(batch.records.len(), 1);
+        assert!(matches!(batch.records[0], JsonlRecord::System { .. }));
+        assert_eq!(
+            batch.bytes_read,
+            std::fs::metadata(&file_path).unwrap().len()
+        );
+    }
 }
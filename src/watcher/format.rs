@@ -0,0 +1,393 @@
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::parser::{
+    self, ResumedFrom, TaskSpawn, ToolResultEvent, ToolUseEvent, ToolUseRef, UsageDelta,
+};
+use super::redact::Redactor;
+use super::rules::ToolClassifier;
+use super::types::{
+    AssistantMessage, ContentBlock, JsonlRecord, RecordLinks, ResultSnippet, Usage, UserMessage,
+};
+
+/// A transcript dialect: how its lines parse into records, and what the
+/// watcher reads off them. The extractors work on the record model every
+/// dialect parses into; a dialect overrides the ones its records express
+/// differently.
+pub trait TranscriptFormat: fmt::Debug + Sync {
+    /// Short name, e.g. for logs.
+    fn name(&self) -> &'static str;
+
+    /// Whether the first parseable line of a file is in this dialect.
+    fn sniff(&self, line: &Value) -> bool;
+
+    /// One line as a record. None for blank or invalid lines.
+    fn parse_line(&self, line: &str) -> Option<JsonlRecord>;
+
+    /// The tool uses of a record, borrowed from it. [`Self::tool_uses`]
+    /// builds its owned events from these.
+    fn tool_use_refs<'a>(
+        &self,
+        record: &'a JsonlRecord,
+        redactor: &'a Redactor,
+        classifier: &'a ToolClassifier,
+    ) -> Vec<ToolUseRef<'a>> {
+        parser::extract_tool_uses_ref(record, redactor, classifier).collect()
+    }
+
+    fn tool_uses(
+        &self,
+        record: &JsonlRecord,
+        redactor: &Redactor,
+        classifier: &ToolClassifier,
+    ) -> Vec<ToolUseEvent> {
+        self.tool_use_refs(record, redactor, classifier)
+            .iter()
+            .map(ToolUseRef::to_event)
+            .collect()
+    }
+
+    fn task_spawns(&self, record: &JsonlRecord) -> Vec<TaskSpawn> {
+        parser::extract_task_spawns(record)
+    }
+
+    fn tool_results(&self, record: &JsonlRecord) -> Vec<ToolResultEvent> {
+        parser::extract_tool_results(record)
+    }
+
+    fn text(&self, record: &JsonlRecord) -> Option<String> {
+        parser::extract_text(record)
+    }
+
+    fn usage(&self, record: &JsonlRecord) -> Option<UsageDelta> {
+        parser::extract_usage(record)
+    }
+
+    fn is_user_prompt(&self, record: &JsonlRecord) -> bool {
+        parser::is_user_prompt(record)
+    }
+
+    fn prompt_text(&self, record: &JsonlRecord) -> Option<String> {
+        parser::prompt_text(record)
+    }
+
+    /// A record the model wrote, answering a prompt.
+    fn is_response(&self, record: &JsonlRecord) -> bool {
+        matches!(record, JsonlRecord::Assistant { .. })
+    }
+
+    fn is_turn_end(&self, record: &JsonlRecord) -> bool {
+        parser::is_turn_end(record)
+    }
+
+    fn turn_duration(&self, record: &JsonlRecord) -> Option<Duration> {
+        parser::turn_duration(record)
+    }
+
+    /// The conversation was compacted here.
+    fn is_compaction(&self, record: &JsonlRecord) -> bool {
+        parser::is_compaction(record)
+    }
+
+    /// The session a resumed session's file says it carries on from.
+    fn resumed_from(&self, record: &JsonlRecord) -> Option<ResumedFrom> {
+        parser::resumed_from(record)
+    }
+
+    /// The record's own ID, which a later `summary` may name.
+    fn uuid<'a>(&self, record: &'a JsonlRecord) -> Option<&'a str> {
+        parser::record_uuid(record)
+    }
+
+    fn timestamp(&self, record: &JsonlRecord) -> Option<SystemTime> {
+        parser::record_timestamp(record)
+    }
+}
+
+/// Claude Code's transcripts: records tagged by `type`.
+#[derive(Debug, Clone, Copy)]
+pub struct ClaudeCode;
+
+impl TranscriptFormat for ClaudeCode {
+    fn name(&self) -> &'static str {
+        "claude-code"
+    }
+
+    fn sniff(&self, line: &Value) -> bool {
+        line.get("type").is_some_and(Value::is_string)
+    }
+
+    fn parse_line(&self, line: &str) -> Option<JsonlRecord> {
+        parser::parse_line(line)
+    }
+}
+
+/// Transcripts tagged by `role` (`user`, `assistant`, `tool`, `system`)
+/// with the content of each message under `delta`. Tool calls and their
+/// outputs are `tool_call` and `tool_output` blocks, and a turn ends with
+/// a `turn_end` system event.
+#[derive(Debug, Clone, Copy)]
+pub struct RoleBased;
+
+const ROLE_TURN_END: &str = "turn_end";
+
+impl TranscriptFormat for RoleBased {
+    fn name(&self) -> &'static str {
+        "role-based"
+    }
+
+    fn sniff(&self, line: &Value) -> bool {
+        line.get("role").is_some_and(Value::is_string) && line.get("type").is_none()
+    }
+
+    fn parse_line(&self, line: &str) -> Option<JsonlRecord> {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+        serde_json::from_str::<RoleRecord>(trimmed)
+            .ok()
+            .map(RoleRecord::into_record)
+    }
+
+    fn is_turn_end(&self, record: &JsonlRecord) -> bool {
+        self.turn_end(record).is_some()
+    }
+
+    fn turn_duration(&self, record: &JsonlRecord) -> Option<Duration> {
+        self.turn_end(record)?.map(Duration::from_millis)
+    }
+}
+
+impl RoleBased {
+    /// `Some(duration_ms)` for a `turn_end` event.
+    fn turn_end(&self, record: &JsonlRecord) -> Option<Option<u64>> {
+        match record {
+            JsonlRecord::System {
+                subtype: Some(event),
+                duration_ms,
+                ..
+            } if event == ROLE_TURN_END => Some(*duration_ms),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "role", rename_all = "lowercase")]
+enum RoleRecord {
+    User {
+        #[serde(default)]
+        delta: RoleDelta,
+        #[serde(default)]
+        timestamp: Option<String>,
+    },
+    Assistant {
+        #[serde(default)]
+        delta: RoleDelta,
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(default)]
+        model: Option<String>,
+        #[serde(default)]
+        usage: Option<Usage>,
+        #[serde(default)]
+        timestamp: Option<String>,
+    },
+    /// Outputs of the tools the assistant called.
+    Tool {
+        #[serde(default)]
+        delta: RoleDelta,
+        #[serde(default)]
+        timestamp: Option<String>,
+    },
+    System {
+        event: Option<String>,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RoleDelta {
+    #[serde(default)]
+    content: Vec<RoleBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum RoleBlock {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "tool_call")]
+    ToolCall {
+        id: String,
+        name: String,
+        #[serde(default)]
+        arguments: Value,
+    },
+    #[serde(rename = "tool_output")]
+    ToolOutput {
+        call_id: String,
+        #[serde(default)]
+        error: bool,
+        #[serde(default)]
+        output: ResultSnippet,
+    },
+    #[serde(other)]
+    Other,
+}
+
+impl RoleRecord {
+    fn into_record(self) -> JsonlRecord {
+        let content = |delta: RoleDelta| -> Vec<ContentBlock> {
+            delta
+                .content
+                .into_iter()
+                .map(RoleBlock::into_block)
+                .collect()
+        };
+        match self {
+            RoleRecord::User { delta, timestamp } | RoleRecord::Tool { delta, timestamp } => {
+                JsonlRecord::User {
+                    message: UserMessage {
+                        content: content(delta),
+                    },
+                    timestamp,
+                    is_sidechain: false,
+                    links: RecordLinks::default(),
+                }
+            }
+            RoleRecord::Assistant {
+                delta,
+                id,
+                model,
+                usage,
+                timestamp,
+            } => JsonlRecord::Assistant {
+                message: AssistantMessage {
+                    content: content(delta),
+                    id,
+                    model,
+                    usage,
+                },
+                timestamp,
+                links: RecordLinks::default(),
+            },
+            RoleRecord::System { event, duration_ms } => JsonlRecord::System {
+                subtype: event,
+                duration_ms,
+                links: RecordLinks::default(),
+            },
+            RoleRecord::Unknown => JsonlRecord::Unknown,
+        }
+    }
+}
+
+impl RoleBlock {
+    fn into_block(self) -> ContentBlock {
+        match self {
+            RoleBlock::Text { text } => ContentBlock::Text { text },
+            RoleBlock::ToolCall {
+                id,
+                name,
+                arguments,
+            } => ContentBlock::ToolUse {
+                id,
+                name,
+                input: arguments,
+            },
+            RoleBlock::ToolOutput {
+                call_id,
+                error,
+                output,
+            } => ContentBlock::ToolResult {
+                tool_use_id: call_id,
+                is_error: error,
+                content: output,
+            },
+            RoleBlock::Other => ContentBlock::Other,
+        }
+    }
+}
+
+/// Every known dialect, in the order they are sniffed.
+pub const FORMATS: [&dyn TranscriptFormat; 2] = [&ClaudeCode, &RoleBased];
+
+/// Files not sniffed yet are read as Claude Code transcripts.
+pub const DEFAULT_FORMAT: &dyn TranscriptFormat = &ClaudeCode;
+
+/// The dialect `line` is in. None when it isn't JSON or no dialect
+/// claims it, so the next line gets a try.
+pub fn sniff(line: &str) -> Option<&'static dyn TranscriptFormat> {
+    let value: Value = serde_json::from_str(line.trim()).ok()?;
+    FORMATS.into_iter().find(|format| format.sniff(&value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLE_LINES: [&str; 5] = [
+        r#"{"role":"user","timestamp":"2025-06-01T10:00:00Z","delta":{"content":[{"type":"text","text":"fix the tests"}]}}"#,
+        r#"{"role":"assistant","id":"msg_1","model":"opus","usage":{"input_tokens":10,"output_tokens":20},"delta":{"content":[{"type":"text","text":"On it"},{"type":"tool_call","id":"c1","name":"Read","arguments":{"file_path":"/src/lib.rs"}}]}}"#,
+        r#"{"role":"tool","delta":{"content":[{"type":"tool_output","call_id":"c1","error":true}]}}"#,
+        r#"{"role":"system","event":"turn_end","duration_ms":1500}"#,
+        r#"{"role":"critic","delta":{}}"#,
+    ];
+
+    #[test]
+    fn sniffs_each_dialect() {
+        let claude = r#"{"type":"user","message":{"role":"user","content":"hi"}}"#;
+        assert_eq!(sniff(claude).map(|f| f.name()), Some("claude-code"));
+        assert_eq!(sniff(ROLE_LINES[0]).map(|f| f.name()), Some("role-based"));
+        assert!(sniff("not json").is_none());
+        assert!(sniff(r#"{"something":"else"}"#).is_none());
+    }
+
+    #[test]
+    fn role_based_records_feed_the_shared_extractors() {
+        let format = RoleBased;
+        let records: Vec<JsonlRecord> = ROLE_LINES
+            .iter()
+            .filter_map(|line| format.parse_line(line))
+            .collect();
+        assert_eq!(records.len(), 5);
+        let [prompt, response, output, end, unknown] = &records[..] else {
+            unreachable!()
+        };
+
+        assert!(format.is_user_prompt(prompt));
+        assert_eq!(format.prompt_text(prompt).as_deref(), Some("fix the tests"));
+        assert!(format.timestamp(prompt).is_some());
+
+        assert!(format.is_response(response));
+        assert_eq!(format.text(response).as_deref(), Some("On it"));
+        let tools = format.tool_uses(response, &Redactor::default(), &ToolClassifier::default());
+        assert_eq!(tools.len(), 1);
+        assert_eq!(
+            (tools[0].tool_id.as_str(), tools[0].display_status.as_str()),
+            ("c1", "Reading lib.rs")
+        );
+        let usage = format.usage(response).unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (10, 20));
+
+        assert!(!format.is_user_prompt(output));
+        let results = format.tool_results(output);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].tool_id, "c1");
+        assert!(results[0].is_error);
+
+        assert!(format.is_turn_end(end));
+        assert_eq!(format.turn_duration(end), Some(Duration::from_millis(1500)));
+        // Claude Code's own turn end marker means nothing here
+        assert!(!ClaudeCode.is_turn_end(end));
+
+        assert!(matches!(unknown, JsonlRecord::Unknown));
+    }
+}
@@ -1,4 +1,9 @@
+pub mod archive;
 pub mod discovery;
 pub mod file_watcher;
+pub mod format;
 pub mod parser;
+pub mod redact;
+pub mod rules;
+pub mod settings;
 pub mod types;
@@ -0,0 +1,187 @@
+use ratatui::style::Color;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::state::alerts::matches_tool_pattern;
+use crate::ui::sprites::AnimState;
+
+/// What a `[[rules]]` entry matches tool uses on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleMatcher {
+    /// Tool name, where `*` stands for any run of characters.
+    Tool(String),
+    /// Regex searched for in the command of a Bash tool use.
+    Command(String),
+    /// MCP tool as `server` or `server__tool`, where `*` stands for any run
+    /// of characters.
+    Mcp(String),
+}
+
+impl RuleMatcher {
+    /// `command /^cargo test/`, as shown in the expanded sidebar details.
+    pub fn describe(&self) -> String {
+        match self {
+            RuleMatcher::Tool(pattern) => format!("tool {pattern}"),
+            RuleMatcher::Command(regex) => format!("command /{regex}/"),
+            RuleMatcher::Mcp(pattern) => format!("mcp {pattern}"),
+        }
+    }
+}
+
+/// A validated `[[rules]]` entry: tool uses it matches are shown with its
+/// label, animation and color instead of the defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleSpec {
+    pub matcher: RuleMatcher,
+    pub label: String,
+    pub anim: AnimState,
+    /// Sidebar status color; the status's own color when unset.
+    pub color: Option<Color>,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Tool(String),
+    Command(Regex),
+    Mcp(String),
+}
+
+impl Matcher {
+    fn matches(&self, name: &str, input: &Value) -> bool {
+        match self {
+            Matcher::Tool(pattern) => matches_tool_pattern(pattern, name),
+            Matcher::Command(regex) => {
+                name == "Bash"
+                    && input
+                        .get("command")
+                        .and_then(Value::as_str)
+                        .is_some_and(|command| regex.is_match(command))
+            }
+            Matcher::Mcp(pattern) => {
+                let Some(tool) = name.strip_prefix("mcp__") else {
+                    return false;
+                };
+                if pattern.contains("__") {
+                    matches_tool_pattern(pattern, tool)
+                } else {
+                    let server = tool.split("__").next().unwrap_or(tool);
+                    matches_tool_pattern(pattern, server)
+                }
+            }
+        }
+    }
+}
+
+/// The config's status rules, compiled. The first rule matching a tool use
+/// decides how it is shown.
+#[derive(Debug, Clone, Default)]
+pub struct ToolClassifier {
+    rules: Vec<(RuleSpec, Matcher)>,
+}
+
+impl ToolClassifier {
+    /// Compile `specs` in order. A command regex that doesn't compile skips
+    /// its rule; the config warned about it when it was loaded.
+    pub fn new(specs: &[RuleSpec]) -> Self {
+        let rules = specs
+            .iter()
+            .filter_map(|spec| {
+                let matcher = match &spec.matcher {
+                    RuleMatcher::Tool(pattern) => Matcher::Tool(pattern.clone()),
+                    RuleMatcher::Command(regex) => Matcher::Command(Regex::new(regex).ok()?),
+                    RuleMatcher::Mcp(pattern) => Matcher::Mcp(pattern.clone()),
+                };
+                Some((spec.clone(), matcher))
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Index of the first rule matching a `name` tool use with `input`.
+    pub fn classify(&self, name: &str, input: &Value) -> Option<usize> {
+        self.rules
+            .iter()
+            .position(|(_, matcher)| matcher.matches(name, input))
+    }
+
+    pub fn rule(&self, index: usize) -> Option<&RuleSpec> {
+        self.rules.get(index).map(|(spec, _)| spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(matcher: RuleMatcher, label: &str) -> RuleSpec {
+        RuleSpec {
+            matcher,
+            label: label.to_string(),
+            anim: AnimState::Testing,
+            color: None,
+        }
+    }
+
+    fn bash(command: &str) -> Value {
+        json!({ "command": command })
+    }
+
+    #[test]
+    fn command_regex_matches_bash_only() {
+        let classifier = ToolClassifier::new(&[rule(
+            RuleMatcher::Command(r"^cargo (test|nextest)\b".to_string()),
+            "Testing",
+        )]);
+        assert_eq!(
+            classifier.classify("Bash", &bash("cargo test -p core")),
+            Some(0)
+        );
+        assert_eq!(
+            classifier.classify("Bash", &bash("cargo nextest run")),
+            Some(0)
+        );
+        assert_eq!(classifier.classify("Bash", &bash("cargo build")), None);
+        assert_eq!(classifier.classify("Bash", &json!({})), None);
+        assert_eq!(classifier.classify("Grep", &bash("cargo test")), None);
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let classifier = ToolClassifier::new(&[
+            rule(RuleMatcher::Command("cargo test".to_string()), "Testing"),
+            rule(RuleMatcher::Tool("Bash".to_string()), "Shell"),
+            rule(RuleMatcher::Command("cargo".to_string()), "Never"),
+        ]);
+        assert_eq!(classifier.classify("Bash", &bash("cargo test")), Some(0));
+        assert_eq!(classifier.classify("Bash", &bash("cargo build")), Some(1));
+        assert_eq!(classifier.rule(1).unwrap().label, "Shell");
+        assert_eq!(classifier.classify("Read", &json!({})), None);
+    }
+
+    #[test]
+    fn mcp_patterns_match_servers_and_tools() {
+        let classifier = ToolClassifier::new(&[
+            rule(RuleMatcher::Mcp("github__create_*".to_string()), "Opening"),
+            rule(RuleMatcher::Mcp("github".to_string()), "GitHub"),
+        ]);
+        let input = json!({});
+        assert_eq!(
+            classifier.classify("mcp__github__create_issue", &input),
+            Some(0)
+        );
+        assert_eq!(
+            classifier.classify("mcp__github__get_issue", &input),
+            Some(1)
+        );
+        assert_eq!(classifier.classify("mcp__gitlab__get_issue", &input), None);
+        assert_eq!(classifier.classify("github", &input), None);
+    }
+
+    #[test]
+    fn no_rules_classify_nothing() {
+        let classifier = ToolClassifier::default();
+        assert_eq!(classifier.classify("Bash", &bash("cargo test")), None);
+        assert!(classifier.rule(0).is_none());
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Env var Claude Code uses to relocate its data directory.
+pub const CLAUDE_CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// The subset of Claude Code's `settings.json` we care about.
+/// Unknown fields are ignored.
+#[derive(Debug, Default, Deserialize)]
+struct ClaudeSettings {
+    #[serde(default)]
+    env: HashMap<String, Value>,
+}
+
+/// Read `<claude_dir>/settings.json` and return data directories it points at.
+/// Missing or malformed files yield an empty list.
+pub fn settings_roots(claude_dir: &Path, home: Option<&Path>) -> Vec<PathBuf> {
+    let Ok(data) = std::fs::read_to_string(claude_dir.join("settings.json")) else {
+        return Vec::new();
+    };
+    parse_settings_roots(&data, home)
+}
+
+fn parse_settings_roots(data: &str, home: Option<&Path>) -> Vec<PathBuf> {
+    let Ok(settings) = serde_json::from_str::<ClaudeSettings>(data) else {
+        return Vec::new();
+    };
+    settings
+        .env
+        .get(CLAUDE_CONFIG_DIR_ENV)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| vec![expand_home(s, home)])
+        .unwrap_or_default()
+}
+
+/// Expand a leading `~/` using `home`, if known.
+fn expand_home(path: &str, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Discover extra Claude data directories beyond `claude_dir`.
+///
+/// Sources: the `CLAUDE_CONFIG_DIR` env var and `env.CLAUDE_CONFIG_DIR` in
+/// `<claude_dir>/settings.json`. The result is deduplicated and never
+/// contains `claude_dir` itself.
+pub fn discover_roots(
+    claude_dir: &Path,
+    env_config_dir: Option<PathBuf>,
+    home: Option<&Path>,
+) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    let candidates = env_config_dir
+        .into_iter()
+        .chain(settings_roots(claude_dir, home));
+    for root in candidates {
+        if root != claude_dir && !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELOCATED: &str = r#"{
+        "$schema": "https://json.schemastore.org/claude-code-settings.json",
+        "model": "opus",
+        "permissions": {"allow": ["Bash(cargo test:*)"], "deny": []},
+        "env": {"CLAUDE_CONFIG_DIR": "~/work/.claude", "DISABLE_TELEMETRY": "1"},
+        "hooks": {"SessionStart": [{"matcher": "startup", "hooks": []}]}
+    }"#;
+
+    const NO_ENV: &str = r#"{
+        "model": "sonnet",
+        "includeCoAuthoredBy": false,
+        "statusLine": {"type": "command", "command": "starship"}
+    }"#;
+
+    const NON_STRING_ENV: &str = r#"{"env": {"CLAUDE_CONFIG_DIR": 42}}"#;
+
+    #[test]
+    fn reads_relocated_dir_and_expands_home() {
+        let roots = parse_settings_roots(RELOCATED, Some(Path::new("/home/me")));
+        assert_eq!(roots, vec![PathBuf::from("/home/me/work/.claude")]);
+    }
+
+    #[test]
+    fn settings_without_env_yield_nothing() {
+        assert!(parse_settings_roots(NO_ENV, None).is_empty());
+    }
+
+    #[test]
+    fn tolerates_wrong_types_and_garbage() {
+        assert!(parse_settings_roots(NON_STRING_ENV, None).is_empty());
+        assert!(parse_settings_roots("{ not json", None).is_empty());
+    }
+
+    #[test]
+    fn missing_settings_file_is_fine() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(settings_roots(dir.path(), None).is_empty());
+    }
+
+    #[test]
+    fn discover_merges_env_and_settings_without_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("settings.json"),
+            r#"{"env": {"CLAUDE_CONFIG_DIR": "/data/claude"}}"#,
+        )
+        .unwrap();
+        let roots = discover_roots(dir.path(), Some(PathBuf::from("/data/claude")), None);
+        assert_eq!(roots, vec![PathBuf::from("/data/claude")]);
+
+        let roots = discover_roots(dir.path(), Some(PathBuf::from("/other")), None);
+        assert_eq!(
+            roots,
+            vec![PathBuf::from("/other"), PathBuf::from("/data/claude")]
+        );
+    }
+
+    #[test]
+    fn discover_skips_the_primary_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = discover_roots(dir.path(), Some(dir.path().to_path_buf()), None);
+        assert!(roots.is_empty());
+    }
+}
@@ -1,18 +1,45 @@
-use serde::Deserialize;
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 pub enum JsonlRecord {
     #[serde(rename = "assistant")]
-    Assistant { message: AssistantMessage },
+    Assistant {
+        message: AssistantMessage,
+        #[serde(default)]
+        timestamp: Option<String>,
+        #[serde(flatten)]
+        links: RecordLinks,
+    },
     #[serde(rename = "user")]
-    User { message: UserMessage },
+    User {
+        message: UserMessage,
+        #[serde(default)]
+        timestamp: Option<String>,
+        /// Set on records of a Task sub-agent's transcript.
+        #[serde(default, rename = "isSidechain")]
+        is_sidechain: bool,
+        #[serde(flatten)]
+        links: RecordLinks,
+    },
     #[serde(rename = "system")]
     System {
         subtype: Option<String>,
         #[serde(default)]
         duration_ms: Option<u64>,
+        #[serde(flatten)]
+        links: RecordLinks,
+    },
+    /// Written when a conversation is resumed or compacted, naming the
+    /// last message it carries on from.
+    #[serde(rename = "summary")]
+    Summary {
+        #[serde(default, rename = "leafUuid")]
+        leaf_uuid: Option<String>,
     },
     #[serde(rename = "progress")]
     Progress {
@@ -24,16 +51,60 @@ pub enum JsonlRecord {
     Unknown,
 }
 
+/// How a record ties into the conversation: its own ID, and the session
+/// a resumed session's file carries on from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct RecordLinks {
+    #[serde(default)]
+    pub uuid: Option<String>,
+    #[serde(default, rename = "parentSessionId")]
+    pub parent_session_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AssistantMessage {
     pub content: Vec<ContentBlock>,
+    /// API message id. A message with several content blocks is written as
+    /// one record per block, each repeating the id and usage.
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// Token counts the API reported for one message.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UserMessage {
+    #[serde(deserialize_with = "blocks_or_text")]
     pub content: Vec<ContentBlock>,
 }
 
+/// Typed prompts are stored as a bare string rather than a block list.
+fn blocks_or_text<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<ContentBlock>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Content {
+        Blocks(Vec<ContentBlock>),
+        Text(String),
+    }
+    Ok(match Content::deserialize(de)? {
+        Content::Blocks(blocks) => blocks,
+        Content::Text(text) => vec![ContentBlock::Text { text }],
+    })
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 pub enum ContentBlock {
@@ -45,13 +116,156 @@ pub enum ContentBlock {
         input: Value,
     },
     #[serde(rename = "tool_result")]
-    ToolResult { tool_use_id: String },
+    ToolResult {
+        tool_use_id: String,
+        /// The tool failed; its content is the error.
+        #[serde(default)]
+        is_error: bool,
+        #[serde(default)]
+        content: ResultSnippet,
+    },
     #[serde(rename = "text")]
     Text { text: String },
     #[serde(other)]
     Other,
 }
 
+/// Characters of a tool result's content that are kept.
+pub const RESULT_SNIPPET_CHARS: usize = 200;
+
+/// The start of a tool result's content, which is either a string or a
+/// list of blocks whose text is joined. Results can hold whole files, so
+/// only the first [`RESULT_SNIPPET_CHARS`] characters are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultSnippet(pub String);
+
+impl<'de> Deserialize<'de> for ResultSnippet {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let mut snippet = Snippet::default();
+        Part {
+            snippet: &mut snippet,
+            level: Level::Content,
+        }
+        .deserialize(de)?;
+        Ok(Self(snippet.text))
+    }
+}
+
+/// Text gathered for a [`ResultSnippet`]. Past the limit the rest is
+/// dropped as it's read, never copied.
+#[derive(Default)]
+struct Snippet {
+    text: String,
+    chars: usize,
+    /// Pieces of text seen, blocks being joined by newlines.
+    parts: usize,
+}
+
+impl Snippet {
+    fn push(&mut self, part: &str) {
+        if self.parts > 0 {
+            self.push_chars("\n");
+        }
+        self.parts += 1;
+        self.push_chars(part);
+    }
+
+    fn push_chars(&mut self, s: &str) {
+        let room = RESULT_SNIPPET_CHARS - self.chars;
+        for c in s.chars().take(room) {
+            self.text.push(c);
+            self.chars += 1;
+        }
+    }
+}
+
+/// Where in a tool result's content a [`Part`] sits.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Level {
+    /// The content itself: a string or a list of blocks.
+    Content,
+    /// One block of the list.
+    Block,
+    /// A block's `text`.
+    Text,
+}
+
+/// Reads one level of a tool result's content into the snippet. Anything
+/// without text, such as an image block, is skipped.
+struct Part<'a> {
+    snippet: &'a mut Snippet,
+    level: Level,
+}
+
+impl<'de> DeserializeSeed<'de> for Part<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<(), D::Error> {
+        de.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Part<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("tool result content")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<(), E> {
+        if self.level != Level::Block {
+            self.snippet.push(v);
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        if self.level == Level::Content {
+            while let Some(()) = seq.next_element_seed(Part {
+                snippet: &mut *self.snippet,
+                level: Level::Block,
+            })? {}
+        } else {
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if self.level == Level::Block && key == "text" {
+                map.next_value_seed(Part {
+                    snippet: &mut *self.snippet,
+                    level: Level::Text,
+                })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,7 +275,7 @@ mod tests {
         let json = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"tool_1","name":"Read","input":{"file_path":"/tmp/test.rs"}}]}}"#;
         let record: JsonlRecord = serde_json::from_str(json).unwrap();
         match record {
-            JsonlRecord::Assistant { message } => {
+            JsonlRecord::Assistant { message, .. } => {
                 assert_eq!(message.content.len(), 1);
                 match &message.content[0] {
                     ContentBlock::ToolUse { id, name, .. } => {
@@ -75,13 +289,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn tool_result_content_as_string_or_blocks() {
+        let content_of = |json: &str| -> (bool, String) {
+            match serde_json::from_str(json).unwrap() {
+                JsonlRecord::User { message, .. } => match &message.content[0] {
+                    ContentBlock::ToolResult {
+                        is_error, content, ..
+                    } => (*is_error, content.0.clone()),
+                    _ => panic!("Expected ToolResult"),
+                },
+                _ => panic!("Expected User record"),
+            }
+        };
+        assert_eq!(
+            content_of(
+                r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","is_error":true,"content":"Exit code 1\nerror: could not compile"}]}}"#
+            ),
+            (true, "Exit code 1\nerror: could not compile".to_string())
+        );
+        assert_eq!(
+            content_of(
+                r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":[{"type":"text","text":"first"},{"type":"image","source":{}},{"type":"text","text":"second"}]}]}}"#
+            ),
+            (false, "first\nsecond".to_string())
+        );
+        // Long results are cut; odd shapes are no content
+        let long = format!(
+            r#"{{"type":"user","message":{{"content":[{{"type":"tool_result","tool_use_id":"t1","content":"{}"}}]}}}}"#,
+            "x".repeat(5000)
+        );
+        assert_eq!(content_of(&long).1.len(), RESULT_SNIPPET_CHARS);
+        let long_blocks = format!(
+            r#"{{"type":"user","message":{{"content":[{{"type":"tool_result","tool_use_id":"t1","content":[{{"type":"text","text":"{}"}},{{"type":"text","text":"{}"}}]}}]}}}}"#,
+            "x".repeat(150),
+            "y".repeat(150)
+        );
+        let cut = content_of(&long_blocks).1;
+        assert_eq!(cut.chars().count(), RESULT_SNIPPET_CHARS);
+        assert!(cut.starts_with(&format!("{}\ny", "x".repeat(150))));
+        for odd in [r#"{"odd":1}"#, "42", "null", r#"[1,[2],{"text":3}]"#] {
+            let json = format!(
+                r#"{{"type":"user","message":{{"content":[{{"type":"tool_result","tool_use_id":"t1","content":{odd}}}]}}}}"#
+            );
+            assert_eq!(content_of(&json).1, "", "{odd}");
+        }
+    }
+
     #[test]
     fn deserialize_tool_result_record() {
         let json = r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"tool_1"}]}}"#;
         let record: JsonlRecord = serde_json::from_str(json).unwrap();
         match record {
-            JsonlRecord::User { message } => match &message.content[0] {
-                ContentBlock::ToolResult { tool_use_id } => {
+            JsonlRecord::User { message, .. } => match &message.content[0] {
+                ContentBlock::ToolResult { tool_use_id, .. } => {
                     assert_eq!(tool_use_id, "tool_1");
                 }
                 _ => panic!("Expected ToolResult"),
@@ -108,7 +369,7 @@ mod tests {
             r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Hello world"}]}}"#;
         let record: JsonlRecord = serde_json::from_str(json).unwrap();
         match record {
-            JsonlRecord::Assistant { message } => match &message.content[0] {
+            JsonlRecord::Assistant { message, .. } => match &message.content[0] {
                 ContentBlock::Text { text } => {
                     assert_eq!(text, "Hello world");
                 }
@@ -118,6 +379,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deserialize_usage_and_model() {
+        let json = r#"{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","content":[],"usage":{"input_tokens":12,"output_tokens":340,"cache_read_input_tokens":9000,"service_tier":"standard"}}}"#;
+        let record: JsonlRecord = serde_json::from_str(json).unwrap();
+        match record {
+            JsonlRecord::Assistant { message, .. } => {
+                assert_eq!(message.id.as_deref(), Some("msg_1"));
+                assert_eq!(message.model.as_deref(), Some("claude-sonnet-4-5"));
+                let usage = message.usage.unwrap();
+                assert_eq!(
+                    (
+                        usage.input_tokens,
+                        usage.output_tokens,
+                        usage.cache_read_input_tokens
+                    ),
+                    (12, 340, 9000)
+                );
+            }
+            _ => panic!("Expected Assistant"),
+        }
+    }
+
+    #[test]
+    fn deserialize_string_prompt_with_timestamp() {
+        let json = r#"{"type":"user","timestamp":"2025-06-01T10:00:00.500Z","message":{"role":"user","content":"fix the tests"}}"#;
+        let record: JsonlRecord = serde_json::from_str(json).unwrap();
+        match record {
+            JsonlRecord::User {
+                message, timestamp, ..
+            } => {
+                assert_eq!(timestamp.as_deref(), Some("2025-06-01T10:00:00.500Z"));
+                assert!(
+                    matches!(&message.content[..], [ContentBlock::Text { text }] if text == "fix the tests")
+                );
+            }
+            _ => panic!("Expected User"),
+        }
+    }
+
+    #[test]
+    fn deserialize_links_and_summaries() {
+        let json = r#"{"type":"user","uuid":"u2","parentUuid":"u1","parentSessionId":"s0","sessionId":"s1","message":{"content":"go on"}}"#;
+        match serde_json::from_str(json).unwrap() {
+            JsonlRecord::User { links, message, .. } => {
+                assert_eq!(links.uuid.as_deref(), Some("u2"));
+                assert_eq!(links.parent_session_id.as_deref(), Some("s0"));
+                assert_eq!(message.content.len(), 1);
+            }
+            _ => panic!("Expected User"),
+        }
+        let json = r#"{"type":"summary","summary":"Fixing the auth bug","leafUuid":"a9"}"#;
+        match serde_json::from_str(json).unwrap() {
+            JsonlRecord::Summary { leaf_uuid } => assert_eq!(leaf_uuid.as_deref(), Some("a9")),
+            _ => panic!("Expected Summary"),
+        }
+        let json = r#"{"type":"system","subtype":"compact_boundary","uuid":"c1","compactMetadata":{"trigger":"auto","preTokens":155000}}"#;
+        match serde_json::from_str(json).unwrap() {
+            JsonlRecord::System { subtype, links, .. } => {
+                assert_eq!(subtype.as_deref(), Some("compact_boundary"));
+                assert_eq!(links.uuid.as_deref(), Some("c1"));
+            }
+            _ => panic!("Expected System"),
+        }
+    }
+
     #[test]
     fn unknown_record_types_dont_crash() {
         let json = r#"{"type":"unknown_future_type","data":123}"#;
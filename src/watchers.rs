@@ -0,0 +1,248 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::status_file;
+
+/// One running dashboard instance and the Claude directory it watches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatcherEntry {
+    pub pid: u32,
+    pub claude_dir: PathBuf,
+    /// Unix timestamp (seconds) the instance started.
+    pub started_at: u64,
+    /// Project directory name the instance is scoped to with `--project`;
+    /// None for all projects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+/// Read the registry. Missing or unparsable files yield no entries.
+pub fn load(path: &Path) -> Vec<WatcherEntry> {
+    fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Drop entries whose process is no longer alive.
+pub fn prune<F>(entries: Vec<WatcherEntry>, is_alive: F) -> Vec<WatcherEntry>
+where
+    F: Fn(u32) -> bool,
+{
+    entries.into_iter().filter(|e| is_alive(e.pid)).collect()
+}
+
+/// Other live instances watching `claude_dir` for the same projects.
+/// Instances scoped to different projects are meant to run side by side.
+pub fn conflicts<'a>(entries: &'a [WatcherEntry], me: &WatcherEntry) -> Vec<&'a WatcherEntry> {
+    entries
+        .iter()
+        .filter(|e| e.pid != me.pid && e.claude_dir == me.claude_dir && e.project == me.project)
+        .collect()
+}
+
+/// Add `me` to the registry, pruning dead entries on the way, and return
+/// the other live instances already watching the same directory.
+pub fn register<F>(path: &Path, me: WatcherEntry, is_alive: F) -> io::Result<Vec<WatcherEntry>>
+where
+    F: Fn(u32) -> bool,
+{
+    let _lock = lock(path)?;
+    let mut entries = prune(load(path), is_alive);
+    entries.retain(|e| e.pid != me.pid);
+    let others: Vec<WatcherEntry> = conflicts(&entries, &me).into_iter().cloned().collect();
+    entries.push(me);
+    status_file::write_atomic(path, &entries)?;
+    Ok(others)
+}
+
+/// Remove `pid` (and any dead entries) from the registry.
+pub fn unregister<F>(path: &Path, pid: u32, is_alive: F) -> io::Result<()>
+where
+    F: Fn(u32) -> bool,
+{
+    let _lock = lock(path)?;
+    let mut entries = prune(load(path), is_alive);
+    entries.retain(|e| e.pid != pid);
+    if entries.is_empty() {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    } else {
+        status_file::write_atomic(path, &entries)
+    }
+}
+
+/// Hold an exclusive lock on `<path>.lock` while the registry is read and
+/// rewritten, so instances starting or stopping together don't drop each
+/// other's entries. Released when the returned file is dropped; the lock
+/// file itself stays, as removing it would race with the next locker.
+fn lock(path: &Path) -> io::Result<fs::File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Header warning for instances already watching our directory.
+pub fn conflict_warning(others: &[WatcherEntry]) -> Option<String> {
+    match others {
+        [] => None,
+        [one] => Some(format!(
+            "another instance (pid {}) is watching this directory",
+            one.pid
+        )),
+        many => {
+            let pids: Vec<String> = many.iter().map(|e| e.pid.to_string()).collect();
+            Some(format!(
+                "other instances (pids {}) are watching this directory",
+                pids.join(", ")
+            ))
+        }
+    }
+}
+
+/// Compare directories by their canonical form where possible, so
+/// `~/.claude` and a symlink to it count as the same directory.
+pub fn normalize_dir(dir: &Path) -> PathBuf {
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: u32, dir: &str) -> WatcherEntry {
+        WatcherEntry {
+            pid,
+            claude_dir: PathBuf::from(dir),
+            started_at: 1_760_000_000,
+            project: None,
+        }
+    }
+
+    #[test]
+    fn registers_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watchers.json");
+        let others = register(&path, entry(10, "/a"), |_| true).unwrap();
+        assert!(others.is_empty());
+        register(&path, entry(11, "/b"), |_| true).unwrap();
+        assert_eq!(load(&path), vec![entry(10, "/a"), entry(11, "/b")]);
+    }
+
+    #[test]
+    fn detects_live_instance_on_same_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watchers.json");
+        register(&path, entry(4242, "/home/me/.claude"), |_| true).unwrap();
+        register(&path, entry(7, "/elsewhere"), |_| true).unwrap();
+        let others = register(&path, entry(99, "/home/me/.claude"), |_| true).unwrap();
+        assert_eq!(others, vec![entry(4242, "/home/me/.claude")]);
+        assert_eq!(
+            conflict_warning(&others).as_deref(),
+            Some("another instance (pid 4242) is watching this directory")
+        );
+    }
+
+    #[test]
+    fn instances_for_different_projects_do_not_conflict() {
+        let scoped = |pid, project: &str| WatcherEntry {
+            project: Some(project.to_string()),
+            ..entry(pid, "/home/me/.claude")
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watchers.json");
+        register(&path, scoped(1, "-src-a"), |_| true).unwrap();
+        assert!(register(&path, scoped(2, "-src-b"), |_| true)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            register(&path, scoped(3, "-src-a"), |_| true).unwrap(),
+            vec![scoped(1, "-src-a")]
+        );
+        // Entries written before projects existed read back as all-projects
+        let old = r#"[{"pid":4,"claude_dir":"/a","started_at":1}]"#;
+        assert_eq!(
+            serde_json::from_str::<Vec<WatcherEntry>>(old).unwrap()[0].project,
+            None
+        );
+    }
+
+    #[test]
+    fn prunes_dead_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watchers.json");
+        register(&path, entry(4242, "/a"), |_| true).unwrap();
+        // 4242 has died since
+        let others = register(&path, entry(99, "/a"), |pid| pid != 4242).unwrap();
+        assert!(others.is_empty());
+        assert_eq!(load(&path), vec![entry(99, "/a")]);
+    }
+
+    #[test]
+    fn re_registering_own_pid_replaces_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watchers.json");
+        register(&path, entry(5, "/a"), |_| true).unwrap();
+        let others = register(&path, entry(5, "/a"), |_| true).unwrap();
+        assert!(others.is_empty());
+        assert_eq!(load(&path).len(), 1);
+    }
+
+    #[test]
+    fn unregister_removes_file_when_last() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watchers.json");
+        register(&path, entry(5, "/a"), |_| true).unwrap();
+        register(&path, entry(6, "/a"), |_| true).unwrap();
+        unregister(&path, 5, |_| true).unwrap();
+        assert_eq!(load(&path), vec![entry(6, "/a")]);
+        unregister(&path, 6, |_| true).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn concurrent_registrations_are_all_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watchers.json");
+        std::thread::scope(|scope| {
+            for pid in 1..=16 {
+                let path = &path;
+                scope.spawn(move || register(path, entry(pid, "/a"), |_| true).unwrap());
+            }
+        });
+        let mut pids: Vec<u32> = load(&path).iter().map(|e| e.pid).collect();
+        pids.sort_unstable();
+        assert_eq!(pids, (1..=16).collect::<Vec<_>>());
+
+        std::thread::scope(|scope| {
+            for pid in 1..=8 {
+                let path = &path;
+                scope.spawn(move || unregister(path, pid, |_| true).unwrap());
+            }
+        });
+        let mut pids: Vec<u32> = load(&path).iter().map(|e| e.pid).collect();
+        pids.sort_unstable();
+        assert_eq!(pids, (9..=16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn multiple_conflicts_are_listed() {
+        let others = vec![entry(1, "/a"), entry(2, "/a")];
+        assert_eq!(
+            conflict_warning(&others).as_deref(),
+            Some("other instances (pids 1, 2) are watching this directory")
+        );
+        assert_eq!(conflict_warning(&[]), None);
+    }
+}
@@ -1,10 +1,15 @@
 use std::fs;
 use std::io::Write;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
+use pixel_agents_tui::api::AppEvent;
 use pixel_agents_tui::app::App;
+use pixel_agents_tui::schema::SCHEMA_VERSION;
 use pixel_agents_tui::state::agent::AgentStatus;
 use pixel_agents_tui::state::sdd::SddPhase;
+use pixel_agents_tui::state::stats::ToolCounts;
+use pixel_agents_tui::status_file::{self, StatusReport};
 
 fn setup_fake_claude_dir(dir: &Path) {
     let projects_dir = dir.join("projects").join("test-project");
@@ -50,8 +55,7 @@ fn full_lifecycle_simulation() {
 
     let mut app = App::new(dir.path().to_path_buf());
 
-    // Force immediate session scan (tick 19 → next tick will be 20)
-    app.tick_count = 19;
+    // The first tick scans for sessions
     app.tick();
 
     assert_eq!(app.agents.len(), 1);
@@ -78,6 +82,70 @@ fn full_lifecycle_simulation() {
     );
 }
 
+/// Replaying a session fills in the statistics screen's counters.
+#[test]
+fn replay_counts_tools_turns_and_files() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_fake_claude_dir(dir.path());
+    let jsonl_path = dir
+        .path()
+        .join("projects")
+        .join("test-project")
+        .join("session-1.jsonl");
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.tick();
+
+    let agent = app.agents.values().next().unwrap();
+    // Read, Write and Task, and the Skill call among the others
+    assert_eq!(
+        agent.tool_counts,
+        ToolCounts {
+            reads: 1,
+            writes: 1,
+            bash: 0,
+            web: 0,
+            task: 1,
+            other: 1,
+        }
+    );
+    assert_eq!(agent.tools_started, agent.tool_counts.total());
+    assert_eq!(agent.turns.completed(), 1);
+    assert_eq!(agent.turns.total_duration(), Duration::from_secs(5));
+    // Read and then written, one file
+    assert_eq!(
+        agent.touched_file_names().into_iter().collect::<Vec<_>>(),
+        ["auth.rs"]
+    );
+
+    // A second turn running a command in another directory
+    let mut f = fs::OpenOptions::new()
+        .append(true)
+        .open(&jsonl_path)
+        .unwrap();
+    writeln!(f, r#"{{"type":"assistant","message":{{"content":[{{"type":"tool_use","id":"t5","name":"Bash","input":{{"command":"cargo test"}}}}]}}}}"#).unwrap();
+    writeln!(f, r#"{{"type":"assistant","message":{{"content":[{{"type":"tool_use","id":"t6","name":"Edit","input":{{"file_path":"/tests/auth.rs"}}}}]}}}}"#).unwrap();
+    writeln!(f, r#"{{"type":"assistant","message":{{"content":[{{"type":"tool_use","id":"t7","name":"Edit","input":{{"file_path":"/src/main.rs"}}}}]}}}}"#).unwrap();
+    writeln!(
+        f,
+        r#"{{"type":"system","subtype":"turn_duration","duration_ms":2500}}"#
+    )
+    .unwrap();
+    drop(f);
+    app.tick();
+
+    let agent = app.agents.values().next().unwrap();
+    assert_eq!((agent.tool_counts.bash, agent.tool_counts.writes), (1, 3));
+    assert_eq!(agent.tool_counts.total(), 7);
+    assert_eq!(agent.turns.completed(), 2);
+    assert_eq!(agent.turns.total_duration(), Duration::from_millis(7500));
+    // The same name in two directories counts once
+    assert_eq!(
+        agent.touched_file_names().into_iter().collect::<Vec<_>>(),
+        ["auth.rs", "main.rs"]
+    );
+}
+
 /// Verify that mid-turn state (before turn_duration) preserves active tools and sub-agents.
 #[test]
 fn mid_turn_has_active_tools_and_sub_agents() {
@@ -99,7 +167,6 @@ fn mid_turn_has_active_tools_and_sub_agents() {
     }
 
     let mut app = App::new(dir.path().to_path_buf());
-    app.tick_count = 19;
     app.tick();
 
     assert_eq!(app.agents.len(), 1);
@@ -138,7 +205,6 @@ fn incremental_read_picks_up_new_lines() {
     }
 
     let mut app = App::new(dir.path().to_path_buf());
-    app.tick_count = 19;
     app.tick(); // tick 20: discovers session + reads first line
 
     assert_eq!(app.agents.len(), 1);
@@ -168,3 +234,560 @@ fn incremental_read_picks_up_new_lines() {
     assert_eq!(agent.active_tools[0].tool_name, "Read");
     assert!(agent.active_tools[0].is_reading);
 }
+
+/// Tool lifecycles resolve even when roles are swapped (tool_use in a user
+/// record, tool_result in an assistant record), as seen with MCP sampling.
+#[test]
+fn swapped_role_tool_lifecycle_resolves() {
+    let dir = tempfile::tempdir().unwrap();
+    let projects_dir = dir.path().join("projects").join("swapped-project");
+    fs::create_dir_all(&projects_dir).unwrap();
+    let jsonl_path = projects_dir.join("session-swapped.jsonl");
+    {
+        let mut f = fs::File::create(&jsonl_path).unwrap();
+        // tool_use carried by a user record
+        writeln!(f, r#"{{"type":"user","message":{{"content":[{{"type":"tool_use","id":"u1","name":"Read","input":{{"file_path":"/src/a.rs"}}}}]}}}}"#).unwrap();
+        // regular tool_use
+        writeln!(f, r#"{{"type":"assistant","message":{{"content":[{{"type":"tool_use","id":"u2","name":"Bash","input":{{"command":"ls"}}}}]}}}}"#).unwrap();
+        // tool_result carried by an assistant record
+        writeln!(f, r#"{{"type":"assistant","message":{{"content":[{{"type":"tool_result","tool_use_id":"u1"}}]}}}}"#).unwrap();
+    }
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.tick();
+
+    let agent = app.agents.values().next().unwrap();
+    assert_eq!(agent.status, AgentStatus::Active);
+    let ids: Vec<&str> = agent
+        .active_tools
+        .iter()
+        .map(|t| t.tool_id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["u2"]);
+
+    // Resolve the remaining tool via a user-record result as usual
+    {
+        let mut f = fs::OpenOptions::new()
+            .append(true)
+            .open(&jsonl_path)
+            .unwrap();
+        writeln!(
+            f,
+            r#"{{"type":"user","message":{{"content":[{{"type":"tool_result","tool_use_id":"u2"}}]}}}}"#
+        )
+        .unwrap();
+    }
+    app.tick();
+    let agent = app.agents.values().next().unwrap();
+    assert!(agent.active_tools.is_empty());
+}
+
+/// A directory mixing Claude Code transcripts with role-based ones: each
+/// file is read in the dialect sniffed from its first record.
+#[test]
+fn mixed_transcript_formats_build_the_same_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let projects_dir = dir.path().join("projects").join("mixed-project");
+    fs::create_dir_all(&projects_dir).unwrap();
+    let role_path = projects_dir.join("session-role.jsonl");
+    fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/role-based-session.jsonl"),
+        &role_path,
+    )
+    .unwrap();
+    let claude_path = projects_dir.join("session-claude.jsonl");
+    append_lines(
+        &claude_path,
+        &[
+            r#"{"type":"user","message":{"role":"user","content":"Refactor the parser"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Refactoring the parser"},{"type":"tool_use","id":"t1","name":"Skill","input":{"skill":"sdd-apply"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1"}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t2","name":"Edit","input":{"file_path":"/src/parser.rs"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t2","is_error":true}]}}"#,
+            r#"{"type":"system","subtype":"turn_duration","duration_ms":6000}"#,
+            r#"{"type":"user","message":{"role":"user","content":"Now add tests"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t3","name":"Write","input":{"file_path":"/tests/parser.rs"}}]}}"#,
+        ],
+    );
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.tick();
+    assert_eq!(app.agents.len(), 2);
+
+    for (path, format) in [(&role_path, "role-based"), (&claude_path, "claude-code")] {
+        let agent = app
+            .agents
+            .values()
+            .find(|agent| &agent.session_file == path)
+            .unwrap();
+        assert_eq!(agent.transcript_format().name(), format);
+        assert_eq!(agent.status, AgentStatus::Active, "{format}");
+        assert_eq!(agent.sdd_phase, Some(SddPhase::Apply), "{format}");
+        // The newest prompt, not the first thing ever said
+        assert_eq!(agent.prompt_summary, "Now add tests", "{format}");
+        let tools: Vec<&str> = agent
+            .active_tools
+            .iter()
+            .map(|tool| tool.display_status.as_str())
+            .collect();
+        assert_eq!(tools, ["Writing parser.rs"], "{format}");
+        let turn = agent.turns.recent().next().unwrap();
+        assert_eq!(agent.turns.completed(), 1, "{format}");
+        assert!(turn.errored, "{format}");
+        assert_eq!(turn.duration, Some(Duration::from_secs(6)), "{format}");
+    }
+    let role = app
+        .agents
+        .values()
+        .find(|agent| agent.session_file == role_path)
+        .unwrap();
+    assert_eq!(role.usage.total_input_tokens, 1200);
+    assert!(role.latency.last().is_some());
+
+    // Appended records keep the sniffed dialect
+    append_lines(
+        &role_path,
+        &[
+            r#"{"role":"tool","delta":{"content":[{"type":"tool_output","call_id":"c3"}]}}"#,
+            r#"{"role":"system","event":"turn_end","duration_ms":2000}"#,
+        ],
+    );
+    app.tick();
+    let role = app
+        .agents
+        .values()
+        .find(|agent| agent.session_file == role_path)
+        .unwrap();
+    assert_eq!(role.status, AgentStatus::Waiting);
+    assert_eq!(role.turns.completed(), 2);
+}
+
+fn append_lines(path: &Path, lines: &[&str]) {
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .unwrap();
+    for line in lines {
+        writeln!(f, "{line}").unwrap();
+    }
+}
+
+/// A long history found at startup: the agent reflects where the session
+/// stands now, not its first turns, and is as idle as the file is old.
+#[test]
+fn startup_replay_recovers_present_state_from_long_history() {
+    let dir = tempfile::tempdir().unwrap();
+    let projects_dir = dir.path().join("projects").join("replay-project");
+    fs::create_dir_all(&projects_dir).unwrap();
+
+    let mut history = vec![
+        r#"{"type":"user","message":{"role":"user","content":"Plan the migration"}}"#.to_string(),
+        r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Exploring the schema"},{"type":"tool_use","id":"s0","name":"Skill","input":{"skill":"sdd-explore"}}]}}"#.to_string(),
+        r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"s0"}]}}"#.to_string(),
+        // Left unresolved when the turn ended
+        r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"lost","name":"Bash","input":{"command":"sleep 100"}}]}}"#.to_string(),
+        r#"{"type":"system","subtype":"turn_duration","duration_ms":9000}"#.to_string(),
+    ];
+    for turn in 1..=40 {
+        history.extend([
+            format!(r#"{{"type":"user","message":{{"role":"user","content":"Step {turn}"}}}}"#),
+            format!(r#"{{"type":"assistant","message":{{"content":[{{"type":"text","text":"On step {turn}"}},{{"type":"tool_use","id":"r{turn}","name":"Read","input":{{"file_path":"/src/m{turn}.rs"}}}}]}}}}"#),
+            format!(r#"{{"type":"user","message":{{"content":[{{"type":"tool_result","tool_use_id":"r{turn}"}}]}}}}"#),
+            r#"{"type":"system","subtype":"turn_duration","duration_ms":2000}"#.to_string(),
+        ]);
+    }
+    let lines: Vec<&str> = history.iter().map(String::as_str).collect();
+
+    // Still working: the last prompt's tools are half done
+    let working = projects_dir.join("session-working.jsonl");
+    append_lines(&working, &lines);
+    append_lines(
+        &working,
+        &[
+            r#"{"type":"user","message":{"role":"user","content":"Run the migration"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"e1","name":"Edit","input":{"file_path":"/db/up.sql"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"e1"}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"b1","name":"Bash","input":{"command":"make migrate"}}]}}"#,
+        ],
+    );
+    // Finished its last turn a while ago
+    let idle = projects_dir.join("session-idle.jsonl");
+    append_lines(&idle, &lines);
+    let written = std::time::SystemTime::now() - Duration::from_secs(200);
+    fs::File::options()
+        .write(true)
+        .open(&idle)
+        .unwrap()
+        .set_modified(written)
+        .unwrap();
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.tick();
+    assert_eq!(app.agents.len(), 2);
+    let agent = |path: &Path| {
+        app.agents
+            .values()
+            .find(|agent| agent.session_file == path)
+            .unwrap()
+    };
+
+    let working = agent(&working);
+    assert_eq!(working.prompt_summary, "Run the migration");
+    assert_eq!(working.status, AgentStatus::Active);
+    let tools: Vec<&str> = working
+        .active_tools
+        .iter()
+        .map(|tool| tool.tool_id.as_str())
+        .collect();
+    assert_eq!(tools, ["b1"]);
+    // The explore phase was 41 turns ago
+    assert_eq!(working.sdd_phase, None);
+    assert!(working.last_activity.elapsed() < Duration::from_secs(60));
+    // History still counts
+    assert_eq!(working.turns.completed(), 41);
+
+    let idle = agent(&idle);
+    assert_eq!(idle.prompt_summary, "Step 40");
+    assert_eq!(idle.status, AgentStatus::Waiting);
+    assert!(idle.active_tools.is_empty());
+    assert!(idle.last_activity.elapsed() >= Duration::from_secs(200));
+}
+
+/// An open `AskUserQuestion` marks the agent as needing input until its
+/// answer arrives or the turn ends.
+#[test]
+fn ask_user_question_needs_input_until_answered() {
+    let dir = tempfile::tempdir().unwrap();
+    let projects_dir = dir.path().join("projects").join("ask-project");
+    fs::create_dir_all(&projects_dir).unwrap();
+    let jsonl_path = projects_dir.join("session-ask.jsonl");
+    append_lines(
+        &jsonl_path,
+        &[
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"ls"}}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"q1","name":"AskUserQuestion","input":{"questions":[]}}]}}"#,
+        ],
+    );
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.tick();
+    let agent = app.agents.values().next().unwrap();
+    assert_eq!(agent.status, AgentStatus::NeedsInput);
+    assert_eq!(app.needs_input_count(), 1);
+
+    // Another tool finishing doesn't answer the question
+    append_lines(
+        &jsonl_path,
+        &[r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1"}]}}"#],
+    );
+    app.tick();
+    assert_eq!(
+        app.agents.values().next().unwrap().status,
+        AgentStatus::NeedsInput
+    );
+
+    append_lines(
+        &jsonl_path,
+        &[r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"q1"}]}}"#],
+    );
+    app.tick();
+    assert_eq!(
+        app.agents.values().next().unwrap().status,
+        AgentStatus::Active
+    );
+    assert_eq!(app.needs_input_count(), 0);
+
+    // A second question, left open when the turn ends
+    append_lines(
+        &jsonl_path,
+        &[
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"q2","name":"AskUserQuestion","input":{"questions":[]}}]}}"#,
+        ],
+    );
+    app.tick();
+    assert_eq!(
+        app.agents.values().next().unwrap().status,
+        AgentStatus::NeedsInput
+    );
+    append_lines(
+        &jsonl_path,
+        &[r#"{"type":"system","subtype":"turn_duration","duration_ms":800}"#],
+    );
+    app.tick();
+    assert_eq!(
+        app.agents.values().next().unwrap().status,
+        AgentStatus::Waiting
+    );
+}
+
+/// A headless client mirroring a `--serve` instance sees the same agents.
+#[cfg(unix)]
+#[test]
+fn served_snapshots_reach_connected_client() {
+    use std::time::{Duration, Instant};
+
+    use pixel_agents_tui::app::StateSource;
+    use pixel_agents_tui::remote::{RemoteClient, Server};
+
+    let dir = tempfile::tempdir().unwrap();
+    setup_fake_claude_dir(dir.path());
+    let socket = dir.path().join("serve.sock");
+
+    let mut server_app = App::new(dir.path().to_path_buf());
+    server_app.tick();
+    assert_eq!(server_app.agents.len(), 1);
+    let mut server = Server::bind(&socket, None).unwrap();
+
+    let mut client_app = App::new(Default::default());
+    client_app.source = StateSource::Remote(RemoteClient::connect(socket.clone()));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while client_app.agents.is_empty() {
+        assert!(
+            Instant::now() < deadline,
+            "client never received a snapshot"
+        );
+        server.publish(&server_app.snapshot(Instant::now()));
+        std::thread::sleep(Duration::from_millis(20));
+        client_app.tick();
+    }
+    assert_eq!(server.client_count(), 1);
+    assert!(client_app.connection_banner(Instant::now()).is_none());
+
+    // Ages drift by the transfer time, everything else matches exactly
+    let now = Instant::now();
+    let strip_ages = |app: &App| {
+        let mut snapshot = app.snapshot(now);
+        for agent in &mut snapshot.agents {
+            agent.idle_for = Duration::ZERO;
+            agent.in_status_for = Duration::ZERO;
+            for (_, age) in &mut agent.sdd_history {
+                *age = Duration::ZERO;
+            }
+        }
+        snapshot
+    };
+    assert_eq!(strip_ages(&client_app), strip_ages(&server_app));
+}
+
+/// `--status` reads everything in one pass and prints one object per agent.
+#[test]
+fn status_json_describes_each_agent() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_fake_claude_dir(dir.path());
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.poll_once();
+    let json = serde_json::to_value(StatusReport::new(&app)).unwrap();
+    assert_eq!(json["schema_version"], SCHEMA_VERSION);
+    let agents = json["agents"].as_array().unwrap();
+    assert_eq!(agents.len(), 1);
+    let agent = agents[0].as_object().unwrap();
+    let mut keys: Vec<&str> = agent.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    assert_eq!(
+        keys,
+        [
+            "id",
+            "project",
+            "prompt_summary",
+            "sdd_phase",
+            "status",
+            "sub_agents",
+            "tool"
+        ]
+    );
+    assert_eq!(agent["id"], 1);
+    assert_eq!(agent["project"], "test-project");
+    assert_eq!(agent["status"], "waiting");
+    assert_eq!(agent["sdd_phase"], "Apply");
+    assert_eq!(agent["sub_agents"], 0);
+    assert!(agent["tool"].is_null());
+    assert!(agent["prompt_summary"]
+        .as_str()
+        .unwrap()
+        .contains("fix the auth bug"));
+
+    // A second pass picks up where the first stopped, as `--watch` does
+    let session = dir
+        .path()
+        .join("projects")
+        .join("test-project")
+        .join("session-1.jsonl");
+    append_lines(
+        &session,
+        &[
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t5","name":"Task","input":{"description":"Check tests"}}]}}"#,
+        ],
+    );
+    app.poll_once();
+    let agent = &status_file::agent_summaries(&app)[0];
+    assert_eq!(agent.status, AgentStatus::Active);
+    assert!(agent.tool.is_some());
+    assert_eq!(agent.sub_agents, 1);
+}
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// A resumed session, read before the one it resumed, ends up at the
+/// earlier session's desk rather than a second one, and stays there.
+#[test]
+fn resumed_session_merges_into_the_agent_it_resumed() {
+    let dir = tempfile::tempdir().unwrap();
+    let projects_dir = dir.path().join("projects").join("resume-project");
+    fs::create_dir_all(&projects_dir).unwrap();
+    // Sorted first, so read first: the session it resumed isn't known yet
+    let after = projects_dir.join("a-resumed.jsonl");
+    let before = projects_dir.join("b-original.jsonl");
+    fs::copy(fixture("resumed-session-after.jsonl"), &after).unwrap();
+    fs::copy(fixture("resumed-session-before.jsonl"), &before).unwrap();
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.tick();
+    assert_eq!(app.agents.len(), 1);
+    let (&id, agent) = app.agents.iter().next().unwrap();
+    assert_eq!(id, 2, "keeps the ID of the session it resumed");
+    assert_eq!(agent.id, 2);
+    assert_eq!(agent.session_file, after);
+    assert_eq!(agent.current_request, "Now cover it with a test");
+    assert_eq!(agent.status, AgentStatus::Active);
+    assert_eq!(agent.active_tools.len(), 1);
+    assert_eq!(agent.active_tools[0].tool_name, "Write");
+
+    // The next scan doesn't bring the earlier file back
+    append_lines(
+        &after,
+        &[
+            r#"{"type":"user","uuid":"c2f2","message":{"content":[{"type":"tool_result","tool_use_id":"t2"}]}}"#,
+        ],
+    );
+    app.request_rescan();
+    app.tick();
+    assert_eq!(app.agents.len(), 1);
+    assert!(app.agents[&2].active_tools.is_empty());
+}
+
+/// A compaction written live clears the tools started before it, which
+/// will never get their results.
+#[test]
+fn compaction_clears_active_tools() {
+    let dir = tempfile::tempdir().unwrap();
+    let projects_dir = dir.path().join("projects").join("compact-project");
+    fs::create_dir_all(&projects_dir).unwrap();
+    let path = projects_dir.join("session-compact.jsonl");
+    let lines = fs::read_to_string(fixture("compacted-session.jsonl")).unwrap();
+    let lines: Vec<&str> = lines.lines().collect();
+    let boundary = lines
+        .iter()
+        .position(|line| line.contains("compact_boundary"))
+        .unwrap();
+    append_lines(&path, &lines[..boundary]);
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.tick();
+    let agent = app.agents.values().next().unwrap();
+    assert_eq!(agent.active_tools.len(), 2);
+    assert_eq!(agent.sub_agents.len(), 1);
+
+    append_lines(&path, &lines[boundary..]);
+    app.tick();
+    let agent = app.agents.values().next().unwrap();
+    assert_eq!(agent.status, AgentStatus::Active);
+    assert_eq!(agent.active_tools.len(), 1);
+    assert_eq!(agent.active_tools[0].tool_name, "Read");
+    assert!(agent.sub_agents.is_empty());
+
+    // Read from the start, the history comes to the same
+    let mut fresh = App::new(dir.path().to_path_buf());
+    fresh.tick();
+    let agent = fresh.agents.values().next().unwrap();
+    assert_eq!(agent.active_tools.len(), 1);
+    assert_eq!(agent.active_tools[0].tool_name, "Read");
+    assert!(agent.sub_agents.is_empty());
+}
+
+/// Library callers scan when they choose and get every change as an event,
+/// in the order the records made it.
+#[test]
+fn poll_reports_the_fixture_as_events() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_fake_claude_dir(dir.path());
+    let session_file = dir
+        .path()
+        .join("projects")
+        .join("test-project")
+        .join("session-1.jsonl");
+
+    let mut app = App::new(dir.path().to_path_buf());
+    app.scan_now();
+    let tool = |tool_id: &str, tool_name: &str| (tool_id.to_string(), tool_name.to_string());
+    let started = |(tool_id, tool_name)| AppEvent::ToolStarted {
+        id: 1,
+        tool_id,
+        tool_name,
+    };
+    let completed = |(tool_id, tool_name)| AppEvent::ToolCompleted {
+        id: 1,
+        tool_id,
+        tool_name,
+        failed: false,
+    };
+    assert_eq!(
+        app.poll(),
+        [
+            AppEvent::AgentDiscovered {
+                id: 1,
+                session_file: session_file.clone(),
+            },
+            started(tool("t1", "Read")),
+            AppEvent::StatusChanged {
+                id: 1,
+                from: AgentStatus::Waiting,
+                to: AgentStatus::Active,
+            },
+            completed(tool("t1", "Read")),
+            started(tool("t2", "Skill")),
+            AppEvent::SddPhaseChanged {
+                id: 1,
+                from: None,
+                to: Some(SddPhase::Apply),
+            },
+            started(tool("t3", "Write")),
+            started(tool("t4", "Task")),
+            completed(tool("t3", "Write")),
+            AppEvent::TurnCompleted {
+                id: 1,
+                duration: Some(Duration::from_secs(5)),
+            },
+            AppEvent::StatusChanged {
+                id: 1,
+                from: AgentStatus::Active,
+                to: AgentStatus::Waiting,
+            },
+        ]
+    );
+    assert!(app.poll().is_empty());
+
+    // A snapshot stays as it was taken
+    let snapshot = app.app_snapshot(Instant::now());
+    let agent = snapshot.agent(1).unwrap();
+    assert_eq!(agent.sdd_phase, Some(SddPhase::Apply));
+    assert_eq!(snapshot.count(AgentStatus::Waiting), 1);
+
+    fs::remove_file(&session_file).unwrap();
+    app.scan_now();
+    assert_eq!(
+        app.poll(),
+        [AppEvent::AgentRemoved {
+            id: 1,
+            session_file
+        }]
+    );
+    assert!(app.agents.is_empty());
+    assert_eq!(snapshot.agents.len(), 1);
+}
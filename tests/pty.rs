@@ -0,0 +1,206 @@
+//! Drives the real binary in a pseudo-terminal: raw mode, key handling,
+//! redraws and restoring the terminal on exit. Slower and more timing
+//! dependent than the rest of the suite, so ignored by default; run with
+//!
+//!     cargo test --test pty -- --ignored
+#![cfg(unix)]
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use pixel_agents_tui::persist;
+use pixel_agents_tui::watcher::discovery::SESSIONS_FILE_NAME;
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+
+const ROWS: u16 = 30;
+const COLS: u16 = 120;
+/// How long to wait for the screen to show something before failing.
+const SCREEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SESSION_ID: &str = "0f3c9a2e-5b1d-4c7e-9a41-3d2b8c6e1f70";
+
+/// A Claude data directory with one session, mid-way through a Bash call.
+fn setup_claude_dir(dir: &Path) {
+    let project = dir.join("projects").join("-home-u-demo-repo");
+    fs::create_dir_all(&project).unwrap();
+    let lines = [
+        r#"{"type":"user","message":{"role":"user","content":"Run the test suite"}}"#,
+        r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test"}}]}}"#,
+    ];
+    fs::write(
+        project.join(format!("{SESSION_ID}.jsonl")),
+        lines.join("\n") + "\n",
+    )
+    .unwrap();
+}
+
+/// The dashboard running in a pseudo-terminal, with a virtual screen fed
+/// from its output.
+struct Dashboard {
+    child: Box<dyn Child + Send + Sync>,
+    writer: Box<dyn Write + Send>,
+    output: Receiver<Vec<u8>>,
+    screen: vt100::Parser,
+    /// Keeps the terminal open until the test is done.
+    _master: Box<dyn MasterPty + Send>,
+}
+
+impl Dashboard {
+    /// Start `--attach` on `claude_dir`, with runtime, state and config
+    /// files kept under `home`.
+    fn attach(claude_dir: &Path, home: &Path) -> Self {
+        let pair = native_pty_system()
+            .openpty(PtySize {
+                rows: ROWS,
+                cols: COLS,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .unwrap();
+        let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_pixel-agents-tui"));
+        cmd.args(["--attach", "--claude-dir"]);
+        cmd.arg(claude_dir);
+        cmd.env_clear();
+        cmd.env("HOME", home);
+        cmd.env("XDG_RUNTIME_DIR", runtime_dir(home));
+        cmd.env("XDG_STATE_HOME", home.join("state"));
+        cmd.env("XDG_CONFIG_HOME", home.join("config"));
+        cmd.env("TERM", "xterm-256color");
+        cmd.env("LANG", "C.UTF-8");
+        let child = pair.slave.spawn_command(cmd).unwrap();
+        // The child holds its own copy; ours would keep the terminal from
+        // reporting the end of output
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader().unwrap();
+        let (tx, output) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut buf = [0; 8192];
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 || tx.send(buf[..n].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            child,
+            writer: pair.master.take_writer().unwrap(),
+            output,
+            screen: vt100::Parser::new(ROWS, COLS, 0),
+            _master: pair.master,
+        }
+    }
+
+    fn send(&mut self, keys: &str) {
+        self.writer.write_all(keys.as_bytes()).unwrap();
+        self.writer.flush().unwrap();
+    }
+
+    /// Take in output until `ready` holds for the screen, failing with
+    /// what's on it after [`SCREEN_TIMEOUT`].
+    fn wait_for(&mut self, what: &str, ready: impl Fn(&vt100::Screen) -> bool) {
+        let deadline = Instant::now() + SCREEN_TIMEOUT;
+        while !ready(self.screen.screen()) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.output.recv_timeout(left) {
+                Ok(bytes) => self.screen.process(&bytes),
+                Err(_) => panic!(
+                    "timed out waiting for {what}; the screen shows:\n{}",
+                    self.screen.screen().contents()
+                ),
+            }
+        }
+    }
+
+    fn wait_for_text(&mut self, text: &str) {
+        self.wait_for(&format!("{text:?}"), |screen| {
+            screen.contents().contains(text)
+        });
+    }
+
+    /// Wait for the process to exit on its own, taking in its last output.
+    fn wait_exit(&mut self) -> portable_pty::ExitStatus {
+        let deadline = Instant::now() + SCREEN_TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                while let Ok(bytes) = self.output.recv_timeout(Duration::from_millis(200)) {
+                    self.screen.process(&bytes);
+                }
+                return status;
+            }
+            assert!(Instant::now() < deadline, "the dashboard didn't exit");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        // Don't leave a dashboard running after a failed assertion
+        let _ = self.child.kill();
+    }
+}
+
+fn runtime_dir(home: &Path) -> PathBuf {
+    home.join("run")
+}
+
+fn pid_file(home: &Path) -> PathBuf {
+    runtime_dir(home).join("pixel-agents-tui.pid")
+}
+
+#[test]
+#[ignore = "spawns the binary in a pseudo-terminal; run with --ignored"]
+fn attach_select_search_focus_and_quit() {
+    let claude_dir = tempfile::tempdir().unwrap();
+    setup_claude_dir(claude_dir.path());
+    let home = tempfile::tempdir().unwrap();
+    fs::create_dir_all(runtime_dir(home.path())).unwrap();
+
+    let mut dashboard = Dashboard::attach(claude_dir.path(), home.path());
+    // A first run greets before anything else; any key moves on
+    dashboard.wait_for_text("Welcome to Pixel Agents");
+    let pid: u32 = fs::read_to_string(pid_file(home.path()))
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap();
+    assert_eq!(Some(pid), dashboard.child.process_id());
+    dashboard.wait_for("the alternate screen", |screen| {
+        screen.alternate_screen() && screen.hide_cursor()
+    });
+    dashboard.send(" ");
+    dashboard.wait_for_text("Agent #1 \u{b7}");
+    dashboard.wait_for_text("\u{25b8} Agent Details");
+
+    dashboard.send("1");
+    dashboard.wait_for_text(SESSION_ID);
+    dashboard.wait_for_text("cargo test");
+
+    // A search takes every key, `q` included
+    dashboard.send("/quit");
+    dashboard.wait_for_text("/quit_");
+    dashboard.send("\r");
+    dashboard.wait_for_text("Agent Details (search: \"quit\")");
+
+    dashboard.send("\t");
+    dashboard.wait_for_text("\u{25b8} Office");
+
+    dashboard.send("q");
+    let status = dashboard.wait_exit();
+    assert!(status.success(), "exited with {status:?}");
+    let screen = dashboard.screen.screen();
+    assert!(!screen.alternate_screen(), "left on the alternate screen");
+    assert!(!screen.hide_cursor(), "left the cursor hidden");
+    assert!(!pid_file(home.path()).exists());
+    // The next run skips the greeting
+    let state = home.path().join("state").join("pixel-agents-tui");
+    assert!(state.join(persist::STATE_FILE_NAME).exists());
+    // And numbers the session as before
+    assert!(fs::read_to_string(state.join(SESSIONS_FILE_NAME))
+        .unwrap()
+        .contains(SESSION_ID));
+}